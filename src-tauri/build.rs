@@ -1,3 +1,21 @@
 fn main() {
+    // tauri_build emits its own rerun-if-changed lines, which switch off
+    // cargo's default of rerunning on any package change; watch the sources
+    // so the build date follows every rebuild of the crate.
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    let build_timestamp = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+        });
+    println!(
+        "cargo:rustc-env=UPS_MONITOR_BUILD_TIMESTAMP={}",
+        build_timestamp
+    );
     tauri_build::build()
 }
@@ -1,38 +1,251 @@
-use chrono::{DateTime, Duration as ChronoDuration, NaiveDate, Utc};
+use chrono::{
+    DateTime, Datelike, Duration as ChronoDuration, Local, NaiveDate, NaiveDateTime, NaiveTime,
+    TimeZone, Utc,
+};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
 use hidapi::HidApi;
 use rodio::{Decoder, OutputStream, Sink};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use std::io::BufReader;
+use std::cell::Cell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
 use std::sync::{Arc, Mutex, MutexGuard};
 use std::thread;
-use std::time::Duration;
-use tauri::{AppHandle, Emitter, Manager, State};
-use tauri_plugin_notification::NotificationExt;
-#[cfg(target_os = "windows")]
+use std::time::{Duration, Instant};
 use tauri::{
     menu::{MenuBuilder, MenuItemBuilder},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
+    AppHandle, Emitter, Manager, State,
 };
+use tauri_plugin_notification::NotificationExt;
 #[cfg(target_os = "windows")]
-use windows::Win32::Graphics::Dwm::{DwmSetWindowAttribute, DWMWA_WINDOW_CORNER_PREFERENCE, DWM_WINDOW_CORNER_PREFERENCE};
+use windows::Win32::Graphics::Dwm::{
+    DwmSetWindowAttribute, DWMWA_WINDOW_CORNER_PREFERENCE, DWM_WINDOW_CORNER_PREFERENCE,
+};
 
+const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
+const BUILD_TIMESTAMP: &str = env!("UPS_MONITOR_BUILD_TIMESTAMP");
 const UPS_VID: u16 = 0x0925;
 const UPS_PID: u16 = 0x1234;
 const MAX_EVENTS: usize = 1000;
 const MAX_DATA_POINTS: usize = 5000;
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const LOOP_RATE_WINDOW_MS: u64 = 10_000;
+const PROTECTION_SUSPEND_MAX_MINUTES: u64 = 7 * 24 * 60;
+const CONFIG_WATCH_INTERVAL_MS: u64 = 2_000;
+const SIMULATION_MAX_SECONDS: u64 = 600;
+const SIMULATION_MAX_DRAIN_RATE: f64 = 20.0;
+const SIMULATION_TICK_MS: u64 = 1_000;
+// Battery voltage is the sixth electrical field, so the status bits come after it.
+const FRAME_MIN_STATUS_FIELD: u64 = 6;
+const FRAME_MAX_STATUS_FIELD: u64 = 31;
+const MAX_DISCHARGE_SESSIONS: usize = 20;
+const MAX_DISCHARGE_POINTS: usize = 2000;
+const MAX_FRAME_BYTES: usize = 256;
+const MEGATEC_INFO_QUERY: &[u8] = b"I\r";
 const BATTERY_LOW_SHUTDOWN_DELAY_MINUTES: u64 = 5;
 const BATTERY_CRITICAL_SHUTDOWN_DELAY_MINUTES: u64 = 1;
+const NOMINAL_MAINS_VOLTAGES: [f64; 7] = [100.0, 110.0, 120.0, 127.0, 220.0, 230.0, 240.0];
+// Strict-restore voltage band around the nominal when the user sets none.
+const STRICT_RESTORE_VOLTAGE_TOLERANCE: f64 = 0.15;
+const STRICT_RESTORE_FREQUENCY_TOLERANCE: f64 = 1.0;
+const HEARTBEAT_INTERVAL_MS: u64 = 60_000;
+const SETTINGS_REVISION: u64 = 2;
+const RUNTIME_STATE_SAVE_INTERVAL_MS: u64 = 15_000;
+const LAST_STATUS_SAVE_INTERVAL_MS: u64 = 60_000;
+const HID_RETRY_BASE_MS: u64 = 1_500;
+const HID_RETRY_MAX_MS: u64 = 60_000;
+// A device that refused access is left alone this long instead of being
+// reopened on every enumeration pass.
+const HID_PERMISSION_RETRY_MS: u64 = 30_000;
+// Executable-name prefixes (lower case, no extension) of software that keeps
+// the UPS's HID interface open.
+const CONFLICTING_SOFTWARE: [(&str, &str); 11] = [
+    ("upsilon", "UPSilon 2000"),
+    ("winpower", "WinPower"),
+    ("viewpower", "ViewPower"),
+    ("powerpanel", "PowerPanel"),
+    ("powerchute", "PowerChute"),
+    ("apcupsd", "apcupsd"),
+    ("upsmon", "NUT upsmon"),
+    ("upsd", "NUT upsd"),
+    ("usbhid-ups", "NUT usbhid-ups"),
+    ("nutdrv_qx", "NUT nutdrv_qx"),
+    ("blazer_usb", "NUT blazer_usb"),
+];
+const RESTORED_OUTAGE_MAX_AGE_MS: u64 = 6 * 60 * 60 * 1000;
+const REPORT_CHECK_INTERVAL_MS: u64 = 60 * 60 * 1000;
+const PREFLIGHT_INTERVAL_MS: u64 = 24 * 60 * 60 * 1000;
+const INFO_REFRESH_TIMEOUT_MS: u64 = 2500;
+const PROJECTION_WINDOW_MS: u64 = 5 * 60 * 1000;
+const PROJECTION_MIN_SPAN_MS: u64 = 60 * 1000;
+const PROJECTION_MIN_SAMPLES: usize = 5;
+const PROJECTION_MIN_SLOPE_PER_MINUTE: f64 = 0.05;
+const BATTERY_FULL_PERCENT: u64 = 99;
+// On AC below this the battery is still recovering even without a visible trend.
+const CHARGED_MIN_PERCENT: u64 = 95;
+// Below this input voltage the grid is considered gone rather than sagging.
+const UTILITY_LOST_MAX_VOLTAGE: f64 = 30.0;
+const LIFETIME_STATS_SAVE_INTERVAL_MS: u64 = 60_000;
+const VOLTAGE_HISTOGRAM_BUCKET: f64 = 2.0;
+const FREQUENCY_HISTOGRAM_BUCKET: f64 = 0.5;
+// Ceilings for the configurable reading limits. Histograms fill every bucket
+// between the lowest and highest reading, so these also bound their size.
+const VOLTAGE_READING_CEIL: f64 = 1000.0;
+const FREQUENCY_READING_CEIL: f64 = 100.0;
+const REMOTE_API_MAX_REQUEST_BYTES: usize = 8 * 1024;
+// The whole request must arrive within this, however slowly it trickles in.
+const REMOTE_API_REQUEST_TIMEOUT_MS: u64 = 5_000;
+const REMOTE_API_MAX_CONNECTIONS: u64 = 8;
+const NUT_MAX_LINE_BYTES: u64 = 512;
+const NUT_MAX_CONNECTIONS: u64 = 16;
+// upsmon polls every few seconds; a silent client gives its place back.
+const NUT_CLIENT_IDLE_TIMEOUT_MS: u64 = 60_000;
+const SUPPORT_BUNDLE_EVENTS: usize = 200;
+const SUPPORT_BUNDLE_DATA_POINTS: usize = 500;
+const SUPPORT_BUNDLE_LOG_LINES: usize = 500;
+const LOG_DIR_NAME: &str = "logs";
+const LOG_FILE_NAME: &str = "ups-monitor.log";
+const LOG_MAX_BYTES: u64 = 1024 * 1024;
+// Current file plus rotated .1 .. .4 copies.
+const LOG_MAX_FILES: usize = 5;
+const LOG_TAIL_MAX_LINES: usize = 2000;
+const RAW_PACKET_LOG_CAPACITY: usize = 50;
+const SECRET_KEY_MARKERS: [&str; 5] = ["token", "password", "secret", "apikey", "credential"];
+// Maps whose values are redacted whatever their keys are called; scripts often
+// take credentials from the environment.
+const SECRET_VALUE_MAPS: [&str; 1] = ["shutdownPC.environment"];
+
+const ALERT_WINDOW_LABEL: &str = "alert";
+const ALERT_QUEUE_SPACING_MS: u64 = 6_000;
+// Windows that never subscribed still get ups-data, just at this rate.
+const UPS_DATA_FALLBACK_INTERVAL_MS: u64 = 5_000;
+const UPS_DATA_MAX_INTERVAL_MS: u64 = 60_000;
+const ALERT_WINDOW_WIDTH: f64 = 380.0;
+const ALERT_WINDOW_HEIGHT: f64 = 170.0;
+const ALERT_WINDOW_MARGIN: f64 = 16.0;
+const FLASH_WINDOW_LABEL: &str = "flash";
+// One pulse per second stays well under the three-flashes-per-second
+// photosensitivity threshold.
+const SCREEN_FLASH_ON_MS: u64 = 250;
+const SCREEN_FLASH_OFF_MS: u64 = 750;
+const TRAY_FLASH_INTERVAL_MS: u64 = 500;
+const ALERT_CHANNELS: [&str; 3] = ["notification", "popup", "sound"];
+const ESCALATION_MAX_DURATION_MS: u64 = 30 * 60 * 1000;
+// Upper bound for maxSoundRepeats; every loop still checks the sound generation.
+const SOUND_REPEATS_LIMIT: u64 = 500;
+const SOUND_CANCEL_POLL_MS: u64 = 70;
+// Consecutive failures to open the default output device before audio is
+// treated as unavailable rather than briefly busy.
+const AUDIO_UNAVAILABLE_AFTER_FAILURES: u64 = 3;
+// How long an alert waits for the output device to open before going by the
+// last known audio availability instead.
+const AUDIO_OPEN_WAIT_MS: u64 = 500;
+const SHUTDOWN_ACTIONS: [&str; 2] = ["shutdown", "sleep"];
+// Restart only makes sense as a reversible test of the whole flow, so it is
+// offered to manual triggers and never saved as the protective action.
+const MANUAL_SHUTDOWN_ACTIONS: [&str; 3] = ["shutdown", "sleep", "restart"];
+const STANDBY_ALERT_MIN_INTERVAL_MS: u64 = 7 * 24 * 60 * 60 * 1000;
+// Percent above standby minimum needed before the condition can be raised again.
+const STANDBY_CHARGE_HYSTERESIS_PERCENT: u64 = 5;
+const ALERT_TEST_SHUTDOWN_CANCEL_MS: u64 = 60_000;
+// A sleep or restart action returns control quickly; after this long without
+// the OS going down the phase falls back to idle.
+const SHUTDOWN_EXECUTING_HOLD_MS: u64 = 120_000;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", from = "AlertConfigRaw")]
 struct AlertConfig {
+    channels: Vec<String>,
+    sound_repeats: u64,
+    // Keep replaying sound/popup every `repeat_delay_seconds` until acknowledged.
+    escalate: bool,
+    repeat_delay_seconds: u64,
+}
+
+fn default_repeat_delay_seconds() -> u64 {
+    60
+}
+
+// Configs saved before channel routing only had `playSound`/`showPopup`;
+// a popup always came with the native notification.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AlertConfigRaw {
+    channels: Option<Vec<String>>,
+    #[serde(default)]
     play_sound: bool,
+    #[serde(default)]
     show_popup: bool,
+    #[serde(default)]
     sound_repeats: u64,
+    #[serde(default)]
+    escalate: bool,
+    #[serde(default = "default_repeat_delay_seconds")]
+    repeat_delay_seconds: u64,
+}
+
+impl From<AlertConfigRaw> for AlertConfig {
+    fn from(raw: AlertConfigRaw) -> Self {
+        let channels = raw.channels.unwrap_or_else(|| {
+            let mut channels = Vec::new();
+            if raw.show_popup {
+                channels.push("notification".to_string());
+                channels.push("popup".to_string());
+            }
+            if raw.play_sound {
+                channels.push("sound".to_string());
+            }
+            channels
+        });
+        Self {
+            channels,
+            sound_repeats: raw.sound_repeats,
+            escalate: raw.escalate,
+            repeat_delay_seconds: raw.repeat_delay_seconds,
+        }
+    }
+}
+
+impl AlertConfig {
+    fn new(channels: &[&str], sound_repeats: u64) -> Self {
+        Self {
+            channels: channels.iter().map(|channel| channel.to_string()).collect(),
+            sound_repeats,
+            escalate: false,
+            repeat_delay_seconds: default_repeat_delay_seconds(),
+        }
+    }
+
+    fn has_channel(&self, channel: &str) -> bool {
+        self.channels.iter().any(|item| item == channel)
+    }
+
+    fn normalize_channels(&mut self) {
+        let mut channels = Vec::new();
+        for channel in ALERT_CHANNELS {
+            if self.has_channel(channel) {
+                channels.push(channel.to_string());
+            }
+        }
+        self.channels = channels;
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ChannelResult {
+    channel: String,
+    ok: bool,
+    error: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,6 +253,24 @@ struct AlertConfig {
 struct ShutdownOnAcFault {
     enabled: bool,
     delay_minutes: u64,
+    #[serde(default)]
+    sticky: bool,
+    #[serde(default = "default_sticky_recovery_seconds")]
+    sticky_recovery_seconds: u64,
+    // Shortens the delay to the estimated runtime minus the margin when the
+    // battery would not last the configured delay.
+    #[serde(default)]
+    cap_delay_to_runtime: bool,
+    #[serde(default = "default_runtime_safety_margin_minutes")]
+    runtime_safety_margin_minutes: u64,
+}
+
+fn default_sticky_recovery_seconds() -> u64 {
+    120
+}
+
+fn default_runtime_safety_margin_minutes() -> u64 {
+    3
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,7 +287,29 @@ struct ShutdownPCSettings {
     on_battery_critical: ShutdownToggle,
     auto_save_files: bool,
     shutdown_command: String,
+    // Applied to whichever shutdown command runs; None keeps the app's own.
+    #[serde(default)]
+    working_directory: Option<String>,
+    #[serde(default)]
+    environment: BTreeMap<String, String>,
     action: String,
+    #[serde(default)]
+    confirm_if_user_active: bool,
+    #[serde(default = "default_user_active_within_seconds")]
+    user_active_within_seconds: u64,
+    #[serde(default = "default_confirmation_window_seconds")]
+    confirmation_window_seconds: u64,
+    // 0 disables the guard on manual shutdown requests.
+    #[serde(default)]
+    manual_shutdown_min_battery_percent: u64,
+}
+
+fn default_user_active_within_seconds() -> u64 {
+    120
+}
+
+fn default_confirmation_window_seconds() -> u64 {
+    60
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,1900 +325,14177 @@ struct AlertSettings {
     ac_fault: AlertConfig,
     battery_low: AlertConfig,
     battery_critical: AlertConfig,
+    #[serde(default = "default_charge_failure_alert")]
+    charge_failure: AlertConfig,
+    #[serde(default = "default_connection_alert")]
+    ups_disconnected: AlertConfig,
+    #[serde(default = "default_connection_alert")]
+    ups_connected: AlertConfig,
+    #[serde(default = "default_temperature_rising_alert")]
+    temperature_rising: AlertConfig,
+    #[serde(default = "default_battery_not_holding_alert")]
+    battery_not_holding: AlertConfig,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct AppSettings {
-    start_with_windows: bool,
-    start_minimized: bool,
-    #[serde(default)]
-    monitor_only_mode: bool,
-    polling_interval: u64,
-    enable_notifications: bool,
-    alerts: AlertSettings,
-    #[serde(rename = "shutdownPC", alias = "shutdownPc")]
-    shutdown_pc: ShutdownPCSettings,
-    ups_control: UpsControlSettings,
-    save_history: bool,
-    history_interval: u64,
-    low_battery_threshold: u64,
-    critical_battery_threshold: u64,
-    custom_sounds_path: Option<String>,
+fn default_charge_failure_alert() -> AlertConfig {
+    AlertConfig::new(&["notification", "popup", "sound"], 3)
 }
 
-impl Default for AppSettings {
-    fn default() -> Self {
-        Self {
-            start_with_windows: false,
-            start_minimized: false,
-            monitor_only_mode: false,
-            polling_interval: 1000,
-            enable_notifications: true,
-            alerts: AlertSettings {
-                ac_fault: AlertConfig {
-                    play_sound: true,
-                    show_popup: true,
-                    sound_repeats: 3,
-                },
-                battery_low: AlertConfig {
-                    play_sound: true,
-                    show_popup: true,
-                    sound_repeats: 5,
-                },
-                battery_critical: AlertConfig {
-                    play_sound: true,
-                    show_popup: true,
-                    sound_repeats: 10,
-                },
-            },
-            shutdown_pc: ShutdownPCSettings {
-                on_ac_fault: ShutdownOnAcFault {
-                    enabled: true,
-                    delay_minutes: 18,
-                },
-                on_battery_low: ShutdownToggle { enabled: false },
-                on_battery_critical: ShutdownToggle { enabled: true },
-                auto_save_files: true,
-                shutdown_command: String::new(),
-                action: "shutdown".to_string(),
-            },
-            ups_control: UpsControlSettings {
-                shutdown_ups_after_pc: true,
-                ups_shutdown_delay: 2,
-            },
-            save_history: true,
-            history_interval: 300,
-            low_battery_threshold: 20,
-            critical_battery_threshold: 10,
-            custom_sounds_path: None,
-        }
-    }
+fn default_connection_alert() -> AlertConfig {
+    AlertConfig::new(&[], 1)
 }
 
-impl AppSettings {
-    fn apply_monitor_only_defaults(&mut self) {
-        self.enable_notifications = false;
-        self.alerts.ac_fault.play_sound = false;
-        self.alerts.ac_fault.show_popup = false;
-        self.alerts.battery_low.play_sound = false;
-        self.alerts.battery_low.show_popup = false;
-        self.alerts.battery_critical.play_sound = false;
-        self.alerts.battery_critical.show_popup = false;
+fn default_temperature_rising_alert() -> AlertConfig {
+    AlertConfig::new(&["notification", "popup"], 1)
+}
 
-        self.shutdown_pc.on_ac_fault.enabled = false;
-        self.shutdown_pc.on_battery_low.enabled = false;
-        self.shutdown_pc.on_battery_critical.enabled = false;
-        self.shutdown_pc.auto_save_files = false;
-        self.shutdown_pc.shutdown_command.clear();
+fn default_battery_not_holding_alert() -> AlertConfig {
+    AlertConfig::new(&["notification", "popup"], 1)
+}
 
-        self.ups_control.shutdown_ups_after_pc = false;
-        self.save_history = false;
+impl AlertSettings {
+    fn configs(&self) -> [&AlertConfig; 8] {
+        [
+            &self.ac_fault,
+            &self.battery_low,
+            &self.battery_critical,
+            &self.charge_failure,
+            &self.ups_disconnected,
+            &self.ups_connected,
+            &self.temperature_rising,
+            &self.battery_not_holding,
+        ]
     }
 
-    fn normalize(mut self) -> Self {
-        self.polling_interval = clamp_u64(self.polling_interval, 500, 10_000, 1000);
-        self.history_interval = clamp_u64(self.history_interval, 60, 3600, 300);
-        self.low_battery_threshold = clamp_u64(self.low_battery_threshold, 5, 50, 20);
-        self.critical_battery_threshold =
-            clamp_u64(self.critical_battery_threshold, 5, 30, 10).min(self.low_battery_threshold);
-
-        self.alerts.ac_fault.sound_repeats =
-            clamp_u64(self.alerts.ac_fault.sound_repeats, 1, 30, 3);
-        self.alerts.battery_low.sound_repeats =
-            clamp_u64(self.alerts.battery_low.sound_repeats, 1, 30, 5);
-        self.alerts.battery_critical.sound_repeats =
-            clamp_u64(self.alerts.battery_critical.sound_repeats, 1, 30, 10);
-
-        self.shutdown_pc.on_ac_fault.delay_minutes =
-            clamp_u64(self.shutdown_pc.on_ac_fault.delay_minutes, 1, 60, 18);
-        self.ups_control.ups_shutdown_delay =
-            clamp_u64(self.ups_control.ups_shutdown_delay, 1, 10, 2);
+    fn configs_mut(&mut self) -> [&mut AlertConfig; 8] {
+        [
+            &mut self.ac_fault,
+            &mut self.battery_low,
+            &mut self.battery_critical,
+            &mut self.charge_failure,
+            &mut self.ups_disconnected,
+            &mut self.ups_connected,
+            &mut self.temperature_rising,
+            &mut self.battery_not_holding,
+        ]
+    }
+}
 
-        if self.shutdown_pc.action != "shutdown" && self.shutdown_pc.action != "sleep" {
-            self.shutdown_pc.action = "shutdown".to_string();
-        }
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RechargeCheckSettings {
+    enabled: bool,
+    min_rise_percent: u64,
+    window_minutes: u64,
+}
 
-        if self.monitor_only_mode {
-            self.apply_monitor_only_defaults();
+impl Default for RechargeCheckSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_rise_percent: 10,
+            window_minutes: 240,
         }
-
-        self
     }
 }
 
+// A fast temperature climb usually means a dead fan or a battery heating up,
+// well before any absolute limit is reached.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct UpsStatusFlags {
-    raw: String,
-    utility_fail: bool,
-    battery_low: bool,
-    bypass_active: bool,
-    ups_failed: bool,
-    ups_is_standby: bool,
-    test_in_progress: bool,
-    shutdown_active: bool,
-    beeper_on: bool,
+struct TemperatureTrendSettings {
+    enabled: bool,
+    rise_per_hour: f64,
+    // Below this rate a raised alert clears; keeps it from flapping at the threshold.
+    clear_per_hour: f64,
+    window_minutes: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct UpsData {
-    r#type: String,
-    input_voltage: f64,
-    fault_voltage: f64,
-    output_voltage: f64,
-    load_percent: u64,
-    frequency: f64,
-    battery_voltage: f64,
-    temperature: f64,
-    battery_percent: u64,
-    estimated_runtime: u64,
-    timestamp: String,
-    status: UpsStatusFlags,
+impl Default for TemperatureTrendSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            rise_per_hour: 30.0,
+            clear_per_hour: 15.0,
+            window_minutes: 15,
+        }
+    }
 }
 
+// A healthy battery sits near full on AC. Staying low for hours with AC present
+// points at a bad cell or a dead charger, which the on-battery thresholds miss.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct UpsInfo {
-    manufacturer: String,
-    product: String,
-    vendor_id: String,
-    product_id: String,
-    firmware: Option<String>,
+struct StandbyChargeSettings {
+    enabled: bool,
+    min_percent: u64,
+    duration_hours: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct HistoryEvent {
-    id: u64,
-    time: String,
-    classification: String,
-    name: String,
-    remarks: String,
+impl Default for StandbyChargeSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_percent: 80,
+            duration_hours: 6,
+        }
+    }
 }
 
+// Fixed-interval sampling misses brief voltage dips and surges. When enabled, a
+// data point is also logged as soon as a metric moves past its delta from the
+// last logged point, but never more often than min_interval_seconds and at
+// most max_points_per_hour times, so a flapping reading cannot flush the
+// capped history.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct DataHistoryEntry {
-    id: u64,
-    time: String,
-    input_voltage: f64,
-    output_voltage: f64,
-    frequency: f64,
-    load_percent: u64,
-    battery_voltage: f64,
-    battery_percent: u64,
-    temperature: f64,
+struct ChangeLoggingSettings {
+    enabled: bool,
+    voltage_delta: f64,
+    frequency_delta: f64,
+    battery_voltage_delta: f64,
+    temperature_delta: f64,
+    load_delta: u64,
+    battery_percent_delta: u64,
+    min_interval_seconds: u64,
+    #[serde(default = "default_change_points_per_hour")]
+    max_points_per_hour: u64,
 }
 
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct HistoryFilter {
-    classification: Option<String>,
-    date_from: Option<String>,
-    date_to: Option<String>,
+fn default_change_points_per_hour() -> u64 {
+    30
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct SoundRepeatConfig {
-    ac_fault: u64,
-    battery_low: u64,
-    critical: u64,
-    default: u64,
+impl Default for ChangeLoggingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            voltage_delta: 10.0,
+            frequency_delta: 1.0,
+            battery_voltage_delta: 1.0,
+            temperature_delta: 3.0,
+            load_delta: 10,
+            battery_percent_delta: 10,
+            min_interval_seconds: 5,
+            max_points_per_hour: default_change_points_per_hour(),
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct SoundConfig {
-    repeat_config: SoundRepeatConfig,
-    repeat_delay: u64,
-    custom_sounds_path: Option<String>,
-    sounds: SoundFiles,
+impl ChangeLoggingSettings {
+    fn exceeded(&self, last: &DataHistoryEntry, status: &UpsData) -> bool {
+        (status.input_voltage - last.input_voltage).abs() > self.voltage_delta
+            || (status.output_voltage - last.output_voltage).abs() > self.voltage_delta
+            || (status.frequency - last.frequency).abs() > self.frequency_delta
+            || (status.battery_voltage - last.battery_voltage).abs() > self.battery_voltage_delta
+            || (status.temperature - last.temperature).abs() > self.temperature_delta
+            || status.load_percent.abs_diff(last.load_percent) > self.load_delta
+            || status.battery_percent.abs_diff(last.battery_percent) > self.battery_percent_delta
+    }
 }
 
+// A recurring maintenance window, such as a weekly generator test, during
+// which protection is suspended as if `suspend_protection` had been called.
+// Times are local; weekday counts from Monday = 0.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct SoundFiles {
-    ac_fault: String,
-    battery_low: String,
-    critical: String,
+struct ProtectionWindowSettings {
+    enabled: bool,
+    weekday: u64,
+    start_hour: u64,
+    start_minute: u64,
+    duration_minutes: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct SoundInfo {
-    name: String,
-    path: String,
-    custom: bool,
-    location: Option<String>,
+impl Default for ProtectionWindowSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            weekday: 5,
+            start_hour: 9,
+            start_minute: 0,
+            duration_minutes: 120,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct SoundConfigPatch {
-    repeat_config: Option<RepeatConfigPatch>,
-    repeat_delay: Option<u64>,
-    custom_sounds_path: Option<Option<String>>,
+impl ProtectionWindowSettings {
+    // End of the occurrence that contains `now`, if any.
+    fn active_until(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        if !self.enabled {
+            return None;
+        }
+        let start_time =
+            NaiveTime::from_hms_opt(self.start_hour as u32, self.start_minute as u32, 0)?;
+        let days_back =
+            (i64::from(now.weekday().num_days_from_monday()) - self.weekday as i64).rem_euclid(7);
+        let mut start = (now.date() - ChronoDuration::days(days_back)).and_time(start_time);
+        if start > now {
+            start -= ChronoDuration::days(7);
+        }
+        let end = start + ChronoDuration::minutes(self.duration_minutes as i64);
+        (now < end).then_some(end)
+    }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+// Alerts that do not depend on hearing the alarm: taskbar/tray flashing, an
+// optional full-screen flash and an alert window that waits for acknowledgment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct RepeatConfigPatch {
-    ac_fault: Option<u64>,
-    battery_low: Option<u64>,
-    critical: Option<u64>,
-    default: Option<u64>,
+struct VisualAlertSettings {
+    enabled: bool,
+    flash_count: u64,
+    flash_screen: bool,
+    persist_until_ack: bool,
 }
 
-#[derive(Debug, Clone, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct ShutdownSimulationResult {
-    scheduled: bool,
-    cancelled: bool,
-    minutes: u64,
-    shutdown_time: String,
+impl Default for VisualAlertSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            flash_count: 5,
+            flash_screen: false,
+            persist_until_ack: false,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize)]
+// Generators can bring AC back with unstable frequency; in strict mode AC only
+// counts as restored after enough consecutive samples inside these bands. A
+// band left unset follows the supply (see `strict_restore_bands`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct ShutdownScheduledPayload {
-    minutes: u64,
-    shutdown_time: String,
-}
-
-#[derive(Debug, Clone)]
-enum DecodedPacket {
-    Status(UpsData),
-    Version(String),
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum AlertKind {
-    AcFault,
-    BatteryLow,
-    BatteryCritical,
+struct StrictRestoreSettings {
+    enabled: bool,
+    min_frequency: Option<f64>,
+    max_frequency: Option<f64>,
+    min_voltage: Option<f64>,
+    max_voltage: Option<f64>,
+    required_samples: u64,
 }
 
-impl AlertKind {
-    fn from_str(value: &str) -> Option<Self> {
-        match value {
-            "acFault" | "ac_fault" | "ac-fault" | "ac" => Some(Self::AcFault),
-            "batteryLow" | "battery_low" | "battery-low" | "low" => Some(Self::BatteryLow),
-            "batteryCritical" | "critical" | "battery_critical" | "battery-critical" => {
-                Some(Self::BatteryCritical)
-            }
-            _ => None,
+impl Default for StrictRestoreSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_frequency: None,
+            max_frequency: None,
+            min_voltage: None,
+            max_voltage: None,
+            required_samples: 10,
         }
     }
+}
 
-    fn event_name(self) -> &'static str {
-        match self {
-            Self::AcFault => "Fallo de energia",
-            Self::BatteryLow => "Bateria baja",
-            Self::BatteryCritical => "Bateria critica",
+impl StrictRestoreSettings {
+    // An incomplete or inverted band goes back to following the supply.
+    fn normalize(&mut self) {
+        self.required_samples = clamp_u64(self.required_samples, 1, 600, 10);
+        if !is_valid_optional_band(self.min_frequency, self.max_frequency) {
+            self.min_frequency = None;
+            self.max_frequency = None;
         }
-    }
-
-    fn alert_type(self) -> &'static str {
-        match self {
-            Self::AcFault => "warning",
-            Self::BatteryLow => "battery",
-            Self::BatteryCritical => "critical",
+        if !is_valid_optional_band(self.min_voltage, self.max_voltage) {
+            self.min_voltage = None;
+            self.max_voltage = None;
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct UrgentAlertPayload {
-    title: String,
-    message: String,
-    alert_type: String,
-    created_at: String,
+fn is_valid_optional_band(min: Option<f64>, max: Option<f64>) -> bool {
+    match (min, max) {
+        (None, None) => true,
+        (Some(min), Some(max)) => is_valid_band(min, max),
+        _ => false,
+    }
 }
 
-struct AppState {
-    config_path: PathBuf,
-    events_path: PathBuf,
-    data_path: PathBuf,
-    sounds_path: PathBuf,
-    settings: Mutex<AppSettings>,
-    events: Mutex<Vec<HistoryEvent>>,
-    data_history: Mutex<Vec<DataHistoryEntry>>,
-    last_status: Mutex<Option<UpsData>>,
-    device_info: Mutex<Option<UpsInfo>>,
-    is_connected: Mutex<bool>,
-    has_emitted_disconnected: Mutex<bool>,
-    is_on_battery: Mutex<bool>,
-    was_battery_low: Mutex<bool>,
-    was_battery_critical: Mutex<bool>,
-    battery_start_ms: Mutex<Option<u64>>,
-    last_data_save_ms: Mutex<u64>,
-    scheduled_shutdown_at_ms: Mutex<Option<u64>>,
-    scheduled_shutdown_reason: Mutex<Option<String>>,
-    last_error: Mutex<Option<String>>,
-    stop_monitor: AtomicBool,
-    allow_process_exit: AtomicBool,
-    pending_show_main_window: AtomicBool,
-    sound_generation: AtomicU64,
-    last_forced_popup_ms: AtomicU64,
+// The (voltage, frequency) bands AC must stay in. Unset bands are centred on
+// the standard mains values nearest to `reference`, the last reading on AC.
+fn strict_restore_bands(
+    strict: &StrictRestoreSettings,
+    reference: (f64, f64),
+) -> ((f64, f64), (f64, f64)) {
+    let (voltage, frequency) = reference;
+    let nominal_voltage = NOMINAL_MAINS_VOLTAGES
+        .iter()
+        .copied()
+        .min_by(|a, b| (a - voltage).abs().total_cmp(&(b - voltage).abs()))
+        .unwrap_or(120.0);
+    let nominal_frequency = if frequency < 55.0 { 50.0 } else { 60.0 };
+    let voltage_band = strict.min_voltage.zip(strict.max_voltage).unwrap_or((
+        nominal_voltage * (1.0 - STRICT_RESTORE_VOLTAGE_TOLERANCE),
+        nominal_voltage * (1.0 + STRICT_RESTORE_VOLTAGE_TOLERANCE),
+    ));
+    let frequency_band = strict.min_frequency.zip(strict.max_frequency).unwrap_or((
+        nominal_frequency - STRICT_RESTORE_FREQUENCY_TOLERANCE,
+        nominal_frequency + STRICT_RESTORE_FREQUENCY_TOLERANCE,
+    ));
+    (voltage_band, frequency_band)
 }
 
-type SharedState = Arc<AppState>;
-
-impl AppState {
-    fn new(app: &AppHandle) -> Self {
-        let app_data_dir = app.path().app_data_dir().unwrap_or_else(|_| {
-            std::env::current_dir()
-                .unwrap_or_else(|_| PathBuf::from("."))
-                .join(".ups-monitor-pro")
-        });
-
-        let history_dir = app_data_dir.join("history");
-        let sounds_path = app_data_dir.join("sounds");
-        let _ = fs::create_dir_all(&history_dir);
-        let _ = fs::create_dir_all(&sounds_path);
-
-        let config_path = app_data_dir.join("config.json");
-        let events_path = history_dir.join("events.json");
-        let data_path = history_dir.join("data.json");
-
-        let settings: AppSettings = read_json_or_default::<AppSettings>(&config_path).normalize();
-        write_json_pretty(&config_path, &settings);
-
-        let events: Vec<HistoryEvent> = read_json_or_default(&events_path);
-        let data_history: Vec<DataHistoryEntry> = read_json_or_default(&data_path);
+// Monitor loop timing. The defaults were tuned against the RICHCOMM unit; slow
+// devices may need longer reads, fast ones a lower sleep floor.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HidTuning {
+    // Read timeout while connected but before the first status frame.
+    connect_read_timeout_ms: u64,
+    // Read timeout for the first frame after opening the device.
+    idle_read_timeout_ms: u64,
+    // Longest read once status frames are flowing; the polling interval caps it too.
+    #[serde(default)]
+    status_read_timeout_ms: u64,
+    sleep_floor_ms: u64,
+    sleep_ceil_ms: u64,
+    // Pause between device scans while disconnected.
+    #[serde(default)]
+    disconnected_sleep_ms: u64,
+}
 
+impl Default for HidTuning {
+    fn default() -> Self {
         Self {
-            config_path,
-            events_path,
-            data_path,
-            sounds_path,
-            settings: Mutex::new(settings),
-            events: Mutex::new(events),
-            data_history: Mutex::new(data_history),
-            last_status: Mutex::new(None),
-            device_info: Mutex::new(None),
-            is_connected: Mutex::new(false),
-            has_emitted_disconnected: Mutex::new(false),
-            is_on_battery: Mutex::new(false),
-            was_battery_low: Mutex::new(false),
-            was_battery_critical: Mutex::new(false),
-            battery_start_ms: Mutex::new(None),
-            last_data_save_ms: Mutex::new(0),
-            scheduled_shutdown_at_ms: Mutex::new(None),
-            scheduled_shutdown_reason: Mutex::new(None),
-            last_error: Mutex::new(None),
-            stop_monitor: AtomicBool::new(false),
-            allow_process_exit: AtomicBool::new(false),
-            pending_show_main_window: AtomicBool::new(false),
-            sound_generation: AtomicU64::new(0),
-            last_forced_popup_ms: AtomicU64::new(0),
+            connect_read_timeout_ms: 120,
+            idle_read_timeout_ms: 100,
+            status_read_timeout_ms: 600,
+            sleep_floor_ms: 30,
+            sleep_ceil_ms: 280,
+            disconnected_sleep_ms: 150,
         }
     }
+}
 
-    fn save_settings(&self) {
-        let settings = lock(&self.settings).clone();
-        write_json_pretty(&self.config_path, &settings);
+impl HidTuning {
+    fn normalize(&mut self) {
+        let defaults = HidTuning::default();
+        self.connect_read_timeout_ms = clamp_u64(
+            self.connect_read_timeout_ms,
+            20,
+            2000,
+            defaults.connect_read_timeout_ms,
+        );
+        self.idle_read_timeout_ms = clamp_u64(
+            self.idle_read_timeout_ms,
+            20,
+            2000,
+            defaults.idle_read_timeout_ms,
+        );
+        self.status_read_timeout_ms = clamp_u64(
+            self.status_read_timeout_ms,
+            20,
+            2000,
+            defaults.status_read_timeout_ms,
+        );
+        self.sleep_floor_ms = self.sleep_floor_ms.min(1000);
+        self.sleep_ceil_ms = self.sleep_ceil_ms.clamp(self.sleep_floor_ms, 5000);
+        self.disconnected_sleep_ms = clamp_u64(
+            self.disconnected_sleep_ms,
+            20,
+            5000,
+            defaults.disconnected_sleep_ms,
+        );
     }
 
-    fn save_events(&self) {
-        let events = lock(&self.events).clone();
-        write_json_pretty(&self.events_path, &events);
+    fn read_timeout_ms(
+        &self,
+        polling_interval_ms: u64,
+        has_device: bool,
+        has_recent_status: bool,
+    ) -> u64 {
+        match (has_device, has_recent_status) {
+            (true, true) => polling_interval_ms.min(self.status_read_timeout_ms),
+            (true, false) => self.connect_read_timeout_ms,
+            (false, _) => self.idle_read_timeout_ms,
+        }
     }
 
-    fn save_data_history(&self) {
-        let data = lock(&self.data_history).clone();
-        write_json_pretty(&self.data_path, &data);
+    // Sleeps out whatever of the polling interval the read did not use.
+    fn sleep_ms(
+        &self,
+        polling_interval_ms: u64,
+        read_timeout_ms: u64,
+        is_connected: bool,
+        has_recent_status: bool,
+    ) -> u64 {
+        match (is_connected, has_recent_status) {
+            (true, true) => polling_interval_ms
+                .saturating_sub(read_timeout_ms)
+                .clamp(self.sleep_floor_ms, self.sleep_ceil_ms),
+            (true, false) => self.sleep_floor_ms,
+            (false, _) => self.disconnected_sleep_ms,
+        }
     }
+}
 
-    fn log_event(&self, classification: &str, name: &str, remarks: &str) {
-        if lock(&self.settings).monitor_only_mode {
-            return;
-        }
+// Plausible ranges for status frames. Anything outside is treated as a
+// misparsed or corrupted reading rather than recorded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReadingLimits {
+    // Applies to input, fault and output voltage.
+    voltage_min: f64,
+    voltage_max: f64,
+    load_max: u64,
+    frequency_min: f64,
+    frequency_max: f64,
+    temperature_min: f64,
+    temperature_max: f64,
+    battery_voltage_max: f64,
+}
 
-        let mut events = lock(&self.events);
-        events.insert(
-            0,
-            HistoryEvent {
-                id: now_millis(),
-                time: now_iso(),
-                classification: classification.to_string(),
-                name: name.to_string(),
-                remarks: remarks.to_string(),
-            },
-        );
-        if events.len() > MAX_EVENTS {
-            events.truncate(MAX_EVENTS);
+impl Default for ReadingLimits {
+    fn default() -> Self {
+        Self {
+            voltage_min: 0.0,
+            voltage_max: 300.0,
+            load_max: 150,
+            frequency_min: 0.0,
+            frequency_max: 70.0,
+            temperature_min: -20.0,
+            temperature_max: 80.0,
+            battery_voltage_max: 150.0,
         }
-        drop(events);
-        self.save_events();
     }
+}
 
-    fn log_data_point_if_needed(&self, status: &UpsData) {
-        let settings = lock(&self.settings).clone();
-        if !settings.save_history {
-            return;
+impl ReadingLimits {
+    fn normalize(&mut self) {
+        let defaults = ReadingLimits::default();
+        for (value, ceiling) in [
+            (&mut self.voltage_min, VOLTAGE_READING_CEIL),
+            (&mut self.voltage_max, VOLTAGE_READING_CEIL),
+            (&mut self.frequency_min, FREQUENCY_READING_CEIL),
+            (&mut self.frequency_max, FREQUENCY_READING_CEIL),
+        ] {
+            *value = value.clamp(0.0, ceiling);
         }
-
-        let now = now_millis();
-        let mut last_save = lock(&self.last_data_save_ms);
-        if now.saturating_sub(*last_save) < settings.history_interval.saturating_mul(1000) {
-            return;
+        let ranges = [
+            (
+                &mut self.voltage_min,
+                &mut self.voltage_max,
+                defaults.voltage_min,
+                defaults.voltage_max,
+            ),
+            (
+                &mut self.frequency_min,
+                &mut self.frequency_max,
+                defaults.frequency_min,
+                defaults.frequency_max,
+            ),
+            (
+                &mut self.temperature_min,
+                &mut self.temperature_max,
+                defaults.temperature_min,
+                defaults.temperature_max,
+            ),
+        ];
+        for (min, max, default_min, default_max) in ranges {
+            if !min.is_finite() || !max.is_finite() || *min >= *max {
+                *min = default_min;
+                *max = default_max;
+            }
         }
-        *last_save = now;
-        drop(last_save);
-
-        let mut data = lock(&self.data_history);
-        data.insert(
-            0,
-            DataHistoryEntry {
-                id: now,
-                time: now_iso(),
-                input_voltage: status.input_voltage,
-                output_voltage: status.output_voltage,
-                frequency: status.frequency,
-                load_percent: status.load_percent,
-                battery_voltage: status.battery_voltage,
-                battery_percent: status.battery_percent,
-                temperature: status.temperature,
-            },
-        );
-
-        if data.len() > MAX_DATA_POINTS {
-            data.truncate(MAX_DATA_POINTS);
+        if !self.battery_voltage_max.is_finite() || self.battery_voltage_max <= 0.0 {
+            self.battery_voltage_max = defaults.battery_voltage_max;
         }
-
-        drop(data);
-        self.save_data_history();
+        self.load_max = clamp_u64(self.load_max, 100, 1000, defaults.load_max);
     }
 }
 
-fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
-    mutex.lock().unwrap_or_else(|e| e.into_inner())
+// Some units stop reporting unless they are queried now and then. The
+// keepalive write is only there to keep them talking; its reply, if any, is
+// decoded like any other frame.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KeepaliveSettings {
+    enabled: bool,
+    // Megatec command without the trailing CR, at most 7 ASCII characters.
+    command: String,
+    interval_seconds: u64,
+    // Consecutive failed writes before the device is treated as gone.
+    max_failures: u64,
 }
 
-fn clamp_u64(value: u64, min: u64, max: u64, fallback: u64) -> u64 {
-    if value == 0 {
-        return fallback;
+impl Default for KeepaliveSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            command: "Q1".to_string(),
+            interval_seconds: 30,
+            max_failures: 3,
+        }
     }
-    value.max(min).min(max)
 }
 
-fn now_millis() -> u64 {
-    std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map(|d| d.as_millis() as u64)
-        .unwrap_or(0)
+impl KeepaliveSettings {
+    fn normalize(&mut self) {
+        let defaults = KeepaliveSettings::default();
+        let command = self.command.trim();
+        self.command = if !command.is_empty()
+            && command.len() <= 7
+            && command
+                .bytes()
+                .all(|byte| byte.is_ascii_graphic() || byte == b' ')
+        {
+            command.to_string()
+        } else {
+            defaults.command
+        };
+        self.interval_seconds = clamp_u64(self.interval_seconds, 5, 600, defaults.interval_seconds);
+        self.max_failures = clamp_u64(self.max_failures, 1, 20, defaults.max_failures);
+    }
+
+    fn frame(&self) -> Vec<u8> {
+        let mut frame = self.command.as_bytes().to_vec();
+        frame.push(b'\r');
+        frame
+    }
 }
 
-fn now_iso() -> String {
-    Utc::now().to_rfc3339()
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AutoReportSettings {
+    schedule: String,
+    destination: Option<String>,
 }
 
-fn write_json_pretty<T: Serialize>(path: &Path, value: &T) {
-    if let Ok(text) = serde_json::to_string_pretty(value) {
-        let _ = fs::write(path, text);
+impl Default for AutoReportSettings {
+    fn default() -> Self {
+        Self {
+            schedule: "off".to_string(),
+            destination: None,
+        }
     }
 }
 
-fn read_json_or_default<T>(path: &Path) -> T
-where
-    T: DeserializeOwned + Default,
-{
-    match fs::read_to_string(path) {
-        Ok(content) => serde_json::from_str::<T>(&content).unwrap_or_default(),
-        Err(_) => T::default(),
-    }
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RemoteApiSettings {
+    enabled: bool,
+    bind_address: String,
+    port: u16,
+    token: Option<String>,
 }
 
-fn emit_if_possible<T: Serialize + Clone>(app: &AppHandle, event: &str, payload: T) {
-    let _ = app.emit(event, payload);
+impl Default for RemoteApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "127.0.0.1".to_string(),
+            port: 8765,
+            token: None,
+        }
+    }
 }
 
-fn mark_disconnected(app: &AppHandle, state: &SharedState) {
-    let mut connected = lock(&state.is_connected);
-    let was_connected = *connected;
-    *connected = false;
-    drop(connected);
+// What monitor-only mode still records. Both stay subject to `save_history`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MonitorOnlyLogging {
+    events: bool,
+    data_points: bool,
+}
 
-    let mut has_emitted_disconnected = lock(&state.has_emitted_disconnected);
-    if !was_connected && *has_emitted_disconnected {
-        return;
+impl Default for MonitorOnlyLogging {
+    fn default() -> Self {
+        Self {
+            events: true,
+            data_points: true,
+        }
     }
-    *has_emitted_disconnected = true;
-    drop(has_emitted_disconnected);
+}
 
-    *lock(&state.is_on_battery) = false;
-    *lock(&state.was_battery_low) = false;
-    *lock(&state.was_battery_critical) = false;
-    *lock(&state.battery_start_ms) = None;
-    *lock(&state.last_status) = None;
-    let _ = cancel_scheduled_shutdown(state, app, true);
-    state.sound_generation.fetch_add(1, Ordering::Relaxed);
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NutServerSettings {
+    enabled: bool,
+    bind_address: String,
+    port: u16,
+    ups_name: String,
+}
 
-    if was_connected {
-        state.log_event("Critical Event", "UPS disconnected", "UPS disconnected");
+impl Default for NutServerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "127.0.0.1".to_string(),
+            port: 3493,
+            ups_name: "ups".to_string(),
+        }
     }
-    emit_if_possible(app, "ups-disconnected", ());
 }
 
-fn mark_connected(app: &AppHandle, state: &SharedState) {
-    let mut connected = lock(&state.is_connected);
-    if *connected {
-        return;
-    }
-
-    *connected = true;
-    drop(connected);
-    *lock(&state.has_emitted_disconnected) = false;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AppSettings {
+    start_with_windows: bool,
+    start_minimized: bool,
+    // Keeps the main window above others, e.g. to watch a shutdown countdown.
+    #[serde(default)]
+    always_on_top: bool,
+    #[serde(default)]
+    monitor_only_mode: bool,
+    #[serde(default)]
+    monitor_only_logging: MonitorOnlyLogging,
+    polling_interval: u64,
+    enable_notifications: bool,
+    alerts: AlertSettings,
+    // Cap applied to every alert's soundRepeats.
+    #[serde(default = "default_max_sound_repeats")]
+    max_sound_repeats: u64,
+    // Without an audio device the sound channel raises the alert window instead.
+    #[serde(default = "default_popup_when_audio_unavailable")]
+    popup_when_audio_unavailable: bool,
+    #[serde(rename = "shutdownPC", alias = "shutdownPc")]
+    shutdown_pc: ShutdownPCSettings,
+    ups_control: UpsControlSettings,
+    save_history: bool,
+    // Opt-in: builds without gzip support cannot read a compressed data.json
+    // and would start over with an empty history after a downgrade.
+    #[serde(default)]
+    compress_data_history: bool,
+    // Reloads config.json when another process (editor, sync tool,
+    // configuration management) changes it.
+    #[serde(default)]
+    watch_config_file: bool,
+    history_interval: u64,
+    #[serde(default = "default_on_battery_history_interval")]
+    on_battery_history_interval: u64,
+    low_battery_threshold: u64,
+    critical_battery_threshold: u64,
+    #[serde(default)]
+    recharge_check: RechargeCheckSettings,
+    #[serde(default)]
+    strict_restore: StrictRestoreSettings,
+    #[serde(default)]
+    protection_window: ProtectionWindowSettings,
+    #[serde(default)]
+    temperature_trend: TemperatureTrendSettings,
+    #[serde(default)]
+    standby_charge: StandbyChargeSettings,
+    #[serde(default)]
+    change_logging: ChangeLoggingSettings,
+    // Turns the screen off when AC fails, independent of the shutdown action.
+    #[serde(default)]
+    display_off_on_ac_fault: bool,
+    #[serde(default)]
+    alert_in_focus_mode: bool,
+    // The always-on-top alert window (or its PowerShell fallback). Turning it
+    // off keeps the in-app urgent alert.
+    #[serde(default = "default_force_system_popup")]
+    force_system_popup: bool,
+    // Below the 10 s minimum escalation delay by default, so only bursts of
+    // the same alert are held back.
+    #[serde(default = "default_popup_min_interval_seconds")]
+    popup_min_interval_seconds: u64,
+    #[serde(default = "default_popup_timeout_seconds")]
+    popup_timeout_seconds: u64,
+    // Monitor for the alert window: "primary", "active" (the one with the
+    // foreground window) or a monitor name or zero-based index.
+    #[serde(default = "default_alert_display")]
+    alert_display: String,
+    #[serde(default)]
+    visual_alerts: VisualAlertSettings,
+    #[serde(default = "default_notify_on_shutdown_cancel")]
+    notify_on_shutdown_cancel: bool,
+    #[serde(default)]
+    custom_name: Option<String>,
+    #[serde(default)]
+    minimum_notification_severity: Severity,
+    #[serde(default = "default_show_predicted_runtime")]
+    show_predicted_runtime: bool,
+    #[serde(default)]
+    runtime_source: RuntimeSource,
+    #[serde(default)]
+    auto_report: AutoReportSettings,
+    #[serde(default)]
+    remote_api: RemoteApiSettings,
+    #[serde(default)]
+    nut_server: NutServerSettings,
+    // Single folder from older configs; normalize folds it into the list below.
+    #[serde(default, skip_serializing)]
+    custom_sounds_path: Option<String>,
+    // Searched in order before the app sounds folder; the first match wins.
+    #[serde(default)]
+    custom_sounds_paths: Vec<String>,
+    #[serde(default = "default_reconnect_grace_seconds")]
+    reconnect_grace_seconds: u64,
+    // Shutdown scheduling is deferred (not alerts or logging) right after monitoring starts.
+    #[serde(default)]
+    startup_grace_seconds: u64,
+    #[serde(default = "default_report_id_prefixed")]
+    report_id_prefixed: bool,
+    #[serde(default = "default_hid_read_buffer_size")]
+    hid_read_buffer_size: u64,
+    #[serde(default)]
+    hid_tuning: HidTuning,
+    #[serde(default)]
+    keepalive: KeepaliveSettings,
+    #[serde(default)]
+    reading_limits: ReadingLimits,
+    // Multiplies the reported battery voltage to get the 24V pack voltage the
+    // percent curve expects: 1.0 for pack readings (RICHCOMM and most Megatec
+    // units), 12.0 for per-cell readings (~2.1V), 0.1 for tenths (e.g. 264).
+    #[serde(default = "default_battery_voltage_scale")]
+    battery_voltage_scale: f64,
+    // Status bits are found as the last token with exactly this many 0/1
+    // characters; `status_bits_field` pins them to a fixed token index for
+    // devices where that guess fails.
+    #[serde(default = "default_status_bits_count")]
+    status_bits_count: u64,
+    #[serde(default)]
+    status_bits_field: Option<u64>,
+    #[serde(default = "default_log_level")]
+    log_level: String,
+    // Keeps the last raw frames for get_raw_packet_log and support bundles.
+    #[serde(default = "default_debug_capture")]
+    debug_capture: bool,
+    // Repeats of the same event within this window update one row; 0 disables.
+    #[serde(default = "default_duplicate_event_window_seconds")]
+    duplicate_event_window_seconds: u64,
+    #[serde(default)]
+    settings_revision: u64,
+}
 
-    state.log_event("General Event", "UPS connected", "UPS connected");
-    emit_if_possible(app, "ups-connected", ());
+fn default_duplicate_event_window_seconds() -> u64 {
+    30
 }
 
-fn emit_error_once(app: &AppHandle, state: &SharedState, message: String) {
-    let mut last_error = lock(&state.last_error);
-    if last_error.as_ref() == Some(&message) {
-        return;
-    }
-    *last_error = Some(message.clone());
-    drop(last_error);
-    emit_if_possible(app, "ups-error", message);
+fn default_log_level() -> String {
+    "info".to_string()
 }
 
-fn clear_last_error(state: &SharedState) {
-    *lock(&state.last_error) = None;
+fn default_debug_capture() -> bool {
+    true
 }
 
-fn alert_config_for_kind(settings: &AppSettings, kind: AlertKind) -> AlertConfig {
-    match kind {
-        AlertKind::AcFault => settings.alerts.ac_fault.clone(),
-        AlertKind::BatteryLow => settings.alerts.battery_low.clone(),
-        AlertKind::BatteryCritical => settings.alerts.battery_critical.clone(),
-    }
+fn default_report_id_prefixed() -> bool {
+    true
 }
 
-fn alert_sound_file_name(kind: AlertKind) -> &'static str {
-    match kind {
-        AlertKind::AcFault => "alert-ac-fault.wav",
-        AlertKind::BatteryLow => "alert-battery-low.wav",
-        AlertKind::BatteryCritical => "alert-critical.wav",
-    }
+fn default_show_predicted_runtime() -> bool {
+    true
 }
 
-fn resolve_sound_path(state: &SharedState, settings: &AppSettings, kind: AlertKind) -> Option<PathBuf> {
-    let file_name = alert_sound_file_name(kind);
+fn default_notify_on_shutdown_cancel() -> bool {
+    true
+}
 
-    if let Some(custom_path) = settings.custom_sounds_path.as_ref() {
-        let custom_file = PathBuf::from(custom_path).join(file_name);
-        if custom_file.exists() {
-            return Some(custom_file);
-        }
-    }
+fn default_force_system_popup() -> bool {
+    true
+}
 
-    let app_file = state.sounds_path.join(file_name);
-    if app_file.exists() {
-        return Some(app_file);
-    }
+fn default_popup_when_audio_unavailable() -> bool {
+    true
+}
 
-    None
+fn default_popup_min_interval_seconds() -> u64 {
+    6
 }
 
-fn play_fallback_beep() {
-    #[cfg(target_os = "windows")]
-    {
-        let _ = Command::new("powershell")
-            .args([
-                "-NoProfile",
-                "-WindowStyle",
-                "Hidden",
-                "-Command",
-                "[console]::beep(950,220)",
-            ])
-            .spawn();
-    }
+fn default_popup_timeout_seconds() -> u64 {
+    12
 }
 
-fn play_sound_with_generation(state: SharedState, sound_path: Option<PathBuf>, repeats: u64) -> bool {
-    let generation = state.sound_generation.fetch_add(1, Ordering::Relaxed) + 1;
-    let loop_count = repeats.max(1).min(30);
+fn default_alert_display() -> String {
+    "primary".to_string()
+}
 
-    tauri::async_runtime::spawn_blocking(move || {
-        let stream = OutputStream::try_default().ok();
+fn default_hid_read_buffer_size() -> u64 {
+    64
+}
 
-        for _ in 0..loop_count {
-            if state.sound_generation.load(Ordering::Relaxed) != generation {
-                return;
-            }
+fn default_status_bits_count() -> u64 {
+    8
+}
 
-            let mut played_from_file = false;
-            if let (Some(path), Some((_, stream_handle))) = (sound_path.as_ref(), stream.as_ref()) {
-                if let Ok(file) = fs::File::open(path) {
-                    if let Ok(source) = Decoder::new(BufReader::new(file)) {
-                        if let Ok(sink) = Sink::try_new(stream_handle) {
-                            sink.append(source);
-                            played_from_file = true;
-                            while !sink.empty() {
-                                if state.sound_generation.load(Ordering::Relaxed) != generation {
-                                    sink.stop();
-                                    return;
-                                }
-                                thread::sleep(Duration::from_millis(70));
-                            }
-                        }
-                    }
-                }
-            }
+fn default_battery_voltage_scale() -> f64 {
+    1.0
+}
 
-            if !played_from_file {
-                play_fallback_beep();
-                thread::sleep(Duration::from_millis(260));
-            }
+fn default_reconnect_grace_seconds() -> u64 {
+    10
+}
 
-            if state.sound_generation.load(Ordering::Relaxed) != generation {
-                return;
-            }
-            thread::sleep(Duration::from_millis(140));
-        }
-    });
+fn default_on_battery_history_interval() -> u64 {
+    15
+}
 
-    true
+fn default_max_sound_repeats() -> u64 {
+    30
 }
 
-fn notify_windows(app: &AppHandle, title: &str, message: &str) -> bool {
-    match app
-        .notification()
-        .builder()
-        .title(title)
-        .body(message)
-        .show()
-    {
-        Ok(_) => true,
-        Err(error) => {
-            eprintln!("notification error: {}", error);
-            false
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            start_with_windows: false,
+            start_minimized: false,
+            always_on_top: false,
+            monitor_only_mode: false,
+            monitor_only_logging: MonitorOnlyLogging::default(),
+            polling_interval: 1000,
+            enable_notifications: true,
+            alerts: AlertSettings {
+                ac_fault: AlertConfig::new(&["notification", "popup", "sound"], 3),
+                battery_low: AlertConfig::new(&["notification", "popup", "sound"], 5),
+                battery_critical: AlertConfig::new(&["notification", "popup", "sound"], 10),
+                charge_failure: default_charge_failure_alert(),
+                ups_disconnected: default_connection_alert(),
+                ups_connected: default_connection_alert(),
+                temperature_rising: default_temperature_rising_alert(),
+                battery_not_holding: default_battery_not_holding_alert(),
+            },
+            max_sound_repeats: default_max_sound_repeats(),
+            popup_when_audio_unavailable: default_popup_when_audio_unavailable(),
+            shutdown_pc: ShutdownPCSettings {
+                on_ac_fault: ShutdownOnAcFault {
+                    enabled: true,
+                    delay_minutes: 18,
+                    sticky: false,
+                    sticky_recovery_seconds: default_sticky_recovery_seconds(),
+                    cap_delay_to_runtime: false,
+                    runtime_safety_margin_minutes: default_runtime_safety_margin_minutes(),
+                },
+                on_battery_low: ShutdownToggle { enabled: false },
+                on_battery_critical: ShutdownToggle { enabled: true },
+                auto_save_files: true,
+                shutdown_command: String::new(),
+                working_directory: None,
+                environment: BTreeMap::new(),
+                confirm_if_user_active: false,
+                user_active_within_seconds: default_user_active_within_seconds(),
+                confirmation_window_seconds: default_confirmation_window_seconds(),
+                manual_shutdown_min_battery_percent: 0,
+                action: "shutdown".to_string(),
+            },
+            ups_control: UpsControlSettings {
+                shutdown_ups_after_pc: true,
+                ups_shutdown_delay: 2,
+            },
+            save_history: true,
+            compress_data_history: false,
+            watch_config_file: false,
+            history_interval: 300,
+            on_battery_history_interval: default_on_battery_history_interval(),
+            low_battery_threshold: 20,
+            critical_battery_threshold: 10,
+            recharge_check: RechargeCheckSettings::default(),
+            temperature_trend: TemperatureTrendSettings::default(),
+            standby_charge: StandbyChargeSettings::default(),
+            change_logging: ChangeLoggingSettings::default(),
+            strict_restore: StrictRestoreSettings::default(),
+            protection_window: ProtectionWindowSettings::default(),
+            display_off_on_ac_fault: false,
+            alert_in_focus_mode: false,
+            force_system_popup: default_force_system_popup(),
+            popup_min_interval_seconds: default_popup_min_interval_seconds(),
+            popup_timeout_seconds: default_popup_timeout_seconds(),
+            alert_display: default_alert_display(),
+            visual_alerts: VisualAlertSettings::default(),
+            notify_on_shutdown_cancel: true,
+            custom_name: None,
+            minimum_notification_severity: Severity::Info,
+            show_predicted_runtime: true,
+            runtime_source: RuntimeSource::default(),
+            auto_report: AutoReportSettings::default(),
+            remote_api: RemoteApiSettings::default(),
+            nut_server: NutServerSettings::default(),
+            custom_sounds_path: None,
+            custom_sounds_paths: Vec::new(),
+            reconnect_grace_seconds: default_reconnect_grace_seconds(),
+            startup_grace_seconds: 0,
+            report_id_prefixed: default_report_id_prefixed(),
+            hid_read_buffer_size: default_hid_read_buffer_size(),
+            hid_tuning: HidTuning::default(),
+            keepalive: KeepaliveSettings::default(),
+            reading_limits: ReadingLimits::default(),
+            battery_voltage_scale: default_battery_voltage_scale(),
+            status_bits_count: default_status_bits_count(),
+            status_bits_field: None,
+            log_level: default_log_level(),
+            debug_capture: default_debug_capture(),
+            duplicate_event_window_seconds: default_duplicate_event_window_seconds(),
+            settings_revision: SETTINGS_REVISION,
         }
     }
 }
 
-fn escape_ps_single_quote(input: &str) -> String {
-    input.replace('\'', "''")
-}
+impl AppSettings {
+    fn apply_monitor_only_defaults(&mut self) {
+        self.enable_notifications = false;
+        for config in self.alerts.configs_mut() {
+            config.channels.clear();
+        }
 
-fn should_force_popup(app: &AppHandle, state: &SharedState) -> bool {
-    let now = now_millis();
-    let last = state.last_forced_popup_ms.load(Ordering::Relaxed);
-    if now.saturating_sub(last) < 6_000 {
-        return false;
+        self.shutdown_pc.on_ac_fault.enabled = false;
+        self.shutdown_pc.on_battery_low.enabled = false;
+        self.shutdown_pc.on_battery_critical.enabled = false;
+        self.shutdown_pc.auto_save_files = false;
+        self.shutdown_pc.shutdown_command.clear();
+        self.display_off_on_ac_fault = false;
+
+        self.ups_control.shutdown_ups_after_pc = false;
     }
 
-    if let Some(window) = app.get_webview_window("main") {
-        let is_visible = window.is_visible().unwrap_or(false);
-        let is_focused = window.is_focused().unwrap_or(false);
-        if is_visible && is_focused {
-            return false;
+    // Monitor-only suppression is applied here, at evaluation time, so the
+    // stored configuration survives toggling the mode on and off.
+    // History logging is not part of it; see `logs_events`/`logs_data_points`.
+    fn effective(&self) -> Self {
+        let mut settings = self.clone();
+        if settings.monitor_only_mode {
+            settings.apply_monitor_only_defaults();
         }
+        settings
     }
 
-    state.last_forced_popup_ms.store(now, Ordering::Relaxed);
-    true
-}
-
-fn force_windows_popup(title: &str, message: &str, alert_type: &str) {
-    #[cfg(target_os = "windows")]
-    {
-        let popup_flags = if alert_type == "critical" { "0x1010" } else { "0x1030" };
-        let safe_title = escape_ps_single_quote(title);
-        let safe_message = escape_ps_single_quote(message);
-        let script = format!(
-            "$w=New-Object -ComObject WScript.Shell; $null=$w.Popup('{}', 12, '{}', {})",
-            safe_message, safe_title, popup_flags
-        );
-
-        let _ = Command::new("powershell")
-            .args(["-NoProfile", "-WindowStyle", "Hidden", "-Command", &script])
-            .spawn();
+    fn logs_events(&self) -> bool {
+        self.save_history && (!self.monitor_only_mode || self.monitor_only_logging.events)
     }
-}
-
-fn emit_urgent_alert(app: &AppHandle, title: &str, message: &str, alert_type: &str) {
-    emit_if_possible(
-        app,
-        "urgent-alert",
-        UrgentAlertPayload {
-            title: title.to_string(),
-            message: message.to_string(),
-            alert_type: alert_type.to_string(),
-            created_at: now_iso(),
-        },
-    );
-}
 
-fn cancel_scheduled_shutdown(state: &SharedState, app: &AppHandle, emit_event: bool) -> bool {
-    let had_schedule = lock(&state.scheduled_shutdown_at_ms).take().is_some();
-    *lock(&state.scheduled_shutdown_reason) = None;
-    if had_schedule && emit_event {
-        emit_if_possible(app, "shutdown-cancelled", ());
+    fn logs_data_points(&self) -> bool {
+        self.save_history && (!self.monitor_only_mode || self.monitor_only_logging.data_points)
     }
-    had_schedule
-}
 
-fn schedule_shutdown_after_minutes(
-    state: &SharedState,
-    app: &AppHandle,
-    delay_minutes: u64,
-    reason: &str,
-) -> bool {
-    let safe_minutes = delay_minutes.max(1).min(120);
-    let target_ms = now_millis().saturating_add(safe_minutes * 60 * 1000);
-
-    let mut shutdown_guard = lock(&state.scheduled_shutdown_at_ms);
-    let should_replace = shutdown_guard
-        .map(|existing| target_ms < existing)
-        .unwrap_or(true);
-    if !should_replace {
-        return true;
+    fn looks_flattened_by_monitor_only(&self) -> bool {
+        self.monitor_only_mode
+            && !self.enable_notifications
+            && self
+                .alerts
+                .configs()
+                .iter()
+                .all(|config| config.channels.is_empty())
+            && !self.shutdown_pc.on_ac_fault.enabled
+            && !self.shutdown_pc.on_battery_low.enabled
+            && !self.shutdown_pc.on_battery_critical.enabled
+            && !self.ups_control.shutdown_ups_after_pc
     }
 
-    *shutdown_guard = Some(target_ms);
-    drop(shutdown_guard);
-
-    *lock(&state.scheduled_shutdown_reason) = Some(reason.to_string());
-    emit_if_possible(
-        app,
-        "shutdown-scheduled",
-        ShutdownScheduledPayload {
-            minutes: safe_minutes,
-            shutdown_time: (Utc::now() + ChronoDuration::minutes(safe_minutes as i64)).to_rfc3339(),
-        },
-    );
-    true
-}
-
-fn execute_shutdown_command(settings: &AppSettings) -> Result<(), String> {
-    let custom_command = settings.shutdown_pc.shutdown_command.trim();
-    if !custom_command.is_empty() {
-        Command::new("cmd")
-            .args(["/C", custom_command])
-            .spawn()
-            .map(|_| ())
-            .map_err(|err| format!("No se pudo ejecutar comando personalizado: {}", err))?;
-        return Ok(());
-    }
-
-    let action = settings.shutdown_pc.action.as_str();
-    if action == "sleep" {
-        Command::new("rundll32.exe")
-            .args(["powrprof.dll,SetSuspendState", "0,1,0"])
-            .spawn()
-            .map(|_| ())
-            .map_err(|err| format!("No se pudo ejecutar suspension: {}", err))?;
-        return Ok(());
-    }
-
-    Command::new("shutdown")
-        .args(["/s", "/t", "0", "/f"])
-        .spawn()
-        .map(|_| ())
-        .map_err(|err| format!("No se pudo ejecutar apagado: {}", err))
-}
+    // Upgrades configs written by older versions; returns notices for the user.
+    fn migrate(&mut self) -> Vec<String> {
+        let mut notices = Vec::new();
+        if self.settings_revision < 1 && self.monitor_only_mode {
+            // Monitor-only used to force history off. Keep it off, but say it can be enabled now.
+            notices.push(
+                "El modo solo monitor ya no desactiva el historial. El guardado sigue desactivado; puedes activarlo en Ajustes > Historial."
+                    .to_string(),
+            );
+        }
+        if self.settings_revision < 2 && self.looks_flattened_by_monitor_only() {
+            // The old monitor-only mode overwrote these; the user's values are gone,
+            // so bring back the defaults instead of leaving everything disabled.
+            let defaults = AppSettings::default();
+            self.enable_notifications = defaults.enable_notifications;
+            for (config, default) in self
+                .alerts
+                .configs_mut()
+                .into_iter()
+                .zip(defaults.alerts.configs())
+            {
+                config.channels = default.channels.clone();
+            }
+            self.shutdown_pc.on_ac_fault.enabled = defaults.shutdown_pc.on_ac_fault.enabled;
+            self.shutdown_pc.on_battery_low.enabled = defaults.shutdown_pc.on_battery_low.enabled;
+            self.shutdown_pc.on_battery_critical.enabled =
+                defaults.shutdown_pc.on_battery_critical.enabled;
+            self.shutdown_pc.auto_save_files = defaults.shutdown_pc.auto_save_files;
+            self.ups_control.shutdown_ups_after_pc = defaults.ups_control.shutdown_ups_after_pc;
+            notices.push(
+                "Se restauraron las alertas y el apagado predeterminados que el modo solo monitor habia desactivado. Revisalos antes de desactivar el modo."
+                    .to_string(),
+            );
+        }
+        self.settings_revision = SETTINGS_REVISION;
+        notices
+    }
 
-fn process_pending_shutdown(app: &AppHandle, state: &SharedState, settings: &AppSettings) {
-    if settings.monitor_only_mode {
-        return;
+    // Kept in the settings when missing: the command runs from the app
+    // directory until it is back (see `shutdown_working_directory`).
+    fn missing_shutdown_working_directory(&self) -> Option<&str> {
+        self.shutdown_pc
+            .working_directory
+            .as_deref()
+            .filter(|dir| !Path::new(dir).is_dir())
     }
 
-    let is_due = {
-        let shutdown_at = *lock(&state.scheduled_shutdown_at_ms);
-        shutdown_at
-            .map(|ts| now_millis() >= ts)
-            .unwrap_or(false)
-    };
+    fn normalize(mut self) -> Self {
+        self.polling_interval = clamp_u64(self.polling_interval, 500, 10_000, 1000);
+        self.history_interval = clamp_u64(self.history_interval, 60, 3600, 300);
+        self.on_battery_history_interval = clamp_u64(self.on_battery_history_interval, 5, 300, 15);
+        self.low_battery_threshold = clamp_u64(self.low_battery_threshold, 5, 50, 20);
+        self.critical_battery_threshold =
+            clamp_u64(self.critical_battery_threshold, 5, 30, 10).min(self.low_battery_threshold);
+
+        self.max_sound_repeats = clamp_u64(self.max_sound_repeats, 1, SOUND_REPEATS_LIMIT, 30);
+        let max_repeats = self.max_sound_repeats;
+        self.alerts.ac_fault.sound_repeats =
+            clamp_u64(self.alerts.ac_fault.sound_repeats, 1, max_repeats, 3).min(max_repeats);
+        self.alerts.battery_low.sound_repeats =
+            clamp_u64(self.alerts.battery_low.sound_repeats, 1, max_repeats, 5).min(max_repeats);
+        self.alerts.battery_critical.sound_repeats = clamp_u64(
+            self.alerts.battery_critical.sound_repeats,
+            1,
+            max_repeats,
+            10,
+        )
+        .min(max_repeats);
+        self.alerts.charge_failure.sound_repeats =
+            clamp_u64(self.alerts.charge_failure.sound_repeats, 1, max_repeats, 3).min(max_repeats);
+        self.alerts.ups_disconnected.sound_repeats = clamp_u64(
+            self.alerts.ups_disconnected.sound_repeats,
+            1,
+            max_repeats,
+            1,
+        );
+        self.alerts.ups_connected.sound_repeats =
+            clamp_u64(self.alerts.ups_connected.sound_repeats, 1, max_repeats, 1);
+        self.alerts.temperature_rising.sound_repeats = clamp_u64(
+            self.alerts.temperature_rising.sound_repeats,
+            1,
+            max_repeats,
+            1,
+        );
+        self.alerts.battery_not_holding.sound_repeats = clamp_u64(
+            self.alerts.battery_not_holding.sound_repeats,
+            1,
+            max_repeats,
+            1,
+        );
+        for config in self.alerts.configs_mut() {
+            config.repeat_delay_seconds = clamp_u64(config.repeat_delay_seconds, 10, 600, 60);
+        }
+        for config in self.alerts.configs_mut() {
+            config.normalize_channels();
+        }
+
+        self.recharge_check.min_rise_percent =
+            clamp_u64(self.recharge_check.min_rise_percent, 1, 50, 10);
+        self.recharge_check.window_minutes =
+            clamp_u64(self.recharge_check.window_minutes, 30, 2880, 240);
+        let trend_defaults = TemperatureTrendSettings::default();
+        if !self.temperature_trend.rise_per_hour.is_finite()
+            || self.temperature_trend.rise_per_hour <= 0.0
+        {
+            self.temperature_trend.rise_per_hour = trend_defaults.rise_per_hour;
+        }
+        if !self.temperature_trend.clear_per_hour.is_finite()
+            || self.temperature_trend.clear_per_hour < 0.0
+            || self.temperature_trend.clear_per_hour >= self.temperature_trend.rise_per_hour
+        {
+            self.temperature_trend.clear_per_hour = self.temperature_trend.rise_per_hour / 2.0;
+        }
+        self.temperature_trend.window_minutes = clamp_u64(
+            self.temperature_trend.window_minutes,
+            5,
+            120,
+            trend_defaults.window_minutes,
+        );
+        self.standby_charge.min_percent = clamp_u64(self.standby_charge.min_percent, 10, 95, 80);
+        self.standby_charge.duration_hours =
+            clamp_u64(self.standby_charge.duration_hours, 1, 72, 6);
+        self.protection_window.weekday = self.protection_window.weekday.min(6);
+        self.protection_window.start_hour = self.protection_window.start_hour.min(23);
+        self.protection_window.start_minute = self.protection_window.start_minute.min(59);
+        self.protection_window.duration_minutes =
+            clamp_u64(self.protection_window.duration_minutes, 5, 1440, 120);
+        let change_defaults = ChangeLoggingSettings::default();
+        let change = &mut self.change_logging;
+        for (delta, fallback) in [
+            (&mut change.voltage_delta, change_defaults.voltage_delta),
+            (&mut change.frequency_delta, change_defaults.frequency_delta),
+            (
+                &mut change.battery_voltage_delta,
+                change_defaults.battery_voltage_delta,
+            ),
+            (
+                &mut change.temperature_delta,
+                change_defaults.temperature_delta,
+            ),
+        ] {
+            if !delta.is_finite() || *delta <= 0.0 {
+                *delta = fallback;
+            }
+        }
+        change.load_delta = clamp_u64(change.load_delta, 1, 100, change_defaults.load_delta);
+        change.battery_percent_delta = clamp_u64(
+            change.battery_percent_delta,
+            1,
+            100,
+            change_defaults.battery_percent_delta,
+        );
+        change.min_interval_seconds = clamp_u64(change.min_interval_seconds, 1, 300, 5);
+        change.max_points_per_hour = clamp_u64(
+            change.max_points_per_hour,
+            1,
+            360,
+            change_defaults.max_points_per_hour,
+        );
+
+        self.shutdown_pc.on_ac_fault.delay_minutes =
+            clamp_u64(self.shutdown_pc.on_ac_fault.delay_minutes, 1, 60, 18);
+        self.shutdown_pc.on_ac_fault.sticky_recovery_seconds = clamp_u64(
+            self.shutdown_pc.on_ac_fault.sticky_recovery_seconds,
+            10,
+            1800,
+            120,
+        );
+        self.shutdown_pc.on_ac_fault.runtime_safety_margin_minutes = clamp_u64(
+            self.shutdown_pc.on_ac_fault.runtime_safety_margin_minutes,
+            1,
+            30,
+            3,
+        );
+        self.ups_control.ups_shutdown_delay =
+            clamp_u64(self.ups_control.ups_shutdown_delay, 1, 10, 2);
+        self.reconnect_grace_seconds = self.reconnect_grace_seconds.min(120);
+        self.startup_grace_seconds = self.startup_grace_seconds.min(600);
+        self.popup_min_interval_seconds = clamp_u64(self.popup_min_interval_seconds, 1, 300, 6);
+        self.popup_timeout_seconds = clamp_u64(self.popup_timeout_seconds, 3, 120, 12);
+        self.visual_alerts.flash_count = clamp_u64(self.visual_alerts.flash_count, 1, 50, 5);
+        self.hid_read_buffer_size = clamp_u64(self.hid_read_buffer_size, 8, 1024, 64);
+        self.hid_tuning.normalize();
+        self.keepalive.normalize();
+        self.reading_limits.normalize();
+        if let Some(legacy) = self.custom_sounds_path.take() {
+            self.custom_sounds_paths.insert(0, legacy);
+        }
+        self.custom_sounds_paths =
+            normalize_sound_paths(std::mem::take(&mut self.custom_sounds_paths));
+        self.duplicate_event_window_seconds = self.duplicate_event_window_seconds.min(3600);
+        self.status_bits_count = clamp_u64(self.status_bits_count, 1, 16, 8);
+        self.status_bits_field = self
+            .status_bits_field
+            .filter(|field| (FRAME_MIN_STATUS_FIELD..=FRAME_MAX_STATUS_FIELD).contains(field));
+        if !(self.battery_voltage_scale.is_finite() && self.battery_voltage_scale > 0.0) {
+            self.battery_voltage_scale = default_battery_voltage_scale();
+        }
+        self.log_level = self.log_level.trim().to_ascii_lowercase();
+        if parse_log_level(&self.log_level).is_none() {
+            self.log_level = default_log_level();
+        }
+
+        self.strict_restore.normalize();
+
+        if self.auto_report.schedule != "off" && self.auto_report.schedule != "monthly" {
+            self.auto_report.schedule = "off".to_string();
+        }
+
+        let shutdown = &mut self.shutdown_pc;
+        shutdown.working_directory = shutdown
+            .working_directory
+            .take()
+            .map(|dir| dir.trim().to_string())
+            .filter(|dir| !dir.is_empty());
+        shutdown.environment = std::mem::take(&mut shutdown.environment)
+            .into_iter()
+            .map(|(key, value)| (key.trim().to_string(), value))
+            .filter(|(key, _)| !key.is_empty() && !key.contains(['=', '\0']))
+            .collect();
+        if let Some(dir) = self.missing_shutdown_working_directory() {
+            log::warn!(
+                "shutdown working directory {} not found; the command will run from the app directory",
+                dir
+            );
+        }
+
+        self.alert_display = self.alert_display.trim().to_string();
+        if self.alert_display.is_empty() {
+            self.alert_display = default_alert_display();
+        }
+
+        self.custom_name = self
+            .custom_name
+            .take()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty());
+
+        if self.remote_api.port == 0 {
+            self.remote_api.port = RemoteApiSettings::default().port;
+        }
+        if self.remote_api.bind_address.trim().is_empty() {
+            self.remote_api.bind_address = RemoteApiSettings::default().bind_address;
+        }
+        if self
+            .remote_api
+            .token
+            .as_ref()
+            .map(|token| token.trim().is_empty())
+            .unwrap_or(false)
+        {
+            self.remote_api.token = None;
+        }
+
+        if self.nut_server.port == 0 {
+            self.nut_server.port = NutServerSettings::default().port;
+        }
+        if self.nut_server.bind_address.trim().is_empty() {
+            self.nut_server.bind_address = NutServerSettings::default().bind_address;
+        }
+        let ups_name = self.nut_server.ups_name.trim();
+        if ups_name.is_empty() || ups_name.contains(char::is_whitespace) {
+            self.nut_server.ups_name = NutServerSettings::default().ups_name;
+        }
+
+        self.shutdown_pc.user_active_within_seconds =
+            clamp_u64(self.shutdown_pc.user_active_within_seconds, 10, 1800, 120);
+        self.shutdown_pc.confirmation_window_seconds =
+            clamp_u64(self.shutdown_pc.confirmation_window_seconds, 10, 600, 60);
+        self.shutdown_pc.manual_shutdown_min_battery_percent = self
+            .shutdown_pc
+            .manual_shutdown_min_battery_percent
+            .min(100);
+
+        if !SHUTDOWN_ACTIONS.contains(&self.shutdown_pc.action.as_str()) {
+            self.shutdown_pc.action = "shutdown".to_string();
+        }
+
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpsStatusFlags {
+    raw: String,
+    utility_fail: bool,
+    battery_low: bool,
+    bypass_active: bool,
+    ups_failed: bool,
+    ups_is_standby: bool,
+    test_in_progress: bool,
+    shutdown_active: bool,
+    beeper_on: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpsData {
+    r#type: String,
+    input_voltage: f64,
+    fault_voltage: f64,
+    output_voltage: f64,
+    load_percent: u64,
+    frequency: f64,
+    battery_voltage: f64,
+    temperature: f64,
+    battery_percent: u64,
+    // Remaining minutes while on battery; on AC it is a prediction for a
+    // full outage starting now, and absent when predictions are disabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    estimated_runtime: Option<u64>,
+    // Minutes reported by the UPS itself in extended status frames.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    device_runtime: Option<u64>,
+    #[serde(default)]
+    runtime_predicted: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    projected_minutes_remaining: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    charging_projection_minutes: Option<u64>,
+    // Wall-clock times from the observed drain on battery; unlike the minutes
+    // above they never fall back to the formula.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    projected_empty_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    projected_shutdown_at: Option<String>,
+    #[serde(default)]
+    charging: bool,
+    #[serde(default)]
+    utility_state: UtilityState,
+    #[serde(default)]
+    charge_state: ChargeState,
+    timestamp: String,
+    status: UpsStatusFlags,
+    // Snapshot restored from the previous session; cleared by the first live packet.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    stale: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum UtilityState {
+    #[default]
+    Normal,
+    // On battery while the input still carries voltage: a brownout or sag.
+    Degraded,
+    Lost,
+}
+
+// Where `estimated_runtime` comes from. `device` trusts only the UPS figure;
+// `auto` uses it when the frame carries one and the formula otherwise.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum RuntimeSource {
+    Computed,
+    Device,
+    #[default]
+    Auto,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ChargeState {
+    #[default]
+    Unknown,
+    Charging,
+    Charged,
+    Discharging,
+}
+
+impl ChargeState {
+    // The protocol has no charging bit. Percent is derived from battery voltage,
+    // so its slope on AC stands in for the voltage trend.
+    fn derive(utility_fail: bool, battery_percent: u64, slope: Option<f64>) -> Self {
+        if utility_fail {
+            return Self::Discharging;
+        }
+        let rising = slope
+            .map(|value| value > PROJECTION_MIN_SLOPE_PER_MINUTE)
+            .unwrap_or(false);
+        if rising || battery_percent < CHARGED_MIN_PERCENT {
+            return Self::Charging;
+        }
+        match slope {
+            Some(value) if value.abs() <= PROJECTION_MIN_SLOPE_PER_MINUTE => Self::Charged,
+            None if battery_percent >= BATTERY_FULL_PERCENT => Self::Charged,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl UtilityState {
+    fn classify(on_battery: bool, input_voltage: f64) -> Self {
+        if !on_battery {
+            Self::Normal
+        } else if input_voltage < UTILITY_LOST_MAX_VOLTAGE {
+            Self::Lost
+        } else {
+            Self::Degraded
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Normal => "Utility normal",
+            Self::Degraded => "Utility degraded",
+            Self::Lost => "Utility lost",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpsInfo {
+    manufacturer: String,
+    product: String,
+    vendor_id: String,
+    product_id: String,
+    firmware: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    custom_name: Option<String>,
+    #[serde(default)]
+    display_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HistoryEvent {
+    // Equal to `seq` for events written since sequences exist.
+    id: u64,
+    // Monotonic insertion order; wall-clock `time` can jump back on DST or NTP.
+    #[serde(default)]
+    seq: u64,
+    time: String,
+    classification: String,
+    #[serde(default)]
+    severity: Option<Severity>,
+    // Stable identifier for filtering and tooling; `name` is display text.
+    #[serde(default)]
+    key: String,
+    name: String,
+    remarks: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    session_id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    app_version: Option<String>,
+    // Set by the user once an incident has been handled.
+    #[serde(default)]
+    resolved: bool,
+    // Repeats folded into this row by duplicate suppression; None means it happened once.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    repeat_count: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_seen: Option<String>,
+}
+
+impl HistoryEvent {
+    fn severity(&self) -> Severity {
+        self.severity
+            .unwrap_or_else(|| Severity::from_classification(&self.classification, &self.key))
+    }
+}
+
+// Event key -> display name. Older files only stored the name, so the same
+// table maps names back to keys when they are loaded.
+const EVENT_CATALOG: [(&str, &str); 37] = [
+    ("app_terminated", "Application terminated unexpectedly"),
+    ("app_started", "Application started"),
+    ("app_stopped", "Application stopped"),
+    ("shutdown_recovered", "Recovered from power-event shutdown"),
+    ("ups_connected", "UPS connected"),
+    ("ups_disconnected", "UPS disconnected"),
+    ("usb_reenumerated", "USB re-enumeration"),
+    ("ac_fault", "AC Fault"),
+    ("ac_restored", "Normal AC value"),
+    ("utility_changed", "Utility changed"),
+    ("outage_resumed", "Outage resumed"),
+    ("battery_low", "Battery Low"),
+    ("battery_critical", "Battery Critical"),
+    ("battery_full", "Battery full"),
+    ("charge_failure", "Charge failure"),
+    ("temperature_rising", "Temperature rising"),
+    ("temperature_stable", "Temperature stable"),
+    ("battery_not_holding", "Battery not holding charge"),
+    ("shutdown_executed", "Shutdown execution"),
+    ("shutdown_command", "Shutdown command"),
+    ("shutdown_delay_capped", "Shutdown delay capped to runtime"),
+    ("ups_shutdown_escalated", "Shutdown escalated by UPS"),
+    ("report_generated", "Power report generated"),
+    ("preflight_failed", "Pre-flight check failed"),
+    ("remote_shutdown_cancel", "Remote shutdown cancel"),
+    ("remote_alarm_silence", "Remote alarm silence"),
+    ("storage_recovered", "Storage recovered from backup"),
+    ("protection_suspended", "Protection suspended"),
+    ("protection_resumed", "Protection resumed"),
+    ("settings_reloaded", "Settings reloaded from disk"),
+    ("settings_reload_failed", "Settings reload failed"),
+    ("simulation_started", "Outage simulation started"),
+    ("simulation_ended", "Outage simulation ended"),
+    ("alert_display_missing", "Alert monitor missing"),
+    ("audio_unavailable", "Audio output unavailable"),
+    ("audio_restored", "Audio output restored"),
+    ("ups_permission_denied", "UPS access denied"),
+];
+
+// These bound outage and monitoring spans, so every occurrence must stay a row.
+const DUPLICATE_EXEMPT_EVENT_KEYS: [&str; 8] = [
+    "ac_fault",
+    "ac_restored",
+    "app_started",
+    "app_stopped",
+    "app_terminated",
+    "shutdown_executed",
+    "ups_connected",
+    "ups_disconnected",
+];
+
+// Keys logged at Severity::Critical; a legacy critical row of one of these
+// reads back as critical rather than as a warning.
+const CRITICAL_EVENT_KEYS: [&str; 4] = [
+    "battery_critical",
+    "shutdown_command",
+    "shutdown_executed",
+    "ups_shutdown_escalated",
+];
+
+// Folds a repeat of the newest unresolved event with the same key,
+// classification and remarks into it when it was last seen within `window_ms`.
+fn fold_repeated_event(
+    events: &mut [HistoryEvent],
+    key: &str,
+    classification: &str,
+    remarks: &str,
+    at_ms: u64,
+    window_ms: u64,
+) -> bool {
+    if window_ms == 0 || DUPLICATE_EXEMPT_EVENT_KEYS.contains(&key) {
+        return false;
+    }
+    let Some(previous) = events.iter_mut().find(|event| event.key == key) else {
+        return false;
+    };
+    let last_seen_ms = parse_rfc3339_utc(previous.last_seen.as_deref().unwrap_or(&previous.time))
+        .map(|at| at.timestamp_millis().max(0) as u64)
+        .unwrap_or(0);
+    if previous.resolved
+        || previous.classification != classification
+        || previous.remarks != remarks
+        || at_ms < last_seen_ms
+        || at_ms - last_seen_ms > window_ms
+    {
+        return false;
+    }
+
+    previous.repeat_count = Some(previous.repeat_count.unwrap_or(1) + 1);
+    previous.last_seen = Some(iso_from_millis(at_ms));
+    true
+}
+
+// Files written before sequences existed are ordered newest first, so they are
+// numbered from that order. The sequence also replaces their millisecond ids,
+// which collided when two events landed in the same millisecond.
+// Returns the next sequence to hand out.
+fn assign_event_sequences(events: &mut [HistoryEvent]) -> u64 {
+    if events.iter().any(|event| event.seq == 0) {
+        let count = events.len() as u64;
+        for (index, event) in events.iter_mut().enumerate() {
+            event.seq = count - index as u64;
+            event.id = event.seq;
+        }
+    } else {
+        events.sort_by_key(|event| std::cmp::Reverse(event.seq));
+    }
+    events.iter().map(|event| event.seq).max().unwrap_or(0) + 1
+}
+
+fn event_display_name(key: &str) -> &str {
+    EVENT_CATALOG
+        .iter()
+        .find(|(item, _)| *item == key)
+        .map(|(_, name)| *name)
+        .unwrap_or(key)
+}
+
+fn event_key_for_name(name: &str) -> String {
+    EVENT_CATALOG
+        .iter()
+        .find(|(_, item)| item.eq_ignore_ascii_case(name))
+        .map(|(key, _)| key.to_string())
+        .unwrap_or_else(|| {
+            name.trim()
+                .to_lowercase()
+                .chars()
+                .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '_' })
+                .collect()
+        })
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct EventKeyInfo {
+    key: &'static str,
+    name: &'static str,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    #[default]
+    Info,
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    fn from_str(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "info" => Some(Self::Info),
+            "warning" => Some(Self::Warning),
+            "critical" => Some(Self::Critical),
+            _ => None,
+        }
+    }
+
+    // Events logged before severities existed only carry the classification;
+    // the backfilled key tells which critical rows were logged as critical.
+    fn from_classification(classification: &str, key: &str) -> Self {
+        if classification != "Critical Event" {
+            Self::Info
+        } else if CRITICAL_EVENT_KEYS.contains(&key) {
+            Self::Critical
+        } else {
+            Self::Warning
+        }
+    }
+
+    fn classification(self) -> &'static str {
+        match self {
+            Self::Info => "General Event",
+            Self::Warning | Self::Critical => "Critical Event",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AvailabilityStats {
+    period_start: String,
+    period_end: String,
+    // None when nothing in the period was monitored.
+    availability_percent: Option<f64>,
+    monitored_seconds: u64,
+    on_battery_seconds: u64,
+    unmonitored_seconds: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EventStatistics {
+    total: usize,
+    info: usize,
+    warning: usize,
+    critical: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AboutInfo {
+    app_version: String,
+    build_date: Option<String>,
+    data_dir: String,
+    config_path: String,
+    events_path: String,
+    data_path: String,
+    sounds_path: String,
+    firmware: Option<String>,
+    event_count: usize,
+    data_point_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DiagnosticsSnapshot {
+    generated_at: String,
+    app_version: String,
+    os_version: String,
+    session_id: u64,
+    connected: bool,
+    on_battery: bool,
+    battery_time_seconds: Option<u64>,
+    scheduled_shutdown_at: Option<String>,
+    scheduled_shutdown_reason: Option<String>,
+    last_error: Option<String>,
+    last_status: Option<UpsData>,
+    device_info: Option<UpsInfo>,
+}
+
+// Inline counterpart of the support bundle, meant to be pasted into an issue.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StateSnapshot {
+    generated_at: String,
+    app_version: String,
+    session_id: u64,
+    connection: ConnectionState,
+    on_battery: bool,
+    battery_time_seconds: Option<u64>,
+    scheduled_shutdown_at: Option<String>,
+    scheduled_shutdown_reason: Option<String>,
+    last_status: Option<UpsData>,
+    last_raw_frame: Option<RawFrameInfo>,
+    device_info: Option<UpsInfo>,
+    settings: serde_json::Value,
+    redacted_fields: Vec<String>,
+}
+
+// What the monitor loop is actually doing, so "high CPU" reports can be told
+// apart from a spinning read loop. The rate is recomputed once per window.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MonitorLoopStats {
+    polling_interval_ms: u64,
+    read_timeout_ms: u64,
+    sleep_ms: u64,
+    iterations: u64,
+    iterations_per_second: f64,
+    #[serde(skip)]
+    window_started_ms: Option<u64>,
+    #[serde(skip)]
+    window_iterations: u64,
+}
+
+impl MonitorLoopStats {
+    fn record(&mut self, now_ms: u64, polling_interval_ms: u64, read_timeout_ms: u64) {
+        self.polling_interval_ms = polling_interval_ms;
+        self.read_timeout_ms = read_timeout_ms;
+        self.iterations = self.iterations.saturating_add(1);
+
+        let Some(started) = self.window_started_ms else {
+            self.window_started_ms = Some(now_ms);
+            return;
+        };
+        self.window_iterations += 1;
+        let elapsed_ms = now_ms.saturating_sub(started);
+        if elapsed_ms >= LOOP_RATE_WINDOW_MS {
+            self.iterations_per_second = self.window_iterations as f64 * 1000.0 / elapsed_ms as f64;
+            self.window_started_ms = Some(now_ms);
+            self.window_iterations = 0;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResourceUsage {
+    pid: u32,
+    cpu_time_ms: Option<u64>,
+    memory_bytes: Option<u64>,
+    uptime_seconds: u64,
+    average_cpu_percent: Option<f64>,
+    monitor_loop: MonitorLoopStats,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HidDeviceSummary {
+    vendor_id: String,
+    product_id: String,
+    manufacturer: Option<String>,
+    product: Option<String>,
+    serial_number: Option<String>,
+    interface_number: i32,
+    path: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SupportBundleSummary {
+    path: String,
+    files: Vec<String>,
+    event_count: usize,
+    data_point_count: usize,
+    hid_device_count: usize,
+    log_line_count: usize,
+    redacted_fields: Vec<String>,
+    // Distinct serials, device strings and commands replaced by placeholders.
+    redacted_identifiers: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OutageRecord {
+    started_at: String,
+    ended_at: Option<String>,
+    duration_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MetricSummary {
+    min: f64,
+    avg: f64,
+    max: f64,
+    samples: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DailySummary {
+    date: String,
+    input_voltage: Option<MetricSummary>,
+    outage_count: usize,
+    outage_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PowerReport {
+    period: String,
+    from: String,
+    to: String,
+    // Later than `from` when the capped history no longer reaches back to the
+    // start of the period; every figure below only covers this range.
+    covered_from: String,
+    generated_at: String,
+    outages: Vec<OutageRecord>,
+    outage_seconds: u64,
+    availability_percent: f64,
+    input_voltage: Option<MetricSummary>,
+    output_voltage: Option<MetricSummary>,
+    battery_voltage: Option<MetricSummary>,
+    alert_counts: BTreeMap<String, usize>,
+    daily: Vec<DailySummary>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReportResult {
+    path: String,
+    format: String,
+    outage_count: usize,
+    availability_percent: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ShutdownMarker {
+    reason: String,
+    action: String,
+    attempted_at: String,
+    #[serde(default)]
+    battery_percent: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionHeartbeat {
+    session_id: u64,
+    app_version: String,
+    started_at: String,
+    last_heartbeat: String,
+    stopped_cleanly: bool,
+}
+
+// Volatile monitor state flushed periodically so a crash mid-outage can resume it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct RuntimeSnapshot {
+    saved_at: String,
+    is_on_battery: bool,
+    battery_start_ms: Option<u64>,
+    was_battery_low: bool,
+    was_battery_critical: bool,
+    last_data_save_ms: u64,
+    // Kept so ids of deleted data points are never handed out again.
+    next_data_id: u64,
+    last_discharge_point_ms: u64,
+    stable_ac_samples: u64,
+    charge_cycle_active: bool,
+    charge_after_outage: bool,
+    // Persisted so the weekly cap on the standby-charge alert survives restarts.
+    standby_alert_ms: u64,
+    protection_override: Option<ProtectionOverride>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProtectionOverride {
+    until_ms: u64,
+    reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProtectionState {
+    suspended: bool,
+    // "manual" for `suspend_protection`, "schedule" for the weekly window.
+    source: Option<String>,
+    reason: Option<String>,
+    until: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DataHistoryEntry {
+    id: u64,
+    time: String,
+    input_voltage: f64,
+    output_voltage: f64,
+    frequency: f64,
+    load_percent: u64,
+    battery_voltage: f64,
+    battery_percent: u64,
+    temperature: f64,
+}
+
+impl DataHistoryEntry {
+    fn from_status(id: u64, at_ms: u64, status: &UpsData) -> Self {
+        Self {
+            id,
+            time: iso_from_millis(at_ms),
+            input_voltage: status.input_voltage,
+            output_voltage: status.output_voltage,
+            frequency: status.frequency,
+            load_percent: status.load_percent,
+            battery_voltage: status.battery_voltage,
+            battery_percent: status.battery_percent,
+            temperature: status.temperature,
+        }
+    }
+}
+
+// Anything this large is a millisecond timestamp, not an assigned id.
+const LEGACY_TIMESTAMP_ID_MIN: u64 = 1_000_000_000_000;
+
+// Data point ids are assigned once and kept across loads. Older versions used
+// the timestamp, which collided within one millisecond; such a file is
+// renumbered from its newest-first order a single time. Returns the next id
+// to hand out and whether anything was renumbered.
+fn assign_data_ids(data: &mut [DataHistoryEntry]) -> (u64, bool) {
+    let mut seen = HashSet::new();
+    let legacy = data.iter().any(|entry| {
+        entry.id == 0 || entry.id >= LEGACY_TIMESTAMP_ID_MIN || !seen.insert(entry.id)
+    });
+    if legacy {
+        let count = data.len() as u64;
+        for (index, entry) in data.iter_mut().enumerate() {
+            entry.id = count - index as u64;
+        }
+    }
+    let next = data.iter().map(|entry| entry.id).max().unwrap_or(0) + 1;
+    (next, legacy)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DischargeSession {
+    outage_id: u64,
+    started_at: String,
+    ended_at: Option<String>,
+    points: Vec<DataHistoryEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DischargeSessionSummary {
+    outage_id: u64,
+    started_at: String,
+    ended_at: Option<String>,
+    point_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RechargeExpectation {
+    restored_at_ms: u64,
+    deadline_ms: u64,
+    baseline_percent: u64,
+    baseline_voltage: f64,
+    target_percent: u64,
+}
+
+// Odometer-style counters. `on_battery_since_ms`/`last_accounted_ms` persist
+// the running outage so a restart mid-outage resumes it instead of counting a
+// second transfer; time while the app was not running is never accrued.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LifetimeStats {
+    since: String,
+    transfers_to_battery: u64,
+    on_battery_ms: u64,
+    controlled_shutdowns: u64,
+    #[serde(default)]
+    on_battery_since_ms: Option<u64>,
+    #[serde(default)]
+    last_accounted_ms: Option<u64>,
+    // Sample counts per bucket index (value / bucket width, floored).
+    #[serde(default)]
+    voltage_buckets: BTreeMap<i64, u64>,
+    #[serde(default)]
+    frequency_buckets: BTreeMap<i64, u64>,
+}
+
+impl LifetimeStats {
+    fn fresh() -> Self {
+        Self {
+            since: now_iso(),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HistogramMetric {
+    InputVoltage,
+    Frequency,
+}
+
+impl HistogramMetric {
+    fn bucket_width(self) -> f64 {
+        match self {
+            HistogramMetric::InputVoltage => VOLTAGE_HISTOGRAM_BUCKET,
+            HistogramMetric::Frequency => FREQUENCY_HISTOGRAM_BUCKET,
+        }
+    }
+
+    fn entry_value(self, entry: &DataHistoryEntry) -> f64 {
+        match self {
+            HistogramMetric::InputVoltage => entry.input_voltage,
+            HistogramMetric::Frequency => entry.frequency,
+        }
+    }
+
+    fn lifetime_buckets(self, stats: &LifetimeStats) -> &BTreeMap<i64, u64> {
+        match self {
+            HistogramMetric::InputVoltage => &stats.voltage_buckets,
+            HistogramMetric::Frequency => &stats.frequency_buckets,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Histogram {
+    period: String,
+    bucket_width: f64,
+    // One more edge than counts; bucket i covers [edges[i], edges[i + 1]).
+    edges: Vec<f64>,
+    counts: Vec<u64>,
+    samples: u64,
+}
+
+fn histogram_bucket(value: f64, width: f64) -> i64 {
+    (value / width).floor() as i64
+}
+
+// Fills the gaps between the lowest and highest bucket seen with zeros; the
+// reading limit ceilings keep that span small.
+fn build_histogram(period: &str, buckets: &BTreeMap<i64, u64>, width: f64) -> Histogram {
+    let (Some(&first), Some(&last)) = (buckets.keys().next(), buckets.keys().next_back()) else {
+        return Histogram {
+            period: period.to_string(),
+            bucket_width: width,
+            ..Histogram::default()
+        };
+    };
+    let counts: Vec<u64> = (first..=last)
+        .map(|index| buckets.get(&index).copied().unwrap_or(0))
+        .collect();
+    Histogram {
+        period: period.to_string(),
+        bucket_width: width,
+        edges: (first..=last + 1)
+            .map(|index| index as f64 * width)
+            .collect(),
+        samples: counts.iter().sum(),
+        counts,
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HistoryFilter {
+    classification: Option<String>,
+    keys: Option<Vec<String>>,
+    severity: Option<String>,
+    resolved: Option<bool>,
+    date_from: Option<String>,
+    date_to: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeleteSummary {
+    deleted: usize,
+    remaining: usize,
+}
+
+// `ids` and a filter are exclusive. None means delete everything, which is what
+// an empty id list has always meant.
+fn deletion_targets(
+    ids: Option<Vec<u64>>,
+    matched: Option<Vec<u64>>,
+) -> Result<Option<HashSet<u64>>, String> {
+    match (ids.filter(|ids| !ids.is_empty()), matched) {
+        (Some(_), Some(_)) => Err("Indica ids o un filtro, no ambos".to_string()),
+        (Some(ids), None) => Ok(Some(ids.into_iter().collect())),
+        (None, Some(matched)) => Ok(Some(matched.into_iter().collect())),
+        (None, None) => Ok(None),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SoundRepeatConfig {
+    ac_fault: u64,
+    battery_low: u64,
+    critical: u64,
+    default: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SoundConfig {
+    repeat_config: SoundRepeatConfig,
+    repeat_delay: u64,
+    // First entry of `custom_sounds_paths`, kept for older callers.
+    custom_sounds_path: Option<String>,
+    custom_sounds_paths: Vec<String>,
+    sounds: SoundFiles,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SoundFiles {
+    ac_fault: String,
+    battery_low: String,
+    critical: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SoundInfo {
+    name: String,
+    path: String,
+    custom: bool,
+    location: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SoundConfigPatch {
+    repeat_config: Option<RepeatConfigPatch>,
+    repeat_delay: Option<u64>,
+    custom_sounds_path: Option<Option<String>>,
+    custom_sounds_paths: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RepeatConfigPatch {
+    ac_fault: Option<u64>,
+    battery_low: Option<u64>,
+    critical: Option<u64>,
+    default: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RawFrameInfo {
+    ascii: String,
+    parsed: bool,
+    received_at: String,
+}
+
+// How to give this user access to the UPS on the current platform.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SetupGuidance {
+    platform: String,
+    permission_denied: bool,
+    device_path: Option<String>,
+    vendor_id: String,
+    product_id: String,
+    // Linux only; generated from the device's own VID/PID.
+    udev_rule: Option<String>,
+    steps: Vec<String>,
+    error: Option<String>,
+    // From the last check_conflicting_software run; never scanned on a poll.
+    conflicting_software: Vec<ConflictingProcess>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ProcessEntry {
+    name: String,
+    pid: Option<u32>,
+    services: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConflictingProcess {
+    product: String,
+    process: String,
+    pid: Option<u32>,
+    services: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConflictReport {
+    checked_at: String,
+    conflicts: Vec<ConflictingProcess>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SimulationStep {
+    offset_ms: u64,
+    key: String,
+    detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OutageSimulationReport {
+    duration_seconds: u64,
+    drain_rate: f64,
+    start_percent: u64,
+    end_percent: u64,
+    cancelled: bool,
+    timeline: Vec<SimulationStep>,
+}
+
+// An outage rehearsal driven through `handle_status_packet` from its own
+// thread. Real packets only update `previous_status` while it runs, and the
+// events and schedules it raises carry `EventOrigin::Simulation`.
+struct OutageSimulation {
+    started_ms: u64,
+    previous_status: Option<UpsData>,
+    cancelled: bool,
+    timeline: Vec<SimulationStep>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ShutdownSimulationResult {
+    scheduled: bool,
+    // The cancel is emitted `cancel_after_ms` after the command returns.
+    cancelled: bool,
+    minutes: u64,
+    shutdown_time: String,
+    cancel_after_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ShutdownScheduledPayload {
+    minutes: u64,
+    shutdown_time: String,
+    reason: String,
+    // Battery runtime at scheduling time, so the UI can flag a delay the
+    // battery cannot cover.
+    estimated_runtime_minutes: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ScheduledShutdown {
+    at_ms: u64,
+    reason: String,
+    origin: EventOrigin,
+}
+
+// Who raised an event or schedule. A rehearsal keeps its own event keys and
+// its schedules never run the shutdown command or touch a real one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EventOrigin {
+    Real,
+    Simulation,
+    AlertTest,
+}
+
+impl EventOrigin {
+    fn event_key(self, key: &str) -> String {
+        match self {
+            Self::Real => key.to_string(),
+            Self::Simulation => format!("{}_simulated", key),
+            Self::AlertTest => format!("{}_test", key),
+        }
+    }
+
+    fn event_name(self, key: &str) -> String {
+        match self {
+            Self::Real => event_display_name(key).to_string(),
+            Self::Simulation => format!("{} (simulated)", event_display_name(key)),
+            Self::AlertTest => format!("{} (test)", event_display_name(key)),
+        }
+    }
+}
+
+thread_local! {
+    // Set by the thread driving a rehearsal, so events and schedules raised
+    // from other threads meanwhile stay real.
+    static EVENT_ORIGIN: Cell<EventOrigin> = const { Cell::new(EventOrigin::Real) };
+}
+
+fn current_event_origin() -> EventOrigin {
+    EVENT_ORIGIN.with(Cell::get)
+}
+
+fn with_event_origin<T>(origin: EventOrigin, run: impl FnOnce() -> T) -> T {
+    let previous = EVENT_ORIGIN.with(|current| current.replace(origin));
+    let result = run();
+    EVENT_ORIGIN.with(|current| current.set(previous));
+    result
+}
+
+// Higher wins when two triggers compete for the reason of one schedule.
+fn shutdown_reason_priority(reason: &str) -> u8 {
+    match reason {
+        "ups-shutdown" => 4,
+        "battery-critical" => 3,
+        "battery-low" => 2,
+        "ac-fault" => 1,
+        _ => 0,
+    }
+}
+
+// The deadline only ever moves earlier. The reason follows the more severe
+// trigger, or a `forced` newcomer whose own deadline wins, so a protective
+// deadline never runs another trigger's action. Returns None when nothing
+// changes.
+fn merge_shutdown_schedule(
+    current: Option<&ScheduledShutdown>,
+    candidate: ScheduledShutdown,
+    forced: bool,
+) -> Option<ScheduledShutdown> {
+    let Some(current) = current else {
+        return Some(candidate);
+    };
+    if current.origin != candidate.origin {
+        // A real schedule replaces a rehearsal one and is never moved by one.
+        return (candidate.origin == EventOrigin::Real).then_some(candidate);
+    }
+
+    let at_ms = current.at_ms.min(candidate.at_ms);
+    let candidate_priority = shutdown_reason_priority(&candidate.reason);
+    let current_priority = shutdown_reason_priority(&current.reason);
+    let takes_reason = (forced && candidate.at_ms <= current.at_ms)
+        || candidate_priority > current_priority
+        || (candidate_priority == current_priority && candidate.at_ms < current.at_ms);
+    let reason = if takes_reason {
+        candidate.reason
+    } else {
+        current.reason.clone()
+    };
+
+    let merged = ScheduledShutdown {
+        at_ms,
+        reason,
+        origin: current.origin,
+    };
+    (merged != *current).then_some(merged)
+}
+
+// When the UPS starts its own shutdown timer, a schedule that is still in the
+// future is pulled to `now`. Nothing to escalate without a pending schedule.
+fn ups_shutdown_escalation(
+    current: Option<&ScheduledShutdown>,
+    now: u64,
+) -> Option<ScheduledShutdown> {
+    let current = current.filter(|schedule| schedule.at_ms > now)?;
+    let candidate = ScheduledShutdown {
+        at_ms: now,
+        reason: "ups-shutdown".to_string(),
+        origin: current.origin,
+    };
+    merge_shutdown_schedule(Some(current), candidate, true)
+}
+
+// How a `(` status frame is laid out for the connected device.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FrameFormat {
+    battery_voltage_scale: f64,
+    status_bits_count: usize,
+    status_bits_field: Option<usize>,
+}
+
+impl FrameFormat {
+    fn from_settings(settings: &AppSettings) -> Self {
+        Self {
+            battery_voltage_scale: settings.battery_voltage_scale,
+            status_bits_count: settings.status_bits_count as usize,
+            status_bits_field: settings.status_bits_field.map(|field| field as usize),
+        }
+    }
+
+    // The pinned index, or the last token shaped like the status bits. A
+    // runtime appended after the bits is numeric but never this long. A pinned
+    // token that is not shaped like the bits rejects the frame.
+    fn status_field(&self, parts: &[&str]) -> Option<usize> {
+        let is_status_bits = |part: &str| {
+            part.len() == self.status_bits_count && part.chars().all(|ch| ch == '0' || ch == '1')
+        };
+        if let Some(field) = self.status_bits_field {
+            return parts
+                .get(field)
+                .is_some_and(|part| is_status_bits(part))
+                .then_some(field);
+        }
+        parts.iter().rposition(|part| is_status_bits(part))
+    }
+}
+
+impl Default for FrameFormat {
+    fn default() -> Self {
+        Self::from_settings(&AppSettings::default())
+    }
+}
+
+#[derive(Debug, Clone)]
+enum DecodedPacket {
+    Status(Box<UpsData>),
+    Version(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AlertKind {
+    AcFault,
+    BatteryLow,
+    BatteryCritical,
+    ChargeFailure,
+    UpsDisconnected,
+    UpsConnected,
+    TemperatureRising,
+    BatteryNotHolding,
+}
+
+impl AlertKind {
+    fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "acFault" | "ac_fault" | "ac-fault" | "ac" => Some(Self::AcFault),
+            "batteryLow" | "battery_low" | "battery-low" | "low" => Some(Self::BatteryLow),
+            "batteryCritical" | "critical" | "battery_critical" | "battery-critical" => {
+                Some(Self::BatteryCritical)
+            }
+            "chargeFailure" | "charge_failure" | "charge-failure" => Some(Self::ChargeFailure),
+            "upsDisconnected" | "ups_disconnected" | "disconnected" => Some(Self::UpsDisconnected),
+            "upsConnected" | "ups_connected" | "connected" => Some(Self::UpsConnected),
+            "temperatureRising" | "temperature_rising" | "temperature-rising" => {
+                Some(Self::TemperatureRising)
+            }
+            "batteryNotHolding" | "battery_not_holding" | "battery-not-holding" => {
+                Some(Self::BatteryNotHolding)
+            }
+            _ => None,
+        }
+    }
+
+    fn event_name(self) -> &'static str {
+        match self {
+            Self::AcFault => "Fallo de energia",
+            Self::BatteryLow => "Bateria baja",
+            Self::BatteryCritical => "Bateria critica",
+            Self::ChargeFailure => "Fallo de carga",
+            Self::UpsDisconnected => "UPS desconectado",
+            Self::UpsConnected => "UPS conectado",
+            Self::TemperatureRising => "Temperatura en aumento",
+            Self::BatteryNotHolding => "Bateria no retiene carga",
+        }
+    }
+
+    fn severity(self) -> Severity {
+        match self {
+            Self::BatteryCritical => Severity::Critical,
+            Self::UpsConnected => Severity::Info,
+            _ => Severity::Warning,
+        }
+    }
+
+    fn event_key(self) -> &'static str {
+        match self {
+            Self::AcFault => "ac_fault",
+            Self::BatteryLow => "battery_low",
+            Self::BatteryCritical => "battery_critical",
+            Self::ChargeFailure => "charge_failure",
+            Self::UpsDisconnected => "ups_disconnected",
+            Self::UpsConnected => "ups_connected",
+            Self::TemperatureRising => "temperature_rising",
+            Self::BatteryNotHolding => "battery_not_holding",
+        }
+    }
+
+    fn alert_type(self) -> &'static str {
+        match self {
+            Self::AcFault => "warning",
+            Self::BatteryLow => "battery",
+            Self::BatteryCritical => "critical",
+            Self::ChargeFailure | Self::BatteryNotHolding => "battery",
+            Self::UpsDisconnected | Self::UpsConnected | Self::TemperatureRising => "warning",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UrgentAlertPayload {
+    title: String,
+    message: String,
+    alert_type: String,
+    created_at: String,
+}
+
+struct Escalation {
+    kind: AlertKind,
+    message: String,
+    started_ms: u64,
+    last_played_ms: u64,
+}
+
+struct QueuedAlert {
+    key: String,
+    payload: UrgentAlertPayload,
+}
+
+#[derive(Default)]
+struct AlertQueue {
+    items: VecDeque<QueuedAlert>,
+    worker_running: bool,
+    // Type of the alert on screen, while the worker is showing one.
+    showing: Option<String>,
+}
+
+impl AlertQueue {
+    // A newer alert of the same kind replaces the queued one; critical alerts go
+    // first. Returns true when the alert should cut short a non-critical one
+    // already on screen.
+    fn push(&mut self, alert: QueuedAlert) -> bool {
+        let preempts = alert.payload.alert_type == "critical"
+            && self
+                .showing
+                .as_deref()
+                .is_some_and(|showing| showing != "critical");
+        if let Some(existing) = self.items.iter_mut().find(|item| item.key == alert.key) {
+            existing.payload = alert.payload;
+            return preempts;
+        }
+        if alert.payload.alert_type == "critical" {
+            let position = self
+                .items
+                .iter()
+                .position(|item| item.payload.alert_type != "critical")
+                .unwrap_or(self.items.len());
+            self.items.insert(position, alert);
+        } else {
+            self.items.push_back(alert);
+        }
+        preempts
+    }
+
+    // Takes the first alert, in queue order, that `ready` lets through.
+    fn pop_ready(&mut self, ready: impl Fn(&QueuedAlert) -> bool) -> Option<QueuedAlert> {
+        let position = self.items.iter().position(ready)?;
+        self.items.remove(position)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct UpsDataSubscription {
+    min_interval_ms: u64,
+    last_sent_ms: Option<u64>,
+}
+
+struct AppState {
+    clock: Arc<dyn Clock>,
+    app_data_dir: PathBuf,
+    config_path: PathBuf,
+    heartbeat_path: PathBuf,
+    runtime_state_path: PathBuf,
+    last_status_path: PathBuf,
+    shutdown_marker_path: PathBuf,
+    events_path: PathBuf,
+    data_path: PathBuf,
+    discharge_path: PathBuf,
+    recharge_path: PathBuf,
+    lifetime_stats_path: PathBuf,
+    sounds_path: PathBuf,
+    settings: Mutex<AppSettings>,
+    settings_notices: Vec<String>,
+    events: Mutex<Vec<HistoryEvent>>,
+    next_event_seq: AtomicU64,
+    data_history: Mutex<Vec<DataHistoryEntry>>,
+    next_data_id: AtomicU64,
+    discharge_sessions: Mutex<Vec<DischargeSession>>,
+    recharge_expectation: Mutex<Option<RechargeExpectation>>,
+    lifetime_stats: Mutex<LifetimeStats>,
+    last_lifetime_save_ms: AtomicU64,
+    last_status: Mutex<Option<UpsData>>,
+    frame_buffer: Mutex<Vec<u8>>,
+    last_raw_frame: Mutex<Option<RawFrameInfo>>,
+    // Oldest first; cleared on disconnect so frames are never attributed to
+    // the next device.
+    raw_packet_log: Mutex<VecDeque<RawFrameInfo>>,
+    // Commands queued for the monitor thread, which owns the open device.
+    device_commands: Mutex<Vec<Vec<u8>>>,
+    firmware_generation: AtomicU64,
+    device_info: Mutex<Option<UpsInfo>>,
+    is_connected: Mutex<bool>,
+    has_emitted_disconnected: Mutex<bool>,
+    pending_disconnect_ms: Mutex<Option<u64>>,
+    is_on_battery: Mutex<bool>,
+    was_battery_low: Mutex<bool>,
+    was_battery_critical: Mutex<bool>,
+    // (time, °C) samples covering the temperature trend window.
+    temperature_samples: Mutex<VecDeque<(u64, f64)>>,
+    temperature_rising: Mutex<bool>,
+    // On AC and below the standby minimum since this time.
+    standby_low_since_ms: Mutex<Option<u64>>,
+    battery_not_holding: Mutex<bool>,
+    standby_alert_ms: AtomicU64,
+    // Alerts and shutdowns are suppressed while either of these is active;
+    // history keeps recording. See `effective_settings`.
+    protection_override: Mutex<Option<ProtectionOverride>>,
+    protection_window_active: AtomicBool,
+    // End of a weekly window occurrence dismissed by `resume_protection`.
+    dismissed_window_end: Mutex<Option<NaiveDateTime>>,
+    // Fingerprint of the config.json contents this process last wrote or
+    // loaded, so the watcher ignores the app's own saves.
+    config_fingerprint: Mutex<Option<u64>>,
+    config_checked_ms: AtomicU64,
+    simulation: Mutex<Option<OutageSimulation>>,
+    battery_start_ms: Mutex<Option<u64>>,
+    last_data_save_ms: Mutex<u64>,
+    // Times of the change-triggered data points logged in the last hour.
+    change_points_ms: Mutex<VecDeque<u64>>,
+    last_discharge_point_ms: Mutex<u64>,
+    scheduled_shutdown: Mutex<Option<ScheduledShutdown>>,
+    // Action chosen for a manual trigger, overriding `shutdownPC.action`.
+    manual_shutdown_action: Mutex<Option<String>>,
+    ac_restored_at_ms: Mutex<Option<u64>>,
+    shutdown_confirmation_deadline_ms: Mutex<Option<u64>>,
+    stable_ac_samples: Mutex<u64>,
+    // Input voltage and frequency of the last packet on AC, the reference for
+    // strict-restore bands the user left unset.
+    last_ac_reading: Mutex<Option<(f64, f64)>>,
+    percent_samples: Mutex<VecDeque<(u64, f64)>>,
+    charge_cycle_active: Mutex<bool>,
+    // The running charge cycle started when AC came back after an outage.
+    charge_after_outage: Mutex<bool>,
+    utility_state: Mutex<UtilityState>,
+    last_error: Mutex<Option<String>>,
+    // Time of the last connected/disconnected transition.
+    connection_since_ms: Mutex<Option<u64>>,
+    last_packet_ms: AtomicU64,
+    last_keepalive_ms: AtomicU64,
+    keepalive_failures: AtomicU64,
+    device_path: Mutex<Option<String>>,
+    // Device path -> time before which a refused open is not retried.
+    permission_backoff: Mutex<HashMap<String, u64>>,
+    setup_guidance: Mutex<Option<SetupGuidance>>,
+    conflicting_software: Mutex<Vec<ConflictingProcess>>,
+    conflict_notified: AtomicBool,
+    session_id: u64,
+    session_started_at: String,
+    previous_session: Option<SessionHeartbeat>,
+    previous_shutdown: Option<ShutdownMarker>,
+    last_heartbeat_ms: AtomicU64,
+    last_runtime_save_ms: AtomicU64,
+    last_status_save_ms: AtomicU64,
+    // Set when an on-battery session was restored from disk and the first
+    // status packet has not confirmed it yet.
+    restored_outage_pending: AtomicBool,
+    // Set when the lifetime counters were saved mid-outage; the first update
+    // after a restart resumes them without accruing the downtime.
+    lifetime_resume_pending: AtomicBool,
+    last_report_check_ms: AtomicU64,
+    last_preflight_ms: AtomicU64,
+    last_histogram_sample_ms: AtomicU64,
+    // The configured alert monitor was missing last time; warns once per loss.
+    alert_display_missing: AtomicBool,
+    audio_failures: AtomicU64,
+    audio_unavailable: AtomicBool,
+    // Window label -> ups-data throttle requested by that window.
+    ups_data_subscriptions: Mutex<HashMap<String, UpsDataSubscription>>,
+    ups_data_fallback_last_ms: Mutex<Option<u64>>,
+    remote_api_running: AtomicBool,
+    nut_server_running: AtomicBool,
+    stop_recorded: AtomicBool,
+    stop_monitor: AtomicBool,
+    allow_process_exit: AtomicBool,
+    pending_show_main_window: AtomicBool,
+    sound_generation: AtomicU64,
+    alert_queue: Mutex<AlertQueue>,
+    // Popup key -> last time a popup was forced for it.
+    popup_last_shown_ms: Mutex<HashMap<String, u64>>,
+    pending_alert: Mutex<Option<UrgentAlertPayload>>,
+    // Last alert sent through dispatch_alert, before the custom name label.
+    last_alert: Mutex<Option<(AlertKind, UrgentAlertPayload)>>,
+    alert_generation: AtomicU64,
+    // Bumped on acknowledgment and on every new flash so older flash loops stop.
+    visual_generation: AtomicU64,
+    monitoring_started_ms: AtomicU64,
+    // Process CPU time when monitoring started; the average only covers what
+    // was spent since then, matching the uptime it is divided by.
+    monitoring_started_cpu_ms: Mutex<Option<u64>>,
+    monitor_loop: Mutex<MonitorLoopStats>,
+    // While set, a schedule created by `test_alert` is held back and later cancelled.
+    deferred_shutdown_kinds: Mutex<Vec<AlertKind>>,
+    // Last phase published through `shutdown-phase-changed`.
+    shutdown_phase: Mutex<ShutdownPhaseState>,
+    shutdown_executing_ms: AtomicU64,
+    escalations: Mutex<Vec<Escalation>>,
+}
+
+type SharedState = Arc<AppState>;
+
+impl AppState {
+    fn new(app: &AppHandle) -> Self {
+        let app_data_dir = app.path().app_data_dir().unwrap_or_else(|_| {
+            std::env::current_dir()
+                .unwrap_or_else(|_| PathBuf::from("."))
+                .join(".ups-monitor-pro")
+        });
+        Self::load(app_data_dir)
+    }
+
+    fn load(app_data_dir: PathBuf) -> Self {
+        let history_dir = app_data_dir.join("history");
+        let sounds_path = app_data_dir.join("sounds");
+        let _ = fs::create_dir_all(&history_dir);
+        let _ = fs::create_dir_all(&sounds_path);
+
+        let config_path = app_data_dir.join("config.json");
+        let heartbeat_path = app_data_dir.join("heartbeat.json");
+        let runtime_state_path = app_data_dir.join("runtime-state.json");
+        let last_status_path = app_data_dir.join("last-status.json");
+        let shutdown_marker_path = app_data_dir.join("shutdown-marker.json");
+        let events_path = history_dir.join("events.json");
+        let data_path = history_dir.join("data.json");
+        let discharge_path = history_dir.join("discharge.json");
+        let recharge_path = history_dir.join("recharge.json");
+        let lifetime_stats_path = history_dir.join("lifetime.json");
+
+        let mut recovered_files = Vec::new();
+        let (mut settings, recovered): (AppSettings, bool) = read_json_with_backup(&config_path);
+        if recovered {
+            recovered_files.push("config.json");
+        }
+        let mut settings_notices = settings.migrate();
+        let settings = settings.normalize();
+        if let Some(dir) = settings.missing_shutdown_working_directory() {
+            settings_notices.push(format!(
+                "No se encontro la carpeta de trabajo del comando de apagado ({}). Se usara la carpeta de la aplicacion mientras no exista.",
+                dir
+            ));
+        }
+        let config_bytes = serde_json::to_vec_pretty(&settings).unwrap_or_default();
+        let _ = write_atomic(&config_path, &config_bytes, true);
+        let config_fingerprint = content_fingerprint(&config_bytes);
+
+        let (mut events, recovered): (Vec<HistoryEvent>, bool) =
+            read_json_with_backup(&events_path);
+        if recovered {
+            recovered_files.push("events.json");
+        }
+        for event in events.iter_mut() {
+            if event.key.is_empty() {
+                event.key = event_key_for_name(&event.name);
+            }
+            event.severity = Some(event.severity());
+        }
+        let next_event_seq = assign_event_sequences(&mut events);
+        let previous_session: Option<SessionHeartbeat> = read_json_or_default(&heartbeat_path);
+        let previous_shutdown: Option<ShutdownMarker> = read_json_or_default(&shutdown_marker_path);
+        let _ = fs::remove_file(&shutdown_marker_path);
+        let session_id = previous_session
+            .as_ref()
+            .map(|session| session.session_id + 1)
+            .unwrap_or(1);
+        let (mut data_history, recovered): (Vec<DataHistoryEntry>, bool) =
+            read_json_with_backup(&data_path);
+        if recovered {
+            recovered_files.push("data.json");
+        }
+        let (next_data_id, data_renumbered) = assign_data_ids(&mut data_history);
+        let discharge_sessions: Vec<DischargeSession> = read_json_or_default(&discharge_path);
+        let recharge_expectation: Option<RechargeExpectation> =
+            read_json_or_default(&recharge_path);
+        let lifetime_stats = read_json_or_default::<Option<LifetimeStats>>(&lifetime_stats_path)
+            .unwrap_or_else(LifetimeStats::fresh);
+        let lifetime_resume_pending = lifetime_stats.on_battery_since_ms.is_some();
+        let runtime: RuntimeSnapshot = read_json_or_default(&runtime_state_path);
+        let last_status =
+            read_json_or_default::<Option<UpsData>>(&last_status_path).map(|mut status| {
+                status.stale = true;
+                status
+            });
+
+        let state = Self {
+            clock: Arc::new(SystemClock),
+            app_data_dir,
+            config_path,
+            heartbeat_path,
+            runtime_state_path,
+            last_status_path,
+            shutdown_marker_path,
+            events_path,
+            data_path,
+            discharge_path,
+            recharge_path,
+            lifetime_stats_path,
+            sounds_path,
+            settings: Mutex::new(settings),
+            settings_notices,
+            events: Mutex::new(events),
+            next_event_seq: AtomicU64::new(next_event_seq),
+            data_history: Mutex::new(data_history),
+            next_data_id: AtomicU64::new(next_data_id.max(runtime.next_data_id)),
+            discharge_sessions: Mutex::new(discharge_sessions),
+            recharge_expectation: Mutex::new(recharge_expectation),
+            lifetime_stats: Mutex::new(lifetime_stats),
+            last_lifetime_save_ms: AtomicU64::new(0),
+            last_status: Mutex::new(last_status),
+            frame_buffer: Mutex::new(Vec::new()),
+            last_raw_frame: Mutex::new(None),
+            raw_packet_log: Mutex::new(VecDeque::new()),
+            device_commands: Mutex::new(Vec::new()),
+            firmware_generation: AtomicU64::new(0),
+            device_info: Mutex::new(None),
+            is_connected: Mutex::new(false),
+            has_emitted_disconnected: Mutex::new(false),
+            pending_disconnect_ms: Mutex::new(None),
+            is_on_battery: Mutex::new(runtime.is_on_battery),
+            was_battery_low: Mutex::new(runtime.was_battery_low),
+            was_battery_critical: Mutex::new(runtime.was_battery_critical),
+            temperature_samples: Mutex::new(VecDeque::new()),
+            temperature_rising: Mutex::new(false),
+            standby_low_since_ms: Mutex::new(None),
+            battery_not_holding: Mutex::new(false),
+            standby_alert_ms: AtomicU64::new(runtime.standby_alert_ms),
+            protection_override: Mutex::new(runtime.protection_override.clone()),
+            protection_window_active: AtomicBool::new(false),
+            dismissed_window_end: Mutex::new(None),
+            config_fingerprint: Mutex::new(Some(config_fingerprint)),
+            config_checked_ms: AtomicU64::new(0),
+            simulation: Mutex::new(None),
+            battery_start_ms: Mutex::new(runtime.battery_start_ms),
+            last_data_save_ms: Mutex::new(runtime.last_data_save_ms),
+            change_points_ms: Mutex::new(VecDeque::new()),
+            last_discharge_point_ms: Mutex::new(runtime.last_discharge_point_ms),
+            scheduled_shutdown: Mutex::new(None),
+            manual_shutdown_action: Mutex::new(None),
+            ac_restored_at_ms: Mutex::new(None),
+            shutdown_confirmation_deadline_ms: Mutex::new(None),
+            stable_ac_samples: Mutex::new(runtime.stable_ac_samples),
+            last_ac_reading: Mutex::new(None),
+            percent_samples: Mutex::new(VecDeque::new()),
+            charge_cycle_active: Mutex::new(runtime.charge_cycle_active),
+            charge_after_outage: Mutex::new(runtime.charge_after_outage),
+            utility_state: Mutex::new(UtilityState::Normal),
+            last_error: Mutex::new(None),
+            connection_since_ms: Mutex::new(None),
+            last_packet_ms: AtomicU64::new(0),
+            last_keepalive_ms: AtomicU64::new(0),
+            keepalive_failures: AtomicU64::new(0),
+            device_path: Mutex::new(None),
+            permission_backoff: Mutex::new(HashMap::new()),
+            setup_guidance: Mutex::new(None),
+            conflicting_software: Mutex::new(Vec::new()),
+            conflict_notified: AtomicBool::new(false),
+            session_id,
+            session_started_at: now_iso(),
+            previous_session,
+            previous_shutdown,
+            last_heartbeat_ms: AtomicU64::new(0),
+            last_runtime_save_ms: AtomicU64::new(0),
+            last_status_save_ms: AtomicU64::new(0),
+            restored_outage_pending: AtomicBool::new(runtime.is_on_battery),
+            lifetime_resume_pending: AtomicBool::new(lifetime_resume_pending),
+            last_report_check_ms: AtomicU64::new(0),
+            last_preflight_ms: AtomicU64::new(0),
+            last_histogram_sample_ms: AtomicU64::new(0),
+            alert_display_missing: AtomicBool::new(false),
+            audio_failures: AtomicU64::new(0),
+            audio_unavailable: AtomicBool::new(false),
+            ups_data_subscriptions: Mutex::new(HashMap::new()),
+            ups_data_fallback_last_ms: Mutex::new(None),
+            remote_api_running: AtomicBool::new(false),
+            nut_server_running: AtomicBool::new(false),
+            stop_recorded: AtomicBool::new(false),
+            stop_monitor: AtomicBool::new(false),
+            allow_process_exit: AtomicBool::new(false),
+            pending_show_main_window: AtomicBool::new(false),
+            sound_generation: AtomicU64::new(0),
+            alert_queue: Mutex::new(AlertQueue::default()),
+            popup_last_shown_ms: Mutex::new(HashMap::new()),
+            pending_alert: Mutex::new(None),
+            last_alert: Mutex::new(None),
+            alert_generation: AtomicU64::new(0),
+            visual_generation: AtomicU64::new(0),
+            monitoring_started_ms: AtomicU64::new(0),
+            monitoring_started_cpu_ms: Mutex::new(None),
+            monitor_loop: Mutex::new(MonitorLoopStats::default()),
+            deferred_shutdown_kinds: Mutex::new(Vec::new()),
+            shutdown_phase: Mutex::new(ShutdownPhaseState::default()),
+            shutdown_executing_ms: AtomicU64::new(0),
+            escalations: Mutex::new(Vec::new()),
+        };
+        state.reconcile_previous_shutdown();
+        if data_renumbered {
+            state.save_data_history();
+        }
+        if !recovered_files.is_empty() {
+            let remarks = format!(
+                "Restored from backup after a failed read: {}",
+                recovered_files.join(", ")
+            );
+            log::warn!("{}", remarks);
+            state.log_event("storage_recovered", Severity::Warning, &remarks);
+        }
+        state
+    }
+
+    fn log_dir(&self) -> PathBuf {
+        self.app_data_dir.join(LOG_DIR_NAME)
+    }
+
+    fn effective_settings(&self) -> AppSettings {
+        let mut settings = lock(&self.settings).effective();
+        if self.protection_suspended() {
+            settings.apply_monitor_only_defaults();
+        }
+        settings
+    }
+
+    fn protection_suspended(&self) -> bool {
+        let now = self.now_ms();
+        lock(&self.protection_override)
+            .as_ref()
+            .is_some_and(|suspension| now < suspension.until_ms)
+            || self.protection_window_active.load(Ordering::Relaxed)
+    }
+
+    fn protection_state(&self, local_now: NaiveDateTime) -> ProtectionState {
+        let now = self.now_ms();
+        if let Some(suspension) = lock(&self.protection_override)
+            .as_ref()
+            .filter(|suspension| now < suspension.until_ms)
+        {
+            return ProtectionState {
+                suspended: true,
+                source: Some("manual".to_string()),
+                reason: Some(suspension.reason.clone()),
+                until: Some(iso_from_millis(suspension.until_ms)),
+            };
+        }
+        if self.protection_window_active.load(Ordering::Relaxed) {
+            let window = lock(&self.settings).protection_window.clone();
+            let until = window
+                .active_until(local_now)
+                .and_then(|end| Local.from_local_datetime(&end).earliest())
+                .map(|end| iso_from_millis(end.timestamp_millis().max(0) as u64));
+            return ProtectionState {
+                suspended: true,
+                source: Some("schedule".to_string()),
+                reason: Some("Ventana de mantenimiento semanal".to_string()),
+                until,
+            };
+        }
+        ProtectionState {
+            suspended: false,
+            source: None,
+            reason: None,
+            until: None,
+        }
+    }
+
+    fn suspend_protection(&self, minutes: u64, reason: &str) {
+        let now = self.now_ms();
+        *lock(&self.protection_override) = Some(ProtectionOverride {
+            until_ms: now.saturating_add(minutes.saturating_mul(60_000)),
+            reason: reason.to_string(),
+        });
+        self.save_runtime_state();
+        self.log_event(
+            "protection_suspended",
+            Severity::Warning,
+            &format!("Protection suspended for {} min: {}", minutes, reason),
+        );
+    }
+
+    // Ends a manual suspension and dismisses the weekly window occurrence in
+    // progress, if any. Returns false when protection was not suspended.
+    fn resume_protection(&self, local_now: NaiveDateTime) -> bool {
+        if !self.protection_suspended() {
+            return false;
+        }
+        *lock(&self.protection_override) = None;
+        if self.protection_window_active.swap(false, Ordering::Relaxed) {
+            *lock(&self.dismissed_window_end) = lock(&self.settings)
+                .protection_window
+                .active_until(local_now);
+        }
+        self.save_runtime_state();
+        self.log_event(
+            "protection_resumed",
+            Severity::Info,
+            "Protection resumed manually",
+        );
+        true
+    }
+
+    // Expires a manual suspension whose time is up and follows the weekly
+    // window. Returns the new state when it changed since the last call.
+    fn refresh_protection(&self, local_now: NaiveDateTime) -> Option<ProtectionState> {
+        let was_suspended = self.protection_suspended();
+        let now = self.now_ms();
+        let expired = {
+            let mut suspension = lock(&self.protection_override);
+            match suspension.as_ref() {
+                Some(current) if now >= current.until_ms => suspension.take(),
+                _ => None,
+            }
+        };
+        if let Some(expired) = expired.as_ref() {
+            self.save_runtime_state();
+            self.log_event(
+                "protection_resumed",
+                Severity::Info,
+                &format!("Protection suspension expired: {}", expired.reason),
+            );
+        }
+
+        let window_end = lock(&self.settings)
+            .protection_window
+            .active_until(local_now);
+        let window_active = {
+            let mut dismissed = lock(&self.dismissed_window_end);
+            if dismissed.is_some() && *dismissed != window_end {
+                *dismissed = None;
+            }
+            window_end.is_some() && dismissed.is_none()
+        };
+        if self
+            .protection_window_active
+            .swap(window_active, Ordering::Relaxed)
+            != window_active
+        {
+            if window_active {
+                self.log_event(
+                    "protection_suspended",
+                    Severity::Warning,
+                    "Weekly maintenance window started",
+                );
+            } else {
+                self.log_event(
+                    "protection_resumed",
+                    Severity::Info,
+                    "Weekly maintenance window ended",
+                );
+            }
+        }
+
+        let state = self.protection_state(local_now);
+        (state.suspended != was_suspended || expired.is_some()).then_some(state)
+    }
+
+    fn now_ms(&self) -> u64 {
+        self.clock.now_ms()
+    }
+
+    // Returns the schedule now in effect when the request changed it.
+    fn schedule_shutdown(
+        &self,
+        delay_minutes: u64,
+        reason: &str,
+        forced: bool,
+    ) -> Option<ScheduledShutdown> {
+        let safe_minutes = delay_minutes.clamp(1, 120);
+        let candidate = ScheduledShutdown {
+            at_ms: self.now_ms().saturating_add(safe_minutes * 60 * 1000),
+            reason: reason.to_string(),
+            origin: current_event_origin(),
+        };
+        let mut shutdown_guard = lock(&self.scheduled_shutdown);
+        let merged = merge_shutdown_schedule(shutdown_guard.as_ref(), candidate, forced)?;
+        *shutdown_guard = Some(merged.clone());
+        Some(merged)
+    }
+
+    // Derived from the schedule, confirmation window and deferred kinds, so
+    // the phase can never disagree with the state that drives the shutdown.
+    fn shutdown_phase_state(&self) -> ShutdownPhaseState {
+        let now = self.now_ms();
+        let schedule = lock(&self.scheduled_shutdown).clone();
+        let reason = schedule.as_ref().map(|schedule| schedule.reason.clone());
+        let executing_ms = self.shutdown_executing_ms.load(Ordering::Relaxed);
+        if schedule.is_none()
+            && executing_ms > 0
+            && now.saturating_sub(executing_ms) < SHUTDOWN_EXECUTING_HOLD_MS
+        {
+            return ShutdownPhaseState {
+                phase: ShutdownPhase::Executing,
+                reason: None,
+                deadline: None,
+            };
+        }
+        let confirmation =
+            (*lock(&self.shutdown_confirmation_deadline_ms)).filter(|until| now < *until);
+        if let (Some(until), Some(_)) = (confirmation, schedule.as_ref()) {
+            return ShutdownPhaseState {
+                phase: ShutdownPhase::AwaitingConfirmation,
+                reason,
+                deadline: Some(iso_from_millis(until)),
+            };
+        }
+        if let Some(schedule) = schedule {
+            return ShutdownPhaseState {
+                phase: ShutdownPhase::Scheduled,
+                reason,
+                deadline: Some(iso_from_millis(schedule.at_ms)),
+            };
+        }
+        let deferred = lock(&self.deferred_shutdown_kinds).first().copied();
+        if let Some(kind) = deferred {
+            return ShutdownPhaseState {
+                phase: ShutdownPhase::GracePeriod,
+                reason: Some(kind.event_key().to_string()),
+                deadline: None,
+            };
+        }
+        ShutdownPhaseState::default()
+    }
+
+    fn begin_outage_simulation(&self) -> bool {
+        let mut simulation = lock(&self.simulation);
+        if simulation.is_some() {
+            return false;
+        }
+        *simulation = Some(OutageSimulation {
+            started_ms: self.now_ms(),
+            previous_status: lock(&self.last_status).clone(),
+            cancelled: false,
+            timeline: Vec::new(),
+        });
+        true
+    }
+
+    fn simulation_active(&self) -> bool {
+        lock(&self.simulation).is_some()
+    }
+
+    fn simulation_cancelled(&self) -> bool {
+        lock(&self.simulation)
+            .as_ref()
+            .is_some_and(|simulation| simulation.cancelled)
+    }
+
+    fn cancel_outage_simulation(&self) -> bool {
+        match lock(&self.simulation).as_mut() {
+            Some(simulation) => {
+                simulation.cancelled = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // The latest real status, restored when the simulation ends.
+    fn update_simulation_baseline(&self, status: UpsData) {
+        if let Some(simulation) = lock(&self.simulation).as_mut() {
+            simulation.previous_status = Some(status);
+        }
+    }
+
+    fn simulation_baseline(&self) -> Option<UpsData> {
+        lock(&self.simulation)
+            .as_ref()
+            .and_then(|simulation| simulation.previous_status.clone())
+    }
+
+    // Adds a timeline entry when called from the simulation thread; false
+    // otherwise.
+    fn record_simulation_step(&self, key: &str, detail: &str) -> bool {
+        if current_event_origin() != EventOrigin::Simulation {
+            return false;
+        }
+        let now = self.now_ms();
+        let mut simulation = lock(&self.simulation);
+        let Some(simulation) = simulation.as_mut() else {
+            return false;
+        };
+        simulation.timeline.push(SimulationStep {
+            offset_ms: now.saturating_sub(simulation.started_ms),
+            key: key.to_string(),
+            detail: detail.to_string(),
+        });
+        true
+    }
+
+    // Ends the simulation and drops everything it latched, so the next real
+    // packet is evaluated as if the outage never happened.
+    fn end_outage_simulation(&self) -> Option<OutageSimulation> {
+        let simulation = lock(&self.simulation).take()?;
+        *lock(&self.is_on_battery) = false;
+        *lock(&self.was_battery_low) = false;
+        *lock(&self.was_battery_critical) = false;
+        *lock(&self.battery_start_ms) = None;
+        *lock(&self.stable_ac_samples) = 0;
+        lock(&self.percent_samples).clear();
+        *lock(&self.utility_state) = simulation
+            .previous_status
+            .as_ref()
+            .map(|status| status.utility_state)
+            .unwrap_or(UtilityState::Normal);
+        *lock(&self.last_status) = simulation.previous_status.clone();
+        self.sound_generation.fetch_add(1, Ordering::Relaxed);
+        self.save_runtime_state();
+        Some(simulation)
+    }
+
+    // Stores the current phase and returns it when it differs from the last
+    // one published.
+    fn update_shutdown_phase(&self) -> Option<ShutdownPhaseState> {
+        let current = self.shutdown_phase_state();
+        let mut published = lock(&self.shutdown_phase);
+        if *published == current {
+            return None;
+        }
+        *published = current.clone();
+        Some(current)
+    }
+
+    // Clears the schedule, or only one raised by `origin` when given.
+    fn clear_scheduled_shutdown(&self, origin: Option<EventOrigin>) -> bool {
+        let had_schedule = {
+            let mut schedule = lock(&self.scheduled_shutdown);
+            let other_origin = schedule
+                .as_ref()
+                .zip(origin)
+                .is_some_and(|(current, origin)| current.origin != origin);
+            if other_origin {
+                return false;
+            }
+            schedule.take().is_some()
+        };
+        *lock(&self.manual_shutdown_action) = None;
+        *lock(&self.ac_restored_at_ms) = None;
+        *lock(&self.shutdown_confirmation_deadline_ms) = None;
+        had_schedule
+    }
+
+    // Claims a due schedule and its manual action under the schedule lock, so
+    // of two callers racing on the same deadline only one runs the command. A
+    // test schedule is never claimed.
+    fn take_due_shutdown(&self) -> Option<(ScheduledShutdown, Option<String>)> {
+        let now = self.now_ms();
+        let mut schedule = lock(&self.scheduled_shutdown);
+        let due = schedule.as_ref().is_some_and(|current| {
+            now >= current.at_ms && current.origin != EventOrigin::AlertTest
+        });
+        if !due {
+            return None;
+        }
+        let claimed = schedule.take()?;
+        let manual_action = lock(&self.manual_shutdown_action).take();
+        *lock(&self.ac_restored_at_ms) = None;
+        *lock(&self.shutdown_confirmation_deadline_ms) = None;
+        Some((claimed, manual_action))
+    }
+
+    fn shutdown_due(&self) -> bool {
+        let now = self.now_ms();
+        lock(&self.scheduled_shutdown)
+            .as_ref()
+            .map(|schedule| now >= schedule.at_ms)
+            .unwrap_or(false)
+    }
+
+    // Returns true when the standby-charge alert should fire. The timer only runs
+    // while on AC, so recharging after an outage has the whole window to recover.
+    fn update_standby_charge(
+        &self,
+        standby: &StandbyChargeSettings,
+        on_battery: bool,
+        percent: u64,
+    ) -> bool {
+        let mut low_since = lock(&self.standby_low_since_ms);
+        let mut raised = lock(&self.battery_not_holding);
+        if !standby.enabled {
+            *low_since = None;
+            *raised = false;
+            return false;
+        }
+        if on_battery {
+            *low_since = None;
+            return false;
+        }
+        if percent >= standby.min_percent + STANDBY_CHARGE_HYSTERESIS_PERCENT {
+            *raised = false;
+        }
+        if percent >= standby.min_percent {
+            *low_since = None;
+            return false;
+        }
+
+        let now = self.now_ms();
+        let since = *low_since.get_or_insert(now);
+        if *raised || now.saturating_sub(since) < standby.duration_hours.saturating_mul(3_600_000) {
+            return false;
+        }
+        *raised = true;
+        let last_alert = self.standby_alert_ms.load(Ordering::Relaxed);
+        if last_alert > 0 && now.saturating_sub(last_alert) < STANDBY_ALERT_MIN_INTERVAL_MS {
+            return false;
+        }
+        self.standby_alert_ms.store(now, Ordering::Relaxed);
+        true
+    }
+
+    // Fits the rise rate over every sample in the configured window and reports
+    // when the rising state flips, so one noisy reading cannot trip it. No rate
+    // is reported until the window is covered.
+    fn update_temperature_trend(
+        &self,
+        trend: &TemperatureTrendSettings,
+        temperature: f64,
+    ) -> Option<TemperatureTrend> {
+        let now = self.now_ms();
+        let window_ms = trend.window_minutes.saturating_mul(60_000);
+        let mut samples = lock(&self.temperature_samples);
+        samples.push_back((now, temperature));
+        // Keep one sample at or beyond the window start as the anchor.
+        while samples.len() > 2 && now.saturating_sub(samples[1].0) >= window_ms {
+            samples.pop_front();
+        }
+        let (anchor_ms, _) = *samples.front()?;
+        let span_ms = now.saturating_sub(anchor_ms);
+        if span_ms < window_ms || span_ms == 0 {
+            return None;
+        }
+        let rate = least_squares_slope(samples.make_contiguous(), 3_600_000.0)?;
+        drop(samples);
+
+        let mut rising = lock(&self.temperature_rising);
+        if !*rising && rate >= trend.rise_per_hour {
+            *rising = true;
+            return Some(TemperatureTrend::Rising(rate));
+        }
+        if *rising && rate < trend.clear_per_hour {
+            *rising = false;
+            return Some(TemperatureTrend::Cleared(rate));
+        }
+        None
+    }
+
+    fn record_connection_change(&self, at_ms: u64, device_path: Option<String>) {
+        *lock(&self.connection_since_ms) = Some(at_ms);
+        *lock(&self.device_path) = device_path;
+    }
+
+    fn record_packet(&self) {
+        self.last_packet_ms.store(self.now_ms(), Ordering::Relaxed);
+    }
+
+    fn connection_state(&self) -> ConnectionState {
+        let last_packet_ms = self.last_packet_ms.load(Ordering::Relaxed);
+        ConnectionState {
+            connected: *lock(&self.is_connected),
+            since: lock(&self.connection_since_ms).map(iso_from_millis),
+            last_packet_at: (last_packet_ms > 0).then(|| iso_from_millis(last_packet_ms)),
+            last_error: lock(&self.last_error).clone(),
+            device_path: lock(&self.device_path).clone(),
+        }
+    }
+
+    fn keepalive_due(&self, keepalive: &KeepaliveSettings) -> bool {
+        if !keepalive.enabled {
+            return false;
+        }
+        let now = self.now_ms();
+        let last = self.last_keepalive_ms.load(Ordering::Relaxed);
+        if last > 0 && now.saturating_sub(last) < keepalive.interval_seconds.saturating_mul(1000) {
+            return false;
+        }
+        self.last_keepalive_ms.store(now, Ordering::Relaxed);
+        true
+    }
+
+    // Called at the top of every iteration so the retry paths that `continue`
+    // early are counted too.
+    fn record_loop_iteration(&self, polling_interval_ms: u64, read_timeout_ms: u64) {
+        let now = self.now_ms();
+        lock(&self.monitor_loop).record(now, polling_interval_ms, read_timeout_ms);
+    }
+
+    fn sleep_monitor_loop(&self, sleep_ms: u64) {
+        lock(&self.monitor_loop).sleep_ms = sleep_ms;
+        self.clock.sleep(Duration::from_millis(sleep_ms));
+    }
+
+    // Returns true once `max_failures` writes in a row have failed.
+    fn record_keepalive_result(&self, ok: bool, max_failures: u64) -> bool {
+        if ok {
+            self.keepalive_failures.store(0, Ordering::Relaxed);
+            return false;
+        }
+        let failures = self.keepalive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures < max_failures {
+            return false;
+        }
+        self.keepalive_failures.store(0, Ordering::Relaxed);
+        true
+    }
+
+    // Returns the new availability when it flips, so callers announce each
+    // loss and recovery once.
+    fn record_audio_output(&self, available: bool) -> Option<bool> {
+        if available {
+            self.audio_failures.store(0, Ordering::Relaxed);
+            return self
+                .audio_unavailable
+                .swap(false, Ordering::Relaxed)
+                .then_some(true);
+        }
+        let failures = self.audio_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures < AUDIO_UNAVAILABLE_AFTER_FAILURES
+            || self.audio_unavailable.swap(true, Ordering::Relaxed)
+        {
+            return None;
+        }
+        Some(false)
+    }
+
+    fn record_raw_frame(&self, frame: RawFrameInfo, capture: bool) {
+        let mut log = lock(&self.raw_packet_log);
+        if capture {
+            if log.len() >= RAW_PACKET_LOG_CAPACITY {
+                log.pop_front();
+            }
+            log.push_back(frame.clone());
+        } else {
+            log.clear();
+        }
+        drop(log);
+        *lock(&self.last_raw_frame) = Some(frame);
+    }
+
+    fn defer_device_open(&self, path: &str) {
+        let retry_at = self.now_ms().saturating_add(HID_PERMISSION_RETRY_MS);
+        lock(&self.permission_backoff).insert(path.to_string(), retry_at);
+    }
+
+    fn device_open_deferred(&self, path: &str) -> bool {
+        lock(&self.permission_backoff)
+            .get(path)
+            .is_some_and(|retry_at| self.now_ms() < *retry_at)
+    }
+
+    // How long a popup for `key` has to wait before it may be shown again.
+    fn popup_wait_ms(&self, key: &str, min_interval_ms: u64) -> u64 {
+        let now = self.now_ms();
+        lock(&self.popup_last_shown_ms)
+            .get(key)
+            .map(|last| min_interval_ms.saturating_sub(now.saturating_sub(*last)))
+            .unwrap_or(0)
+    }
+
+    fn mark_popup_shown(&self, key: &str) {
+        let now = self.now_ms();
+        lock(&self.popup_last_shown_ms).insert(key.to_string(), now);
+    }
+
+    fn subscribe_ups_data(&self, window_label: &str, min_interval_ms: u64) {
+        let min_interval_ms = min_interval_ms.min(UPS_DATA_MAX_INTERVAL_MS);
+        let mut subscriptions = lock(&self.ups_data_subscriptions);
+        let entry = subscriptions
+            .entry(window_label.to_string())
+            .or_insert(UpsDataSubscription {
+                min_interval_ms,
+                last_sent_ms: None,
+            });
+        entry.min_interval_ms = min_interval_ms;
+    }
+
+    fn unsubscribe_ups_data(&self, window_label: &str) -> bool {
+        lock(&self.ups_data_subscriptions)
+            .remove(window_label)
+            .is_some()
+    }
+
+    // Picks which of the open windows should receive this ups-data packet and
+    // marks them as sent. Unsubscribed windows share the fallback interval.
+    fn ups_data_targets(&self, window_labels: &[String]) -> Vec<String> {
+        let now = self.now_ms();
+        let due = |last: Option<u64>, interval: u64| match last {
+            Some(last) => now.saturating_sub(last) >= interval,
+            None => true,
+        };
+
+        let mut subscriptions = lock(&self.ups_data_subscriptions);
+        let mut fallback_last = lock(&self.ups_data_fallback_last_ms);
+        let fallback_due = due(*fallback_last, UPS_DATA_FALLBACK_INTERVAL_MS);
+        let mut fallback_used = false;
+        let mut targets = Vec::new();
+
+        for label in window_labels {
+            match subscriptions.get_mut(label) {
+                Some(subscription)
+                    if due(subscription.last_sent_ms, subscription.min_interval_ms) =>
+                {
+                    subscription.last_sent_ms = Some(now);
+                    targets.push(label.clone());
+                }
+                Some(_) => {}
+                None if fallback_due => {
+                    fallback_used = true;
+                    targets.push(label.clone());
+                }
+                None => {}
+            }
+        }
+
+        if fallback_used {
+            *fallback_last = Some(now);
+        }
+        targets
+    }
+
+    // Returns true when the mode actually changed.
+    fn set_monitor_only(&self, enabled: bool) -> bool {
+        let mut settings = lock(&self.settings);
+        if settings.monitor_only_mode == enabled {
+            return false;
+        }
+        settings.monitor_only_mode = enabled;
+        drop(settings);
+        self.save_settings();
+        true
+    }
+
+    fn save_settings(&self) {
+        let settings = lock(&self.settings).clone();
+        let _ = self.write_settings_file(&settings);
+    }
+
+    // The fingerprint is recorded before the write so a watcher poll racing
+    // the rename never mistakes this save for an external edit.
+    fn write_settings_file(&self, settings: &AppSettings) -> std::io::Result<()> {
+        let bytes = serde_json::to_vec_pretty(settings).map_err(std::io::Error::other)?;
+        *lock(&self.config_fingerprint) = Some(content_fingerprint(&bytes));
+        write_atomic(&self.config_path, &bytes, true)
+    }
+
+    // Returns the settings from config.json when something other than this
+    // process changed the file since the last save or poll. Parse errors are
+    // reported once per distinct file content.
+    fn poll_external_config(&self) -> Option<Result<AppSettings, String>> {
+        let now = self.now_ms();
+        let checked = self.config_checked_ms.load(Ordering::Relaxed);
+        if now.saturating_sub(checked) < CONFIG_WATCH_INTERVAL_MS {
+            return None;
+        }
+        self.config_checked_ms.store(now, Ordering::Relaxed);
+
+        let bytes = fs::read(&self.config_path).ok()?;
+        let fingerprint = content_fingerprint(&bytes);
+        let mut last = lock(&self.config_fingerprint);
+        if *last == Some(fingerprint) {
+            return None;
+        }
+        *last = Some(fingerprint);
+        drop(last);
+
+        Some(
+            serde_json::from_slice::<AppSettings>(&bytes)
+                .map(|mut settings| {
+                    settings.migrate();
+                    settings.normalize()
+                })
+                .map_err(|error| error.to_string()),
+        )
+    }
+
+    fn save_events(&self) {
+        let events = lock(&self.events).clone();
+        if let Ok(bytes) = encode_json(&events, false) {
+            let _ = write_atomic(&self.events_path, &bytes, true);
+        }
+    }
+
+    fn save_data_history(&self) {
+        let compress = lock(&self.settings).compress_data_history;
+        let data = lock(&self.data_history).clone();
+        if let Ok(bytes) = encode_json(&data, compress) {
+            let _ = write_atomic(&self.data_path, &bytes, true);
+        }
+    }
+
+    fn set_recharge_expectation(&self, expectation: Option<RechargeExpectation>) {
+        *lock(&self.recharge_expectation) = expectation.clone();
+        write_json(&self.recharge_path, &expectation);
+    }
+
+    fn save_lifetime_stats(&self) {
+        let stats = lock(&self.lifetime_stats).clone();
+        write_json(&self.lifetime_stats_path, &stats);
+        self.last_lifetime_save_ms
+            .store(self.now_ms(), Ordering::Relaxed);
+    }
+
+    // Not gated on monitor-only mode: the counters are passive bookkeeping.
+    fn update_lifetime_stats(&self, is_on_battery: bool) {
+        let now = self.now_ms();
+        let resumed = self.lifetime_resume_pending.swap(false, Ordering::Relaxed);
+        let mut stats = lock(&self.lifetime_stats);
+        let transitioned = match (is_on_battery, stats.on_battery_since_ms) {
+            (true, None) => {
+                stats.transfers_to_battery += 1;
+                stats.on_battery_since_ms = Some(now);
+                stats.last_accounted_ms = Some(now);
+                true
+            }
+            (true, Some(_)) => {
+                // A session restored from disk resumes from now; the gap
+                // while the app was down is not accrued.
+                if let Some(last) = stats.last_accounted_ms.filter(|_| !resumed) {
+                    stats.on_battery_ms += now.saturating_sub(last);
+                }
+                stats.last_accounted_ms = Some(now);
+                resumed
+            }
+            (false, Some(_)) => {
+                if let Some(last) = stats.last_accounted_ms.filter(|_| !resumed) {
+                    stats.on_battery_ms += now.saturating_sub(last);
+                }
+                stats.on_battery_since_ms = None;
+                stats.last_accounted_ms = None;
+                true
+            }
+            (false, None) => false,
+        };
+        drop(stats);
+
+        let last_save = self.last_lifetime_save_ms.load(Ordering::Relaxed);
+        if transitioned
+            || (is_on_battery && now.saturating_sub(last_save) >= LIFETIME_STATS_SAVE_INTERVAL_MS)
+        {
+            self.save_lifetime_stats();
+        }
+    }
+
+    // Sampled once per history interval, the cadence of the data points the
+    // period histograms are built from, so "all" weights time the same way.
+    fn record_histogram_sample(&self, status: &UpsData) {
+        let now = self.now_ms();
+        let interval_ms = lock(&self.settings).history_interval.saturating_mul(1000);
+        let last = self.last_histogram_sample_ms.load(Ordering::Relaxed);
+        if last > 0 && now.saturating_sub(last) < interval_ms {
+            return;
+        }
+        self.last_histogram_sample_ms.store(now, Ordering::Relaxed);
+
+        let mut stats = lock(&self.lifetime_stats);
+        *stats
+            .voltage_buckets
+            .entry(histogram_bucket(
+                status.input_voltage,
+                VOLTAGE_HISTOGRAM_BUCKET,
+            ))
+            .or_insert(0) += 1;
+        *stats
+            .frequency_buckets
+            .entry(histogram_bucket(
+                status.frequency,
+                FREQUENCY_HISTOGRAM_BUCKET,
+            ))
+            .or_insert(0) += 1;
+    }
+
+    // "all" reads the lifetime counters; other periods are rebuilt from the
+    // sampled data history.
+    fn histogram(
+        &self,
+        metric: HistogramMetric,
+        period: &str,
+        now: DateTime<Utc>,
+    ) -> Option<Histogram> {
+        let width = metric.bucket_width();
+        if period == "all" {
+            let stats = lock(&self.lifetime_stats);
+            return Some(build_histogram(
+                period,
+                metric.lifetime_buckets(&stats),
+                width,
+            ));
+        }
+
+        let (from, to) = resolve_report_period(period, now)?;
+        let mut buckets = BTreeMap::new();
+        for entry in lock(&self.data_history).iter() {
+            let in_period = parse_rfc3339_utc(&entry.time)
+                .map(|at| at >= from && at <= to)
+                .unwrap_or(false);
+            if in_period {
+                *buckets
+                    .entry(histogram_bucket(metric.entry_value(entry), width))
+                    .or_insert(0) += 1;
+            }
+        }
+        Some(build_histogram(period, &buckets, width))
+    }
+
+    fn delete_events(
+        &self,
+        ids: Option<Vec<u64>>,
+        filter: Option<HistoryFilter>,
+    ) -> Result<DeleteSummary, String> {
+        let mut events = lock(&self.events);
+        let matched = filter.map(|filter| {
+            filter_events(events.clone(), Some(filter))
+                .iter()
+                .map(|item| item.id)
+                .collect()
+        });
+        let before = events.len();
+        match deletion_targets(ids, matched)? {
+            Some(targets) => events.retain(|item| !targets.contains(&item.id)),
+            None => events.clear(),
+        }
+        let summary = DeleteSummary {
+            deleted: before - events.len(),
+            remaining: events.len(),
+        };
+        drop(events);
+        if summary.deleted > 0 {
+            self.save_events();
+        }
+        Ok(summary)
+    }
+
+    fn delete_data_history(
+        &self,
+        ids: Option<Vec<u64>>,
+        filter: Option<HistoryFilter>,
+    ) -> Result<DeleteSummary, String> {
+        let mut data = lock(&self.data_history);
+        let matched = filter.map(|filter| {
+            filter_data_history(data.clone(), Some(filter))
+                .iter()
+                .map(|item| item.id)
+                .collect()
+        });
+        let before = data.len();
+        match deletion_targets(ids, matched)? {
+            Some(targets) => data.retain(|item| !targets.contains(&item.id)),
+            None => data.clear(),
+        }
+        let summary = DeleteSummary {
+            deleted: before - data.len(),
+            remaining: data.len(),
+        };
+        drop(data);
+        if summary.deleted > 0 {
+            self.save_data_history();
+        }
+        Ok(summary)
+    }
+
+    fn set_events_resolved(&self, ids: &[u64], resolved: bool) -> usize {
+        let mut changed = 0;
+        for event in lock(&self.events).iter_mut() {
+            if ids.contains(&event.id) && event.resolved != resolved {
+                event.resolved = resolved;
+                changed += 1;
+            }
+        }
+        if changed > 0 {
+            self.save_events();
+        }
+        changed
+    }
+
+    fn record_controlled_shutdown(&self) {
+        lock(&self.lifetime_stats).controlled_shutdowns += 1;
+        self.save_lifetime_stats();
+    }
+
+    fn save_discharge_sessions(&self) {
+        let sessions = lock(&self.discharge_sessions).clone();
+        write_json(&self.discharge_path, &sessions);
+    }
+
+    fn log_event(&self, key: &str, severity: Severity, remarks: &str) {
+        self.log_event_at(self.now_ms(), key, severity, remarks);
+    }
+
+    fn log_event_at(&self, at_ms: u64, key: &str, severity: Severity, remarks: &str) {
+        let origin = current_event_origin();
+        self.record_simulation_step(key, remarks);
+        let name = origin.event_name(key);
+        let key = origin.event_key(key);
+        let key = key.as_str();
+        let remarks = remarks.to_string();
+        let (logs_events, window_seconds) = {
+            let settings = lock(&self.settings);
+            (
+                settings.logs_events(),
+                settings.duplicate_event_window_seconds,
+            )
+        };
+        if !logs_events {
+            return;
+        }
+
+        let mut events = lock(&self.events);
+        let classification = severity.classification();
+        if fold_repeated_event(
+            &mut events,
+            key,
+            classification,
+            &remarks,
+            at_ms,
+            window_seconds * 1000,
+        ) {
+            drop(events);
+            self.save_events();
+            return;
+        }
+        let seq = self.next_event_seq.fetch_add(1, Ordering::Relaxed);
+        events.insert(
+            0,
+            HistoryEvent {
+                id: seq,
+                seq,
+                time: iso_from_millis(at_ms),
+                classification: severity.classification().to_string(),
+                severity: Some(severity),
+                key: key.to_string(),
+                name,
+                remarks,
+                session_id: Some(self.session_id),
+                app_version: Some(APP_VERSION.to_string()),
+                resolved: false,
+                repeat_count: None,
+                last_seen: None,
+            },
+        );
+        if events.len() > MAX_EVENTS {
+            events.truncate(MAX_EVENTS);
+        }
+        drop(events);
+        self.save_events();
+    }
+
+    fn write_heartbeat(&self, stopped_cleanly: bool) {
+        write_json(
+            &self.heartbeat_path,
+            &SessionHeartbeat {
+                session_id: self.session_id,
+                app_version: APP_VERSION.to_string(),
+                started_at: self.session_started_at.clone(),
+                last_heartbeat: iso_from_millis(self.now_ms()),
+                stopped_cleanly,
+            },
+        );
+    }
+
+    // A previous session whose heartbeat never recorded a clean stop means the
+    // process died (crash, kill, power loss) while it was running. Our own
+    // protective shutdown is already explained by the shutdown marker.
+    fn record_app_started(&self) {
+        let unclean_previous = self
+            .previous_session
+            .as_ref()
+            .filter(|item| !item.stopped_cleanly && self.previous_shutdown.is_none());
+        if let Some(previous) = unclean_previous {
+            let last_seen_ms = parse_rfc3339_utc(&previous.last_heartbeat)
+                .map(|dt| dt.timestamp_millis().max(0) as u64)
+                .unwrap_or_else(|| self.now_ms());
+            let remarks = format!(
+                "Session {} (v{}) last heartbeat {}",
+                previous.session_id, previous.app_version, previous.last_heartbeat
+            );
+            self.log_event_at(last_seen_ms, "app_terminated", Severity::Warning, &remarks);
+        }
+
+        self.write_heartbeat(false);
+        self.last_heartbeat_ms
+            .store(self.now_ms(), Ordering::Relaxed);
+        let remarks = format!("UPS Monitor v{} (session {})", APP_VERSION, self.session_id);
+        self.log_event("app_started", Severity::Info, &remarks);
+    }
+
+    fn touch_heartbeat(&self) {
+        let now = self.now_ms();
+        let last = self.last_heartbeat_ms.load(Ordering::Relaxed);
+        if now.saturating_sub(last) < HEARTBEAT_INTERVAL_MS {
+            return;
+        }
+        self.last_heartbeat_ms.store(now, Ordering::Relaxed);
+        self.write_heartbeat(false);
+    }
+
+    fn runtime_snapshot(&self) -> RuntimeSnapshot {
+        RuntimeSnapshot {
+            saved_at: iso_from_millis(self.now_ms()),
+            is_on_battery: *lock(&self.is_on_battery),
+            battery_start_ms: *lock(&self.battery_start_ms),
+            was_battery_low: *lock(&self.was_battery_low),
+            was_battery_critical: *lock(&self.was_battery_critical),
+            last_data_save_ms: *lock(&self.last_data_save_ms),
+            next_data_id: self.next_data_id.load(Ordering::Relaxed),
+            last_discharge_point_ms: *lock(&self.last_discharge_point_ms),
+            stable_ac_samples: *lock(&self.stable_ac_samples),
+            charge_cycle_active: *lock(&self.charge_cycle_active),
+            charge_after_outage: *lock(&self.charge_after_outage),
+            standby_alert_ms: self.standby_alert_ms.load(Ordering::Relaxed),
+            protection_override: lock(&self.protection_override).clone(),
+        }
+    }
+
+    // Returns true when the running charge cycle just completed.
+    fn advance_charge_cycle(
+        &self,
+        status: &UpsData,
+        is_on_battery: bool,
+        was_on_battery: bool,
+        slope: Option<f64>,
+    ) -> bool {
+        let mut cycle_active = lock(&self.charge_cycle_active);
+        let mut after_outage = lock(&self.charge_after_outage);
+        if is_on_battery {
+            *cycle_active = false;
+            *after_outage = false;
+            return false;
+        }
+        if was_on_battery {
+            *after_outage = true;
+        }
+        if was_on_battery || status.charging {
+            *cycle_active = true;
+        }
+
+        let stable = slope
+            .map(|value| value.abs() <= PROJECTION_MIN_SLOPE_PER_MINUTE)
+            .unwrap_or(false);
+        if !(*cycle_active && status.battery_percent >= BATTERY_FULL_PERCENT && stable) {
+            return false;
+        }
+        *cycle_active = false;
+        let remarks = if std::mem::take(&mut *after_outage) {
+            "Battery recharged after outage"
+        } else {
+            "Battery full"
+        };
+        drop(after_outage);
+        drop(cycle_active);
+        self.log_event("battery_full", Severity::Info, remarks);
+        true
+    }
+
+    fn save_runtime_state(&self) {
+        write_json(&self.runtime_state_path, &self.runtime_snapshot());
+        self.last_runtime_save_ms
+            .store(self.now_ms(), Ordering::Relaxed);
+    }
+
+    // Rewrites every store. Writes are not buffered and already fsync, so this
+    // mostly guarantees the periodic snapshots are current.
+    fn flush_storage(&self) -> Result<(), String> {
+        let settings = lock(&self.settings).clone();
+        let events = lock(&self.events).clone();
+        let data = lock(&self.data_history).clone();
+        let sessions = lock(&self.discharge_sessions).clone();
+        let expectation = lock(&self.recharge_expectation).clone();
+        let stats = lock(&self.lifetime_stats).clone();
+
+        let results = [
+            self.write_settings_file(&settings),
+            encode_json(&events, false)
+                .and_then(|bytes| write_atomic(&self.events_path, &bytes, true)),
+            encode_json(&data, settings.compress_data_history)
+                .and_then(|bytes| write_atomic(&self.data_path, &bytes, true)),
+            write_json_durable(&self.discharge_path, &sessions),
+            write_json_durable(&self.recharge_path, &expectation),
+            write_json_durable(&self.lifetime_stats_path, &stats),
+            write_json_durable(&self.runtime_state_path, &self.runtime_snapshot()),
+        ];
+        let now = self.now_ms();
+        self.last_lifetime_save_ms.store(now, Ordering::Relaxed);
+        self.last_runtime_save_ms.store(now, Ordering::Relaxed);
+
+        match results.into_iter().find_map(Result::err) {
+            Some(error) => Err(format!("No se pudo guardar el almacenamiento: {}", error)),
+            None => Ok(()),
+        }
+    }
+
+    fn touch_runtime_state(&self) {
+        let now = self.now_ms();
+        if now.saturating_sub(self.last_status_save_ms.load(Ordering::Relaxed))
+            >= LAST_STATUS_SAVE_INTERVAL_MS
+        {
+            self.save_last_status();
+        }
+        let last = self.last_runtime_save_ms.load(Ordering::Relaxed);
+        if now.saturating_sub(last) < RUNTIME_STATE_SAVE_INTERVAL_MS {
+            return;
+        }
+        self.save_runtime_state();
+        self.save_lifetime_stats();
+    }
+
+    // Only live readings are written; a restored snapshot would otherwise
+    // keep being carried forward as if it were current.
+    fn save_last_status(&self) {
+        self.last_status_save_ms
+            .store(self.now_ms(), Ordering::Relaxed);
+        let status = lock(&self.last_status).clone();
+        if let Some(status) = status.filter(|status| !status.stale) {
+            write_json(&self.last_status_path, &status);
+        }
+    }
+
+    fn has_live_status(&self) -> bool {
+        lock(&self.last_status)
+            .as_ref()
+            .is_some_and(|status| !status.stale)
+    }
+
+    // The last status, unless it is the snapshot restored from disk at startup.
+    fn live_status(&self) -> Option<UpsData> {
+        lock(&self.last_status)
+            .clone()
+            .filter(|status| !status.stale)
+    }
+
+    // Written and synced before the shutdown command is spawned: the OS may kill
+    // the process before the event log reaches disk.
+    fn write_shutdown_marker(&self, reason: &str, action: &str) {
+        let marker = ShutdownMarker {
+            reason: reason.to_string(),
+            action: action.to_string(),
+            attempted_at: iso_from_millis(self.now_ms()),
+            battery_percent: lock(&self.last_status)
+                .as_ref()
+                .map(|status| status.battery_percent),
+        };
+        if let Err(error) = write_json_durable(&self.shutdown_marker_path, &marker) {
+            log::error!("shutdown marker error: {}", error);
+        }
+    }
+
+    fn reconcile_previous_shutdown(&self) {
+        let Some(marker) = self.previous_shutdown.as_ref() else {
+            return;
+        };
+        let battery = marker
+            .battery_percent
+            .map(|percent| format!("{}%", percent))
+            .unwrap_or_else(|| "unknown".to_string());
+        let remarks = format!(
+            "Reason: {} · Action: {} · At: {} · Battery: {}",
+            marker.reason, marker.action, marker.attempted_at, battery
+        );
+        self.log_event("shutdown_recovered", Severity::Warning, &remarks);
+    }
+
+    fn clear_shutdown_marker(&self) {
+        let _ = fs::remove_file(&self.shutdown_marker_path);
+    }
+
+    fn record_app_stopped(&self) {
+        if self.stop_recorded.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        let remarks = format!("UPS Monitor v{} (session {})", APP_VERSION, self.session_id);
+        self.log_event("app_stopped", Severity::Info, &remarks);
+        self.save_runtime_state();
+        self.save_last_status();
+        self.write_heartbeat(true);
+    }
+
+    fn log_data_point_if_needed(&self, status: &UpsData) {
+        let settings = lock(&self.settings).clone();
+        if !settings.logs_data_points() {
+            return;
+        }
+
+        let now = self.now_ms();
+        let changed = settings.change_logging.enabled
+            && lock(&self.data_history)
+                .first()
+                .is_some_and(|last| settings.change_logging.exceeded(last, status));
+        let mut last_save = lock(&self.last_data_save_ms);
+        let elapsed = now.saturating_sub(*last_save);
+        let interval_due = elapsed >= settings.history_interval.saturating_mul(1000);
+        let change_due = changed
+            && elapsed
+                >= settings
+                    .change_logging
+                    .min_interval_seconds
+                    .saturating_mul(1000);
+        if !interval_due {
+            if !change_due {
+                return;
+            }
+            let mut change_points = lock(&self.change_points_ms);
+            while change_points
+                .front()
+                .is_some_and(|at| now.saturating_sub(*at) >= 3_600_000)
+            {
+                change_points.pop_front();
+            }
+            if change_points.len() as u64 >= settings.change_logging.max_points_per_hour {
+                return;
+            }
+            change_points.push_back(now);
+        }
+        *last_save = now;
+        drop(last_save);
+
+        self.push_data_point(now, status);
+        self.save_data_history();
+    }
+
+    fn push_data_point(&self, at_ms: u64, status: &UpsData) {
+        let id = self.next_data_id.fetch_add(1, Ordering::Relaxed);
+        let mut data = lock(&self.data_history);
+        data.insert(0, DataHistoryEntry::from_status(id, at_ms, status));
+        if data.len() > MAX_DATA_POINTS {
+            data.truncate(MAX_DATA_POINTS);
+        }
+    }
+
+    fn start_discharge_session(&self, outage_id: u64) {
+        if !lock(&self.settings).logs_data_points() {
+            return;
+        }
+
+        let mut sessions = lock(&self.discharge_sessions);
+        sessions.insert(
+            0,
+            DischargeSession {
+                outage_id,
+                started_at: iso_from_millis(outage_id),
+                ended_at: None,
+                points: Vec::new(),
+            },
+        );
+        sessions.truncate(MAX_DISCHARGE_SESSIONS);
+        drop(sessions);
+
+        *lock(&self.last_discharge_point_ms) = 0;
+        self.save_discharge_sessions();
+    }
+
+    fn end_discharge_session(&self) {
+        let mut sessions = lock(&self.discharge_sessions);
+        let Some(session) = sessions.first_mut().filter(|item| item.ended_at.is_none()) else {
+            return;
+        };
+        session.ended_at = Some(iso_from_millis(self.now_ms()));
+        drop(sessions);
+        self.save_discharge_sessions();
+    }
+
+    fn log_discharge_point_if_needed(&self, status: &UpsData) {
+        let settings = lock(&self.settings).clone();
+        if !settings.logs_data_points() {
+            return;
+        }
+
+        let now = self.now_ms();
+        let mut last_point = lock(&self.last_discharge_point_ms);
+        let interval_ms = settings.on_battery_history_interval.saturating_mul(1000);
+        if now.saturating_sub(*last_point) < interval_ms {
+            return;
+        }
+
+        let mut sessions = lock(&self.discharge_sessions);
+        let Some(session) = sessions.first_mut().filter(|item| item.ended_at.is_none()) else {
+            return;
+        };
+        if session.points.len() >= MAX_DISCHARGE_POINTS {
+            return;
+        }
+        let id = self.next_data_id.fetch_add(1, Ordering::Relaxed);
+        session
+            .points
+            .push(DataHistoryEntry::from_status(id, now, status));
+        *last_point = now;
+        drop(sessions);
+        drop(last_point);
+        self.save_discharge_sessions();
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn clamp_u64(value: u64, min: u64, max: u64, fallback: u64) -> u64 {
+    if value == 0 {
+        return fallback;
+    }
+    value.max(min).min(max)
+}
+
+fn is_valid_band(min: f64, max: f64) -> bool {
+    min.is_finite() && max.is_finite() && min < max
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// Time source for deadlines and pacing, so shutdown and debounce logic can be
+// driven by a manual clock in tests.
+trait Clock: Send + Sync {
+    fn now_ms(&self) -> u64;
+    fn sleep(&self, duration: Duration);
+}
+
+struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_ms(&self) -> u64 {
+        now_millis()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+fn now_iso() -> String {
+    Utc::now().to_rfc3339()
+}
+
+fn iso_from_millis(ms: u64) -> String {
+    DateTime::<Utc>::from_timestamp_millis(ms as i64)
+        .map(|dt| dt.to_rfc3339())
+        .unwrap_or_else(now_iso)
+}
+
+// Only config.json is meant to be edited by hand; every other store is written
+// compact to keep rewrites small. Config keeps its previous version as a backup.
+fn write_json<T: Serialize>(path: &Path, value: &T) {
+    if let Ok(bytes) = encode_json(value, false) {
+        let _ = write_atomic(path, &bytes, false);
+    }
+}
+
+fn path_with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+static TEMP_FILE_SEQ: AtomicU64 = AtomicU64::new(0);
+static ATOMIC_RENAME: Mutex<()> = Mutex::new(());
+
+// Writes a sibling temp file, fsyncs it and renames it over the target, so a
+// power cut leaves the old contents or the new ones, never a truncated file.
+// With `backup`, the file being replaced is kept as `<name>.bak`. Every write
+// gets its own temp file, so concurrent saves of one path never mix.
+fn write_atomic(path: &Path, bytes: &[u8], backup: bool) -> std::io::Result<()> {
+    let seq = TEMP_FILE_SEQ.fetch_add(1, Ordering::Relaxed);
+    let temp = path_with_suffix(path, &format!(".{}-{}.tmp", std::process::id(), seq));
+    let written = fs::File::create(&temp).and_then(|mut file| {
+        file.write_all(bytes)?;
+        file.sync_all()
+    });
+    if let Err(error) = written {
+        let _ = fs::remove_file(&temp);
+        return Err(error);
+    }
+
+    let _rename = lock(&ATOMIC_RENAME);
+    if backup && path.exists() {
+        fs::rename(path, path_with_suffix(path, ".bak"))?;
+    }
+    fs::rename(&temp, path)
+}
+
+fn encode_json<T: Serialize>(value: &T, compress: bool) -> std::io::Result<Vec<u8>> {
+    let bytes = serde_json::to_vec(value).map_err(std::io::Error::other)?;
+    if !compress {
+        return Ok(bytes);
+    }
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&bytes)?;
+    encoder.finish()
+}
+
+fn content_fingerprint(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn write_json_durable<T: Serialize>(path: &Path, value: &T) -> std::io::Result<()> {
+    write_atomic(path, &encode_json(value, false)?, false)
+}
+
+// Accepts plain JSON as well as gzip, detected by its magic bytes, so toggling
+// compression never strands an existing file.
+fn read_json<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let mut bytes = fs::read(path).ok()?;
+    if bytes.starts_with(&GZIP_MAGIC) {
+        let mut plain = Vec::new();
+        GzDecoder::new(bytes.as_slice())
+            .read_to_end(&mut plain)
+            .ok()?;
+        bytes = plain;
+    }
+    serde_json::from_slice::<T>(&bytes).ok()
+}
+
+fn read_json_or_default<T>(path: &Path) -> T
+where
+    T: DeserializeOwned + Default,
+{
+    read_json(path).unwrap_or_default()
+}
+
+// Falls back to `<name>.bak` when the primary file is missing or unreadable and
+// copies it back, so the next rotation does not push the broken file into the
+// backup slot. Returns whether the backup was used.
+fn read_json_with_backup<T>(path: &Path) -> (T, bool)
+where
+    T: DeserializeOwned + Default,
+{
+    if let Some(value) = read_json(path) {
+        return (value, false);
+    }
+    let backup = path_with_suffix(path, ".bak");
+    match read_json(&backup) {
+        Some(value) => {
+            let _ = fs::copy(&backup, path);
+            (value, true)
+        }
+        None => (T::default(), false),
+    }
+}
+
+fn emit_if_possible<T: Serialize + Clone>(app: &AppHandle, event: &str, payload: T) {
+    let _ = app.emit(event, payload);
+}
+
+fn mark_disconnected(app: &AppHandle, state: &SharedState) {
+    let mut connected = lock(&state.is_connected);
+    let was_connected = *connected;
+    *connected = false;
+    drop(connected);
+
+    *lock(&state.last_status) = None;
+    lock(&state.frame_buffer).clear();
+    lock(&state.raw_packet_log).clear();
+
+    // A USB re-enumeration drops the device for a moment; hold the disconnect
+    // back until the grace window runs out so a quick return logs nothing.
+    let grace_ms = lock(&state.settings)
+        .reconnect_grace_seconds
+        .saturating_mul(1000);
+    let mut pending_disconnect = lock(&state.pending_disconnect_ms);
+    if pending_disconnect.is_some() {
+        return;
+    }
+    if was_connected && grace_ms > 0 {
+        *pending_disconnect = Some(state.now_ms());
+        return;
+    }
+    drop(pending_disconnect);
+
+    finish_disconnect(app, state, was_connected, state.now_ms());
+}
+
+fn finalize_pending_disconnect(app: &AppHandle, state: &SharedState, settings: &AppSettings) {
+    let mut pending_disconnect = lock(&state.pending_disconnect_ms);
+    let Some(disconnected_at) = *pending_disconnect else {
+        return;
+    };
+    let grace_ms = settings.reconnect_grace_seconds.saturating_mul(1000);
+    if state.now_ms().saturating_sub(disconnected_at) < grace_ms {
+        return;
+    }
+    *pending_disconnect = None;
+    drop(pending_disconnect);
+
+    finish_disconnect(app, state, true, disconnected_at);
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConnectionState {
+    connected: bool,
+    since: Option<String>,
+    last_packet_at: Option<String>,
+    last_error: Option<String>,
+    device_path: Option<String>,
+}
+
+fn finish_disconnect(
+    app: &AppHandle,
+    state: &SharedState,
+    was_connected: bool,
+    disconnected_at: u64,
+) {
+    let mut has_emitted_disconnected = lock(&state.has_emitted_disconnected);
+    if !was_connected && *has_emitted_disconnected {
+        return;
+    }
+    *has_emitted_disconnected = true;
+    drop(has_emitted_disconnected);
+    state.record_connection_change(disconnected_at, None);
+
+    *lock(&state.is_on_battery) = false;
+    *lock(&state.was_battery_low) = false;
+    *lock(&state.was_battery_critical) = false;
+    *lock(&state.battery_start_ms) = None;
+    state.end_discharge_session();
+    let _ = cancel_scheduled_shutdown(state, app, CancelReason::Disconnected);
+    state.sound_generation.fetch_add(1, Ordering::Relaxed);
+
+    if was_connected {
+        log::warn!("UPS disconnected");
+        state.log_event_at(
+            disconnected_at,
+            "ups_disconnected",
+            Severity::Warning,
+            "UPS disconnected",
+        );
+        let settings = state.effective_settings();
+        if !settings.monitor_only_mode {
+            let kind = AlertKind::UpsDisconnected;
+            let _ = dispatch_alert(
+                app,
+                state,
+                &settings,
+                kind,
+                kind.event_name(),
+                "Se perdio la comunicacion con el UPS",
+            );
+        }
+    }
+    emit_if_possible(app, "ups-disconnected", state.connection_state());
+}
+
+fn mark_connected(app: &AppHandle, state: &SharedState, device_path: &str) {
+    let mut connected = lock(&state.is_connected);
+    if *connected {
+        return;
+    }
+
+    *connected = true;
+    drop(connected);
+    *lock(&state.has_emitted_disconnected) = false;
+
+    let pending_disconnect = lock(&state.pending_disconnect_ms).take();
+    if let Some(disconnected_at) = pending_disconnect {
+        // A re-enumeration keeps the connection's original `since`.
+        *lock(&state.device_path) = Some(device_path.to_string());
+        let remarks = format!(
+            "UPS reconnected after {:.1} s",
+            state.now_ms().saturating_sub(disconnected_at) as f64 / 1000.0
+        );
+        log::info!("{}", remarks);
+        state.log_event("usb_reenumerated", Severity::Info, &remarks);
+        return;
+    }
+
+    log::info!("UPS connected");
+    state.record_connection_change(state.now_ms(), Some(device_path.to_string()));
+    state.log_event("ups_connected", Severity::Info, "UPS connected");
+    let settings = state.effective_settings();
+    if !settings.monitor_only_mode {
+        let kind = AlertKind::UpsConnected;
+        let _ = dispatch_alert(
+            app,
+            state,
+            &settings,
+            kind,
+            kind.event_name(),
+            "Comunicacion con el UPS establecida",
+        );
+    }
+    emit_if_possible(app, "ups-connected", state.connection_state());
+}
+
+fn emit_error_once(app: &AppHandle, state: &SharedState, message: String) {
+    let mut last_error = lock(&state.last_error);
+    if last_error.as_ref() == Some(&message) {
+        return;
+    }
+    *last_error = Some(message.clone());
+    drop(last_error);
+    log::error!("{}", message);
+    emit_if_possible(app, "ups-error", message);
+}
+
+// hidapi only reports strings; these cover EACCES/EPERM on Linux and macOS and
+// ERROR_ACCESS_DENIED / ERROR_SHARING_VIOLATION on Windows.
+fn is_permission_error(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    [
+        "permission denied",
+        "not permitted",
+        "access is denied",
+        "access denied",
+        "os error 13",
+        "(0x00000005)",
+        "sharing violation",
+        "being used by another process",
+        "(0x00000020)",
+    ]
+    .iter()
+    .any(|marker| message.contains(marker))
+}
+
+fn udev_rule(vendor_id: u16, product_id: u16) -> String {
+    format!(
+        "SUBSYSTEM==\"hidraw\", ATTRS{{idVendor}}==\"{:04x}\", ATTRS{{idProduct}}==\"{:04x}\", MODE=\"0660\", TAG+=\"uaccess\"",
+        vendor_id, product_id
+    )
+}
+
+fn setup_guidance(
+    device_path: Option<String>,
+    vendor_id: u16,
+    product_id: u16,
+    error: Option<String>,
+) -> SetupGuidance {
+    let platform = std::env::consts::OS;
+    let (udev, steps): (Option<String>, &[&str]) = match platform {
+        "linux" => (
+            Some(udev_rule(vendor_id, product_id)),
+            &[
+                "Guarda la regla en /etc/udev/rules.d/99-ups-monitor.rules",
+                "Ejecuta: sudo udevadm control --reload-rules && sudo udevadm trigger",
+                "Desconecta y vuelve a conectar el cable USB del UPS",
+            ],
+        ),
+        "windows" => (
+            None,
+            &[
+                "Cierra otras aplicaciones que usen el UPS (software del fabricante u otros monitores)",
+                "Desconecta y vuelve a conectar el cable USB del UPS",
+                "Si sigue fallando, reinicia el equipo para liberar el dispositivo",
+            ],
+        ),
+        "macos" => (
+            None,
+            &["Permite UPS Monitor en Ajustes del Sistema > Privacidad y seguridad > Monitorizacion de entrada"],
+        ),
+        _ => (
+            None,
+            &["Comprueba que tu usuario puede leer y escribir el dispositivo HID del UPS"],
+        ),
+    };
+    SetupGuidance {
+        platform: platform.to_string(),
+        permission_denied: error.is_some(),
+        device_path,
+        vendor_id: format!("{:04X}", vendor_id),
+        product_id: format!("{:04X}", product_id),
+        udev_rule: udev,
+        steps: steps.iter().map(|step| step.to_string()).collect(),
+        error,
+        conflicting_software: Vec::new(),
+    }
+}
+
+// Announces a refused open once per device path and backs off that device.
+fn report_permission_error(
+    app: &AppHandle,
+    state: &SharedState,
+    device_info: &hidapi::DeviceInfo,
+    error: String,
+) {
+    let path = device_info.path().to_string_lossy().to_string();
+    state.defer_device_open(&path);
+    let conflicts = lock(&state.conflicting_software).clone();
+    let guidance = SetupGuidance {
+        conflicting_software: conflicts.clone(),
+        ..setup_guidance(
+            Some(path.clone()),
+            device_info.vendor_id(),
+            device_info.product_id(),
+            Some(error.clone()),
+        )
+    };
+    let mut current = lock(&state.setup_guidance);
+    let is_new = current
+        .as_ref()
+        .and_then(|existing| existing.device_path.as_deref())
+        != Some(path.as_str());
+    *current = Some(guidance.clone());
+    drop(current);
+    let mut message = format!("Permission denied opening UPS HID device: {}", error);
+    if !conflicts.is_empty() {
+        message.push_str(&format!(
+            " (possibly held by {})",
+            conflict_products(&conflicts)
+        ));
+    }
+    emit_error_once(app, state, message);
+    if is_new {
+        state.log_event(
+            "ups_permission_denied",
+            Severity::Warning,
+            &format!("{}: {}", path, error),
+        );
+        emit_if_possible(app, "ups-permission-error", guidance);
+    }
+}
+
+// `tasklist /FO CSV /NH /SVC`: "name.exe","1234","Svc1,Svc2" with N/A for no service.
+fn parse_tasklist_csv(output: &str) -> Vec<ProcessEntry> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields = line
+                .trim()
+                .trim_matches('"')
+                .split("\",\"")
+                .collect::<Vec<_>>();
+            let name = fields.first().filter(|name| !name.is_empty())?;
+            let services = fields
+                .get(2)
+                .filter(|services| **services != "N/A")
+                .map(|services| {
+                    services
+                        .split(',')
+                        .map(|service| service.trim().to_string())
+                        .collect()
+                })
+                .unwrap_or_default();
+            Some(ProcessEntry {
+                name: name.to_string(),
+                pid: fields.get(1).and_then(|pid| pid.parse().ok()),
+                services,
+            })
+        })
+        .collect()
+}
+
+// `ps -A -o pid=,comm=`: right-aligned pid, then the command name.
+fn parse_ps_output(output: &str) -> Vec<ProcessEntry> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (pid, name) = line.trim().split_once(char::is_whitespace)?;
+            Some(ProcessEntry {
+                name: name.trim().to_string(),
+                pid: pid.parse().ok(),
+                services: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+fn list_processes() -> Result<Vec<ProcessEntry>, String> {
+    let windows = cfg!(target_os = "windows");
+    let (program, args): (&str, &[&str]) = if windows {
+        ("tasklist", &["/FO", "CSV", "/NH", "/SVC"])
+    } else {
+        ("ps", &["-A", "-o", "pid=,comm="])
+    };
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|err| format!("No se pudo listar los procesos con {}: {}", program, err))?;
+    if !output.status.success() {
+        return Err(format!("{} termino con {}", program, output.status));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(if windows {
+        parse_tasklist_csv(&stdout)
+    } else {
+        parse_ps_output(&stdout)
+    })
+}
+
+fn find_conflicting_software(processes: &[ProcessEntry]) -> Vec<ConflictingProcess> {
+    processes
+        .iter()
+        .filter_map(|process| {
+            let file_name = Path::new(&process.name)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_ascii_lowercase())?;
+            let stem = file_name.strip_suffix(".exe").unwrap_or(&file_name);
+            let (_, product) = CONFLICTING_SOFTWARE
+                .iter()
+                .find(|(prefix, _)| stem.starts_with(prefix))?;
+            Some(ConflictingProcess {
+                product: product.to_string(),
+                process: process.name.clone(),
+                pid: process.pid,
+                services: process.services.clone(),
+            })
+        })
+        .collect()
+}
+
+fn conflict_products(conflicts: &[ConflictingProcess]) -> String {
+    let mut products = conflicts
+        .iter()
+        .map(|conflict| conflict.product.as_str())
+        .collect::<Vec<_>>();
+    products.dedup();
+    products.join(", ")
+}
+
+// Only run on request: listing processes is too slow for the monitor loop.
+fn run_conflict_check(app: &AppHandle, state: &SharedState) -> ConflictReport {
+    let (conflicts, error) = match list_processes() {
+        Ok(processes) => (find_conflicting_software(&processes), None),
+        Err(error) => (Vec::new(), Some(error)),
+    };
+    *lock(&state.conflicting_software) = conflicts.clone();
+    if let Some(guidance) = lock(&state.setup_guidance).as_mut() {
+        guidance.conflicting_software = conflicts.clone();
+    }
+    if !conflicts.is_empty() {
+        let products = conflict_products(&conflicts);
+        log::warn!("conflicting UPS software running: {}", products);
+        if !state.conflict_notified.swap(true, Ordering::Relaxed) {
+            let message = format!(
+                "Cierra {} para que UPS Monitor pueda leer el dispositivo",
+                products
+            );
+            let _ = notify_windows(app, "Otro software usa el UPS", &message);
+        }
+    }
+    ConflictReport {
+        checked_at: now_iso(),
+        conflicts,
+        error,
+    }
+}
+
+fn clear_last_error(state: &SharedState) {
+    *lock(&state.last_error) = None;
+}
+
+fn alert_config_for_kind(settings: &AppSettings, kind: AlertKind) -> AlertConfig {
+    match kind {
+        AlertKind::AcFault => settings.alerts.ac_fault.clone(),
+        AlertKind::BatteryLow => settings.alerts.battery_low.clone(),
+        AlertKind::BatteryCritical => settings.alerts.battery_critical.clone(),
+        AlertKind::ChargeFailure => settings.alerts.charge_failure.clone(),
+        AlertKind::UpsDisconnected => settings.alerts.ups_disconnected.clone(),
+        AlertKind::UpsConnected => settings.alerts.ups_connected.clone(),
+        AlertKind::TemperatureRising => settings.alerts.temperature_rising.clone(),
+        AlertKind::BatteryNotHolding => settings.alerts.battery_not_holding.clone(),
+    }
+}
+
+fn alert_sound_file_name(kind: AlertKind) -> &'static str {
+    match kind {
+        AlertKind::AcFault
+        | AlertKind::UpsDisconnected
+        | AlertKind::UpsConnected
+        | AlertKind::TemperatureRising => "alert-ac-fault.wav",
+        AlertKind::BatteryLow | AlertKind::ChargeFailure | AlertKind::BatteryNotHolding => {
+            "alert-battery-low.wav"
+        }
+        AlertKind::BatteryCritical => "alert-critical.wav",
+    }
+}
+
+fn normalize_sound_paths(paths: Vec<String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for path in paths {
+        let path = path.trim().to_string();
+        if !path.is_empty() && !normalized.contains(&path) {
+            normalized.push(path);
+        }
+    }
+    normalized
+}
+
+fn resolve_sound_path(
+    state: &SharedState,
+    settings: &AppSettings,
+    kind: AlertKind,
+) -> Option<PathBuf> {
+    let file_name = alert_sound_file_name(kind);
+    settings
+        .custom_sounds_paths
+        .iter()
+        .map(PathBuf::from)
+        .chain(std::iter::once(state.sounds_path.clone()))
+        .map(|dir| dir.join(file_name))
+        .find(|candidate| candidate.exists())
+}
+
+fn play_fallback_beep() {
+    #[cfg(target_os = "windows")]
+    {
+        let _ = Command::new("powershell")
+            .args([
+                "-NoProfile",
+                "-WindowStyle",
+                "Hidden",
+                "-Command",
+                "[console]::beep(950,220)",
+            ])
+            .spawn();
+    }
+}
+
+// Opens and drops the default output device.
+fn report_audio_output(app: &AppHandle, state: &SharedState, available: bool) {
+    match state.record_audio_output(available) {
+        Some(false) => {
+            let remarks = "No audio output device; sound alerts fall back to the beep";
+            log::warn!("{}", remarks);
+            state.log_event("audio_unavailable", Severity::Warning, remarks);
+            emit_if_possible(app, "audio-unavailable", ());
+        }
+        Some(true) => {
+            log::info!("audio output available again");
+            state.log_event(
+                "audio_restored",
+                Severity::Info,
+                "Audio output device available again",
+            );
+            emit_if_possible(app, "audio-restored", ());
+        }
+        None => {}
+    }
+}
+
+// Returns once the playback thread has tried the output device, with whether
+// it opened, so the caller can fall back without opening it a second time. A
+// device that hangs on open is judged by the cached availability instead.
+fn play_sound_with_generation(
+    app: &AppHandle,
+    state: SharedState,
+    sound_path: Option<PathBuf>,
+    repeats: u64,
+) -> bool {
+    let generation = state.sound_generation.fetch_add(1, Ordering::Relaxed) + 1;
+    let loop_count = repeats.max(1).min(lock(&state.settings).max_sound_repeats);
+    let app = app.clone();
+    let waiting_state = state.clone();
+    let (opened_sender, opened_receiver) = mpsc::channel();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let stream = match OutputStream::try_default() {
+            Ok(stream) => Some(stream),
+            Err(error) => {
+                log::debug!("audio output unavailable: {}", error);
+                None
+            }
+        };
+        let _ = opened_sender.send(stream.is_some());
+        report_audio_output(&app, &state, stream.is_some());
+
+        for _ in 0..loop_count {
+            if state.sound_generation.load(Ordering::Relaxed) != generation {
+                return;
+            }
+
+            let mut played_from_file = false;
+            if let (Some(path), Some((_, stream_handle))) = (sound_path.as_ref(), stream.as_ref()) {
+                if let Ok(file) = fs::File::open(path) {
+                    if let Ok(source) = Decoder::new(BufReader::new(file)) {
+                        if let Ok(sink) = Sink::try_new(stream_handle) {
+                            sink.append(source);
+                            played_from_file = true;
+                            while !sink.empty() {
+                                if state.sound_generation.load(Ordering::Relaxed) != generation {
+                                    sink.stop();
+                                    return;
+                                }
+                                thread::sleep(Duration::from_millis(SOUND_CANCEL_POLL_MS));
+                            }
+                        }
+                    }
+                }
+            }
+
+            if !played_from_file {
+                play_fallback_beep();
+                if !sleep_unless_cancelled(&state, generation, 260) {
+                    return;
+                }
+            }
+
+            if !sleep_unless_cancelled(&state, generation, 140) {
+                return;
+            }
+        }
+    });
+
+    match opened_receiver.recv_timeout(Duration::from_millis(AUDIO_OPEN_WAIT_MS)) {
+        Ok(opened) => opened,
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            !waiting_state.audio_unavailable.load(Ordering::Relaxed)
+        }
+        Err(mpsc::RecvTimeoutError::Disconnected) => false,
+    }
+}
+
+// Sleeps in short slices so stop_sound interrupts long repeat sequences right
+// away. Returns false once a newer sound generation has started.
+fn sleep_unless_cancelled(state: &AppState, generation: u64, total_ms: u64) -> bool {
+    let mut remaining = total_ms;
+    loop {
+        if state.sound_generation.load(Ordering::Relaxed) != generation {
+            return false;
+        }
+        if remaining == 0 {
+            return true;
+        }
+        let slice = remaining.min(SOUND_CANCEL_POLL_MS);
+        thread::sleep(Duration::from_millis(slice));
+        remaining -= slice;
+    }
+}
+
+fn notify_windows(app: &AppHandle, title: &str, message: &str) -> bool {
+    match app
+        .notification()
+        .builder()
+        .title(title)
+        .body(message)
+        .show()
+    {
+        Ok(_) => true,
+        Err(error) => {
+            log::warn!("notification error: {}", error);
+            false
+        }
+    }
+}
+
+fn escape_ps_single_quote(input: &str) -> String {
+    input.replace('\'', "''")
+}
+
+// The per-key rate limit is applied by the alert queue, which holds a popup
+// back until its interval has passed instead of dropping it.
+fn should_force_popup(app: &AppHandle, state: &SharedState) -> bool {
+    if !lock(&state.settings).force_system_popup {
+        return false;
+    }
+    if let Some(window) = app.get_webview_window("main") {
+        let is_visible = window.is_visible().unwrap_or(false);
+        let is_focused = window.is_focused().unwrap_or(false);
+        if is_visible && is_focused {
+            return false;
+        }
+    }
+    true
+}
+
+fn force_windows_popup(title: &str, message: &str, alert_type: &str, timeout_seconds: u64) {
+    #[cfg(target_os = "windows")]
+    {
+        let popup_flags = if alert_type == "critical" {
+            "0x1010"
+        } else {
+            "0x1030"
+        };
+        let safe_title = escape_ps_single_quote(title);
+        let safe_message = escape_ps_single_quote(message);
+        let script = format!(
+            "$w=New-Object -ComObject WScript.Shell; $null=$w.Popup('{}', {}, '{}', {})",
+            safe_message, timeout_seconds, safe_title, popup_flags
+        );
+
+        let _ = Command::new("powershell")
+            .args(["-NoProfile", "-WindowStyle", "Hidden", "-Command", &script])
+            .spawn();
+    }
+}
+
+fn urgent_alert_payload(title: &str, message: &str, alert_type: &str) -> UrgentAlertPayload {
+    UrgentAlertPayload {
+        title: title.to_string(),
+        message: message.to_string(),
+        alert_type: alert_type.to_string(),
+        created_at: now_iso(),
+    }
+}
+
+fn emit_urgent_alert(app: &AppHandle, title: &str, message: &str, alert_type: &str) {
+    emit_if_possible(
+        app,
+        "urgent-alert",
+        urgent_alert_payload(title, message, alert_type),
+    );
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MonitorInfo {
+    index: usize,
+    name: Option<String>,
+    width: u32,
+    height: u32,
+    primary: bool,
+}
+
+// Index or case-insensitive name; `None` when nothing matches.
+fn find_monitor(monitors: &[tauri::Monitor], selector: &str) -> Option<tauri::Monitor> {
+    if let Ok(index) = selector.parse::<usize>() {
+        return monitors.get(index).cloned();
+    }
+    monitors
+        .iter()
+        .find(|monitor| {
+            monitor
+                .name()
+                .is_some_and(|name| name.eq_ignore_ascii_case(selector))
+        })
+        .cloned()
+}
+
+#[cfg(target_os = "windows")]
+fn foreground_window_center() -> Option<(f64, f64)> {
+    use windows::Win32::Foundation::RECT;
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowRect};
+
+    let window = unsafe { GetForegroundWindow() };
+    if window.is_invalid() {
+        return None;
+    }
+    let mut rect = RECT::default();
+    unsafe { GetWindowRect(window, &mut rect) }.ok()?;
+    Some((
+        f64::from(rect.left + rect.right) / 2.0,
+        f64::from(rect.top + rect.bottom) / 2.0,
+    ))
+}
+
+// Other platforms have no portable foreground-window query, so this returns
+// None and `alert_monitor` falls back to the monitor under the cursor.
+#[cfg(not(target_os = "windows"))]
+fn foreground_window_center() -> Option<(f64, f64)> {
+    None
+}
+
+fn alert_monitor(app: &AppHandle, state: &SharedState) -> Option<tauri::Monitor> {
+    let selector = lock(&state.settings).alert_display.clone();
+    let selected = match selector.as_str() {
+        "primary" => None,
+        "active" => foreground_window_center()
+            .or_else(|| {
+                app.cursor_position()
+                    .ok()
+                    .map(|position| (position.x, position.y))
+            })
+            .and_then(|(x, y)| app.monitor_from_point(x, y).ok().flatten()),
+        _ => {
+            let found = find_monitor(&app.available_monitors().unwrap_or_default(), &selector);
+            if found.is_some() {
+                state.alert_display_missing.store(false, Ordering::Relaxed);
+            } else if !state.alert_display_missing.swap(true, Ordering::Relaxed) {
+                let remarks = format!("Monitor '{}' not found; using primary", selector);
+                log::warn!("{}", remarks);
+                state.log_event("alert_display_missing", Severity::Warning, &remarks);
+            }
+            found
+        }
+    };
+    selected.or_else(|| app.primary_monitor().ok().flatten())
+}
+
+fn alert_window_position(app: &AppHandle, state: &SharedState) -> Option<(f64, f64)> {
+    let monitor = alert_monitor(app, state)?;
+    let scale = monitor.scale_factor();
+    let area = monitor.work_area();
+    let right = (area.position.x as f64 + area.size.width as f64) / scale;
+    let bottom = (area.position.y as f64 + area.size.height as f64) / scale;
+    Some((
+        right - ALERT_WINDOW_WIDTH - ALERT_WINDOW_MARGIN,
+        bottom - ALERT_WINDOW_HEIGHT - ALERT_WINDOW_MARGIN,
+    ))
+}
+
+fn show_alert_window(
+    app: &AppHandle,
+    state: &SharedState,
+    payload: &UrgentAlertPayload,
+) -> tauri::Result<()> {
+    if let Some(window) = app.get_webview_window(ALERT_WINDOW_LABEL) {
+        let _ = app.emit_to(ALERT_WINDOW_LABEL, "alert-window-update", payload.clone());
+        window.show()?;
+        return window.set_always_on_top(true);
+    }
+
+    // The window is created on demand so it works while the main window only lives in the tray.
+    let mut builder = tauri::WebviewWindowBuilder::new(
+        app,
+        ALERT_WINDOW_LABEL,
+        tauri::WebviewUrl::App("index.html".into()),
+    )
+    .title(&payload.title)
+    .inner_size(ALERT_WINDOW_WIDTH, ALERT_WINDOW_HEIGHT)
+    .resizable(false)
+    .decorations(false)
+    .always_on_top(true)
+    .skip_taskbar(true)
+    .focused(false);
+    if let Some((x, y)) = alert_window_position(app, state) {
+        builder = builder.position(x, y);
+    }
+    builder.build()?;
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn flash_taskbar(window: &tauri::WebviewWindow, count: u64) {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        FlashWindowEx, FLASHWINFO, FLASHW_ALL, FLASHW_STOP,
+    };
+
+    let Ok(hwnd_raw) = window.hwnd() else {
+        return;
+    };
+    let info = FLASHWINFO {
+        cbSize: std::mem::size_of::<FLASHWINFO>() as u32,
+        hwnd: HWND(hwnd_raw.0 as *mut _),
+        dwFlags: if count == 0 { FLASHW_STOP } else { FLASHW_ALL },
+        uCount: count as u32,
+        dwTimeout: 0,
+    };
+    unsafe {
+        let _ = FlashWindowEx(&info);
+    }
+}
+
+// A zero count stops a flash in progress.
+#[cfg(not(target_os = "windows"))]
+fn flash_taskbar(window: &tauri::WebviewWindow, count: u64) {
+    let attention = (count > 0).then_some(tauri::UserAttentionType::Critical);
+    let _ = window.request_user_attention(attention);
+}
+
+// With the main window hidden there is no taskbar button, so the tray icon
+// blinks instead by swapping in a transparent image of the same size.
+fn flash_tray_icon(app: &AppHandle, state: &SharedState, count: u64, generation: u64) {
+    let (Some(tray), Some(icon)) = (app.tray_by_id("main-tray"), app.default_window_icon()) else {
+        return;
+    };
+    let icon = icon.clone();
+    let blank = tauri::image::Image::new_owned(
+        vec![0; (icon.width() * icon.height() * 4) as usize],
+        icon.width(),
+        icon.height(),
+    );
+    for _ in 0..count {
+        if state.visual_generation.load(Ordering::Relaxed) != generation {
+            break;
+        }
+        let _ = tray.set_icon(Some(blank.clone()));
+        thread::sleep(Duration::from_millis(TRAY_FLASH_INTERVAL_MS));
+        let _ = tray.set_icon(Some(icon.clone()));
+        thread::sleep(Duration::from_millis(TRAY_FLASH_INTERVAL_MS));
+    }
+}
+
+fn flash_screen(app: &AppHandle, state: &SharedState, count: u64, generation: u64) {
+    let Some(monitor) = alert_monitor(app, state) else {
+        return;
+    };
+    let scale = monitor.scale_factor();
+    let position = monitor.position();
+    let size = monitor.size();
+    let built = tauri::WebviewWindowBuilder::new(
+        app,
+        FLASH_WINDOW_LABEL,
+        tauri::WebviewUrl::App("index.html".into()),
+    )
+    .position(position.x as f64 / scale, position.y as f64 / scale)
+    .inner_size(size.width as f64 / scale, size.height as f64 / scale)
+    .decorations(false)
+    .resizable(false)
+    .always_on_top(true)
+    .skip_taskbar(true)
+    .focused(false)
+    .visible(false)
+    .build();
+    let Ok(window) = built else {
+        return;
+    };
+    let _ = window.set_ignore_cursor_events(true);
+    for _ in 0..count {
+        if state.visual_generation.load(Ordering::Relaxed) != generation {
+            break;
+        }
+        let _ = window.show();
+        thread::sleep(Duration::from_millis(SCREEN_FLASH_ON_MS));
+        let _ = window.hide();
+        thread::sleep(Duration::from_millis(SCREEN_FLASH_OFF_MS));
+    }
+    let _ = window.close();
+}
+
+fn start_visual_alert(app: &AppHandle, state: &SharedState, visual: &VisualAlertSettings) {
+    let generation = state.visual_generation.fetch_add(1, Ordering::Relaxed) + 1;
+    let count = visual.flash_count;
+    let main_visible = match app.get_webview_window("main") {
+        Some(window) if window.is_visible().unwrap_or(false) => {
+            flash_taskbar(&window, count);
+            true
+        }
+        _ => false,
+    };
+    let with_screen = visual.flash_screen && app.get_webview_window(FLASH_WINDOW_LABEL).is_none();
+    if main_visible && !with_screen {
+        return;
+    }
+
+    let app = app.clone();
+    let state = state.clone();
+    thread::spawn(move || {
+        if with_screen {
+            flash_screen(&app, &state, count, generation);
+        }
+        if !main_visible {
+            flash_tray_icon(&app, &state, count, generation);
+        }
+    });
+}
+
+fn stop_visual_alert(app: &AppHandle, state: &SharedState) {
+    state.visual_generation.fetch_add(1, Ordering::Relaxed);
+    if let Some(window) = app.get_webview_window("main") {
+        flash_taskbar(&window, 0);
+    }
+}
+
+fn close_alert_window(app: &AppHandle, state: &SharedState) {
+    *lock(&state.pending_alert) = None;
+    if let Some(window) = app.get_webview_window(ALERT_WINDOW_LABEL) {
+        let _ = window.close();
+    }
+}
+
+fn display_queued_alert(app: &AppHandle, state: &SharedState, payload: UrgentAlertPayload) {
+    *lock(&state.pending_alert) = Some(payload.clone());
+    let generation = state.alert_generation.fetch_add(1, Ordering::Relaxed) + 1;
+    let (timeout_seconds, persist) = {
+        let settings = lock(&state.settings);
+        let visual = &settings.visual_alerts;
+        (
+            settings.popup_timeout_seconds,
+            visual.enabled && visual.persist_until_ack,
+        )
+    };
+
+    if show_alert_window(app, state, &payload).is_err() {
+        *lock(&state.pending_alert) = None;
+        // A WScript popup with a zero timeout stays until it is dismissed.
+        let popup_timeout = if persist { 0 } else { timeout_seconds };
+        force_windows_popup(
+            &payload.title,
+            &payload.message,
+            &payload.alert_type,
+            popup_timeout,
+        );
+    } else if persist {
+        // The window stays up until acknowledged; a queued alert takes it over
+        // in place instead of waiting behind the acknowledgment.
+        while state.alert_generation.load(Ordering::Relaxed) == generation
+            && lock(&state.alert_queue).items.is_empty()
+        {
+            state.clock.sleep(Duration::from_millis(250));
+        }
+        return;
+    }
+
+    // Acknowledging the alert, or a critical one preempting it, bumps the
+    // generation and ends the wait early.
+    let deadline = state
+        .now_ms()
+        .saturating_add(timeout_seconds.saturating_mul(1000));
+    while state.now_ms() < deadline {
+        if state.alert_generation.load(Ordering::Relaxed) != generation {
+            return;
+        }
+        state.clock.sleep(Duration::from_millis(250));
+    }
+    if state.alert_generation.load(Ordering::Relaxed) == generation {
+        close_alert_window(app, state);
+    }
+}
+
+fn run_alert_queue(app: AppHandle, state: SharedState) {
+    loop {
+        let min_interval_ms = lock(&state.settings)
+            .popup_min_interval_seconds
+            .saturating_mul(1000);
+        let next = {
+            let mut queue = lock(&state.alert_queue);
+            if queue.items.is_empty() {
+                queue.worker_running = false;
+                queue.showing = None;
+                return;
+            }
+            let next = queue.pop_ready(|item| state.popup_wait_ms(&item.key, min_interval_ms) == 0);
+            queue.showing = next.as_ref().map(|alert| alert.payload.alert_type.clone());
+            next
+        };
+        // Everything queued was shown too recently; wait for the earliest key.
+        let Some(alert) = next else {
+            state.clock.sleep(Duration::from_millis(250));
+            continue;
+        };
+
+        state.mark_popup_shown(&alert.key);
+        display_queued_alert(&app, &state, alert.payload);
+        if !lock(&state.alert_queue).items.is_empty() {
+            state
+                .clock
+                .sleep(Duration::from_millis(ALERT_QUEUE_SPACING_MS));
+        }
+    }
+}
+
+fn force_alert_popup(
+    app: &AppHandle,
+    state: &SharedState,
+    key: &str,
+    title: &str,
+    message: &str,
+    alert_type: &str,
+) {
+    let mut queue = lock(&state.alert_queue);
+    let preempts = queue.push(QueuedAlert {
+        key: key.to_string(),
+        payload: urgent_alert_payload(title, message, alert_type),
+    });
+    if queue.worker_running {
+        if preempts {
+            state.alert_generation.fetch_add(1, Ordering::Relaxed);
+        }
+        return;
+    }
+    queue.worker_running = true;
+    drop(queue);
+
+    let app = app.clone();
+    let state = state.clone();
+    thread::spawn(move || run_alert_queue(app, state));
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CancelReason {
+    PowerRestored,
+    User,
+    Remote,
+    Disconnected,
+    SettingsChanged,
+    AlertTest,
+    Simulation,
+}
+
+impl CancelReason {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::PowerRestored => "power-restored",
+            Self::User => "user",
+            Self::Remote => "remote",
+            Self::Disconnected => "ups-disconnected",
+            Self::SettingsChanged => "settings-changed",
+            Self::AlertTest => "alert-test",
+            Self::Simulation => "simulation",
+        }
+    }
+
+    fn message(self) -> &'static str {
+        match self {
+            Self::PowerRestored => "Energia restablecida, apagado cancelado",
+            Self::User => "Apagado cancelado por el usuario",
+            Self::Remote => "Apagado cancelado de forma remota",
+            Self::Disconnected => "UPS desconectado, apagado cancelado",
+            Self::SettingsChanged => "Apagado cancelado por cambio de configuracion",
+            Self::AlertTest => "Prueba de alerta finalizada, apagado cancelado",
+            Self::Simulation => "Simulacion de corte finalizada, apagado cancelado",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ShutdownCancelledPayload {
+    reason: String,
+    message: String,
+}
+
+fn publish_shutdown_phase(app: &AppHandle, state: &SharedState) {
+    if let Some(phase) = state.update_shutdown_phase() {
+        log::info!("shutdown phase: {:?}", phase.phase);
+        emit_if_possible(app, "shutdown-phase-changed", phase);
+    }
+}
+
+fn cancel_scheduled_shutdown(state: &SharedState, app: &AppHandle, reason: CancelReason) -> bool {
+    cancel_shutdown_from(state, app, reason, None)
+}
+
+// Like `cancel_scheduled_shutdown`, but leaves a schedule raised by anyone
+// other than `origin` in place.
+fn cancel_shutdown_from(
+    state: &SharedState,
+    app: &AppHandle,
+    reason: CancelReason,
+    origin: Option<EventOrigin>,
+) -> bool {
+    let had_schedule = state.clear_scheduled_shutdown(origin);
+    if !had_schedule {
+        return had_schedule;
+    }
+    publish_shutdown_phase(app, state);
+    log::info!("shutdown schedule cancelled ({})", reason.as_str());
+    state.record_simulation_step("shutdown_cancelled", reason.as_str());
+
+    emit_if_possible(
+        app,
+        "shutdown-cancelled",
+        ShutdownCancelledPayload {
+            reason: reason.as_str().to_string(),
+            message: reason.message().to_string(),
+        },
+    );
+    let settings = state.effective_settings();
+    if settings.notify_on_shutdown_cancel && settings.enable_notifications {
+        let _ = notify_windows(app, "Apagado cancelado", reason.message());
+    }
+    had_schedule
+}
+
+// With a sticky AC-fault shutdown the schedule only goes away once AC has
+// stayed back for the configured recovery window, so flapping power cannot
+// keep postponing it.
+fn confirm_sustained_ac_recovery(app: &AppHandle, state: &SharedState, settings: &AppSettings) {
+    let mut restored_at = lock(&state.ac_restored_at_ms);
+    let Some(since) = *restored_at else {
+        return;
+    };
+    let required_ms = settings
+        .shutdown_pc
+        .on_ac_fault
+        .sticky_recovery_seconds
+        .saturating_mul(1000);
+    if state.now_ms().saturating_sub(since) < required_ms {
+        return;
+    }
+    *restored_at = None;
+    drop(restored_at);
+
+    let _ = cancel_scheduled_shutdown(state, app, CancelReason::PowerRestored);
+}
+
+fn last_estimated_runtime(state: &SharedState) -> Option<u64> {
+    state
+        .live_status()
+        .and_then(|status| status.estimated_runtime)
+}
+
+// The AC-fault delay the battery can actually cover, or None when the
+// configured delay already fits (or the runtime is unknown).
+fn runtime_capped_delay(
+    delay_minutes: u64,
+    estimated_runtime: Option<u64>,
+    margin_minutes: u64,
+) -> Option<u64> {
+    let runtime = estimated_runtime?;
+    let cap = runtime.saturating_sub(margin_minutes).max(1);
+    (cap < delay_minutes).then_some(cap)
+}
+
+fn schedule_shutdown_after_minutes(
+    state: &SharedState,
+    app: &AppHandle,
+    delay_minutes: u64,
+    reason: &str,
+    forced: bool,
+    estimated_runtime: Option<u64>,
+) -> bool {
+    let Some(merged) = state.schedule_shutdown(delay_minutes, reason, forced) else {
+        return true;
+    };
+    log::info!(
+        "shutdown scheduled for {} ({}, requested by {})",
+        iso_from_millis(merged.at_ms),
+        merged.reason,
+        reason
+    );
+    state.record_simulation_step(
+        "shutdown_scheduled",
+        &format!("{} at {}", merged.reason, iso_from_millis(merged.at_ms)),
+    );
+
+    let remaining_ms = merged.at_ms.saturating_sub(state.now_ms());
+    emit_if_possible(
+        app,
+        "shutdown-scheduled",
+        ShutdownScheduledPayload {
+            minutes: remaining_ms.div_ceil(60_000),
+            shutdown_time: iso_from_millis(merged.at_ms),
+            reason: merged.reason,
+            estimated_runtime_minutes: estimated_runtime,
+        },
+    );
+    publish_shutdown_phase(app, state);
+    true
+}
+
+// Splits a command line into program + args. Double or single quotes group a
+// segment containing spaces; backslashes are literal so Windows paths survive.
+fn split_command_line(input: &str) -> Result<Vec<String>, String> {
+    let mut argv = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut quote: Option<char> = None;
+
+    for ch in input.chars() {
+        match quote {
+            Some(open) if ch == open => quote = None,
+            Some(_) => current.push(ch),
+            None if ch == '"' || ch == '\'' => {
+                quote = Some(ch);
+                in_token = true;
+            }
+            None if ch.is_whitespace() => {
+                if in_token {
+                    argv.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            None => {
+                current.push(ch);
+                in_token = true;
+            }
+        }
+    }
+
+    if quote.is_some() {
+        return Err("Comilla sin cerrar en el comando".to_string());
+    }
+    if in_token {
+        argv.push(current);
+    }
+    Ok(argv)
+}
+
+fn resolve_program(program: &str) -> Option<PathBuf> {
+    let direct = PathBuf::from(program);
+    if direct.components().count() > 1 || direct.is_absolute() {
+        return direct.is_file().then_some(direct);
+    }
+
+    let extensions = if cfg!(target_os = "windows") {
+        std::env::var("PATHEXT")
+            .unwrap_or_else(|_| ".EXE;.CMD;.BAT;.COM".to_string())
+            .split(';')
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    } else {
+        Vec::new()
+    };
+    let path_var = std::env::var_os("PATH")?;
+    for dir in std::env::split_paths(&path_var) {
+        let candidate = dir.join(program);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        for ext in &extensions {
+            let candidate = dir.join(format!("{}{}", program, ext));
+            if candidate.is_file() {
+                return Some(candidate);
+            }
+        }
+    }
+    None
+}
+
+fn builtin_shutdown_argv(action: &str) -> Vec<String> {
+    let argv: &[&str] = if cfg!(target_os = "windows") {
+        match action {
+            "sleep" => &["rundll32.exe", "powrprof.dll,SetSuspendState", "0,1,0"],
+            "restart" => &["shutdown", "/r", "/t", "0", "/f"],
+            _ => &["shutdown", "/s", "/t", "0", "/f"],
+        }
+    } else if cfg!(target_os = "macos") {
+        match action {
+            "sleep" => &["pmset", "sleepnow"],
+            "restart" => &[
+                "osascript",
+                "-e",
+                "tell application \"System Events\" to restart",
+            ],
+            _ => &["shutdown", "-h", "now"],
+        }
+    } else {
+        match action {
+            "sleep" => &["systemctl", "suspend"],
+            "restart" => &["systemctl", "reboot"],
+            _ => &["systemctl", "poweroff"],
+        }
+    };
+    argv.iter().map(|arg| arg.to_string()).collect()
+}
+
+// A manual trigger may pick its own action; it only applies while the schedule
+// still belongs to that trigger, so a protective shutdown that took it over
+// runs the configured command.
+fn argv_for_schedule(
+    settings: &AppSettings,
+    reason: &str,
+    manual_action: Option<&str>,
+) -> Result<Vec<String>, String> {
+    match manual_action {
+        Some(action) if reason == "manual-trigger" => Ok(builtin_shutdown_argv(action)),
+        _ => shutdown_argv(settings),
+    }
+}
+
+fn action_for_schedule<'a>(
+    settings: &'a AppSettings,
+    reason: &str,
+    manual_action: Option<&'a str>,
+) -> &'a str {
+    match manual_action {
+        Some(action) if reason == "manual-trigger" => action,
+        _ => &settings.shutdown_pc.action,
+    }
+}
+
+const SHELL_BUILTINS: [&str; 12] = [
+    "call", "cd", "copy", "del", "echo", "exit", "if", "mkdir", "move", "ren", "set", "start",
+];
+
+// Commands saved before argv parsing always ran through `cmd /C`; chaining,
+// pipes, redirection, variable expansion or a builtin keep that shell mode
+// so they still behave the same.
+fn needs_shell(command: &str) -> bool {
+    if command.contains(['&', '|', '<', '>', '%', '^', ';']) {
+        return true;
+    }
+    command
+        .split_whitespace()
+        .next()
+        .map(|first| SHELL_BUILTINS.contains(&first.to_ascii_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+fn shell_argv_prefix() -> &'static [&'static str] {
+    if cfg!(target_os = "windows") {
+        &["cmd", "/C"]
+    } else {
+        &["sh", "-c"]
+    }
+}
+
+// The program a shell-mode command starts with, so validation checks it and
+// not just the shell. Builtins and names behind a variable are left alone.
+fn shell_command_program(argv: &[String]) -> Option<String> {
+    let [shell, flag, command] = argv else {
+        return None;
+    };
+    if shell_argv_prefix() != [shell.as_str(), flag.as_str()] {
+        return None;
+    }
+    let head = command.split(['&', '|', '<', '>', ';']).next()?;
+    let first = split_command_line(head).ok()?.into_iter().next()?;
+    let is_builtin = SHELL_BUILTINS.contains(&first.to_ascii_lowercase().as_str());
+    (!is_builtin && !first.contains(['%', '$'])).then_some(first)
+}
+
+fn custom_command_argv(command: &str) -> Result<Vec<String>, String> {
+    let command = command.trim();
+    if needs_shell(command) {
+        return Ok(shell_argv_prefix()
+            .iter()
+            .map(|arg| arg.to_string())
+            .chain(std::iter::once(command.to_string()))
+            .collect());
+    }
+    let argv = split_command_line(command)?;
+    if argv.is_empty() {
+        return Err("Comando personalizado vacio".to_string());
+    }
+    Ok(argv)
+}
+
+fn shutdown_argv(settings: &AppSettings) -> Result<Vec<String>, String> {
+    let custom_command = settings.shutdown_pc.shutdown_command.trim();
+    if custom_command.is_empty() {
+        return Ok(builtin_shutdown_argv(&settings.shutdown_pc.action));
+    }
+    custom_command_argv(custom_command)
+}
+
+// Checked again when the command runs: a network share or removable drive
+// missing when settings load may well be back by the time of an outage.
+fn shutdown_working_directory(shutdown: &ShutdownPCSettings) -> Option<&str> {
+    let dir = shutdown.working_directory.as_deref()?;
+    if Path::new(dir).is_dir() {
+        return Some(dir);
+    }
+    log::warn!(
+        "shutdown working directory {} not found; using the app directory",
+        dir
+    );
+    None
+}
+
+fn execute_shutdown_argv(argv: &[String], shutdown: &ShutdownPCSettings) -> Result<(), String> {
+    let (program, args) = argv
+        .split_first()
+        .ok_or_else(|| "Comando de apagado vacio".to_string())?;
+    let mut command = Command::new(program);
+    command.args(args).envs(&shutdown.environment);
+    if let Some(dir) = shutdown_working_directory(shutdown) {
+        command.current_dir(dir);
+    }
+    command
+        .spawn()
+        .map(|_| ())
+        .map_err(|err| format!("No se pudo ejecutar {}: {}", program, err))
+}
+
+// Saves the power a large display draws during an outage while the machine
+// keeps running; any input wakes the screen again.
+#[cfg(target_os = "windows")]
+fn turn_display_off() -> Result<(), String> {
+    use windows::Win32::Foundation::{LPARAM, WPARAM};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        PostMessageW, HWND_BROADCAST, SC_MONITORPOWER, WM_SYSCOMMAND,
+    };
+
+    // 2 = power off. Posted rather than sent so a hung window cannot block the monitor.
+    unsafe {
+        PostMessageW(
+            HWND_BROADCAST,
+            WM_SYSCOMMAND,
+            WPARAM(SC_MONITORPOWER as usize),
+            LPARAM(2),
+        )
+    }
+    .map_err(|err| format!("No se pudo apagar la pantalla: {}", err))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn turn_display_off() -> Result<(), String> {
+    let argv: &[&str] = if cfg!(target_os = "macos") {
+        &["pmset", "displaysleepnow"]
+    } else {
+        &["xset", "dpms", "force", "off"]
+    };
+    Command::new(argv[0])
+        .args(&argv[1..])
+        .spawn()
+        .map(|_| ())
+        .map_err(|err| format!("No se pudo apagar la pantalla con {}: {}", argv[0], err))
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ShutdownCommandCheck {
+    argv: Vec<String>,
+    resolved_path: Option<String>,
+    ok: bool,
+    error: Option<String>,
+}
+
+fn check_shutdown_argv(argv: Result<Vec<String>, String>) -> ShutdownCommandCheck {
+    match argv {
+        Ok(argv) => {
+            let resolved = argv.first().and_then(|program| resolve_program(program));
+            let missing = match (&resolved, shell_command_program(&argv)) {
+                (None, _) => Some(argv.first().cloned().unwrap_or_default()),
+                (Some(_), Some(program)) if resolve_program(&program).is_none() => Some(program),
+                _ => None,
+            };
+            let error = missing.map(|program| format!("No se encontro el ejecutable: {}", program));
+            ShutdownCommandCheck {
+                ok: error.is_none(),
+                resolved_path: resolved.map(|path| path.to_string_lossy().to_string()),
+                argv,
+                error,
+            }
+        }
+        Err(error) => ShutdownCommandCheck {
+            argv: Vec::new(),
+            resolved_path: None,
+            ok: false,
+            error: Some(error),
+        },
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PreflightCheck {
+    name: String,
+    ok: bool,
+    // Not applicable with the current settings; never logged as a failure.
+    skipped: bool,
+    detail: String,
+}
+
+impl PreflightCheck {
+    fn passed(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            ok: true,
+            skipped: false,
+            detail: detail.into(),
+        }
+    }
+
+    fn failed(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            ok: false,
+            skipped: false,
+            detail: detail.into(),
+        }
+    }
+
+    fn skipped(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            ok: true,
+            skipped: true,
+            detail: detail.into(),
+        }
+    }
+}
+
+fn command_preflight(name: &str, check: ShutdownCommandCheck) -> PreflightCheck {
+    match (check.ok, check.resolved_path, check.error) {
+        (true, Some(path), _) => PreflightCheck::passed(name, path),
+        (_, _, error) => PreflightCheck::failed(name, error.unwrap_or_default()),
+    }
+}
+
+// Checks that only need the saved settings; notification delivery is added
+// by `run_preflight` because it needs the app handle.
+fn preflight_checks(state: &SharedState, settings: &AppSettings) -> Vec<PreflightCheck> {
+    let mut checks = Vec::new();
+
+    let action = &settings.shutdown_pc.action;
+    checks.push(command_preflight(
+        "shutdown_action",
+        check_shutdown_argv(Ok(builtin_shutdown_argv(action))),
+    ));
+
+    if settings.shutdown_pc.shutdown_command.trim().is_empty() {
+        checks.push(PreflightCheck::skipped(
+            "custom_command",
+            "Sin comando personalizado",
+        ));
+    } else {
+        checks.push(command_preflight(
+            "custom_command",
+            check_shutdown_argv(shutdown_argv(settings)),
+        ));
+    }
+
+    let kinds = [
+        AlertKind::AcFault,
+        AlertKind::BatteryLow,
+        AlertKind::BatteryCritical,
+        AlertKind::ChargeFailure,
+        AlertKind::UpsDisconnected,
+        AlertKind::UpsConnected,
+        AlertKind::TemperatureRising,
+        AlertKind::BatteryNotHolding,
+    ];
+    let sound_kinds: Vec<AlertKind> = kinds
+        .into_iter()
+        .filter(|kind| alert_config_for_kind(settings, *kind).has_channel("sound"))
+        .collect();
+    let missing: Vec<&str> = sound_kinds
+        .iter()
+        .filter(|kind| resolve_sound_path(state, settings, **kind).is_none())
+        .map(|kind| alert_sound_file_name(*kind))
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    checks.push(if sound_kinds.is_empty() {
+        PreflightCheck::skipped("sounds", "Ninguna alerta usa sonido")
+    } else if missing.is_empty() {
+        PreflightCheck::passed(
+            "sounds",
+            format!("{} alertas con sonido", sound_kinds.len()),
+        )
+    } else if cfg!(target_os = "windows") {
+        // play_fallback_beep still makes the alert audible, but the configured
+        // sound is gone all the same.
+        PreflightCheck::failed(
+            "sounds",
+            format!(
+                "No se encontraron: {} (se usara el pitido)",
+                missing.join(", ")
+            ),
+        )
+    } else {
+        PreflightCheck::failed(
+            "sounds",
+            format!("No se encontraron: {}", missing.join(", ")),
+        )
+    });
+
+    checks
+}
+
+fn notification_preflight(app: &AppHandle) -> PreflightCheck {
+    match app.notification().permission_state() {
+        Ok(tauri_plugin_notification::PermissionState::Granted) => {
+            PreflightCheck::passed("notifications", "Permiso concedido")
+        }
+        Ok(permission) => {
+            PreflightCheck::failed("notifications", format!("Permiso: {:?}", permission))
+        }
+        Err(error) => PreflightCheck::failed("notifications", error.to_string()),
+    }
+}
+
+fn run_preflight(app: &AppHandle, state: &SharedState) -> Vec<PreflightCheck> {
+    state
+        .last_preflight_ms
+        .store(state.now_ms(), Ordering::Relaxed);
+    let settings = lock(&state.settings).clone();
+    let mut checks = preflight_checks(state, &settings);
+    checks.push(notification_preflight(app));
+    for check in checks.iter().filter(|check| !check.ok) {
+        log::warn!("preflight check {} failed: {}", check.name, check.detail);
+        state.log_event(
+            "preflight_failed",
+            Severity::Warning,
+            &format!("{}: {}", check.name, check.detail),
+        );
+    }
+    checks
+}
+
+fn run_scheduled_preflight(app: &AppHandle, state: &SharedState) {
+    let last_run = state.last_preflight_ms.load(Ordering::Relaxed);
+    if state.now_ms().saturating_sub(last_run) >= PREFLIGHT_INTERVAL_MS {
+        run_preflight(app, state);
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum ShutdownPhase {
+    #[default]
+    Idle,
+    // An alert asked for a shutdown during the startup grace; it is scheduled
+    // once the grace ends if the condition still holds.
+    GracePeriod,
+    Scheduled,
+    AwaitingConfirmation,
+    Executing,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ShutdownPhaseState {
+    phase: ShutdownPhase,
+    reason: Option<String>,
+    // Shutdown time while scheduled, end of the window while awaiting confirmation.
+    deadline: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ShutdownImminentPayload {
+    seconds: u64,
+    reason: String,
+    deadline: String,
+    estimated_runtime_minutes: Option<u64>,
+}
+
+#[cfg(target_os = "windows")]
+fn user_idle_seconds() -> Option<u64> {
+    use windows::Win32::System::SystemInformation::GetTickCount;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+    if !unsafe { GetLastInputInfo(&mut info) }.as_bool() {
+        return None;
+    }
+    let now = unsafe { GetTickCount() };
+    Some(u64::from(now.wrapping_sub(info.dwTime)) / 1000)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn user_idle_seconds() -> Option<u64> {
+    None
+}
+
+// Holds a due shutdown for the confirmation window when someone is at the
+// keyboard. Unknown idle time counts as idle, and an expired window proceeds
+// with the shutdown so the protection stays fail-safe.
+fn awaiting_shutdown_confirmation(
+    app: &AppHandle,
+    state: &SharedState,
+    settings: &AppSettings,
+) -> bool {
+    let now = state.now_ms();
+    let mut deadline = lock(&state.shutdown_confirmation_deadline_ms);
+    if let Some(until) = *deadline {
+        if now < until {
+            return true;
+        }
+        *deadline = None;
+        return false;
+    }
+
+    let user_active = user_idle_seconds()
+        .map(|idle| idle < settings.shutdown_pc.user_active_within_seconds)
+        .unwrap_or(false);
+    if !settings.shutdown_pc.confirm_if_user_active || !user_active {
+        return false;
+    }
+
+    let window_seconds = settings.shutdown_pc.confirmation_window_seconds;
+    let until = now.saturating_add(window_seconds * 1000);
+    *deadline = Some(until);
+    drop(deadline);
+
+    let reason = lock(&state.scheduled_shutdown)
+        .as_ref()
+        .map(|schedule| schedule.reason.clone())
+        .unwrap_or_else(|| "shutdown-scheduled".to_string());
+    emit_if_possible(
+        app,
+        "shutdown-imminent",
+        ShutdownImminentPayload {
+            seconds: window_seconds,
+            reason,
+            deadline: iso_from_millis(until),
+            estimated_runtime_minutes: last_estimated_runtime(state),
+        },
+    );
+    publish_shutdown_phase(app, state);
+    show_main_window(app);
+    true
+}
+
+fn process_pending_shutdown(app: &AppHandle, state: &SharedState, settings: &AppSettings) {
+    if settings.monitor_only_mode {
+        return;
+    }
+
+    if !state.shutdown_due() || awaiting_shutdown_confirmation(app, state, settings) {
+        return;
+    }
+    if alert_test_holds_shutdown(state) {
+        return;
+    }
+
+    let Some((schedule, manual_action)) = state.take_due_shutdown() else {
+        return;
+    };
+    let ScheduledShutdown { reason, origin, .. } = schedule;
+    state
+        .shutdown_executing_ms
+        .store(state.now_ms(), Ordering::Relaxed);
+    publish_shutdown_phase(app, state);
+
+    log::warn!("executing shutdown ({})", reason);
+    let title = "Apagado de seguridad";
+    let message = format!("Ejecutando accion configurada ({})", reason);
+    let _ = notify_windows(app, title, &message);
+    if should_force_popup(app, state) {
+        force_alert_popup(
+            app,
+            state,
+            "shutdown-execution",
+            title,
+            &message,
+            "critical",
+        );
+    }
+    emit_urgent_alert(app, title, &message, "critical");
+    with_event_origin(origin, || {
+        state.log_event("shutdown_executed", Severity::Critical, &reason)
+    });
+    if origin == EventOrigin::Simulation {
+        let argv = argv_for_schedule(settings, &reason, manual_action.as_deref());
+        log::warn!("simulation: shutdown command not run ({:?})", argv);
+        with_event_origin(origin, || {
+            state.log_event(
+                "shutdown_command",
+                Severity::Info,
+                &format!("{:?} not run", argv),
+            )
+        });
+        state.shutdown_executing_ms.store(0, Ordering::Relaxed);
+        publish_shutdown_phase(app, state);
+        return;
+    }
+    state.write_shutdown_marker(
+        &reason,
+        action_for_schedule(settings, &reason, manual_action.as_deref()),
+    );
+    state.record_controlled_shutdown();
+    if let Err(error) = state.flush_storage() {
+        log::error!("flush before shutdown failed: {}", error);
+    }
+
+    let result = argv_for_schedule(settings, &reason, manual_action.as_deref()).and_then(|argv| {
+        state.log_event(
+            "shutdown_command",
+            Severity::Critical,
+            &format!("{:?}", argv),
+        );
+        execute_shutdown_argv(&argv, &settings.shutdown_pc)
+    });
+    if let Err(error) = result {
+        state.clear_shutdown_marker();
+        state.shutdown_executing_ms.store(0, Ordering::Relaxed);
+        publish_shutdown_phase(app, state);
+        emit_error_once(app, state, error);
+    }
+}
+
+// Only presentation mode and full-screen apps count as quiet. A locked
+// session (QUNS_NOT_PRESENT) still wants the popup, and Focus Assist is not
+// reported by this API at all.
+#[cfg(target_os = "windows")]
+fn focus_mode_active() -> bool {
+    use windows::Win32::UI::Shell::{
+        SHQueryUserNotificationState, QUNS_BUSY, QUNS_PRESENTATION_MODE,
+        QUNS_RUNNING_D3D_FULL_SCREEN,
+    };
+
+    unsafe { SHQueryUserNotificationState() }
+        .map(|state| {
+            state == QUNS_BUSY
+                || state == QUNS_RUNNING_D3D_FULL_SCREEN
+                || state == QUNS_PRESENTATION_MODE
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn focus_mode_active() -> bool {
+    false
+}
+
+fn raise_popup(
+    app: &AppHandle,
+    state: &SharedState,
+    kind: AlertKind,
+    title: &str,
+    message: &str,
+    quiet: bool,
+) {
+    emit_urgent_alert(app, title, message, kind.alert_type());
+    if !quiet && should_force_popup(app, state) {
+        force_alert_popup(
+            app,
+            state,
+            kind.event_name(),
+            title,
+            message,
+            kind.alert_type(),
+        );
+    }
+}
+
+// Sends one alert through every channel routed for its kind and reports how
+// each channel fared.
+fn dispatch_alert(
+    app: &AppHandle,
+    state: &SharedState,
+    settings: &AppSettings,
+    kind: AlertKind,
+    title: &str,
+    message: &str,
+) -> Vec<ChannelResult> {
+    let labeled_title = settings
+        .custom_name
+        .as_ref()
+        .map(|name| format!("{} · {}", name, title));
+    let title = labeled_title.as_deref().unwrap_or(title);
+    let config = alert_config_for_kind(settings, kind);
+    let below_minimum = kind.severity() < settings.minimum_notification_severity;
+    let quiet = kind.severity() < Severity::Critical
+        && !settings.alert_in_focus_mode
+        && focus_mode_active();
+    let results: Vec<ChannelResult> = config
+        .channels
+        .iter()
+        .map(|channel| {
+            let outcome = match channel.as_str() {
+                "notification" | "popup" if below_minimum => {
+                    Err("Por debajo de la severidad minima".to_string())
+                }
+                "notification" if !settings.enable_notifications => {
+                    Err("Notificaciones desactivadas".to_string())
+                }
+                "notification" => {
+                    if notify_windows(app, title, message) {
+                        Ok(())
+                    } else {
+                        Err("No se pudo mostrar la notificacion".to_string())
+                    }
+                }
+                "popup" => {
+                    raise_popup(app, state, kind, title, message, quiet);
+                    Ok(())
+                }
+                "sound" if quiet => Err("Modo concentracion activo".to_string()),
+                "sound" => {
+                    // Without an output device the beep still plays and the
+                    // alert also gets a popup unless it already has one.
+                    let sound_path = resolve_sound_path(state, settings, kind);
+                    let audio_present = play_sound_with_generation(
+                        app,
+                        state.clone(),
+                        sound_path,
+                        config.sound_repeats,
+                    );
+                    if audio_present {
+                        Ok(())
+                    } else {
+                        if settings.popup_when_audio_unavailable
+                            && !below_minimum
+                            && !config.has_channel("popup")
+                        {
+                            raise_popup(app, state, kind, title, message, quiet);
+                        }
+                        Err("Sin dispositivo de audio".to_string())
+                    }
+                }
+                other => Err(format!("Canal desconocido: {}", other)),
+            };
+            ChannelResult {
+                channel: channel.clone(),
+                ok: outcome.is_ok(),
+                error: outcome.err(),
+            }
+        })
+        .collect();
+    for result in &results {
+        match &result.error {
+            None => log::info!("alert {:?} sent via {}", kind, result.channel),
+            Some(error) => log::info!("alert {:?} skipped {}: {}", kind, result.channel, error),
+        }
+    }
+    results
+}
+
+// Sends the last alert again through its channels, without escalation or a
+// new shutdown schedule.
+fn replay_last_alert_for(
+    app: &AppHandle,
+    state: &SharedState,
+) -> Result<Vec<ChannelResult>, String> {
+    let settings = state.effective_settings();
+    if settings.monitor_only_mode {
+        return Err("Modo solo monitor activo".to_string());
+    }
+    let (kind, payload) = lock(&state.last_alert)
+        .clone()
+        .ok_or_else(|| "No hay alertas recientes".to_string())?;
+    log::info!("replaying alert {:?} from {}", kind, payload.created_at);
+    Ok(dispatch_alert(
+        app,
+        state,
+        &settings,
+        kind,
+        &payload.title,
+        &payload.message,
+    ))
+}
+
+fn alert_condition_active(state: &SharedState, kind: AlertKind) -> bool {
+    match kind {
+        AlertKind::AcFault => *lock(&state.is_on_battery),
+        AlertKind::BatteryLow => *lock(&state.was_battery_low),
+        AlertKind::BatteryCritical => *lock(&state.was_battery_critical),
+        // A failed recharge has no state that clears it; it escalates until acknowledged.
+        AlertKind::ChargeFailure => true,
+        AlertKind::UpsDisconnected => !*lock(&state.is_connected),
+        AlertKind::UpsConnected => false,
+        AlertKind::TemperatureRising => *lock(&state.temperature_rising),
+        AlertKind::BatteryNotHolding => *lock(&state.battery_not_holding),
+    }
+}
+
+fn start_escalation(state: &SharedState, settings: &AppSettings, kind: AlertKind, message: &str) {
+    let config = alert_config_for_kind(settings, kind);
+    if !config.escalate || !(config.has_channel("sound") || config.has_channel("popup")) {
+        return;
+    }
+    let now = state.now_ms();
+    let mut escalations = lock(&state.escalations);
+    escalations.retain(|item| {
+        item.kind != kind
+            && !(kind == AlertKind::BatteryCritical && item.kind == AlertKind::BatteryLow)
+    });
+    escalations.push(Escalation {
+        kind,
+        message: message.to_string(),
+        started_ms: now,
+        last_played_ms: now,
+    });
+}
+
+fn clear_escalations(state: &SharedState) {
+    lock(&state.escalations).clear();
+}
+
+// Drops escalations that were cleared or ran past the cap, and returns the ones due again.
+fn due_escalations(
+    state: &SharedState,
+    settings: &AppSettings,
+    now: u64,
+) -> Vec<(AlertKind, String)> {
+    let mut escalations = lock(&state.escalations);
+    escalations.retain(|item| {
+        let config = alert_config_for_kind(settings, item.kind);
+        config.escalate
+            && now.saturating_sub(item.started_ms) < ESCALATION_MAX_DURATION_MS
+            && alert_condition_active(state, item.kind)
+    });
+
+    let mut due = Vec::new();
+    for item in escalations.iter_mut() {
+        let config = alert_config_for_kind(settings, item.kind);
+        let delay_ms = config.repeat_delay_seconds.saturating_mul(1000);
+        if now.saturating_sub(item.last_played_ms) >= delay_ms {
+            item.last_played_ms = now;
+            due.push((item.kind, item.message.clone()));
+        }
+    }
+    due
+}
+
+fn run_alert_escalations(app: &AppHandle, state: &SharedState, settings: &AppSettings) {
+    for (kind, message) in due_escalations(state, settings, state.now_ms()) {
+        // Repeats only the attention-grabbing channels; the notification was already shown.
+        let mut repeat_settings = settings.clone();
+        for config in repeat_settings.alerts.configs_mut() {
+            config.channels.retain(|channel| channel != "notification");
+        }
+        let _ = dispatch_alert(
+            app,
+            state,
+            &repeat_settings,
+            kind,
+            kind.event_name(),
+            &message,
+        );
+    }
+}
+
+fn handle_alert_transition(
+    app: &AppHandle,
+    state: &SharedState,
+    settings: &AppSettings,
+    kind: AlertKind,
+    status: &UpsData,
+    schedule_shutdown: bool,
+) -> Vec<ChannelResult> {
+    if settings.monitor_only_mode {
+        return Vec::new();
+    }
+
+    let message = format!(
+        "Entrada {:.1}V · Bateria {}% · Carga {}%",
+        status.input_voltage, status.battery_percent, status.load_percent
+    );
+    let title = match (kind, status.utility_state) {
+        (AlertKind::AcFault, UtilityState::Degraded) => "Energia degradada",
+        (AlertKind::AcFault, UtilityState::Lost) => "Corte total de energia",
+        _ => kind.event_name(),
+    };
+    // Recorded here rather than in dispatch_alert so escalations, replays and
+    // routing tests never replace the alert a replay should repeat.
+    *lock(&state.last_alert) = Some((
+        kind,
+        urgent_alert_payload(title, &message, kind.alert_type()),
+    ));
+    let results = dispatch_alert(app, state, settings, kind, title, &message);
+    if settings.visual_alerts.enabled && kind.severity() >= settings.minimum_notification_severity {
+        start_visual_alert(app, state, &settings.visual_alerts);
+    }
+    start_escalation(state, settings, kind, &message);
+    if !schedule_shutdown {
+        return results;
+    }
+
+    if in_startup_grace(state, settings) {
+        let mut deferred = lock(&state.deferred_shutdown_kinds);
+        if !deferred.contains(&kind) {
+            deferred.push(kind);
+        }
+        drop(deferred);
+        publish_shutdown_phase(app, state);
+        return results;
+    }
+    schedule_shutdown_for_kind(app, state, settings, kind, status.estimated_runtime);
+    results
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TemperatureTrend {
+    // °C per hour over the trend window.
+    Rising(f64),
+    Cleared(f64),
+}
+
+fn handle_temperature_trend(
+    app: &AppHandle,
+    state: &SharedState,
+    settings: &AppSettings,
+    trend: TemperatureTrend,
+    temperature: f64,
+) {
+    match trend {
+        TemperatureTrend::Rising(rate) => {
+            let remarks = format!("+{:.1} C/h ({:.1} C)", rate, temperature);
+            log::warn!("temperature rising: {}", remarks);
+            state.log_event("temperature_rising", Severity::Warning, &remarks);
+            if settings.monitor_only_mode {
+                return;
+            }
+            let kind = AlertKind::TemperatureRising;
+            let message = format!(
+                "La temperatura sube {:.1} C/h en {} min (actual {:.1} C)",
+                rate, settings.temperature_trend.window_minutes, temperature
+            );
+            let _ = dispatch_alert(app, state, settings, kind, kind.event_name(), &message);
+            start_escalation(state, settings, kind, &message);
+        }
+        TemperatureTrend::Cleared(rate) => {
+            let remarks = format!("{:+.1} C/h ({:.1} C)", rate, temperature);
+            log::info!("temperature stable: {}", remarks);
+            state.log_event("temperature_stable", Severity::Info, &remarks);
+        }
+    }
+}
+
+fn handle_battery_not_holding(
+    app: &AppHandle,
+    state: &SharedState,
+    settings: &AppSettings,
+    percent: u64,
+) {
+    let standby = &settings.standby_charge;
+    let remarks = format!(
+        "{}% on AC for {}h (min {}%)",
+        percent, standby.duration_hours, standby.min_percent
+    );
+    log::warn!("battery not holding charge: {}", remarks);
+    state.log_event("battery_not_holding", Severity::Warning, &remarks);
+    state.save_runtime_state();
+    if settings.monitor_only_mode {
+        return;
+    }
+    let kind = AlertKind::BatteryNotHolding;
+    let message = format!(
+        "La bateria sigue en {}% tras {} h con energia; puede estar danada o sin cargar",
+        percent, standby.duration_hours
+    );
+    let _ = dispatch_alert(app, state, settings, kind, kind.event_name(), &message);
+    start_escalation(state, settings, kind, &message);
+}
+
+fn in_startup_grace(state: &SharedState, settings: &AppSettings) -> bool {
+    let started = state.monitoring_started_ms.load(Ordering::Relaxed);
+    let grace_ms = settings.startup_grace_seconds.saturating_mul(1000);
+    started > 0 && state.now_ms().saturating_sub(started) < grace_ms
+}
+
+// Once the startup grace ends, schedules the shutdowns it held back if their
+// condition still holds.
+fn apply_deferred_shutdowns(app: &AppHandle, state: &SharedState, settings: &AppSettings) {
+    if in_startup_grace(state, settings) {
+        return;
+    }
+    let deferred = std::mem::take(&mut *lock(&state.deferred_shutdown_kinds));
+    for kind in deferred {
+        if alert_condition_active(state, kind) {
+            schedule_shutdown_for_kind(app, state, settings, kind, last_estimated_runtime(state));
+        }
+    }
+}
+
+fn schedule_shutdown_for_kind(
+    app: &AppHandle,
+    state: &SharedState,
+    settings: &AppSettings,
+    kind: AlertKind,
+    estimated_runtime: Option<u64>,
+) {
+    match kind {
+        AlertKind::AcFault if settings.shutdown_pc.on_ac_fault.enabled => {
+            let on_ac_fault = &settings.shutdown_pc.on_ac_fault;
+            let mut delay_minutes = on_ac_fault.delay_minutes;
+            if on_ac_fault.cap_delay_to_runtime {
+                if let Some(capped) = runtime_capped_delay(
+                    delay_minutes,
+                    estimated_runtime,
+                    on_ac_fault.runtime_safety_margin_minutes,
+                ) {
+                    let remarks = format!(
+                        "Delay {} min exceeds estimated runtime {} min; capped to {} min",
+                        delay_minutes,
+                        estimated_runtime.unwrap_or_default(),
+                        capped
+                    );
+                    log::warn!("{}", remarks);
+                    state.log_event("shutdown_delay_capped", Severity::Warning, &remarks);
+                    delay_minutes = capped;
+                }
+            }
+            let _ = schedule_shutdown_after_minutes(
+                state,
+                app,
+                delay_minutes,
+                "ac-fault",
+                false,
+                estimated_runtime,
+            );
+        }
+        AlertKind::BatteryLow if settings.shutdown_pc.on_battery_low.enabled => {
+            let _ = schedule_shutdown_after_minutes(
+                state,
+                app,
+                BATTERY_LOW_SHUTDOWN_DELAY_MINUTES,
+                "battery-low",
+                false,
+                estimated_runtime,
+            );
+        }
+        AlertKind::BatteryCritical if settings.shutdown_pc.on_battery_critical.enabled => {
+            let _ = schedule_shutdown_after_minutes(
+                state,
+                app,
+                BATTERY_CRITICAL_SHUTDOWN_DELAY_MINUTES,
+                "battery-critical",
+                false,
+                estimated_runtime,
+            );
+        }
+        _ => {}
+    }
+}
+
+fn poll_ups(
+    app: &AppHandle,
+    state: &SharedState,
+    api: &HidApi,
+    connected_device: &mut Option<hidapi::HidDevice>,
+    read_timeout_ms: i32,
+) {
+    if let Some(device) = connected_device.as_ref() {
+        clear_last_error(state);
+        let device_path = lock(&state.device_path).clone().unwrap_or_default();
+        mark_connected(app, state, &device_path);
+        let queued = std::mem::take(&mut *lock(&state.device_commands));
+        for command in queued {
+            let _ = send_megatec_command(device, &command);
+        }
+        if !read_one_packet(app, state, device, read_timeout_ms) {
+            *connected_device = None;
+        }
+        return;
+    }
+
+    let mut found = false;
+
+    for device_info in api.device_list() {
+        if device_info.vendor_id() != UPS_VID || device_info.product_id() != UPS_PID {
+            continue;
+        }
+
+        found = true;
+
+        let info = UpsInfo {
+            manufacturer: device_info
+                .manufacturer_string()
+                .unwrap_or("RICHCOMM")
+                .to_string(),
+            product: device_info
+                .product_string()
+                .unwrap_or("UPS USB Mon")
+                .to_string(),
+            vendor_id: format!("{:04X}", UPS_VID),
+            product_id: format!("{:04X}", UPS_PID),
+            firmware: lock(&state.device_info)
+                .as_ref()
+                .and_then(|existing| existing.firmware.clone()),
+            custom_name: None,
+            display_name: String::new(),
+        };
+        *lock(&state.device_info) = Some(info);
+
+        let path = device_info.path().to_string_lossy();
+        if state.device_open_deferred(&path) {
+            continue;
+        }
+        match api.open_path(device_info.path()) {
+            Ok(device) => {
+                lock(&state.permission_backoff).remove(path.as_ref());
+                *lock(&state.setup_guidance) = None;
+                clear_last_error(state);
+                mark_connected(app, state, &device_info.path().to_string_lossy());
+                let _ = send_megatec_command(&device, MEGATEC_INFO_QUERY);
+                *connected_device = Some(device);
+                if let Some(active_device) = connected_device.as_ref() {
+                    let _ = read_one_packet(app, state, active_device, read_timeout_ms);
+                }
+                return;
+            }
+            Err(error) if is_permission_error(&error.to_string()) => {
+                report_permission_error(app, state, device_info, error.to_string());
+            }
+            Err(error) => {
+                emit_error_once(app, state, format!("Cannot open UPS HID device: {}", error));
+            }
+        }
+    }
+
+    if !found {
+        clear_last_error(state);
+        mark_disconnected(app, state);
+    }
+}
+
+fn send_megatec_command(device: &hidapi::HidDevice, command: &[u8]) -> bool {
+    // Report ID 0 followed by the ASCII command, padded to an 8-byte report.
+    let mut report = [0u8; 9];
+    for (slot, byte) in report[1..].iter_mut().zip(command) {
+        *slot = *byte;
+    }
+    device.write(&report).is_ok()
+}
+
+fn read_one_packet(
+    app: &AppHandle,
+    state: &SharedState,
+    device: &hidapi::HidDevice,
+    read_timeout_ms: i32,
+) -> bool {
+    let (buffer_size, report_id_prefixed, frame_format, debug_capture) = {
+        let settings = lock(&state.settings);
+        (
+            settings.hid_read_buffer_size as usize,
+            settings.report_id_prefixed,
+            FrameFormat::from_settings(&settings),
+            settings.debug_capture,
+        )
+    };
+    let mut buffer = vec![0u8; buffer_size];
+    match device.read_timeout(&mut buffer, read_timeout_ms) {
+        Ok(size) if size > 0 => {
+            let payload = hid_report_payload(&buffer[..size], report_id_prefixed);
+            let frames = {
+                let mut pending = lock(&state.frame_buffer);
+                take_complete_frames(&mut pending, payload)
+            };
+            for frame in frames {
+                let decoded = decode_packet(&frame, &frame_format);
+                state.record_raw_frame(
+                    RawFrameInfo {
+                        ascii: frame_ascii(&frame),
+                        parsed: decoded.is_some(),
+                        received_at: now_iso(),
+                    },
+                    debug_capture,
+                );
+                let Some(decoded) = decoded else {
+                    continue;
+                };
+                state.record_packet();
+                match decoded {
+                    DecodedPacket::Version(firmware) => {
+                        if let Some(info) = lock(&state.device_info).as_mut() {
+                            info.firmware = Some(firmware);
+                        }
+                        state.firmware_generation.fetch_add(1, Ordering::Relaxed);
+                    }
+                    DecodedPacket::Status(status) => {
+                        if state.simulation_active() {
+                            observe_real_packet(state, *status);
+                        } else {
+                            handle_status_packet(app, state, *status);
+                        }
+                    }
+                }
+            }
+            true
+        }
+        Ok(_) => true,
+        Err(error) => {
+            emit_error_once(app, state, format!("HID read error: {}", error));
+            mark_disconnected(app, state);
+            false
+        }
+    }
+}
+
+// Returns true when the deadline of a post-outage recharge expectation passed
+// without the battery reaching its target.
+fn check_recharge_progress(
+    state: &SharedState,
+    settings: &AppSettings,
+    status: &UpsData,
+    is_on_battery: bool,
+) -> bool {
+    let Some(expectation) = lock(&state.recharge_expectation).clone() else {
+        return false;
+    };
+
+    if is_on_battery || !settings.recharge_check.enabled {
+        state.set_recharge_expectation(None);
+        return false;
+    }
+
+    if status.battery_percent >= expectation.target_percent {
+        state.set_recharge_expectation(None);
+        return false;
+    }
+
+    if state.now_ms() < expectation.deadline_ms {
+        return false;
+    }
+
+    state.set_recharge_expectation(None);
+    let remarks = format!(
+        "Battery {}% -> {}% ({:.1}V -> {:.1}V) after {} min on AC, expected {}%",
+        expectation.baseline_percent,
+        status.battery_percent,
+        expectation.baseline_voltage,
+        status.battery_voltage,
+        state.now_ms().saturating_sub(expectation.restored_at_ms) / 60_000,
+        expectation.target_percent
+    );
+    state.log_event("charge_failure", Severity::Warning, &remarks);
+    true
+}
+
+fn start_recharge_expectation(state: &SharedState, settings: &AppSettings, status: &UpsData) {
+    if !settings.recharge_check.enabled {
+        return;
+    }
+
+    let target_percent =
+        (status.battery_percent + settings.recharge_check.min_rise_percent).min(95);
+    if status.battery_percent >= target_percent {
+        return;
+    }
+
+    let restored_at_ms = state.now_ms();
+    state.set_recharge_expectation(Some(RechargeExpectation {
+        restored_at_ms,
+        deadline_ms: restored_at_ms.saturating_add(
+            settings
+                .recharge_check
+                .window_minutes
+                .saturating_mul(60_000),
+        ),
+        baseline_percent: status.battery_percent,
+        baseline_voltage: status.battery_voltage,
+        target_percent,
+    }));
+}
+
+fn strict_restore_confirmed(
+    state: &SharedState,
+    strict: &StrictRestoreSettings,
+    status: &UpsData,
+) -> bool {
+    let reference =
+        lock(&state.last_ac_reading).unwrap_or((status.input_voltage, status.frequency));
+    let ((min_voltage, max_voltage), (min_frequency, max_frequency)) =
+        strict_restore_bands(strict, reference);
+    let in_band = (min_frequency..=max_frequency).contains(&status.frequency)
+        && (min_voltage..=max_voltage).contains(&status.input_voltage);
+
+    let mut stable_samples = lock(&state.stable_ac_samples);
+    if !in_band {
+        *stable_samples = 0;
+        return false;
+    }
+    *stable_samples += 1;
+    if *stable_samples < strict.required_samples {
+        return false;
+    }
+    *stable_samples = 0;
+    true
+}
+
+// Least-squares slope of battery percent over time, in percent per minute.
+fn percent_slope_per_minute(samples: &[(u64, f64)]) -> Option<f64> {
+    let first = samples.first()?.0;
+    let last = samples.last()?.0;
+    if samples.len() < PROJECTION_MIN_SAMPLES || last.saturating_sub(first) < PROJECTION_MIN_SPAN_MS
+    {
+        return None;
+    }
+    least_squares_slope(samples, 60_000.0)
+}
+
+// Least-squares slope of (time ms, value) samples, per `unit_ms` of time.
+fn least_squares_slope(samples: &[(u64, f64)], unit_ms: f64) -> Option<f64> {
+    let first = samples.first()?.0;
+    let count = samples.len() as f64;
+    let points = samples
+        .iter()
+        .map(|(at, value)| (at.saturating_sub(first) as f64 / unit_ms, *value))
+        .collect::<Vec<_>>();
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / count;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / count;
+    let covariance = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum::<f64>();
+    let variance = points
+        .iter()
+        .map(|(x, _)| (x - mean_x).powi(2))
+        .sum::<f64>();
+    if variance <= f64::EPSILON {
+        return None;
+    }
+    Some(covariance / variance)
+}
+
+// Minutes until the trend reaches 0% (discharging) or 100% (charging); None
+// when there is not enough data or the trend is flat or points the other way.
+fn project_percent_minutes(
+    samples: &[(u64, f64)],
+    current_percent: f64,
+    discharging: bool,
+) -> Option<u64> {
+    let slope = percent_slope_per_minute(samples)?;
+    if discharging && slope < -PROJECTION_MIN_SLOPE_PER_MINUTE {
+        return Some((current_percent / -slope).round().max(0.0) as u64);
+    }
+    if !discharging && slope > PROJECTION_MIN_SLOPE_PER_MINUTE {
+        return Some(((100.0 - current_percent) / slope).round().max(0.0) as u64);
+    }
+    None
+}
+
+// Highest battery percent at which an enabled battery-level trigger shuts the
+// PC down; the low threshold is never below the critical one.
+fn shutdown_percent_threshold(settings: &AppSettings) -> Option<u64> {
+    if settings.shutdown_pc.on_battery_low.enabled {
+        Some(settings.low_battery_threshold)
+    } else if settings.shutdown_pc.on_battery_critical.enabled {
+        Some(settings.critical_battery_threshold)
+    } else {
+        None
+    }
+}
+
+// Timestamp at which a draining trend reaches `target_percent`; `slope` is
+// negative, in percent per minute.
+fn projected_time_at(now: u64, current_percent: f64, target_percent: f64, slope: f64) -> String {
+    let minutes = (current_percent - target_percent).max(0.0) / -slope;
+    iso_from_millis(now.saturating_add((minutes * 60_000.0).round() as u64))
+}
+
+// Returns the percent slope while on AC so the charge cycle can be tracked.
+fn apply_percent_projections(
+    state: &SharedState,
+    status: &mut UpsData,
+    is_on_battery: bool,
+    was_on_battery: bool,
+    shutdown_percent: Option<u64>,
+) -> Option<f64> {
+    let now = state.now_ms();
+    let percent = status.battery_percent as f64;
+    let mut samples = lock(&state.percent_samples);
+    if is_on_battery != was_on_battery {
+        samples.clear();
+    }
+    samples.push_back((now, percent));
+    while samples
+        .front()
+        .map(|(at, _)| now.saturating_sub(*at) > PROJECTION_WINDOW_MS)
+        .unwrap_or(false)
+    {
+        samples.pop_front();
+    }
+    let window = samples.iter().copied().collect::<Vec<_>>();
+    drop(samples);
+
+    if is_on_battery {
+        status.projected_minutes_remaining =
+            project_percent_minutes(&window, percent, true).or(status.estimated_runtime);
+        // The least-squares fit over the window already smooths single-sample
+        // noise, and yields nothing until enough samples span a minute.
+        if let Some(slope) = percent_slope_per_minute(&window)
+            .filter(|slope| *slope < -PROJECTION_MIN_SLOPE_PER_MINUTE)
+        {
+            status.projected_empty_at = Some(projected_time_at(now, percent, 0.0, slope));
+            status.projected_shutdown_at = shutdown_percent
+                .map(|threshold| projected_time_at(now, percent, threshold as f64, slope));
+        }
+        return None;
+    }
+
+    let slope = percent_slope_per_minute(&window);
+    if status.battery_percent < 100 {
+        status.charging_projection_minutes = project_percent_minutes(&window, percent, false);
+        status.charging = slope
+            .map(|value| value > PROJECTION_MIN_SLOPE_PER_MINUTE)
+            .unwrap_or(false);
+    }
+    slope
+}
+
+// A charge cycle starts when AC returns or a rising trend is seen, and ends
+// with `battery-full` once the percent sits at the top with a flat trend.
+fn update_charge_cycle(
+    app: &AppHandle,
+    state: &SharedState,
+    status: &UpsData,
+    is_on_battery: bool,
+    was_on_battery: bool,
+    slope: Option<f64>,
+) {
+    if state.advance_charge_cycle(status, is_on_battery, was_on_battery, slope) {
+        emit_if_possible(app, "battery-full", status.battery_percent);
+    }
+}
+
+// Keeps an outage restored from disk only if the UPS is still on battery and the
+// session is recent; otherwise closes it so reports and timers stay consistent.
+fn reconcile_restored_outage(state: &SharedState, status: &UpsData) {
+    if !state.restored_outage_pending.swap(false, Ordering::Relaxed) {
+        return;
+    }
+
+    let started_at = *lock(&state.battery_start_ms);
+    let recent = started_at
+        .map(|started| state.now_ms().saturating_sub(started) < RESTORED_OUTAGE_MAX_AGE_MS)
+        .unwrap_or(false);
+    if status.status.utility_fail && recent {
+        let since = started_at.map(iso_from_millis).unwrap_or_default();
+        state.log_event(
+            "outage_resumed",
+            Severity::Info,
+            &format!("On battery since {}", since),
+        );
+        return;
+    }
+
+    *lock(&state.is_on_battery) = false;
+    *lock(&state.battery_start_ms) = None;
+    *lock(&state.was_battery_low) = false;
+    *lock(&state.was_battery_critical) = false;
+    state.end_discharge_session();
+    let remarks = if status.status.utility_fail {
+        "Restored outage too old; discarded"
+    } else {
+        "Power returned while the app was stopped"
+    };
+    state.log_event("ac_restored", Severity::Info, remarks);
+    state.save_runtime_state();
+}
+
+// The UPS cuts power on its own timer once shutdown_active is set, which can be
+// shorter than ours. Shut down now, skipping the user confirmation window.
+fn escalate_for_ups_shutdown(
+    app: &AppHandle,
+    state: &SharedState,
+    settings: &AppSettings,
+    status: &UpsData,
+) {
+    if !status.status.shutdown_active || settings.monitor_only_mode {
+        return;
+    }
+
+    let now = state.now_ms();
+    let mut schedule_guard = lock(&state.scheduled_shutdown);
+    let previous = schedule_guard.clone();
+    let Some(escalated) = ups_shutdown_escalation(schedule_guard.as_ref(), now) else {
+        return;
+    };
+    *schedule_guard = Some(escalated);
+    drop(schedule_guard);
+    *lock(&state.shutdown_confirmation_deadline_ms) = Some(0);
+    log::warn!("UPS reported shutdown_active, shutting down now");
+
+    let remarks = previous
+        .map(|schedule| {
+            format!(
+                "{}, {}s early",
+                schedule.reason,
+                (schedule.at_ms - now) / 1000
+            )
+        })
+        .unwrap_or_default();
+    state.log_event("ups_shutdown_escalated", Severity::Critical, &remarks);
+
+    let title = "Apagado inmediato";
+    let message = "El UPS va a cortar la energia; apagando el equipo ahora";
+    let _ = notify_windows(app, title, message);
+    emit_urgent_alert(app, title, message, "critical");
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RejectedReading {
+    field: String,
+    value: f64,
+    min: f64,
+    max: f64,
+    substituted: f64,
+}
+
+fn substitute_reading(
+    rejected: &mut Vec<RejectedReading>,
+    field: &str,
+    value: &mut f64,
+    last: Option<f64>,
+    min: f64,
+    max: f64,
+) {
+    let in_range = |candidate: &f64| candidate.is_finite() && (min..=max).contains(candidate);
+    if in_range(value) {
+        return;
+    }
+    let substituted = last.filter(in_range).unwrap_or(if value.is_finite() {
+        value.clamp(min, max)
+    } else {
+        min
+    });
+    rejected.push(RejectedReading {
+        field: field.to_string(),
+        value: *value,
+        min,
+        max,
+        substituted,
+    });
+    *value = substituted;
+}
+
+// Replaces out-of-range fields with the last known value, or the nearest
+// bound when there is none, and reports what was replaced.
+fn sanitize_reading(
+    status: &mut UpsData,
+    last: Option<&UpsData>,
+    limits: &ReadingLimits,
+) -> Vec<RejectedReading> {
+    let mut rejected = Vec::new();
+    let (voltage_min, voltage_max) = (limits.voltage_min, limits.voltage_max);
+    substitute_reading(
+        &mut rejected,
+        "inputVoltage",
+        &mut status.input_voltage,
+        last.map(|item| item.input_voltage),
+        voltage_min,
+        voltage_max,
+    );
+    substitute_reading(
+        &mut rejected,
+        "faultVoltage",
+        &mut status.fault_voltage,
+        last.map(|item| item.fault_voltage),
+        voltage_min,
+        voltage_max,
+    );
+    substitute_reading(
+        &mut rejected,
+        "outputVoltage",
+        &mut status.output_voltage,
+        last.map(|item| item.output_voltage),
+        voltage_min,
+        voltage_max,
+    );
+
+    let mut load = status.load_percent as f64;
+    substitute_reading(
+        &mut rejected,
+        "loadPercent",
+        &mut load,
+        last.map(|item| item.load_percent as f64),
+        0.0,
+        limits.load_max as f64,
+    );
+    status.load_percent = load.round() as u64;
+
+    substitute_reading(
+        &mut rejected,
+        "frequency",
+        &mut status.frequency,
+        last.map(|item| item.frequency),
+        limits.frequency_min,
+        limits.frequency_max,
+    );
+    substitute_reading(
+        &mut rejected,
+        "batteryVoltage",
+        &mut status.battery_voltage,
+        last.map(|item| item.battery_voltage),
+        0.0,
+        limits.battery_voltage_max,
+    );
+    substitute_reading(
+        &mut rejected,
+        "temperature",
+        &mut status.temperature,
+        last.map(|item| item.temperature),
+        limits.temperature_min,
+        limits.temperature_max,
+    );
+
+    if rejected
+        .iter()
+        .any(|item| item.field == "batteryVoltage" || item.field == "loadPercent")
+    {
+        status.battery_percent = calculate_battery_percent(status.battery_voltage);
+        status.estimated_runtime = Some(estimate_runtime(
+            status.battery_percent,
+            status.load_percent,
+        ));
+    }
+    rejected
+}
+
+fn handle_status_packet(app: &AppHandle, state: &SharedState, mut status: UpsData) {
+    let settings = state.effective_settings();
+    let rejected = sanitize_reading(
+        &mut status,
+        state.live_status().as_ref(),
+        &settings.reading_limits,
+    );
+    for reading in rejected {
+        log::warn!(
+            "implausible {} reading {} (allowed {}..{}), using {}",
+            reading.field,
+            reading.value,
+            reading.min,
+            reading.max,
+            reading.substituted
+        );
+        emit_if_possible(app, "reading-rejected", reading);
+    }
+    apply_runtime_source(&mut status, settings.runtime_source);
+    reconcile_restored_outage(state, &status);
+    // Simulated packets drive alerts and scheduling only; statistics, charge
+    // tracking and stored history keep describing the real UPS.
+    let simulated = current_event_origin() == EventOrigin::Simulation;
+
+    let was_on_battery = *lock(&state.is_on_battery);
+    let is_on_battery = if status.status.utility_fail {
+        *lock(&state.stable_ac_samples) = 0;
+        true
+    } else if was_on_battery && settings.strict_restore.enabled {
+        !strict_restore_confirmed(state, &settings.strict_restore, &status)
+    } else {
+        false
+    };
+    if !is_on_battery && !simulated && status.input_voltage > 0.0 && status.frequency > 0.0 {
+        *lock(&state.last_ac_reading) = Some((status.input_voltage, status.frequency));
+    }
+
+    let ac_slope = apply_percent_projections(
+        state,
+        &mut status,
+        is_on_battery,
+        was_on_battery,
+        shutdown_percent_threshold(&settings),
+    );
+    status.charge_state =
+        ChargeState::derive(status.status.utility_fail, status.battery_percent, ac_slope);
+    if !simulated {
+        update_charge_cycle(app, state, &status, is_on_battery, was_on_battery, ac_slope);
+    }
+    status.runtime_predicted = !is_on_battery;
+    status.utility_state = UtilityState::classify(is_on_battery, status.input_voltage);
+    let previous_utility_state =
+        std::mem::replace(&mut *lock(&state.utility_state), status.utility_state);
+    if !is_on_battery && !settings.show_predicted_runtime {
+        status.estimated_runtime = None;
+    }
+
+    let mut ac_fault_triggered = false;
+    if is_on_battery && !was_on_battery {
+        let outage_id = state.now_ms();
+        *lock(&state.battery_start_ms) = Some(outage_id);
+        if !simulated {
+            state.start_discharge_session(outage_id);
+        }
+        let remarks = format!(
+            "{} ({:.1}V)",
+            status.utility_state.label(),
+            status.input_voltage
+        );
+        log::warn!("on battery: {}", remarks);
+        state.log_event("ac_fault", Severity::Warning, &remarks);
+        ac_fault_triggered = true;
+    } else if is_on_battery && status.utility_state != previous_utility_state {
+        let remarks = format!(
+            "{} ({:.1}V)",
+            status.utility_state.label(),
+            status.input_voltage
+        );
+        state.log_event("utility_changed", Severity::Warning, &remarks);
+    }
+
+    if !is_on_battery && was_on_battery {
+        *lock(&state.battery_start_ms) = None;
+        *lock(&state.was_battery_low) = false;
+        *lock(&state.was_battery_critical) = false;
+        log::info!("AC restored at {:.1}V", status.input_voltage);
+        state.log_event("ac_restored", Severity::Info, "Normal AC value");
+        if !simulated {
+            state.end_discharge_session();
+            start_recharge_expectation(state, &settings, &status);
+        }
+        let has_schedule = lock(&state.scheduled_shutdown).is_some();
+        if settings.shutdown_pc.on_ac_fault.sticky && has_schedule {
+            *lock(&state.ac_restored_at_ms) = Some(state.now_ms());
+        } else {
+            let _ = cancel_scheduled_shutdown(state, app, CancelReason::PowerRestored);
+        }
+        state.sound_generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    if is_on_battery {
+        *lock(&state.ac_restored_at_ms) = None;
+    } else {
+        confirm_sustained_ac_recovery(app, state, &settings);
+    }
+
+    let is_low_battery = is_on_battery
+        && (status.status.battery_low || status.battery_percent <= settings.low_battery_threshold);
+    let is_critical_battery =
+        is_on_battery && status.battery_percent <= settings.critical_battery_threshold;
+
+    let battery_low_triggered = {
+        let mut was_low = lock(&state.was_battery_low);
+        let mut triggered = false;
+        if is_low_battery && !is_critical_battery && !*was_low {
+            state.log_event("battery_low", Severity::Warning, "Battery Low");
+            *was_low = true;
+            triggered = true;
+        }
+        if !is_low_battery {
+            *was_low = false;
+        }
+        triggered
+    };
+
+    let battery_critical_triggered = {
+        let mut was_critical = lock(&state.was_battery_critical);
+        let mut triggered = false;
+        if is_critical_battery && !*was_critical {
+            state.log_event("battery_critical", Severity::Critical, "Battery Critical");
+            *was_critical = true;
+            triggered = true;
+        }
+        if !is_critical_battery {
+            *was_critical = false;
+        }
+        triggered
+    };
+
+    let charge_failure_triggered =
+        !simulated && check_recharge_progress(state, &settings, &status, is_on_battery);
+    let temperature_trend = if settings.temperature_trend.enabled && !simulated {
+        state.update_temperature_trend(&settings.temperature_trend, status.temperature)
+    } else {
+        None
+    };
+    let battery_not_holding = !simulated
+        && state.update_standby_charge(
+            &settings.standby_charge,
+            is_on_battery,
+            status.battery_percent,
+        );
+    if !simulated {
+        state.update_lifetime_stats(is_on_battery);
+        state.record_histogram_sample(&status);
+    }
+
+    if ac_fault_triggered {
+        handle_alert_transition(app, state, &settings, AlertKind::AcFault, &status, true);
+        if settings.display_off_on_ac_fault {
+            if let Err(error) = turn_display_off() {
+                log::warn!("{}", error);
+            }
+        }
+    }
+    if battery_low_triggered {
+        handle_alert_transition(app, state, &settings, AlertKind::BatteryLow, &status, true);
+    }
+    if battery_critical_triggered {
+        handle_alert_transition(
+            app,
+            state,
+            &settings,
+            AlertKind::BatteryCritical,
+            &status,
+            true,
+        );
+    }
+    if charge_failure_triggered {
+        handle_alert_transition(
+            app,
+            state,
+            &settings,
+            AlertKind::ChargeFailure,
+            &status,
+            true,
+        );
+    }
+    if let Some(trend) = temperature_trend {
+        handle_temperature_trend(app, state, &settings, trend, status.temperature);
+    }
+    if battery_not_holding {
+        handle_battery_not_holding(app, state, &settings, status.battery_percent);
+    }
+
+    escalate_for_ups_shutdown(app, state, &settings, &status);
+    process_pending_shutdown(app, state, &settings);
+
+    *lock(&state.is_on_battery) = is_on_battery;
+    if is_on_battery != was_on_battery && !simulated {
+        state.save_runtime_state();
+    }
+    *lock(&state.last_status) = Some(status.clone());
+
+    if !simulated {
+        state.log_data_point_if_needed(&status);
+        if is_on_battery {
+            state.log_discharge_point_if_needed(&status);
+        }
+    }
+    emit_ups_data(app, state, &status);
+}
+
+// While a simulation owns the alert state, real packets only refresh the
+// status it restores and keep the stored history going. A real outage ends the
+// rehearsal so the next packet is handled as usual.
+fn observe_real_packet(state: &SharedState, mut status: UpsData) {
+    let settings = state.effective_settings();
+    let _ = sanitize_reading(
+        &mut status,
+        state.simulation_baseline().as_ref(),
+        &settings.reading_limits,
+    );
+    apply_runtime_source(&mut status, settings.runtime_source);
+    status.utility_state = UtilityState::classify(status.status.utility_fail, status.input_voltage);
+    if status.status.utility_fail && state.cancel_outage_simulation() {
+        log::warn!("real outage during a simulation, cancelling it");
+    }
+    state.log_data_point_if_needed(&status);
+    state.update_simulation_baseline(status);
+}
+
+// Sent to each window on its own schedule. The frontend listens on its
+// webview, since a global listener would also receive the other windows' copies.
+fn emit_ups_data(app: &AppHandle, state: &SharedState, status: &UpsData) {
+    let labels: Vec<String> = app.webview_windows().into_keys().collect();
+    for label in state.ups_data_targets(&labels) {
+        let _ = app.emit_to(label.as_str(), "ups-data", status);
+    }
+}
+
+// hidapi prepends the report ID on Windows and for numbered reports; devices
+// without one deliver data from the first byte and must not lose it.
+fn hid_report_payload(raw_data: &[u8], report_id_prefixed: bool) -> &[u8] {
+    if report_id_prefixed && raw_data.len() > 1 {
+        &raw_data[1..]
+    } else {
+        raw_data
+    }
+}
+
+// Devices may split one frame across several interrupt reports, so bytes are
+// accumulated until the 0x0D terminator. Bytes after a terminator start the
+// next frame and stay buffered; NUL padding and an oversized partial frame are
+// dropped.
+fn take_complete_frames(pending: &mut Vec<u8>, chunk: &[u8]) -> Vec<Vec<u8>> {
+    let mut frames = Vec::new();
+    for byte in chunk {
+        match *byte {
+            0x0D => {
+                if !pending.is_empty() {
+                    frames.push(std::mem::take(pending));
+                }
+            }
+            0x00 => {}
+            value => {
+                pending.push(value);
+                if pending.len() > MAX_FRAME_BYTES {
+                    pending.clear();
+                }
+            }
+        }
+    }
+    frames
+}
+
+fn frame_ascii(frame: &[u8]) -> String {
+    frame
+        .iter()
+        .filter(|byte| **byte >= 32 && **byte <= 126)
+        .map(|byte| *byte as char)
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+fn decode_packet(frame: &[u8], format: &FrameFormat) -> Option<DecodedPacket> {
+    if frame.is_empty() {
+        return None;
+    }
+
+    parse_ups_string(&frame_ascii(frame), format)
+}
+
+// Electrical fields are read from the front and the status bits are located
+// by shape rather than position, so a device that adds or drops a field
+// cannot shift the AC-fail bit. Extended Q1 variants append the UPS's own
+// runtime estimate, in minutes, after the status bits.
+fn parse_ups_string(input: &str, format: &FrameFormat) -> Option<DecodedPacket> {
+    if input.starts_with('(') {
+        let parts = input
+            .trim_start_matches('(')
+            .split_whitespace()
+            .collect::<Vec<_>>();
+        let status_field = format.status_field(&parts)?;
+        if (status_field as u64) < FRAME_MIN_STATUS_FIELD {
+            return None;
+        }
+        // Fields missing before the status bits read as zero.
+        let field = |index: usize| {
+            if index < status_field {
+                parts[index]
+            } else {
+                ""
+            }
+        };
+
+        let status_bits = parts[status_field];
+        let battery_voltage = parse_f64(field(5)) * format.battery_voltage_scale;
+        let load_percent = parse_u64(field(3));
+        let battery_percent = calculate_battery_percent(battery_voltage);
+
+        let status = UpsData {
+            r#type: "STATUS".to_string(),
+            input_voltage: parse_f64(field(0)),
+            fault_voltage: parse_f64(field(1)),
+            output_voltage: parse_f64(field(2)),
+            load_percent,
+            frequency: parse_f64(field(4)),
+            battery_voltage,
+            temperature: parse_f64(field(6)),
+            battery_percent,
+            estimated_runtime: Some(estimate_runtime(battery_percent, load_percent)),
+            device_runtime: parts
+                .get(status_field + 1)
+                .and_then(|value| value.parse::<u64>().ok()),
+            runtime_predicted: false,
+            projected_minutes_remaining: None,
+            charging_projection_minutes: None,
+            projected_empty_at: None,
+            projected_shutdown_at: None,
+            charging: false,
+            utility_state: UtilityState::Normal,
+            charge_state: ChargeState::Unknown,
+            timestamp: now_iso(),
+            status: UpsStatusFlags {
+                raw: status_bits.to_string(),
+                utility_fail: status_bit(status_bits, 0),
+                battery_low: status_bit(status_bits, 1),
+                bypass_active: status_bit(status_bits, 2),
+                ups_failed: status_bit(status_bits, 3),
+                ups_is_standby: status_bit(status_bits, 4),
+                test_in_progress: status_bit(status_bits, 5),
+                shutdown_active: status_bit(status_bits, 6),
+                beeper_on: status_bit(status_bits, 7),
+            },
+            stale: false,
+        };
+
+        return Some(DecodedPacket::Status(Box::new(status)));
+    }
+
+    parse_version_frame(input).map(DecodedPacket::Version)
+}
+
+// Megatec answers the `I` query with `#<company> <model> <version>`. The `F`
+// rating reply also starts with `#` but is purely numeric, so it is rejected.
+fn parse_version_frame(input: &str) -> Option<String> {
+    let body = input.strip_prefix('#')?.trim();
+    if !body.chars().any(|ch| ch.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    Some(body.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+fn parse_f64(value: &str) -> f64 {
+    value.parse::<f64>().unwrap_or(0.0)
+}
+
+fn parse_u64(value: &str) -> u64 {
+    value.parse::<u64>().unwrap_or(0)
+}
+
+fn status_bit(bits: &str, index: usize) -> bool {
+    bits.chars().nth(index).map(|ch| ch == '1').unwrap_or(false)
+}
+
+fn calculate_battery_percent(voltage: f64) -> u64 {
+    let min_voltage = 21.0;
+    let max_voltage = 26.8;
+
+    if voltage <= min_voltage {
+        return 0;
+    }
+    if voltage >= max_voltage {
+        return 100;
+    }
+
+    (((voltage - min_voltage) / (max_voltage - min_voltage)) * 100.0)
+        .round()
+        .clamp(0.0, 100.0) as u64
+}
+
+fn apply_runtime_source(status: &mut UpsData, source: RuntimeSource) {
+    let computed = estimate_runtime(status.battery_percent, status.load_percent);
+    status.estimated_runtime = match (source, status.device_runtime) {
+        (RuntimeSource::Computed, _) => Some(computed),
+        (_, Some(device)) => Some(device),
+        (RuntimeSource::Device, None) => None,
+        (RuntimeSource::Auto, None) => Some(computed),
+    };
+}
+
+fn estimate_runtime(battery_percent: u64, load_percent: u64) -> u64 {
+    let base_runtime_minutes = 15.0;
+    let load_factor = (load_percent.max(10) as f64) / 100.0;
+    ((battery_percent as f64 / 100.0) * (base_runtime_minutes / load_factor)).round() as u64
+}
+
+// Doubles per consecutive HID init/refresh failure, starting at the base delay.
+fn hid_retry_delay_ms(failures: u32) -> u64 {
+    let exponent = failures.saturating_sub(1).min(16);
+    HID_RETRY_BASE_MS
+        .saturating_mul(1_u64 << exponent)
+        .min(HID_RETRY_MAX_MS)
+}
+
+// Like `emit_error_once`, keyed on the message without the retry suffix, so a
+// persistent failure is emitted once while `last_error` keeps the current count.
+fn report_hid_retry(
+    app: &AppHandle,
+    state: &SharedState,
+    error: String,
+    failures: u32,
+    delay_ms: u64,
+) {
+    let message = format!(
+        "{} (retry {} in {} s)",
+        error,
+        failures,
+        delay_ms.div_ceil(1000)
+    );
+    let mut last_error = lock(&state.last_error);
+    let is_new = !last_error
+        .as_ref()
+        .is_some_and(|current| current.starts_with(&error));
+    *last_error = Some(message.clone());
+    drop(last_error);
+    log::warn!("{}", message);
+    if is_new {
+        emit_if_possible(app, "ups-error", message);
+    }
+}
+
+fn start_ups_monitor(app: AppHandle, state: SharedState) {
+    state
+        .monitoring_started_ms
+        .store(state.now_ms(), Ordering::Relaxed);
+    *lock(&state.monitoring_started_cpu_ms) = process_usage().0;
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut api: Option<HidApi> = None;
+        let mut connected_device: Option<hidapi::HidDevice> = None;
+        let mut last_device_refresh_ms = 0_u64;
+        let mut hid_failures = 0_u32;
+
+        while !state.stop_monitor.load(Ordering::Relaxed) {
+            state.touch_heartbeat();
+            state.touch_runtime_state();
+            run_config_watch(&app, &state);
+            run_protection_schedule(&app, &state);
+            let settings = state.effective_settings();
+            finalize_pending_disconnect(&app, &state, &settings);
+            run_alert_escalations(&app, &state, &settings);
+            apply_deferred_shutdowns(&app, &state, &settings);
+            run_scheduled_reports(&app, &state, &settings);
+            run_scheduled_preflight(&app, &state);
+            publish_shutdown_phase(&app, &state);
+            let polling_interval_ms = settings.polling_interval.max(500);
+            let has_recent_status = state.has_live_status();
+            let tuning = &settings.hid_tuning;
+            let read_timeout_ms = tuning.read_timeout_ms(
+                polling_interval_ms,
+                connected_device.is_some(),
+                has_recent_status,
+            );
+            state.record_loop_iteration(polling_interval_ms, read_timeout_ms);
+
+            if api.is_none() {
+                match HidApi::new() {
+                    Ok(next_api) => api = Some(next_api),
+                    Err(error) => {
+                        hid_failures = hid_failures.saturating_add(1);
+                        let delay_ms = hid_retry_delay_ms(hid_failures);
+                        mark_disconnected(&app, &state);
+                        report_hid_retry(
+                            &app,
+                            &state,
+                            format!("HID init error: {}", error),
+                            hid_failures,
+                            delay_ms,
+                        );
+                        state.sleep_monitor_loop(delay_ms);
+                        continue;
+                    }
+                }
+            }
+
+            if let Some(device) = connected_device.as_ref() {
+                if state.keepalive_due(&settings.keepalive) {
+                    let ok = send_megatec_command(device, &settings.keepalive.frame());
+                    if state.record_keepalive_result(ok, settings.keepalive.max_failures) {
+                        emit_error_once(
+                            &app,
+                            &state,
+                            format!(
+                                "Keepalive write failed {} times in a row",
+                                settings.keepalive.max_failures
+                            ),
+                        );
+                        connected_device = None;
+                        mark_disconnected(&app, &state);
+                    }
+                }
+            }
+
+            if let Some(api_ref) = api.as_mut() {
+                let now = state.now_ms();
+                let refresh_interval_ms = if connected_device.is_some() {
+                    2_000
+                } else {
+                    350
+                };
+
+                if now.saturating_sub(last_device_refresh_ms) >= refresh_interval_ms {
+                    if let Err(error) = api_ref.refresh_devices() {
+                        hid_failures = hid_failures.saturating_add(1);
+                        let delay_ms = hid_retry_delay_ms(hid_failures);
+                        mark_disconnected(&app, &state);
+                        report_hid_retry(
+                            &app,
+                            &state,
+                            format!("HID refresh error: {}", error),
+                            hid_failures,
+                            delay_ms,
+                        );
+                        api = None;
+                        connected_device = None;
+                        state.sleep_monitor_loop(delay_ms);
+                        continue;
+                    }
+                    // Init and refresh both count until a refresh goes through.
+                    hid_failures = 0;
+                    last_device_refresh_ms = now;
+                }
+
+                poll_ups(
+                    &app,
+                    &state,
+                    api_ref,
+                    &mut connected_device,
+                    read_timeout_ms as i32,
+                );
+            }
+
+            process_pending_shutdown(&app, &state, &settings);
+
+            let is_connected = *lock(&state.is_connected);
+            let sleep_ms = tuning.sleep_ms(
+                polling_interval_ms,
+                read_timeout_ms,
+                is_connected,
+                has_recent_status,
+            );
+            state.sleep_monitor_loop(sleep_ms);
+        }
+    });
+}
+
+fn parse_date_bound(date_str: &str, end_of_day: bool) -> Option<DateTime<Utc>> {
+    let date = NaiveDate::parse_from_str(date_str, "%Y-%m-%d").ok()?;
+    let naive_dt = if end_of_day {
+        date.and_hms_opt(23, 59, 59)?
+    } else {
+        date.and_hms_opt(0, 0, 0)?
+    };
+    Some(DateTime::<Utc>::from_naive_utc_and_offset(naive_dt, Utc))
+}
+
+fn parse_rfc3339_utc(date_str: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(date_str)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+fn diagnostics_snapshot(state: &SharedState) -> DiagnosticsSnapshot {
+    DiagnosticsSnapshot {
+        generated_at: now_iso(),
+        app_version: APP_VERSION.to_string(),
+        os_version: os_version_string(),
+        session_id: state.session_id,
+        connected: *lock(&state.is_connected),
+        on_battery: *lock(&state.is_on_battery),
+        battery_time_seconds: lock(&state.battery_start_ms)
+            .map(|started_at| state.now_ms().saturating_sub(started_at) / 1000),
+        scheduled_shutdown_at: lock(&state.scheduled_shutdown)
+            .as_ref()
+            .map(|schedule| iso_from_millis(schedule.at_ms)),
+        scheduled_shutdown_reason: lock(&state.scheduled_shutdown)
+            .as_ref()
+            .map(|schedule| schedule.reason.clone()),
+        last_error: lock(&state.last_error).clone(),
+        last_status: lock(&state.last_status).clone(),
+        device_info: labeled_device_info(state),
+    }
+}
+
+// The monitor-state guards are held together so the status, timers and
+// schedule all describe the same instant.
+fn state_snapshot(state: &SharedState) -> StateSnapshot {
+    let connection = state.connection_state();
+    let device_info = labeled_device_info(state);
+    let mut settings = serde_json::to_value(lock(&state.settings).clone()).unwrap_or_default();
+    let mut redacted_fields = Vec::new();
+    redact_secrets(&mut settings, "", &mut redacted_fields);
+
+    let last_status = lock(&state.last_status);
+    let last_raw_frame = lock(&state.last_raw_frame);
+    let on_battery = lock(&state.is_on_battery);
+    let battery_start_ms = lock(&state.battery_start_ms);
+    let scheduled_shutdown = lock(&state.scheduled_shutdown);
+    let now = state.now_ms();
+
+    StateSnapshot {
+        generated_at: iso_from_millis(now),
+        app_version: APP_VERSION.to_string(),
+        session_id: state.session_id,
+        connection,
+        on_battery: *on_battery,
+        battery_time_seconds: battery_start_ms
+            .map(|started_at| now.saturating_sub(started_at) / 1000),
+        scheduled_shutdown_at: scheduled_shutdown
+            .as_ref()
+            .map(|schedule| iso_from_millis(schedule.at_ms)),
+        scheduled_shutdown_reason: scheduled_shutdown
+            .as_ref()
+            .map(|schedule| schedule.reason.clone()),
+        last_status: last_status.clone(),
+        last_raw_frame: last_raw_frame.clone(),
+        device_info,
+        settings,
+        redacted_fields,
+    }
+}
+
+fn resource_usage(state: &SharedState) -> ResourceUsage {
+    let (cpu_time_ms, memory_bytes) = process_usage();
+    let started = state.monitoring_started_ms.load(Ordering::Relaxed);
+    let uptime_ms = if started == 0 {
+        0
+    } else {
+        state.now_ms().saturating_sub(started)
+    };
+    ResourceUsage {
+        pid: std::process::id(),
+        cpu_time_ms,
+        memory_bytes,
+        uptime_seconds: uptime_ms / 1000,
+        average_cpu_percent: cpu_time_ms
+            .zip(*lock(&state.monitoring_started_cpu_ms))
+            .filter(|_| uptime_ms > 0)
+            .map(|(cpu_ms, base_ms)| {
+                cpu_ms.saturating_sub(base_ms) as f64 * 100.0 / uptime_ms as f64
+            }),
+        monitor_loop: *lock(&state.monitor_loop),
+    }
+}
+
+// Returns the process CPU time (user + kernel) in ms and its resident memory.
+#[cfg(target_os = "windows")]
+fn process_usage() -> (Option<u64>, Option<u64>) {
+    use windows::Win32::Foundation::FILETIME;
+    use windows::Win32::System::ProcessStatus::{GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS};
+    use windows::Win32::System::Threading::{GetCurrentProcess, GetProcessTimes};
+
+    let process = unsafe { GetCurrentProcess() };
+    let (mut created, mut exited, mut kernel, mut user) = (
+        FILETIME::default(),
+        FILETIME::default(),
+        FILETIME::default(),
+        FILETIME::default(),
+    );
+    let ticks =
+        |time: &FILETIME| (u64::from(time.dwHighDateTime) << 32) | u64::from(time.dwLowDateTime);
+    // FILETIME counts 100 ns intervals.
+    let cpu_time_ms =
+        unsafe { GetProcessTimes(process, &mut created, &mut exited, &mut kernel, &mut user) }
+            .ok()
+            .map(|_| (ticks(&kernel) + ticks(&user)) / 10_000);
+
+    let mut counters = PROCESS_MEMORY_COUNTERS {
+        cb: std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32,
+        ..Default::default()
+    };
+    let memory_bytes = unsafe { GetProcessMemoryInfo(process, &mut counters, counters.cb) }
+        .ok()
+        .map(|_| counters.WorkingSetSize as u64);
+    (cpu_time_ms, memory_bytes)
+}
+
+#[cfg(target_os = "macos")]
+fn process_usage() -> (Option<u64>, Option<u64>) {
+    let Some(output) = Command::new("ps")
+        .args(["-o", "rss=,cputime=", "-p", &std::process::id().to_string()])
+        .output()
+        .ok()
+    else {
+        return (None, None);
+    };
+    let text = String::from_utf8_lossy(&output.stdout);
+    let mut fields = text.split_whitespace();
+    let memory_bytes = fields
+        .next()
+        .and_then(|rss| rss.parse::<u64>().ok())
+        .map(|kb| kb * 1024);
+    // cputime is [[dd-]hh:]mm:ss.cc
+    let cpu_time_ms = fields.next().and_then(|time| {
+        let (days, clock) = time.split_once('-').unwrap_or(("0", time));
+        let mut seconds = days.parse::<f64>().ok()? * 86_400.0;
+        for (index, part) in clock.rsplit(':').enumerate() {
+            seconds += part.parse::<f64>().ok()? * 60_f64.powi(index as i32);
+        }
+        Some((seconds * 1000.0) as u64)
+    });
+    (cpu_time_ms, memory_bytes)
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn process_usage() -> (Option<u64>, Option<u64>) {
+    // utime and stime follow the command name; the kernel reports them in
+    // USER_HZ ticks, which is fixed at 100 for userspace.
+    let cpu_time_ms = fs::read_to_string("/proc/self/stat").ok().and_then(|text| {
+        let fields = text
+            .rsplit_once(')')?
+            .1
+            .split_whitespace()
+            .collect::<Vec<_>>();
+        let utime = fields.get(11)?.parse::<u64>().ok()?;
+        let stime = fields.get(12)?.parse::<u64>().ok()?;
+        Some((utime + stime) * 10)
+    });
+    let memory_bytes = fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|text| {
+            text.lines()
+                .find_map(|line| line.strip_prefix("VmRSS:"))
+                .and_then(|value| {
+                    value
+                        .trim()
+                        .trim_end_matches("kB")
+                        .trim()
+                        .parse::<u64>()
+                        .ok()
+                })
+                .map(|kb| kb * 1024)
+        });
+    (cpu_time_ms, memory_bytes)
+}
+
+fn os_version_string() -> String {
+    #[cfg(target_os = "windows")]
+    let detail = Command::new("cmd")
+        .args(["/C", "ver"])
+        .output()
+        .ok()
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+    #[cfg(target_os = "macos")]
+    let detail = Command::new("sw_vers")
+        .arg("-productVersion")
+        .output()
+        .ok()
+        .map(|output| format!("macOS {}", String::from_utf8_lossy(&output.stdout).trim()));
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let detail = fs::read_to_string("/etc/os-release").ok().and_then(|text| {
+        text.lines()
+            .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+            .map(|value| value.trim_matches('"').to_string())
+    });
+
+    let platform = format!("{} {}", std::env::consts::OS, std::env::consts::ARCH);
+    match detail.filter(|value| !value.is_empty()) {
+        Some(detail) => format!("{} ({})", platform, detail),
+        None => platform,
+    }
+}
+
+fn enumerate_hid_devices() -> Vec<HidDeviceSummary> {
+    let Ok(api) = HidApi::new() else {
+        return Vec::new();
+    };
+    api.device_list()
+        .map(|device| HidDeviceSummary {
+            vendor_id: format!("{:04X}", device.vendor_id()),
+            product_id: format!("{:04X}", device.product_id()),
+            manufacturer: device.manufacturer_string().map(|value| value.to_string()),
+            product: device.product_string().map(|value| value.to_string()),
+            serial_number: device.serial_number().map(|value| value.to_string()),
+            interface_number: device.interface_number(),
+            path: device.path().to_string_lossy().to_string(),
+        })
+        .collect()
+}
+
+// Replaces every value whose key looks like a credential, returning the dotted
+// paths that were redacted so the user can see what was stripped.
+fn redact_secrets(value: &mut serde_json::Value, path: &str, redacted: &mut Vec<String>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                let child_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                if SECRET_VALUE_MAPS.contains(&child_path.as_str()) {
+                    if let serde_json::Value::Object(entries) = child {
+                        for (name, entry) in entries.iter_mut() {
+                            if entry.as_str().is_some_and(|text| !text.is_empty()) {
+                                *entry = serde_json::Value::String("[redacted]".to_string());
+                                redacted.push(format!("{}.{}", child_path, name));
+                            }
+                        }
+                    }
+                    continue;
+                }
+                let lowered = key.to_ascii_lowercase();
+                if SECRET_KEY_MARKERS
+                    .iter()
+                    .any(|marker| lowered.contains(marker))
+                {
+                    let is_empty =
+                        child.is_null() || child.as_str().map(str::is_empty).unwrap_or(false);
+                    if !is_empty {
+                        *child = serde_json::Value::String("[redacted]".to_string());
+                        redacted.push(child_path);
+                    }
+                    continue;
+                }
+                redact_secrets(child, &child_path, redacted);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                redact_secrets(item, &format!("{}[{}]", path, index), redacted);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Keys whose string values identify a specific unit or machine, with the
+// placeholder prefix used for them. Vendor and product ids stay: they name the
+// model, not the unit, and are needed to debug the protocol.
+const IDENTIFIER_KEYS: [(&str, &str); 9] = [
+    ("serialNumber", "serial"),
+    ("product", "product"),
+    ("manufacturer", "manufacturer"),
+    ("customName", "name"),
+    ("displayName", "name"),
+    ("path", "device-path"),
+    ("devicePath", "device-path"),
+    ("shutdownCommand", "command"),
+    ("workingDirectory", "directory"),
+];
+// Keys whose object value is redacted as a whole, one placeholder per entry.
+const IDENTIFIER_MAP_KEYS: [(&str, &str); 1] = [("environment", "env")];
+// Shorter identifiers are too likely to match unrelated text in free-form fields.
+const IDENTIFIER_SCRUB_MIN_LEN: usize = 4;
+
+// Maps each identifier to a stable placeholder for one export, so the same
+// serial shows up as the same `[serial-1]` in every file of a bundle.
+#[derive(Default)]
+struct IdentifierRedactor {
+    placeholders: HashMap<String, String>,
+    counts: HashMap<&'static str, usize>,
+}
+
+impl IdentifierRedactor {
+    fn placeholder(&mut self, kind: &'static str, value: &str) -> String {
+        if let Some(existing) = self.placeholders.get(value) {
+            return existing.clone();
+        }
+        let count = self.counts.entry(kind).or_insert(0);
+        *count += 1;
+        let placeholder = format!("[{}-{}]", kind, count);
+        self.placeholders
+            .insert(value.to_string(), placeholder.clone());
+        placeholder
+    }
+
+    // Replaces identifier keys in every value first, then scrubs the collected
+    // identifiers out of free text such as remarks and errors.
+    fn redact(&mut self, values: &mut [&mut serde_json::Value]) {
+        for value in values.iter_mut() {
+            self.collect(value);
+        }
+        for value in values.iter_mut() {
+            self.scrub_value(value);
+        }
+    }
+
+    fn collect(&mut self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, child) in map.iter_mut() {
+                    let kind = IDENTIFIER_KEYS
+                        .iter()
+                        .find(|(identifier, _)| identifier == key)
+                        .map(|(_, kind)| *kind);
+                    let map_kind = IDENTIFIER_MAP_KEYS
+                        .iter()
+                        .find(|(identifier, _)| identifier == key)
+                        .map(|(_, kind)| *kind);
+                    match (kind, map_kind, child) {
+                        (Some(kind), _, serde_json::Value::String(text)) if !text.is_empty() => {
+                            *text = self.placeholder(kind, text);
+                        }
+                        (_, Some(kind), serde_json::Value::Object(entries)) => {
+                            for entry in entries.values_mut() {
+                                if let Some(text) = entry.as_str().filter(|text| !text.is_empty()) {
+                                    *entry =
+                                        serde_json::Value::String(self.placeholder(kind, text));
+                                }
+                            }
+                        }
+                        (_, _, child) => self.collect(child),
+                    }
+                }
+            }
+            serde_json::Value::Array(items) => {
+                for item in items.iter_mut() {
+                    self.collect(item);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn scrub(&self, text: &str) -> String {
+        let mut originals = self
+            .placeholders
+            .iter()
+            .filter(|(original, _)| original.len() >= IDENTIFIER_SCRUB_MIN_LEN)
+            .collect::<Vec<_>>();
+        originals.sort_by_key(|(original, _)| std::cmp::Reverse(original.len()));
+        originals
+            .into_iter()
+            .fold(text.to_string(), |text, (original, placeholder)| {
+                text.replace(original.as_str(), placeholder)
+            })
+    }
+
+    fn scrub_value(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::String(text) => *text = self.scrub(text),
+            serde_json::Value::Object(map) => {
+                map.values_mut().for_each(|child| self.scrub_value(child))
+            }
+            serde_json::Value::Array(items) => {
+                items.iter_mut().for_each(|item| self.scrub_value(item))
+            }
+            _ => {}
+        }
+    }
+}
+
+fn parse_log_level(level: &str) -> Option<log::LevelFilter> {
+    match level {
+        "off" => Some(log::LevelFilter::Off),
+        "error" => Some(log::LevelFilter::Error),
+        "warn" => Some(log::LevelFilter::Warn),
+        "info" => Some(log::LevelFilter::Info),
+        "debug" => Some(log::LevelFilter::Debug),
+        "trace" => Some(log::LevelFilter::Trace),
+        _ => None,
+    }
+}
+
+// Formats on the calling thread and hands the line to the writer thread, so
+// logging never waits on disk from the monitor loop.
+struct FileLogger {
+    sender: Sender<String>,
+}
+
+impl log::Log for FileLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format!(
+            "{} {:<5} {}: {}",
+            now_iso(),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        let _ = self.sender.send(line);
+    }
+
+    fn flush(&self) {}
+}
+
+fn rotated_log_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+// ups-monitor.log -> .1 -> .2 ...; the oldest beyond `max_files` is dropped.
+fn rotate_log_files(path: &Path, max_files: usize) {
+    if max_files < 2 {
+        let _ = fs::remove_file(path);
+        return;
+    }
+    let _ = fs::remove_file(rotated_log_path(path, max_files - 1));
+    for index in (1..max_files - 1).rev() {
+        let _ = fs::rename(
+            rotated_log_path(path, index),
+            rotated_log_path(path, index + 1),
+        );
+    }
+    let _ = fs::rename(path, rotated_log_path(path, 1));
+}
+
+fn open_log_file(path: &Path) -> Option<std::io::BufWriter<fs::File>> {
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .ok()
+        .map(std::io::BufWriter::new)
+}
+
+// Drains whatever is queued, then flushes once per batch.
+fn run_log_writer(path: PathBuf, receiver: Receiver<String>, max_bytes: u64, max_files: usize) {
+    let mut written = fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+    let mut writer = open_log_file(&path);
+    while let Ok(first) = receiver.recv() {
+        for line in std::iter::once(first).chain(receiver.try_iter()) {
+            if written >= max_bytes {
+                if let Some(mut current) = writer.take() {
+                    let _ = current.flush();
+                }
+                rotate_log_files(&path, max_files);
+                writer = open_log_file(&path);
+                written = 0;
+            }
+            if let Some(current) = writer.as_mut() {
+                if writeln!(current, "{}", line).is_ok() {
+                    written += line.len() as u64 + 1;
+                }
+            }
+        }
+        if let Some(current) = writer.as_mut() {
+            let _ = current.flush();
+        }
+    }
+}
+
+fn init_file_logging(log_dir: &Path, level: log::LevelFilter) {
+    let _ = fs::create_dir_all(log_dir);
+    let (sender, receiver) = mpsc::channel();
+    let path = log_dir.join(LOG_FILE_NAME);
+    let logger = FileLogger { sender };
+    if log::set_logger(Box::leak(Box::new(logger))).is_err() {
+        return;
+    }
+    log::set_max_level(level);
+    thread::spawn(move || run_log_writer(path, receiver, LOG_MAX_BYTES, LOG_MAX_FILES));
+}
+
+// Reads the rotated files oldest first so the tail spans a rotation.
+fn read_log_tail(log_dir: &Path, max_lines: usize) -> Vec<String> {
+    let path = log_dir.join(LOG_FILE_NAME);
+    let mut lines = Vec::new();
+    for index in (0..LOG_MAX_FILES).rev() {
+        let file = if index == 0 {
+            path.clone()
+        } else {
+            rotated_log_path(&path, index)
+        };
+        if let Ok(content) = fs::read_to_string(&file) {
+            lines.extend(content.lines().map(|line| line.to_string()));
+        }
+    }
+
+    let skip = lines.len().saturating_sub(max_lines);
+    lines.split_off(skip)
+}
+
+fn support_bundle_path(dest_path: &str) -> PathBuf {
+    let dest = PathBuf::from(dest_path);
+    let is_zip = dest
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("zip"))
+        .unwrap_or(false);
+    if is_zip {
+        return dest;
+    }
+    dest.join(format!(
+        "ups-monitor-support-{}.zip",
+        Utc::now().format("%Y%m%d-%H%M%S")
+    ))
+}
+
+fn write_zip_entry<W: Write + std::io::Seek>(
+    zip: &mut zip::ZipWriter<W>,
+    name: &str,
+    content: &[u8],
+) -> Result<(), String> {
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    zip.start_file(name, options)
+        .and_then(|_| zip.write_all(content).map_err(zip::result::ZipError::from))
+        .map_err(|err| format!("No se pudo escribir {} en el paquete: {}", name, err))
+}
+
+// The settings as they go into the bundle, with the redacted paths.
+fn support_bundle_settings(
+    settings: &AppSettings,
+) -> Result<(serde_json::Value, Vec<String>), String> {
+    let mut settings_value = serde_json::to_value(settings)
+        .map_err(|err| format!("No se pudo serializar la configuracion: {}", err))?;
+    let mut redacted_fields = Vec::new();
+    redact_secrets(&mut settings_value, "", &mut redacted_fields);
+    Ok((settings_value, redacted_fields))
+}
+
+#[tauri::command]
+fn create_support_bundle(
+    state: State<'_, SharedState>,
+    dest_path: String,
+    redact_identifiers: Option<bool>,
+) -> Result<SupportBundleSummary, String> {
+    let bundle_path = support_bundle_path(&dest_path);
+
+    let settings = lock(&state.settings).clone();
+    let (mut settings_value, redacted_fields) = support_bundle_settings(&settings)?;
+
+    let events = lock(&state.events)
+        .iter()
+        .take(SUPPORT_BUNDLE_EVENTS)
+        .cloned()
+        .collect::<Vec<_>>();
+    let data_points = lock(&state.data_history)
+        .iter()
+        .take(SUPPORT_BUNDLE_DATA_POINTS)
+        .cloned()
+        .collect::<Vec<_>>();
+    let hid_devices = enumerate_hid_devices();
+    let raw_packets = lock(&state.raw_packet_log)
+        .iter()
+        .cloned()
+        .collect::<Vec<_>>();
+    let mut log_lines = read_log_tail(&state.log_dir(), SUPPORT_BUNDLE_LOG_LINES);
+
+    let mut events_value = serde_json::to_value(&events).unwrap_or_default();
+    let mut diagnostics_value =
+        serde_json::to_value(diagnostics_snapshot(&state)).unwrap_or_default();
+    let mut hid_devices_value = serde_json::to_value(&hid_devices).unwrap_or_default();
+    let mut redacted_identifiers = 0;
+    if redact_identifiers.unwrap_or(false) {
+        let mut redactor = IdentifierRedactor::default();
+        redactor.redact(&mut [
+            &mut settings_value,
+            &mut events_value,
+            &mut diagnostics_value,
+            &mut hid_devices_value,
+        ]);
+        log_lines = log_lines.iter().map(|line| redactor.scrub(line)).collect();
+        redacted_identifiers = redactor.placeholders.len();
+    }
+
+    let mut entries: Vec<(&str, Vec<u8>)> = vec![
+        (
+            "settings.json",
+            serde_json::to_vec_pretty(&settings_value).unwrap_or_default(),
+        ),
+        (
+            "events.json",
+            serde_json::to_vec_pretty(&events_value).unwrap_or_default(),
+        ),
+        (
+            "data.json",
+            serde_json::to_vec_pretty(&data_points).unwrap_or_default(),
+        ),
+        (
+            "diagnostics.json",
+            serde_json::to_vec_pretty(&diagnostics_value).unwrap_or_default(),
+        ),
+        (
+            "hid-devices.json",
+            serde_json::to_vec_pretty(&hid_devices_value).unwrap_or_default(),
+        ),
+    ];
+    if !raw_packets.is_empty() {
+        entries.push((
+            "raw-packets.json",
+            serde_json::to_vec_pretty(&raw_packets).unwrap_or_default(),
+        ));
+    }
+    if !log_lines.is_empty() {
+        entries.push(("app.log", log_lines.join("\n").into_bytes()));
+    }
+
+    if let Some(parent) = bundle_path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let file = fs::File::create(&bundle_path)
+        .map_err(|err| format!("No se pudo crear el paquete de soporte: {}", err))?;
+    let mut zip = zip::ZipWriter::new(file);
+    for (name, content) in &entries {
+        write_zip_entry(&mut zip, name, content)?;
+    }
+    zip.finish()
+        .map_err(|err| format!("No se pudo cerrar el paquete de soporte: {}", err))?;
+
+    Ok(SupportBundleSummary {
+        path: bundle_path.to_string_lossy().to_string(),
+        files: entries.iter().map(|(name, _)| name.to_string()).collect(),
+        event_count: events.len(),
+        data_point_count: data_points.len(),
+        hid_device_count: hid_devices.len(),
+        log_line_count: log_lines.len(),
+        redacted_fields,
+        redacted_identifiers,
+    })
+}
+
+fn resolve_report_period(
+    period: &str,
+    now: DateTime<Utc>,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let days = match period {
+        "day" => 1,
+        "week" => 7,
+        "month" => 30,
+        "quarter" => 90,
+        "year" => 365,
+        _ => 0,
+    };
+    if days > 0 {
+        return Some((now - ChronoDuration::days(days), now));
+    }
+
+    // Calendar month, e.g. "2026-09".
+    let first = NaiveDate::parse_from_str(&format!("{}-01", period), "%Y-%m-%d").ok()?;
+    let next = if first.month() == 12 {
+        NaiveDate::from_ymd_opt(first.year() + 1, 1, 1)?
+    } else {
+        NaiveDate::from_ymd_opt(first.year(), first.month() + 1, 1)?
+    };
+    let from = DateTime::<Utc>::from_naive_utc_and_offset(first.and_hms_opt(0, 0, 0)?, Utc);
+    let to = DateTime::<Utc>::from_naive_utc_and_offset(next.and_hms_opt(0, 0, 0)?, Utc);
+    Some((from, to.min(now)))
+}
+
+// Pairs "ac_fault" with the following "ac_restored" (events are stored
+// newest first) and clips every outage to the requested window.
+fn derive_outages(
+    events: &[HistoryEvent],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Vec<OutageRecord> {
+    let mut spans = Vec::new();
+    let mut open: Option<DateTime<Utc>> = None;
+    for event in events.iter().rev() {
+        let Some(at) = parse_rfc3339_utc(&event.time) else {
+            continue;
+        };
+        match event.key.as_str() {
+            "ac_fault" if open.is_none() => open = Some(at),
+            "ac_restored" => {
+                if let Some(start) = open.take() {
+                    spans.push((start, Some(at)));
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(start) = open {
+        spans.push((start, None));
+    }
+
+    spans
+        .into_iter()
+        .filter_map(|(start, end)| {
+            let clipped_end = end.unwrap_or_else(Utc::now).min(to);
+            let clipped_start = start.max(from);
+            if clipped_end <= clipped_start {
+                return None;
+            }
+            Some(OutageRecord {
+                started_at: start.to_rfc3339(),
+                ended_at: end.map(|dt| dt.to_rfc3339()),
+                duration_seconds: (clipped_end - clipped_start).num_seconds().max(0) as u64,
+            })
+        })
+        .collect()
+}
+
+// Pairs opening and closing event keys into [start, end) spans in millis.
+// A second opening while one is active closes the first at the last event seen,
+// and whatever is still open is closed at `end_ms`.
+fn collect_spans(
+    events: &[(i64, &str)],
+    opens: &[&str],
+    closes: &[&str],
+    end_ms: i64,
+) -> Vec<(i64, i64)> {
+    let mut spans = Vec::new();
+    let mut open: Option<i64> = None;
+    let mut last_seen = i64::MIN;
+    for &(at, key) in events {
+        if opens.contains(&key) {
+            if let Some(start) = open.take() {
+                spans.push((start, last_seen.max(start)));
+            }
+            open = Some(at);
+        } else if closes.contains(&key) {
+            if let Some(start) = open.take() {
+                spans.push((start, at));
+            }
+        }
+        last_seen = at;
+    }
+    if let Some(start) = open {
+        spans.push((start, end_ms.max(start)));
+    }
+    spans
+}
+
+fn clip_spans(spans: &[(i64, i64)], from_ms: i64, to_ms: i64) -> Vec<(i64, i64)> {
+    spans
+        .iter()
+        .map(|&(start, end)| (start.max(from_ms), end.min(to_ms)))
+        .filter(|(start, end)| end > start)
+        .collect()
+}
+
+fn overlap_ms(a: &[(i64, i64)], b: &[(i64, i64)]) -> i64 {
+    let mut total = 0;
+    for &(a_start, a_end) in a {
+        for &(b_start, b_end) in b {
+            total += (a_end.min(b_end) - a_start.max(b_start)).max(0);
+        }
+    }
+    total
+}
+
+// Monitored time comes from app lifecycle events minus UPS disconnections;
+// on-battery time is the part of each outage that was actually monitored.
+fn compute_availability(
+    events: &[HistoryEvent],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> AvailabilityStats {
+    let mut timeline: Vec<(i64, &str)> = events
+        .iter()
+        .filter_map(|event| {
+            parse_rfc3339_utc(&event.time).map(|at| (at.timestamp_millis(), event.key.as_str()))
+        })
+        .collect();
+    timeline.sort_by_key(|(at, _)| *at);
+
+    let (from_ms, to_ms) = (from.timestamp_millis(), to.timestamp_millis());
+    let window = [(from_ms, to_ms)];
+    let session_ends = ["app_stopped", "app_terminated", "shutdown_executed"];
+    let mut monitored = collect_spans(&timeline, &["app_started"], &session_ends, to_ms);
+    // Events from before the oldest recorded start still prove the app was running.
+    if let Some(&(first_at, first_key)) = timeline.first() {
+        if first_key != "app_started" {
+            let first_end = timeline
+                .iter()
+                .find(|(_, key)| session_ends.contains(key) || *key == "app_started")
+                .map(|(at, _)| *at)
+                .unwrap_or(to_ms);
+            monitored.insert(0, (first_at, first_end));
+        }
+    }
+    let mut disconnect_closes = vec!["ups_connected"];
+    disconnect_closes.extend(session_ends);
+    let disconnected = collect_spans(&timeline, &["ups_disconnected"], &disconnect_closes, to_ms);
+    let outages = collect_spans(&timeline, &["ac_fault"], &["ac_restored"], to_ms);
+
+    let monitored = clip_spans(&monitored, from_ms, to_ms);
+    let outages = clip_spans(&outages, from_ms, to_ms);
+    let monitored_ms =
+        (overlap_ms(&monitored, &window) - overlap_ms(&disconnected, &monitored)).max(0);
+    let on_battery_ms = (overlap_ms(&outages, &monitored) - overlap_ms(&disconnected, &outages))
+        .clamp(0, monitored_ms);
+    let period_ms = (to_ms - from_ms).max(0);
+
+    AvailabilityStats {
+        period_start: from.to_rfc3339(),
+        period_end: to.to_rfc3339(),
+        availability_percent: (monitored_ms > 0).then(|| {
+            let healthy = (monitored_ms - on_battery_ms) as f64;
+            (healthy / monitored_ms as f64 * 10_000.0).round() / 100.0
+        }),
+        monitored_seconds: (monitored_ms / 1000) as u64,
+        on_battery_seconds: (on_battery_ms / 1000) as u64,
+        unmonitored_seconds: ((period_ms - monitored_ms).max(0) / 1000) as u64,
+    }
+}
+
+fn summarize_metric(values: impl Iterator<Item = f64>) -> Option<MetricSummary> {
+    let mut summary: Option<MetricSummary> = None;
+    let mut total = 0.0;
+    for value in values {
+        total += value;
+        let entry = summary.get_or_insert(MetricSummary {
+            min: value,
+            avg: 0.0,
+            max: value,
+            samples: 0,
+        });
+        entry.min = entry.min.min(value);
+        entry.max = entry.max.max(value);
+        entry.samples += 1;
+    }
+    summary.map(|mut entry| {
+        entry.avg = total / entry.samples as f64;
+        entry
+    })
+}
+
+// Start of the range a full store still describes: once the event log or the
+// data history hits its cap, anything before its oldest entry was dropped.
+fn history_covered_from(
+    events: &[HistoryEvent],
+    data: &[DataHistoryEntry],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> DateTime<Utc> {
+    let oldest = |times: Vec<&str>| times.into_iter().filter_map(parse_rfc3339_utc).min();
+    let events_from = (events.len() >= MAX_EVENTS)
+        .then(|| oldest(events.iter().map(|item| item.time.as_str()).collect()))
+        .flatten();
+    let data_from = (data.len() >= MAX_DATA_POINTS)
+        .then(|| oldest(data.iter().map(|item| item.time.as_str()).collect()))
+        .flatten();
+    [events_from, data_from]
+        .into_iter()
+        .flatten()
+        .fold(from, DateTime::max)
+        .min(to)
+}
+
+fn build_power_report(state: &SharedState, period: &str) -> Result<PowerReport, String> {
+    let now = Utc::now();
+    let (from, to) = resolve_report_period(period, now)
+        .ok_or_else(|| format!("Periodo de informe no valido: {}", period))?;
+    let events = lock(&state.events).clone();
+    let covered_from = {
+        let data = lock(&state.data_history);
+        history_covered_from(&events, &data, from, to)
+    };
+    let in_window = |time: &str| {
+        parse_rfc3339_utc(time)
+            .map(|dt| dt >= covered_from && dt < to)
+            .unwrap_or(false)
+    };
+    let data = lock(&state.data_history)
+        .iter()
+        .filter(|item| in_window(&item.time))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let outages = derive_outages(&events, covered_from, to);
+    let outage_seconds = outages
+        .iter()
+        .map(|item| item.duration_seconds)
+        .sum::<u64>();
+    let window_seconds = (to - covered_from).num_seconds().max(1) as f64;
+    let availability_percent =
+        ((1.0 - outage_seconds as f64 / window_seconds) * 100.0).clamp(0.0, 100.0);
+
+    let mut alert_counts = BTreeMap::new();
+    for event in events.iter().filter(|item| in_window(&item.time)) {
+        if event.severity() >= Severity::Warning {
+            *alert_counts.entry(event.name.clone()).or_insert(0) += 1;
+        }
+    }
+
+    let mut days: BTreeMap<NaiveDate, (Vec<f64>, usize, u64)> = BTreeMap::new();
+    for item in &data {
+        if let Some(dt) = parse_rfc3339_utc(&item.time) {
+            days.entry(dt.date_naive())
+                .or_default()
+                .0
+                .push(item.input_voltage);
+        }
+    }
+    for outage in &outages {
+        if let Some(dt) = parse_rfc3339_utc(&outage.started_at) {
+            let entry = days
+                .entry(dt.date_naive().max(from.date_naive()))
+                .or_default();
+            entry.1 += 1;
+            entry.2 += outage.duration_seconds;
+        }
+    }
+    let daily = days
+        .into_iter()
+        .map(
+            |(date, (voltages, outage_count, outage_seconds))| DailySummary {
+                date: date.format("%Y-%m-%d").to_string(),
+                input_voltage: summarize_metric(voltages.into_iter()),
+                outage_count,
+                outage_seconds,
+            },
+        )
+        .collect();
+
+    Ok(PowerReport {
+        period: period.to_string(),
+        from: from.to_rfc3339(),
+        to: to.to_rfc3339(),
+        covered_from: covered_from.to_rfc3339(),
+        generated_at: now.to_rfc3339(),
+        outages,
+        outage_seconds,
+        availability_percent,
+        input_voltage: summarize_metric(data.iter().map(|item| item.input_voltage)),
+        output_voltage: summarize_metric(data.iter().map(|item| item.output_voltage)),
+        battery_voltage: summarize_metric(data.iter().map(|item| item.battery_voltage)),
+        alert_counts,
+        daily,
+    })
+}
+
+fn escape_html(input: &str) -> String {
+    input
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn format_duration_seconds(seconds: u64) -> String {
+    format!(
+        "{}h {:02}m {:02}s",
+        seconds / 3600,
+        (seconds % 3600) / 60,
+        seconds % 60
+    )
+}
+
+fn metric_row(label: &str, metric: &Option<MetricSummary>) -> String {
+    match metric {
+        Some(m) => format!(
+            "<tr><td>{}</td><td>{:.1}</td><td>{:.1}</td><td>{:.1}</td><td>{}</td></tr>",
+            label, m.min, m.avg, m.max, m.samples
+        ),
+        None => format!(
+            "<tr><td>{}</td><td colspan=\"4\">Sin datos</td></tr>",
+            label
+        ),
+    }
+}
+
+const REPORT_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="es">
+<head>
+<meta charset="utf-8">
+<title>Informe de energia {{period}}</title>
+<style>
+body { font-family: "Segoe UI", Arial, sans-serif; margin: 32px; color: #1f2937; }
+h1 { margin-bottom: 4px; }
+h2 { margin-top: 32px; border-bottom: 1px solid #d1d5db; padding-bottom: 4px; }
+table { border-collapse: collapse; width: 100%; margin-top: 8px; }
+th, td { border: 1px solid #e5e7eb; padding: 6px 10px; text-align: left; font-size: 13px; }
+th { background: #f3f4f6; }
+.muted { color: #6b7280; }
+.kpi { font-size: 28px; font-weight: 600; }
+</style>
+</head>
+<body>
+<h1>Informe de energia</h1>
+<p class="muted">Periodo {{period}}: {{from}} &rarr; {{to}} &middot; generado {{generated_at}}</p>
+{{coverage}}<p class="kpi">Disponibilidad {{availability}}%</p>
+<p>{{outage_count}} cortes &middot; {{outage_total}} en bateria</p>
+<h2>Cortes de energia</h2>
+<table><tr><th>Inicio</th><th>Fin</th><th>Duracion</th></tr>{{outage_rows}}</table>
+<h2>Voltajes</h2>
+<table><tr><th>Medida</th><th>Min</th><th>Prom</th><th>Max</th><th>Muestras</th></tr>{{metric_rows}}</table>
+<h2>Alertas</h2>
+<table><tr><th>Evento</th><th>Cantidad</th></tr>{{alert_rows}}</table>
+<h2>Resumen diario</h2>
+<table><tr><th>Dia</th><th>Entrada min</th><th>Entrada prom</th><th>Entrada max</th><th>Cortes</th><th>Tiempo en bateria</th></tr>{{daily_rows}}</table>
+</body>
+</html>
+"#;
+
+fn render_power_report_html(report: &PowerReport) -> String {
+    let outage_rows = report
+        .outages
+        .iter()
+        .map(|outage| {
+            format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape_html(&outage.started_at),
+                escape_html(outage.ended_at.as_deref().unwrap_or("en curso")),
+                format_duration_seconds(outage.duration_seconds)
+            )
+        })
+        .collect::<String>();
+    let metric_rows = [
+        metric_row("Entrada (V)", &report.input_voltage),
+        metric_row("Salida (V)", &report.output_voltage),
+        metric_row("Bateria (V)", &report.battery_voltage),
+    ]
+    .concat();
+    let alert_rows = report
+        .alert_counts
+        .iter()
+        .map(|(name, count)| format!("<tr><td>{}</td><td>{}</td></tr>", escape_html(name), count))
+        .collect::<String>();
+    let daily_rows = report
+        .daily
+        .iter()
+        .map(|day| {
+            let (min, avg, max) = day
+                .input_voltage
+                .as_ref()
+                .map(|m| {
+                    (
+                        format!("{:.1}", m.min),
+                        format!("{:.1}", m.avg),
+                        format!("{:.1}", m.max),
+                    )
+                })
+                .unwrap_or_else(|| ("-".to_string(), "-".to_string(), "-".to_string()));
+            format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                day.date,
+                min,
+                avg,
+                max,
+                day.outage_count,
+                format_duration_seconds(day.outage_seconds)
+            )
+        })
+        .collect::<String>();
+
+    let coverage = if report.covered_from != report.from {
+        format!(
+            "<p><strong>El historial guardado solo llega hasta {}; el informe no incluye lo anterior.</strong></p>\n",
+            escape_html(&report.covered_from)
+        )
+    } else {
+        String::new()
+    };
+
+    REPORT_TEMPLATE
+        .replace("{{coverage}}", &coverage)
+        .replace("{{period}}", &escape_html(&report.period))
+        .replace("{{from}}", &escape_html(&report.from))
+        .replace("{{to}}", &escape_html(&report.to))
+        .replace("{{generated_at}}", &escape_html(&report.generated_at))
+        .replace(
+            "{{availability}}",
+            &format!("{:.3}", report.availability_percent),
+        )
+        .replace("{{outage_count}}", &report.outages.len().to_string())
+        .replace(
+            "{{outage_total}}",
+            &format_duration_seconds(report.outage_seconds),
+        )
+        .replace("{{outage_rows}}", &outage_rows)
+        .replace("{{metric_rows}}", &metric_rows)
+        .replace("{{alert_rows}}", &alert_rows)
+        .replace("{{daily_rows}}", &daily_rows)
+}
+
+fn write_power_report(
+    state: &SharedState,
+    period: &str,
+    format: &str,
+    dest: &Path,
+) -> Result<ReportResult, String> {
+    if format != "html" {
+        return Err(format!(
+            "Formato de informe no soportado: {} (use html e imprima a PDF desde el visor)",
+            format
+        ));
+    }
+
+    let report = build_power_report(state, period)?;
+    let path = if dest.extension().is_some() {
+        dest.to_path_buf()
+    } else {
+        dest.join(format!("ups-power-report-{}.html", period))
+    };
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    fs::write(&path, render_power_report_html(&report))
+        .map_err(|err| format!("No se pudo escribir el informe: {}", err))?;
+
+    Ok(ReportResult {
+        path: path.to_string_lossy().to_string(),
+        format: format.to_string(),
+        outage_count: report.outages.len(),
+        availability_percent: report.availability_percent,
+    })
+}
+
+fn run_scheduled_reports(app: &AppHandle, state: &SharedState, settings: &AppSettings) {
+    if settings.auto_report.schedule != "monthly" {
+        return;
+    }
+    let Some(destination) = settings.auto_report.destination.as_ref() else {
+        return;
+    };
+
+    let now = state.now_ms();
+    let last_check = state.last_report_check_ms.load(Ordering::Relaxed);
+    if now.saturating_sub(last_check) < REPORT_CHECK_INTERVAL_MS {
+        return;
+    }
+    state.last_report_check_ms.store(now, Ordering::Relaxed);
+
+    let today = DateTime::<Utc>::from_timestamp_millis(now as i64)
+        .unwrap_or_else(Utc::now)
+        .date_naive();
+    let Some(previous_month) = today.with_day(1).and_then(|first| first.pred_opt()) else {
+        return;
+    };
+    let period = previous_month.format("%Y-%m").to_string();
+    let target = PathBuf::from(destination).join(format!("ups-power-report-{}.html", period));
+    if target.exists() {
+        return;
+    }
+
+    match write_power_report(state, &period, "html", &target) {
+        Ok(result) => {
+            state.log_event("report_generated", Severity::Info, &result.path);
+        }
+        Err(error) => emit_error_once(app, state, error),
+    }
+}
+
+#[tauri::command]
+fn generate_report(
+    state: State<'_, SharedState>,
+    period: String,
+    format: Option<String>,
+    dest: String,
+) -> Result<ReportResult, String> {
+    let format = format.unwrap_or_else(|| "html".to_string());
+    let result = write_power_report(&state, &period, &format, Path::new(&dest))?;
+    state.log_event("report_generated", Severity::Info, &result.path);
+    Ok(result)
+}
+
+fn nut_status_flags(flags: &UpsStatusFlags) -> String {
+    let mut tokens = vec![if flags.utility_fail { "OB" } else { "OL" }];
+    if flags.battery_low {
+        tokens.push("LB");
+    }
+    if flags.bypass_active {
+        tokens.push("BYPASS");
+    }
+    if flags.test_in_progress {
+        tokens.push("CAL");
+    }
+    if flags.shutdown_active {
+        tokens.push("FSD");
+    }
+    tokens.join(" ")
+}
+
+fn nut_variables(status: &UpsData, info: Option<&UpsInfo>) -> Vec<(&'static str, String)> {
+    let mut vars = vec![
+        ("device.type", "ups".to_string()),
+        ("battery.charge", status.battery_percent.to_string()),
+        ("battery.voltage", format!("{:.1}", status.battery_voltage)),
+        ("input.voltage", format!("{:.1}", status.input_voltage)),
+        ("input.frequency", format!("{:.1}", status.frequency)),
+        ("output.voltage", format!("{:.1}", status.output_voltage)),
+        ("ups.load", status.load_percent.to_string()),
+        ("ups.temperature", format!("{:.1}", status.temperature)),
+        ("ups.status", nut_status_flags(&status.status)),
+        (
+            "ups.beeper.status",
+            if status.status.beeper_on {
+                "enabled"
+            } else {
+                "disabled"
+            }
+            .to_string(),
+        ),
+    ];
+    if let Some(runtime) = status.estimated_runtime {
+        vars.push(("battery.runtime", (runtime * 60).to_string()));
+    }
+    if let Some(info) = info {
+        vars.push(("ups.mfr", info.manufacturer.clone()));
+        vars.push(("ups.model", info.product.clone()));
+        vars.push(("ups.vendorid", info.vendor_id.clone()));
+        vars.push(("ups.productid", info.product_id.clone()));
+        if let Some(firmware) = info.firmware.as_ref() {
+            vars.push(("ups.firmware", firmware.clone()));
+        }
+    }
+    vars
+}
+
+fn nut_quote(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// Answers one line of the upsd text protocol. Only the read-only subset is
+// implemented; anything that would change the UPS is refused.
+fn nut_response(
+    line: &str,
+    ups_name: &str,
+    status: Option<&UpsData>,
+    info: Option<&UpsInfo>,
+) -> String {
+    let parts = line.split_whitespace().collect::<Vec<_>>();
+    let data_for = |name: &str| -> Result<&UpsData, String> {
+        if name != ups_name {
+            return Err("ERR UNKNOWN-UPS\n".to_string());
+        }
+        status.ok_or_else(|| "ERR DATA-STALE\n".to_string())
+    };
+
+    match parts.as_slice() {
+        ["VER"] => format!("UPS Monitor {} (upsd compatible)\n", APP_VERSION),
+        ["NETVER"] => "1.3\n".to_string(),
+        ["LOGOUT"] => "OK Goodbye\n".to_string(),
+        ["USERNAME", ..] | ["PASSWORD", ..] => "OK\n".to_string(),
+        ["LIST", "UPS"] => format!(
+            "BEGIN LIST UPS\nUPS {} \"UPS Monitor\"\nEND LIST UPS\n",
+            ups_name
+        ),
+        ["LIST", "VAR", name] => match data_for(name) {
+            Ok(data) => {
+                let mut out = format!("BEGIN LIST VAR {}\n", name);
+                for (key, value) in nut_variables(data, info) {
+                    out.push_str(&format!("VAR {} {} \"{}\"\n", name, key, nut_quote(&value)));
+                }
+                out.push_str(&format!("END LIST VAR {}\n", name));
+                out
+            }
+            Err(error) => error,
+        },
+        ["LIST", kind @ ("CMD" | "RW"), name] => {
+            if *name != ups_name {
+                return "ERR UNKNOWN-UPS\n".to_string();
+            }
+            format!("BEGIN LIST {} {}\nEND LIST {} {}\n", kind, name, kind, name)
+        }
+        ["GET", "VAR", name, key] => match data_for(name) {
+            Ok(data) => nut_variables(data, info)
+                .into_iter()
+                .find(|(var, _)| var == key)
+                .map(|(var, value)| format!("VAR {} {} \"{}\"\n", name, var, nut_quote(&value)))
+                .unwrap_or_else(|| "ERR VAR-NOT-SUPPORTED\n".to_string()),
+            Err(error) => error,
+        },
+        ["GET", "UPSDESC", name] if *name == ups_name => {
+            format!("UPSDESC {} \"UPS Monitor\"\n", name)
+        }
+        ["SET", ..]
+        | ["INSTCMD", ..]
+        | ["FSD", ..]
+        | ["LOGIN", ..]
+        | ["PRIMARY", ..]
+        | ["MASTER", ..] => "ERR ACCESS-DENIED\n".to_string(),
+        _ => "ERR UNKNOWN-COMMAND\n".to_string(),
+    }
+}
+
+// Reads the next command line into `line`. False when the client is gone or
+// sent a line without a newline within the limit, which drops the client.
+fn read_nut_line(reader: &mut impl BufRead, line: &mut String) -> bool {
+    line.clear();
+    match reader.take(NUT_MAX_LINE_BYTES).read_line(line) {
+        Ok(0) | Err(_) => false,
+        Ok(_) => line.ends_with('\n'),
+    }
+}
+
+fn handle_nut_client(state: &SharedState, stream: TcpStream) {
+    let _ = stream.set_read_timeout(Some(Duration::from_millis(NUT_CLIENT_IDLE_TIMEOUT_MS)));
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    while read_nut_line(&mut reader, &mut line) {
+        let settings = lock(&state.settings).clone();
+        if !settings.nut_server.enabled {
+            break;
+        }
+        // A restored snapshot could report OB to clients that shut down on it.
+        let status = lock(&state.last_status)
+            .clone()
+            .filter(|status| !status.stale);
+        let info = lock(&state.device_info).clone();
+        let reply = nut_response(
+            line.trim(),
+            &settings.nut_server.ups_name,
+            status.as_ref(),
+            info.as_ref(),
+        );
+        if writer.write_all(reply.as_bytes()).is_err() || line.trim() == "LOGOUT" {
+            break;
+        }
+    }
+}
+
+// NUT clients keep their connection open and poll, so each one gets a thread,
+// up to `NUT_MAX_CONNECTIONS` at a time.
+fn start_nut_server_listener(app: AppHandle, state: SharedState) {
+    if state.nut_server_running.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let nut = lock(&state.settings).nut_server.clone();
+    let listener = match TcpListener::bind((nut.bind_address.as_str(), nut.port)) {
+        Ok(listener) => listener,
+        Err(err) => {
+            state.nut_server_running.store(false, Ordering::Relaxed);
+            emit_error_once(
+                &app,
+                &state,
+                format!("No se pudo iniciar el servidor NUT: {}", err),
+            );
+            return;
+        }
+    };
+
+    let open_connections = Arc::new(AtomicU64::new(0));
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let Some(slot) = ConnectionSlot::acquire(&open_connections, NUT_MAX_CONNECTIONS) else {
+                continue;
+            };
+            let client_state = state.clone();
+            thread::spawn(move || {
+                let _slot = slot;
+                handle_nut_client(&client_state, stream);
+            });
+        }
+    });
+}
+
+fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    if left.len() != right.len() {
+        return false;
+    }
+    left.iter()
+        .zip(right.iter())
+        .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+        == 0
+}
+
+fn remote_request_authorized(settings: &RemoteApiSettings, headers: &str) -> bool {
+    let Some(expected) = settings.token.as_deref() else {
+        return false;
+    };
+    headers.lines().any(|line| {
+        let Some((name, value)) = line.split_once(':') else {
+            return false;
+        };
+        if !name.trim().eq_ignore_ascii_case("authorization") {
+            return false;
+        }
+        value
+            .trim()
+            .strip_prefix("Bearer ")
+            .map(|token| constant_time_eq(token.trim().as_bytes(), expected.as_bytes()))
+            .unwrap_or(false)
+    })
+}
+
+fn write_http_json<T: Serialize>(stream: &mut TcpStream, status: &str, body: &T) {
+    let payload = serde_json::to_string(body).unwrap_or_else(|_| "null".to_string());
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        payload.len(),
+        payload
+    );
+}
+
+fn handle_remote_api_request(app: &AppHandle, state: &SharedState, mut stream: TcpStream) {
+    let deadline = Instant::now() + Duration::from_millis(REMOTE_API_REQUEST_TIMEOUT_MS);
+    let _ = stream.set_write_timeout(Some(Duration::from_millis(REMOTE_API_REQUEST_TIMEOUT_MS)));
+    let mut request = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            write_http_json(&mut stream, "408 Request Timeout", &"request timeout");
+            return;
+        }
+        let _ = stream.set_read_timeout(Some(remaining));
+        match stream.read(&mut chunk) {
+            Ok(0) | Err(_) => break,
+            Ok(count) => request.extend_from_slice(&chunk[..count]),
+        }
+        if request.len() > REMOTE_API_MAX_REQUEST_BYTES {
+            write_http_json(&mut stream, "413 Payload Too Large", &"request too large");
+            return;
+        }
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let (request_line, headers) = request.split_once("\r\n").unwrap_or((&request, ""));
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let path = parts.next().unwrap_or("");
+
+    let settings = lock(&state.settings).clone();
+    if !settings.remote_api.enabled {
+        write_http_json(
+            &mut stream,
+            "503 Service Unavailable",
+            &"remote api disabled",
+        );
+        return;
+    }
+
+    match (method, path) {
+        ("GET", "/status") => {
+            let status = state.live_status();
+            write_http_json(&mut stream, "200 OK", &status);
+        }
+        ("POST", "/shutdown/cancel") | ("POST", "/alarm/silence") => {
+            if settings.monitor_only_mode {
+                write_http_json(&mut stream, "403 Forbidden", &"monitor-only mode");
+                return;
+            }
+            if !remote_request_authorized(&settings.remote_api, headers) {
+                write_http_json(&mut stream, "401 Unauthorized", &"unauthorized");
+                return;
+            }
+
+            if path == "/shutdown/cancel" {
+                let cancelled = cancel_scheduled_shutdown(state, app, CancelReason::Remote);
+                if cancelled {
+                    state.log_event("remote_shutdown_cancel", Severity::Info, "");
+                }
+                write_http_json(&mut stream, "200 OK", &cancelled);
+            } else {
+                clear_escalations(state);
+                state.sound_generation.fetch_add(1, Ordering::Relaxed);
+                state.log_event("remote_alarm_silence", Severity::Info, "");
+                write_http_json(&mut stream, "200 OK", &true);
+            }
+        }
+        _ => write_http_json(&mut stream, "404 Not Found", &"not found"),
+    }
+}
+
+// The listener binds once; later settings changes (token, monitor-only,
+// disable) are read per request. Changing address or port needs a restart.
+fn start_remote_api_server(app: AppHandle, state: SharedState) {
+    if state.remote_api_running.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let api = lock(&state.settings).remote_api.clone();
+    let listener = match TcpListener::bind((api.bind_address.as_str(), api.port)) {
+        Ok(listener) => listener,
+        Err(err) => {
+            state.remote_api_running.store(false, Ordering::Relaxed);
+            emit_error_once(
+                &app,
+                &state,
+                format!("No se pudo iniciar la API remota: {}", err),
+            );
+            return;
+        }
+    };
+
+    // Each request gets its own thread, so a slow client cannot hold up a
+    // shutdown cancel sent during an outage.
+    let open_connections = Arc::new(AtomicU64::new(0));
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let Some(slot) = ConnectionSlot::acquire(&open_connections, REMOTE_API_MAX_CONNECTIONS)
+            else {
+                let _ = stream.set_write_timeout(Some(Duration::from_millis(500)));
+                write_http_json(
+                    &mut stream,
+                    "503 Service Unavailable",
+                    &"too many connections",
+                );
+                continue;
+            };
+            let app = app.clone();
+            let state = state.clone();
+            thread::spawn(move || {
+                let _slot = slot;
+                handle_remote_api_request(&app, &state, stream);
+            });
+        }
+    });
+}
+
+// One open connection of a server capped at a fixed count; dropping it frees
+// the place.
+struct ConnectionSlot(Arc<AtomicU64>);
+
+impl ConnectionSlot {
+    fn acquire(open: &Arc<AtomicU64>, max: u64) -> Option<Self> {
+        if open.fetch_add(1, Ordering::Relaxed) >= max {
+            open.fetch_sub(1, Ordering::Relaxed);
+            return None;
+        }
+        Some(Self(open.clone()))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AlertTestResult {
+    kind: String,
+    channels: Vec<ChannelResult>,
+    shutdown_scheduled: Option<String>,
+    cancel_after_ms: Option<u64>,
+}
+
+// A test schedule never runs the shutdown; a real outage replaces it with its
+// own schedule, which runs normally.
+fn alert_test_holds_shutdown(state: &SharedState) -> bool {
+    lock(&state.scheduled_shutdown)
+        .as_ref()
+        .is_some_and(|schedule| schedule.origin == EventOrigin::AlertTest)
+}
+
+// A status frame that would plausibly raise `kind` under the current thresholds.
+fn fabricated_status(kind: AlertKind, settings: &AppSettings) -> UpsData {
+    let (on_battery, battery_voltage, battery_percent) = match kind {
+        AlertKind::AcFault => (true, 25.8, 80),
+        AlertKind::BatteryLow => (true, 23.0, settings.low_battery_threshold),
+        AlertKind::BatteryCritical => (true, 21.5, settings.critical_battery_threshold),
+        _ => (false, 24.0, 50),
+    };
+    let battery_low = on_battery && kind != AlertKind::AcFault;
+    let (input_voltage, frequency) = if on_battery {
+        (0.0, 0.0)
+    } else {
+        (218.5, 60.1)
+    };
+    let load_percent = 23;
+    UpsData {
+        r#type: "STATUS".to_string(),
+        input_voltage,
+        fault_voltage: 140.0,
+        output_voltage: 219.0,
+        load_percent,
+        frequency,
+        battery_voltage,
+        temperature: 32.0,
+        battery_percent,
+        estimated_runtime: Some(estimate_runtime(battery_percent, load_percent)),
+        device_runtime: None,
+        runtime_predicted: false,
+        projected_minutes_remaining: None,
+        charging_projection_minutes: None,
+        projected_empty_at: None,
+        projected_shutdown_at: None,
+        charging: false,
+        utility_state: UtilityState::classify(on_battery, input_voltage),
+        charge_state: ChargeState::Unknown,
+        timestamp: now_iso(),
+        status: UpsStatusFlags {
+            raw: format!("{}{}001001", on_battery as u8, battery_low as u8),
+            utility_fail: on_battery,
+            battery_low,
+            bypass_active: false,
+            ups_failed: false,
+            ups_is_standby: true,
+            test_in_progress: false,
+            shutdown_active: false,
+            beeper_on: true,
+        },
+        stale: false,
+    }
+}
+
+#[tauri::command]
+fn test_alert(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    kind: String,
+    schedule_shutdown: bool,
+) -> Result<AlertTestResult, String> {
+    let kind = AlertKind::from_str(&kind)
+        .ok_or_else(|| format!("Tipo de alerta desconocido: {}", kind))?;
+    let settings = state.effective_settings();
+    if settings.monitor_only_mode {
+        return Err("Modo solo monitor activo".to_string());
+    }
+    if *lock(&state.is_on_battery) || lock(&state.scheduled_shutdown).is_some() {
+        return Err("Hay un corte o un apagado programado en curso".to_string());
+    }
+
+    let status = fabricated_status(kind, &settings);
+    let channels = with_event_origin(EventOrigin::AlertTest, || {
+        state.log_event(kind.event_key(), kind.severity(), kind.event_name());
+        handle_alert_transition(&app, &state, &settings, kind, &status, schedule_shutdown)
+    });
+    let shutdown_scheduled = lock(&state.scheduled_shutdown)
+        .as_ref()
+        .filter(|schedule| schedule.origin == EventOrigin::AlertTest)
+        .map(|schedule| iso_from_millis(schedule.at_ms));
+
+    if shutdown_scheduled.is_some() {
+        let cancel_app = app.clone();
+        let cancel_state = state.inner().clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(ALERT_TEST_SHUTDOWN_CANCEL_MS));
+            let _ = cancel_shutdown_from(
+                &cancel_state,
+                &cancel_app,
+                CancelReason::AlertTest,
+                Some(EventOrigin::AlertTest),
+            );
+        });
+    }
+
+    Ok(AlertTestResult {
+        kind: kind.event_key().to_string(),
+        channels,
+        cancel_after_ms: shutdown_scheduled
+            .as_ref()
+            .map(|_| ALERT_TEST_SHUTDOWN_CANCEL_MS),
+        shutdown_scheduled,
+    })
+}
+
+#[tauri::command]
+fn test_routing(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    kind: String,
+) -> Result<Vec<ChannelResult>, String> {
+    let kind = AlertKind::from_str(&kind)
+        .ok_or_else(|| format!("Tipo de alerta desconocido: {}", kind))?;
+    let settings = state.effective_settings();
+    let title = format!("Prueba: {}", kind.event_name());
+    Ok(dispatch_alert(
+        &app,
+        &state,
+        &settings,
+        kind,
+        &title,
+        "Prueba de enrutamiento de alertas",
+    ))
+}
+
+#[tauri::command]
+fn flush_storage(state: State<'_, SharedState>) -> Result<bool, String> {
+    state.flush_storage().map(|_| true)
+}
+
+#[tauri::command]
+fn get_lifetime_stats(state: State<'_, SharedState>) -> LifetimeStats {
+    lock(&state.lifetime_stats).clone()
+}
+
+#[tauri::command]
+fn reset_lifetime_stats(state: State<'_, SharedState>) -> LifetimeStats {
+    let mut fresh = LifetimeStats::fresh();
+    {
+        // Keep an outage in progress running so it is measured from the reset.
+        let mut stats = lock(&state.lifetime_stats);
+        if stats.on_battery_since_ms.is_some() {
+            let now = state.now_ms();
+            fresh.on_battery_since_ms = Some(now);
+            fresh.last_accounted_ms = Some(now);
+        }
+        *stats = fresh.clone();
+    }
+    state.save_lifetime_stats();
+    fresh
+}
+
+#[tauri::command]
+fn get_voltage_histogram(
+    state: State<'_, SharedState>,
+    period: String,
+) -> Result<Histogram, String> {
+    state
+        .histogram(HistogramMetric::InputVoltage, &period, Utc::now())
+        .ok_or_else(|| format!("Periodo no valido: {}", period))
+}
+
+#[tauri::command]
+fn get_frequency_histogram(
+    state: State<'_, SharedState>,
+    period: String,
+) -> Result<Histogram, String> {
+    state
+        .histogram(HistogramMetric::Frequency, &period, Utc::now())
+        .ok_or_else(|| format!("Periodo no valido: {}", period))
+}
+
+#[tauri::command]
+fn reset_histograms(state: State<'_, SharedState>) -> bool {
+    {
+        let mut stats = lock(&state.lifetime_stats);
+        stats.voltage_buckets.clear();
+        stats.frequency_buckets.clear();
+    }
+    state.save_lifetime_stats();
+    true
+}
+
+#[tauri::command]
+fn get_hid_tuning(state: State<'_, SharedState>) -> HidTuning {
+    lock(&state.settings).hid_tuning.clone()
+}
+
+#[tauri::command]
+fn set_hid_tuning(state: State<'_, SharedState>, tuning: HidTuning) -> HidTuning {
+    let mut tuning = tuning;
+    tuning.normalize();
+    lock(&state.settings).hid_tuning = tuning.clone();
+    state.save_settings();
+    tuning
+}
+
+#[tauri::command]
+fn get_settings(state: State<'_, SharedState>) -> AppSettings {
+    lock(&state.settings).clone()
+}
+
+// Objects are walked; any other value, arrays included, is compared whole.
+fn collect_json_diff(
+    path: &str,
+    current: &serde_json::Value,
+    default: Option<&serde_json::Value>,
+    out: &mut BTreeMap<String, serde_json::Value>,
+) {
+    match (current, default) {
+        (serde_json::Value::Object(fields), Some(serde_json::Value::Object(default_fields))) => {
+            for (key, value) in fields {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                collect_json_diff(&child, value, default_fields.get(key), out);
+            }
+        }
+        (value, Some(default)) if value == default => {}
+        (value, _) => {
+            out.insert(path.to_string(), value.clone());
+        }
+    }
+}
+
+// Flat `a.b.c` -> value map of everything that differs from a fresh config.
+fn settings_diff(settings: &AppSettings) -> BTreeMap<String, serde_json::Value> {
+    let current = serde_json::to_value(settings).unwrap_or_default();
+    let defaults = serde_json::to_value(AppSettings::default().normalize()).unwrap_or_default();
+    let mut diff = BTreeMap::new();
+    collect_json_diff("", &current, Some(&defaults), &mut diff);
+    diff
+}
+
+#[tauri::command]
+fn get_settings_diff(state: State<'_, SharedState>) -> BTreeMap<String, serde_json::Value> {
+    settings_diff(&lock(&state.settings))
+}
+
+#[tauri::command]
+fn save_settings(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    new_settings: AppSettings,
+) -> Result<bool, String> {
+    apply_settings(&app, &state, new_settings.normalize());
+    state.save_settings();
+    Ok(true)
+}
+
+// Pushes normalized settings into the running app; callers decide whether
+// config.json is rewritten.
+fn apply_settings(app: &AppHandle, state: &SharedState, normalized: AppSettings) {
+    if normalized.monitor_only_mode {
+        silence_for_monitor_only(app, state);
+    }
+    let start_remote_api = normalized.remote_api.enabled;
+    let start_nut_server = normalized.nut_server.enabled;
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.set_always_on_top(normalized.always_on_top);
+    }
+    if let Some(level) = parse_log_level(&normalized.log_level) {
+        log::set_max_level(level);
+    }
+    *lock(&state.settings) = normalized;
+    if start_remote_api {
+        start_remote_api_server(app.clone(), state.clone());
+    }
+    refresh_tray_tooltip(app, state);
+    if start_nut_server {
+        start_nut_server_listener(app.clone(), state.clone());
+    }
+}
+
+// The file is not rewritten after a reload, so values clamped by
+// `normalize` stay as the external source wrote them until the next save.
+fn run_config_watch(app: &AppHandle, state: &SharedState) {
+    if !lock(&state.settings).watch_config_file {
+        return;
+    }
+    match state.poll_external_config() {
+        Some(Ok(settings)) => {
+            log::info!("config.json changed on disk; reloading settings");
+            apply_settings(app, state, settings.clone());
+            state.log_event(
+                "settings_reloaded",
+                Severity::Info,
+                "config.json changed on disk",
+            );
+            emit_if_possible(app, "settings-reloaded", settings);
+        }
+        Some(Err(error)) => {
+            log::warn!("ignoring invalid config.json edit: {}", error);
+            state.log_event(
+                "settings_reload_failed",
+                Severity::Warning,
+                &format!("config.json could not be parsed: {}", error),
+            );
+        }
+        None => {}
+    }
+}
+
+fn silence_for_monitor_only(app: &AppHandle, state: &SharedState) {
+    state.sound_generation.fetch_add(1, Ordering::Relaxed);
+    let _ = cancel_scheduled_shutdown(state, app, CancelReason::SettingsChanged);
+}
+
+fn run_protection_schedule(app: &AppHandle, state: &SharedState) {
+    if let Some(protection) = state.refresh_protection(Local::now().naive_local()) {
+        if protection.suspended {
+            silence_for_monitor_only(app, state);
+        } else {
+            schedule_shutdowns_after_resume(app, state);
+        }
+        emit_if_possible(app, "protection-state-changed", protection);
+    }
+}
+
+// Shutdowns are only scheduled on alert transitions, which a suspension skips.
+// An outage still in progress when protection comes back gets its schedule
+// through the same check the startup grace uses.
+fn schedule_shutdowns_after_resume(app: &AppHandle, state: &SharedState) {
+    let mut deferred = lock(&state.deferred_shutdown_kinds);
+    for kind in [
+        AlertKind::AcFault,
+        AlertKind::BatteryLow,
+        AlertKind::BatteryCritical,
+    ] {
+        if !deferred.contains(&kind) {
+            deferred.push(kind);
+        }
+    }
+    drop(deferred);
+    apply_deferred_shutdowns(app, state, &state.effective_settings());
+}
+
+// A timed monitor-only override: alerts and shutdown scheduling are skipped,
+// history continues, and the monitor loop ends it when the time is up.
+#[tauri::command]
+fn suspend_protection(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    minutes: u64,
+    reason: Option<String>,
+) -> Result<ProtectionState, String> {
+    if minutes == 0 || minutes > PROTECTION_SUSPEND_MAX_MINUTES {
+        return Err(format!(
+            "La suspension debe durar entre 1 y {} minutos",
+            PROTECTION_SUSPEND_MAX_MINUTES
+        ));
+    }
+    let reason = reason
+        .map(|reason| reason.trim().to_string())
+        .filter(|reason| !reason.is_empty())
+        .unwrap_or_else(|| "Mantenimiento".to_string());
+    silence_for_monitor_only(&app, &state);
+    state.suspend_protection(minutes, &reason);
+    let protection = state.protection_state(Local::now().naive_local());
+    emit_if_possible(&app, "protection-state-changed", protection.clone());
+    Ok(protection)
+}
+
+#[tauri::command]
+fn resume_protection(app: AppHandle, state: State<'_, SharedState>) -> ProtectionState {
+    let local_now = Local::now().naive_local();
+    let resumed = state.resume_protection(local_now);
+    let protection = state.protection_state(local_now);
+    if resumed && !protection.suspended {
+        schedule_shutdowns_after_resume(&app, &state);
+    }
+    if resumed {
+        emit_if_possible(&app, "protection-state-changed", protection.clone());
+    }
+    protection
+}
+
+#[tauri::command]
+fn get_protection_state(state: State<'_, SharedState>) -> ProtectionState {
+    state.protection_state(Local::now().naive_local())
+}
+
+// Only the flag changes. Suppression happens in `AppSettings::effective`, so the
+// configured alerts and shutdown options come back as they were when disabled.
+#[tauri::command]
+fn set_monitor_only(app: AppHandle, state: State<'_, SharedState>, enabled: bool) -> bool {
+    if enabled {
+        silence_for_monitor_only(&app, &state);
+    }
+    if state.set_monitor_only(enabled) {
+        emit_if_possible(&app, "monitor-only-changed", enabled);
+    }
+    enabled
+}
+
+#[tauri::command]
+fn is_monitor_only(state: State<'_, SharedState>) -> bool {
+    lock(&state.settings).monitor_only_mode
+}
+
+#[tauri::command]
+fn subscribe_ups_data(
+    state: State<'_, SharedState>,
+    window_label: String,
+    min_interval_ms: u64,
+) -> bool {
+    let label = window_label.trim();
+    if label.is_empty() {
+        return false;
+    }
+    state.subscribe_ups_data(label, min_interval_ms);
+    true
+}
+
+#[tauri::command]
+fn unsubscribe_ups_data(state: State<'_, SharedState>, window_label: String) -> bool {
+    state.unsubscribe_ups_data(window_label.trim())
+}
+
+#[tauri::command]
+fn get_setup_guidance(state: State<'_, SharedState>) -> SetupGuidance {
+    lock(&state.setup_guidance)
+        .clone()
+        .unwrap_or_else(|| SetupGuidance {
+            conflicting_software: lock(&state.conflicting_software).clone(),
+            ..setup_guidance(lock(&state.device_path).clone(), UPS_VID, UPS_PID, None)
+        })
+}
+
+#[tauri::command]
+async fn check_conflicting_software(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+) -> Result<ConflictReport, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || run_conflict_check(&app, &state))
+        .await
+        .map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+fn get_connection_state(state: State<'_, SharedState>) -> ConnectionState {
+    state.connection_state()
+}
+
+#[tauri::command]
+fn get_ups_status(state: State<'_, SharedState>) -> Option<UpsData> {
+    lock(&state.last_status).clone()
+}
+
+#[tauri::command]
+fn inject_status(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    mut status: UpsData,
+) -> Result<bool, String> {
+    if !cfg!(debug_assertions) {
+        return Err(
+            "inject_status solo esta disponible en compilaciones de desarrollo".to_string(),
+        );
+    }
+
+    if status.timestamp.trim().is_empty() {
+        status.timestamp = now_iso();
+    }
+    handle_status_packet(&app, state.inner(), status);
+    Ok(true)
+}
+
+fn labeled_device_info(state: &SharedState) -> Option<UpsInfo> {
+    let custom_name = lock(&state.settings).custom_name.clone();
+    lock(&state.device_info).clone().map(|mut info| {
+        info.display_name = custom_name.clone().unwrap_or_else(|| info.product.clone());
+        info.custom_name = custom_name;
+        info
+    })
+}
+
+fn refresh_tray_tooltip(app: &AppHandle, state: &SharedState) {
+    let tooltip = match lock(&state.settings).custom_name.as_ref() {
+        Some(name) => format!("UPS Monitor - {}", name),
+        None => "UPS Monitor".to_string(),
+    };
+    if let Some(tray) = app.tray_by_id("main-tray") {
+        let _ = tray.set_tooltip(Some(tooltip));
+    }
+}
+
+#[tauri::command]
+fn snapshot_state(
+    state: State<'_, SharedState>,
+    redact_identifiers: Option<bool>,
+) -> serde_json::Value {
+    state_snapshot_value(&state, redact_identifiers.unwrap_or(false))
+}
+
+fn state_snapshot_value(state: &SharedState, redact_identifiers: bool) -> serde_json::Value {
+    let mut snapshot = serde_json::to_value(state_snapshot(state)).unwrap_or_default();
+    if redact_identifiers {
+        IdentifierRedactor::default().redact(&mut [&mut snapshot]);
+    }
+    snapshot
+}
+
+#[tauri::command]
+fn get_monitors(app: AppHandle) -> Vec<MonitorInfo> {
+    let primary = app.primary_monitor().ok().flatten();
+    app.available_monitors()
+        .unwrap_or_default()
+        .into_iter()
+        .enumerate()
+        .map(|(index, monitor)| MonitorInfo {
+            index,
+            name: monitor.name().cloned(),
+            width: monitor.size().width,
+            height: monitor.size().height,
+            primary: primary.as_ref().is_some_and(|primary| {
+                primary.name() == monitor.name() && primary.position() == monitor.position()
+            }),
+        })
+        .collect()
+}
+
+#[tauri::command]
+fn get_resource_usage(state: State<'_, SharedState>) -> ResourceUsage {
+    resource_usage(&state)
+}
+
+#[tauri::command]
+fn get_last_raw_frame(state: State<'_, SharedState>) -> Option<RawFrameInfo> {
+    lock(&state.last_raw_frame).clone()
+}
+
+#[tauri::command]
+fn get_raw_packet_log(state: State<'_, SharedState>) -> Vec<RawFrameInfo> {
+    lock(&state.raw_packet_log).iter().cloned().collect()
+}
+
+#[tauri::command]
+fn get_ups_info(state: State<'_, SharedState>) -> Option<UpsInfo> {
+    labeled_device_info(&state)
+}
+
+#[tauri::command]
+async fn refresh_ups_info(state: State<'_, SharedState>) -> Result<UpsInfo, String> {
+    if !*lock(&state.is_connected) {
+        return Err("UPS desconectado".to_string());
+    }
+
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let generation = state.firmware_generation.load(Ordering::Relaxed);
+        lock(&state.device_commands).push(MEGATEC_INFO_QUERY.to_vec());
+
+        let deadline = now_millis() + INFO_REFRESH_TIMEOUT_MS;
+        while now_millis() < deadline {
+            if state.firmware_generation.load(Ordering::Relaxed) != generation {
+                return labeled_device_info(&state).ok_or_else(|| "UPS desconectado".to_string());
+            }
+            if !*lock(&state.is_connected) {
+                return Err("UPS desconectado".to_string());
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        Err("El UPS no respondio a la consulta de identificacion".to_string())
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+#[tauri::command]
+fn set_device_name(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    name: Option<String>,
+) -> Option<UpsInfo> {
+    let name = name
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty());
+    lock(&state.settings).custom_name = name;
+    state.save_settings();
+    refresh_tray_tooltip(&app, &state);
+
+    let info = labeled_device_info(&state);
+    emit_if_possible(&app, "ups-info", info.clone());
+    info
+}
+
+#[tauri::command]
+fn get_about(app: AppHandle, state: State<'_, SharedState>) -> AboutInfo {
+    let build_date = BUILD_TIMESTAMP
+        .parse::<i64>()
+        .ok()
+        .filter(|secs| *secs > 0)
+        .and_then(|secs| DateTime::<Utc>::from_timestamp(secs, 0))
+        .map(|dt| dt.to_rfc3339());
+
+    AboutInfo {
+        app_version: app.package_info().version.to_string(),
+        build_date,
+        data_dir: state.app_data_dir.to_string_lossy().to_string(),
+        config_path: state.config_path.to_string_lossy().to_string(),
+        events_path: state.events_path.to_string_lossy().to_string(),
+        data_path: state.data_path.to_string_lossy().to_string(),
+        sounds_path: state.sounds_path.to_string_lossy().to_string(),
+        firmware: lock(&state.device_info)
+            .as_ref()
+            .and_then(|info| info.firmware.clone()),
+        event_count: lock(&state.events).len(),
+        data_point_count: lock(&state.data_history).len(),
+    }
+}
+
+#[tauri::command]
+fn open_data_folder(state: State<'_, SharedState>) -> Result<bool, String> {
+    #[cfg(target_os = "windows")]
+    let program = "explorer";
+    #[cfg(target_os = "macos")]
+    let program = "open";
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let program = "xdg-open";
+
+    Command::new(program)
+        .arg(&state.app_data_dir)
+        .spawn()
+        .map(|_| true)
+        .map_err(|err| format!("No se pudo abrir la carpeta de datos: {}", err))
+}
+
+#[tauri::command]
+fn test_notification(app: AppHandle, state: State<'_, SharedState>) -> bool {
+    let _ = notify_windows(
+        &app,
+        "UPS Monitor",
+        "Notificacion de prueba enviada correctamente",
+    );
+    if should_force_popup(&app, &state) {
+        force_alert_popup(
+            &app,
+            &state,
+            "test-notification",
+            "UPS Monitor",
+            "Notificacion de prueba enviada correctamente",
+            "warning",
+        );
+    }
+    true
+}
+
+#[tauri::command]
+fn minimize_main_window(app: AppHandle) -> bool {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.set_skip_taskbar(true);
+        return window.close().is_ok();
+    }
+    false
+}
+
+#[tauri::command]
+fn toggle_maximize_main_window(app: AppHandle) -> bool {
+    if let Some(window) = app.get_webview_window("main") {
+        let is_maximized = window.is_maximized().unwrap_or(false);
+        if is_maximized {
+            return window.unmaximize().is_ok();
+        }
+        return window.maximize().is_ok();
+    }
+    false
+}
+
+#[tauri::command]
+fn set_always_on_top(app: AppHandle, state: State<'_, SharedState>, enabled: bool) -> bool {
+    lock(&state.settings).always_on_top = enabled;
+    state.save_settings();
+    if let Some(window) = app.get_webview_window("main") {
+        return window.set_always_on_top(enabled).is_ok();
+    }
+    false
+}
+
+#[tauri::command]
+fn set_skip_taskbar(app: AppHandle, enabled: bool) -> bool {
+    if let Some(window) = app.get_webview_window("main") {
+        return window.set_skip_taskbar(enabled).is_ok();
+    }
+    false
+}
+
+#[tauri::command]
+fn close_main_window(app: AppHandle) -> bool {
+    request_app_exit(&app);
+    true
+}
+
+fn create_main_window(app: &AppHandle) -> bool {
+    let window_config = app
+        .config()
+        .app
+        .windows
+        .iter()
+        .find(|w| w.label == "main")
+        .or_else(|| app.config().app.windows.first());
+
+    let Some(window_config) = window_config else {
+        return false;
+    };
+
+    if let Some(state) = app.try_state::<SharedState>() {
+        state
+            .pending_show_main_window
+            .store(true, Ordering::Relaxed);
+    }
+
+    let always_on_top = app
+        .try_state::<SharedState>()
+        .map(|state| lock(&state.settings).always_on_top)
+        .unwrap_or(false);
+
+    let window = match tauri::WebviewWindowBuilder::from_config(app, window_config) {
+        Ok(builder) => match builder
+            .decorations(false)
+            .always_on_top(always_on_top)
+            .visible(false)
+            .build()
+        {
+            Ok(window) => window,
+            Err(_) => return false,
+        },
+        Err(_) => return false,
+    };
+
+    #[cfg(target_os = "windows")]
+    {
+        let _ = window.set_decorations(false);
+        let _ = window.set_shadow(false);
+        apply_rounded_corners(&window);
+    }
+
+    let _ = window.set_skip_taskbar(true);
+    true
+}
+
+fn show_main_window(app: &AppHandle) {
+    let app_handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Some(window) = app_handle.get_webview_window("main") {
+            if let Some(state) = app_handle.try_state::<SharedState>() {
+                if state.pending_show_main_window.load(Ordering::Relaxed) {
+                    return;
+                }
+            }
+            let _ = window.set_skip_taskbar(false);
+            let _ = window.show();
+            let _ = window.unminimize();
+            let _ = window.set_focus();
+            return;
+        }
+
+        let _ = create_main_window(&app_handle);
+    });
+}
+
+#[tauri::command]
+fn main_window_ready(app: AppHandle, state: State<'_, SharedState>) -> bool {
+    if !state.pending_show_main_window.swap(false, Ordering::Relaxed) {
+        return false;
+    }
+
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.set_skip_taskbar(false);
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+        return true;
+    }
+    false
+}
+
+fn request_app_exit(app: &AppHandle) {
+    if let Some(state) = app.try_state::<SharedState>() {
+        state.allow_process_exit.store(true, Ordering::Relaxed);
+        state.record_app_stopped();
+    }
+    app.exit(0);
+}
+
+#[tauri::command]
+fn cancel_shutdown(app: AppHandle, state: State<'_, SharedState>) -> bool {
+    cancel_scheduled_shutdown(&state, &app, CancelReason::User)
+}
+
+#[tauri::command]
+fn get_shutdown_phase(state: State<'_, SharedState>) -> ShutdownPhaseState {
+    state.shutdown_phase_state()
+}
+
+#[tauri::command]
+fn confirm_shutdown_now(app: AppHandle, state: State<'_, SharedState>) -> bool {
+    let mut deadline = lock(&state.shutdown_confirmation_deadline_ms);
+    if deadline.is_none() {
+        return false;
+    }
+    *deadline = Some(0);
+    drop(deadline);
+
+    let settings = state.effective_settings();
+    process_pending_shutdown(&app, &state, &settings);
+    true
+}
+
+#[tauri::command]
+fn trigger_shutdown(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    minutes: u64,
+    action: Option<String>,
+) -> Result<(), String> {
+    let settings = lock(&state.settings).clone();
+    if settings.monitor_only_mode {
+        return Err("Modo solo monitor activo".to_string());
+    }
+    if let Some(action) = action.as_deref() {
+        if !MANUAL_SHUTDOWN_ACTIONS.contains(&action) {
+            return Err(format!("Accion no valida: {}", action));
+        }
+    }
+
+    let min_percent = settings.shutdown_pc.manual_shutdown_min_battery_percent;
+    if min_percent > 0 {
+        let battery_percent = state
+            .live_status()
+            .map(|status| status.battery_percent)
+            .ok_or_else(|| "Sin datos del UPS para comprobar la bateria".to_string())?;
+        if battery_percent < min_percent {
+            return Err(format!(
+                "Bateria al {}%, por debajo del minimo configurado ({}%)",
+                battery_percent, min_percent
+            ));
+        }
+    }
+
+    if schedule_shutdown_after_minutes(
+        &state,
+        &app,
+        minutes,
+        "manual-trigger",
+        true,
+        last_estimated_runtime(&state),
+    ) {
+        *lock(&state.manual_shutdown_action) = action;
+        Ok(())
+    } else {
+        Err("No se pudo programar el apagado".to_string())
+    }
+}
+
+#[tauri::command]
+fn simulate_shutdown_flow(
+    app: AppHandle,
+    minutes: Option<u64>,
+    auto_cancel_ms: Option<u64>,
+    state: State<'_, SharedState>,
+) -> Result<ShutdownSimulationResult, String> {
+    if lock(&state.settings).monitor_only_mode {
+        return Err("Modo solo monitor activo".to_string());
+    }
+
+    let safe_minutes = minutes.unwrap_or(5).max(1);
+    let safe_cancel_ms = auto_cancel_ms.unwrap_or(1200).max(100);
+    let shutdown_time = (Utc::now() + ChronoDuration::minutes(safe_minutes as i64)).to_rfc3339();
+
+    emit_if_possible(
+        &app,
+        "shutdown-scheduled",
+        ShutdownScheduledPayload {
+            minutes: safe_minutes,
+            shutdown_time: shutdown_time.clone(),
+            reason: "simulation".to_string(),
+            estimated_runtime_minutes: last_estimated_runtime(&state),
+        },
+    );
+
+    let cancel_app = app.clone();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(safe_cancel_ms));
+        emit_if_possible(
+            &cancel_app,
+            "shutdown-cancelled",
+            ShutdownCancelledPayload {
+                reason: "simulation".to_string(),
+                message: "Simulacion de apagado cancelada".to_string(),
+            },
+        );
+    });
+
+    Ok(ShutdownSimulationResult {
+        scheduled: true,
+        cancelled: true,
+        minutes: safe_minutes,
+        shutdown_time,
+        cancel_after_ms: safe_cancel_ms,
+    })
+}
+
+// Rehearses a whole outage: synthetic on-battery packets go through
+// `handle_status_packet`, so alerts and shutdown scheduling are the real ones,
+// but the shutdown command itself is only logged. Refused while a real outage
+// or schedule is in progress.
+#[tauri::command]
+async fn simulate_outage(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    duration_seconds: u64,
+    drain_rate: f64,
+) -> Result<OutageSimulationReport, String> {
+    if duration_seconds == 0 || duration_seconds > SIMULATION_MAX_SECONDS {
+        return Err(format!(
+            "La simulacion debe durar entre 1 y {} segundos",
+            SIMULATION_MAX_SECONDS
+        ));
+    }
+    if !(drain_rate > 0.0 && drain_rate <= SIMULATION_MAX_DRAIN_RATE) {
+        return Err(format!(
+            "El ritmo de descarga debe ser mayor que 0 y de hasta {} %/s",
+            SIMULATION_MAX_DRAIN_RATE
+        ));
+    }
+    if state.effective_settings().monitor_only_mode {
+        return Err("Modo solo monitor activo".to_string());
+    }
+    if *lock(&state.is_on_battery) || lock(&state.scheduled_shutdown).is_some() {
+        return Err("Hay un corte o un apagado programado en curso".to_string());
+    }
+    let state = state.inner().clone();
+    if !state.begin_outage_simulation() {
+        return Err("Ya hay una simulacion en curso".to_string());
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        run_outage_simulation(&app, &state, duration_seconds, drain_rate)
+    })
+    .await
+    .map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+fn cancel_simulation(state: State<'_, SharedState>) -> bool {
+    state.cancel_outage_simulation()
+}
+
+fn run_outage_simulation(
+    app: &AppHandle,
+    state: &SharedState,
+    duration_seconds: u64,
+    drain_rate: f64,
+) -> OutageSimulationReport {
+    let start_percent = lock(&state.last_status)
+        .as_ref()
+        .map(|status| status.battery_percent)
+        .unwrap_or(100);
+    state.log_event(
+        "simulation_started",
+        Severity::Info,
+        &format!(
+            "{} s, {:.1} %/s from {}%",
+            duration_seconds, drain_rate, start_percent
+        ),
+    );
+
+    let (percent, cancelled) = with_event_origin(EventOrigin::Simulation, || {
+        let mut percent = start_percent;
+        for second in 0..duration_seconds {
+            if state.simulation_cancelled() {
+                break;
+            }
+            percent = simulated_percent(start_percent, drain_rate, second);
+            let settings = state.effective_settings();
+            handle_status_packet(app, state, simulated_status(&settings, percent, true));
+            thread::sleep(Duration::from_millis(SIMULATION_TICK_MS));
+        }
+
+        let cancelled = state.simulation_cancelled();
+        if !cancelled {
+            let settings = state.effective_settings();
+            handle_status_packet(app, state, simulated_status(&settings, percent, false));
+        }
+        let _ = cancel_shutdown_from(
+            state,
+            app,
+            CancelReason::Simulation,
+            Some(EventOrigin::Simulation),
+        );
+        (percent, cancelled)
+    });
+    state.log_event(
+        "simulation_ended",
+        Severity::Info,
+        if cancelled { "Cancelled" } else { "Completed" },
+    );
+    let timeline = state
+        .end_outage_simulation()
+        .map(|simulation| simulation.timeline)
+        .unwrap_or_default();
+    if let Some(status) = lock(&state.last_status).clone() {
+        emit_ups_data(app, state, &status);
+    }
+
+    OutageSimulationReport {
+        duration_seconds,
+        drain_rate,
+        start_percent,
+        end_percent: percent,
+        cancelled,
+        timeline,
+    }
+}
+
+fn simulated_percent(start_percent: u64, drain_rate: f64, elapsed_seconds: u64) -> u64 {
+    (start_percent as f64 - drain_rate * elapsed_seconds as f64)
+        .max(0.0)
+        .round() as u64
+}
+
+fn simulated_status(settings: &AppSettings, battery_percent: u64, on_battery: bool) -> UpsData {
+    let kind = if on_battery {
+        AlertKind::AcFault
+    } else {
+        AlertKind::UpsConnected
+    };
+    let mut status = fabricated_status(kind, settings);
+    status.battery_percent = battery_percent;
+    status.estimated_runtime = Some(estimate_runtime(battery_percent, status.load_percent));
+    status.status.battery_low = on_battery && battery_percent <= settings.low_battery_threshold;
+    status
+}
+
+#[tauri::command]
+fn validate_shutdown_command(command: String) -> ShutdownCommandCheck {
+    check_shutdown_argv(custom_command_argv(&command))
+}
+
+// Reports the exact argv a protective shutdown would run with the saved
+// settings, without executing anything.
+#[tauri::command]
+fn dry_run_shutdown_command(state: State<'_, SharedState>) -> ShutdownCommandCheck {
+    let settings = lock(&state.settings).clone();
+    check_shutdown_argv(shutdown_argv(&settings))
+}
+
+#[tauri::command]
+fn run_preflight_checks(app: AppHandle, state: State<'_, SharedState>) -> Vec<PreflightCheck> {
+    run_preflight(&app, &state)
+}
+
+#[tauri::command]
+fn get_previous_shutdown(state: State<'_, SharedState>) -> Option<ShutdownMarker> {
+    state.previous_shutdown.clone()
+}
+
+#[tauri::command]
+fn get_battery_time(state: State<'_, SharedState>) -> Option<u64> {
+    let start_ms = *lock(&state.battery_start_ms);
+    start_ms.map(|started_at| state.now_ms().saturating_sub(started_at) / 1000)
+}
+
+#[tauri::command]
+fn get_events(state: State<'_, SharedState>, filter: Option<HistoryFilter>) -> Vec<HistoryEvent> {
+    let mut events = lock(&state.events).clone();
+    events.sort_by_key(|event| std::cmp::Reverse(event.seq));
+    filter_events(events, filter)
+}
+
+fn filter_events(
+    mut events: Vec<HistoryEvent>,
+    filter: Option<HistoryFilter>,
+) -> Vec<HistoryEvent> {
+    if let Some(filter) = filter {
+        if let Some(classification) = filter.classification {
+            if classification != "All Events" {
+                events = events
+                    .into_iter()
+                    .filter(|item| item.classification == classification)
+                    .collect();
+            }
+        }
+
+        if let Some(severity) = filter.severity.as_deref().and_then(Severity::from_str) {
+            events.retain(|item| item.severity() == severity);
+        }
+
+        if let Some(keys) = filter.keys.filter(|keys| !keys.is_empty()) {
+            events.retain(|item| keys.contains(&item.key));
+        }
+
+        if let Some(resolved) = filter.resolved {
+            events.retain(|item| item.resolved == resolved);
+        }
+
+        if let Some(date_from) = filter.date_from {
+            if let Some(from_dt) = parse_date_bound(&date_from, false) {
+                events = events
+                    .into_iter()
+                    .filter(|item| {
+                        parse_rfc3339_utc(&item.time)
+                            .map(|item_dt| item_dt >= from_dt)
+                            .unwrap_or(true)
+                    })
+                    .collect();
+            }
+        }
+
+        if let Some(date_to) = filter.date_to {
+            if let Some(to_dt) = parse_date_bound(&date_to, true) {
+                events = events
+                    .into_iter()
+                    .filter(|item| {
+                        parse_rfc3339_utc(&item.time)
+                            .map(|item_dt| item_dt <= to_dt)
+                            .unwrap_or(true)
+                    })
+                    .collect();
+            }
+        }
+    }
+
+    events
+}
+
+#[tauri::command]
+fn get_log_tail(state: State<'_, SharedState>, lines: usize) -> Vec<String> {
+    read_log_tail(&state.log_dir(), lines.clamp(1, LOG_TAIL_MAX_LINES))
+}
+
+#[tauri::command]
+fn set_log_level(state: State<'_, SharedState>, level: String) -> Result<(), String> {
+    let level = level.trim().to_ascii_lowercase();
+    let filter =
+        parse_log_level(&level).ok_or_else(|| format!("Nivel de log no valido: {}", level))?;
+    log::set_max_level(filter);
+    lock(&state.settings).log_level = level;
+    state.save_settings();
+    Ok(())
+}
+
+#[tauri::command]
+fn get_statistics(
+    state: State<'_, SharedState>,
+    period: String,
+) -> Result<AvailabilityStats, String> {
+    let (from, to) = resolve_report_period(&period, Utc::now())
+        .ok_or_else(|| format!("Periodo no valido: {}", period))?;
+    let events = lock(&state.events).clone();
+    Ok(compute_availability(&events, from, to))
+}
+
+#[tauri::command]
+fn get_event_keys() -> Vec<EventKeyInfo> {
+    EVENT_CATALOG
+        .iter()
+        .map(|(key, name)| EventKeyInfo { key, name })
+        .collect()
+}
+
+#[tauri::command]
+fn get_event_statistics(
+    state: State<'_, SharedState>,
+    filter: Option<HistoryFilter>,
+) -> EventStatistics {
+    let events = get_events(state, filter);
+    let mut stats = EventStatistics {
+        total: events.len(),
+        ..EventStatistics::default()
+    };
+    for event in &events {
+        match event.severity() {
+            Severity::Info => stats.info += 1,
+            Severity::Warning => stats.warning += 1,
+            Severity::Critical => stats.critical += 1,
+        }
+    }
+    stats
+}
+
+#[tauri::command]
+fn mark_events_resolved(
+    state: State<'_, SharedState>,
+    ids: Vec<u64>,
+    resolved: bool,
+) -> Vec<HistoryEvent> {
+    state.set_events_resolved(&ids, resolved);
+    lock(&state.events).clone()
+}
+
+#[tauri::command]
+fn delete_events(
+    state: State<'_, SharedState>,
+    ids: Option<Vec<u64>>,
+    filter: Option<HistoryFilter>,
+) -> Result<DeleteSummary, String> {
+    state.delete_events(ids, filter)
+}
+
+#[tauri::command]
+fn get_data_history(
+    state: State<'_, SharedState>,
+    filter: Option<HistoryFilter>,
+) -> Vec<DataHistoryEntry> {
+    let data = lock(&state.data_history).clone();
+    filter_data_history(data, filter)
+}
+
+fn filter_data_history(
+    mut data: Vec<DataHistoryEntry>,
+    filter: Option<HistoryFilter>,
+) -> Vec<DataHistoryEntry> {
+    if let Some(filter) = filter {
+        if let Some(date_from) = filter.date_from {
+            if let Some(from_dt) = parse_date_bound(&date_from, false) {
+                data = data
+                    .into_iter()
+                    .filter(|item| {
+                        parse_rfc3339_utc(&item.time)
+                            .map(|item_dt| item_dt >= from_dt)
+                            .unwrap_or(true)
+                    })
+                    .collect();
+            }
+        }
+
+        if let Some(date_to) = filter.date_to {
+            if let Some(to_dt) = parse_date_bound(&date_to, true) {
+                data = data
+                    .into_iter()
+                    .filter(|item| {
+                        parse_rfc3339_utc(&item.time)
+                            .map(|item_dt| item_dt <= to_dt)
+                            .unwrap_or(true)
+                    })
+                    .collect();
+            }
+        }
+    }
+
+    data
+}
+
+#[tauri::command]
+fn delete_data_history(
+    state: State<'_, SharedState>,
+    ids: Option<Vec<u64>>,
+    filter: Option<HistoryFilter>,
+) -> Result<DeleteSummary, String> {
+    state.delete_data_history(ids, filter)
+}
+
+#[tauri::command]
+fn list_discharge_sessions(state: State<'_, SharedState>) -> Vec<DischargeSessionSummary> {
+    lock(&state.discharge_sessions)
+        .iter()
+        .map(|session| DischargeSessionSummary {
+            outage_id: session.outage_id,
+            started_at: session.started_at.clone(),
+            ended_at: session.ended_at.clone(),
+            point_count: session.points.len(),
+        })
+        .collect()
+}
+
+#[tauri::command]
+fn get_discharge_session(
+    state: State<'_, SharedState>,
+    outage_id: u64,
+) -> Option<DischargeSession> {
+    lock(&state.discharge_sessions)
+        .iter()
+        .find(|session| session.outage_id == outage_id)
+        .cloned()
+}
+
+#[tauri::command]
+fn update_history_interval(state: State<'_, SharedState>, seconds: u64) -> bool {
+    let mut settings = lock(&state.settings);
+    settings.history_interval = clamp_u64(seconds, 60, 3600, settings.history_interval);
+    drop(settings);
+    state.save_settings();
+    true
+}
+
+#[tauri::command]
+fn play_sound(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    sound_type: String,
+    repeats: Option<u64>,
+) -> bool {
+    let settings = lock(&state.settings).clone();
+    let kind = AlertKind::from_str(sound_type.as_str()).unwrap_or(AlertKind::BatteryCritical);
+    let sound_path = resolve_sound_path(&state, &settings, kind);
+    play_sound_with_generation(
+        &app,
+        state.inner().clone(),
+        sound_path,
+        repeats.unwrap_or(1),
+    );
+    true
+}
+
+#[tauri::command]
+fn is_audio_available(state: State<'_, SharedState>) -> bool {
+    !state.audio_unavailable.load(Ordering::Relaxed)
+}
+
+#[tauri::command]
+fn stop_sound(state: State<'_, SharedState>) -> bool {
+    state.sound_generation.fetch_add(1, Ordering::Relaxed);
+    true
+}
+
+#[tauri::command]
+fn test_urgent_alert(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    title: String,
+    message: String,
+    alert_type: String,
+) -> bool {
+    let _ = notify_windows(&app, &title, &message);
+    if should_force_popup(&app, &state) {
+        force_alert_popup(
+            &app,
+            &state,
+            "test-urgent-alert",
+            &title,
+            &message,
+            &alert_type,
+        );
+    }
+    emit_urgent_alert(&app, &title, &message, &alert_type);
+    emit_if_possible(&app, "show-status", ());
+    true
+}
+
+#[tauri::command]
+fn replay_last_alert(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+) -> Result<Vec<ChannelResult>, String> {
+    replay_last_alert_for(&app, &state)
+}
+
+#[tauri::command]
+fn get_pending_alert(state: State<'_, SharedState>) -> Option<UrgentAlertPayload> {
+    lock(&state.pending_alert).clone()
+}
+
+#[tauri::command]
+fn acknowledge_alert(app: AppHandle, state: State<'_, SharedState>) -> bool {
+    clear_escalations(&state);
+    state.alert_generation.fetch_add(1, Ordering::Relaxed);
+    state.sound_generation.fetch_add(1, Ordering::Relaxed);
+    stop_visual_alert(&app, &state);
+    close_alert_window(&app, &state);
+    true
+}
+
+#[tauri::command]
+fn get_available_sounds(state: State<'_, SharedState>) -> Vec<SoundInfo> {
+    let settings = lock(&state.settings).clone();
+    let mut sounds = Vec::new();
+
+    if let Ok(entries) = fs::read_dir(&state.sounds_path) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !is_sound_file(&path) {
+                continue;
+            }
+            sounds.push(SoundInfo {
+                name: file_name_or_default(&path),
+                path: path.to_string_lossy().to_string(),
+                custom: false,
+                location: Some("AppData".to_string()),
+            });
+        }
+    }
+
+    for custom_path in &settings.custom_sounds_paths {
+        if let Ok(entries) = fs::read_dir(custom_path) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if !is_sound_file(&path) {
+                    continue;
+                }
+                sounds.push(SoundInfo {
+                    name: file_name_or_default(&path),
+                    path: path.to_string_lossy().to_string(),
+                    custom: true,
+                    location: Some("Custom".to_string()),
+                });
+            }
+        }
+    }
+
+    sounds
+}
+
+fn is_sound_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| {
+            let ext = ext.to_ascii_lowercase();
+            ext == "wav" || ext == "mp3"
+        })
+        .unwrap_or(false)
+}
+
+fn file_name_or_default(path: &Path) -> String {
+    path.file_name()
+        .and_then(|value| value.to_str())
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| "unknown.wav".to_string())
+}
+
+#[tauri::command]
+fn get_sound_config(state: State<'_, SharedState>) -> SoundConfig {
+    let settings = lock(&state.settings).clone();
+    SoundConfig {
+        repeat_config: SoundRepeatConfig {
+            ac_fault: settings.alerts.ac_fault.sound_repeats,
+            battery_low: settings.alerts.battery_low.sound_repeats,
+            critical: settings.alerts.battery_critical.sound_repeats,
+            default: 3,
+        },
+        repeat_delay: 2500,
+        custom_sounds_path: settings.custom_sounds_paths.first().cloned(),
+        custom_sounds_paths: settings.custom_sounds_paths,
+        sounds: SoundFiles {
+            ac_fault: "alert-ac-fault.wav".to_string(),
+            battery_low: "alert-battery-low.wav".to_string(),
+            critical: "alert-critical.wav".to_string(),
+        },
+    }
+}
 
-    if !is_due {
-        return;
+#[tauri::command]
+fn set_sound_config(state: State<'_, SharedState>, config: SoundConfigPatch) -> bool {
+    let mut settings = lock(&state.settings);
+    let max_repeats = settings.max_sound_repeats;
+
+    if let Some(repeat_config) = config.repeat_config {
+        if let Some(value) = repeat_config.default {
+            let default_value = clamp_u64(
+                value,
+                1,
+                max_repeats,
+                settings.alerts.ac_fault.sound_repeats,
+            );
+            settings.alerts.ac_fault.sound_repeats = default_value;
+            settings.alerts.battery_low.sound_repeats = default_value;
+            settings.alerts.battery_critical.sound_repeats = default_value;
+        }
+
+        if let Some(value) = repeat_config.ac_fault {
+            settings.alerts.ac_fault.sound_repeats = clamp_u64(
+                value,
+                1,
+                max_repeats,
+                settings.alerts.ac_fault.sound_repeats,
+            );
+        }
+        if let Some(value) = repeat_config.battery_low {
+            settings.alerts.battery_low.sound_repeats = clamp_u64(
+                value,
+                1,
+                max_repeats,
+                settings.alerts.battery_low.sound_repeats,
+            );
+        }
+        if let Some(value) = repeat_config.critical {
+            settings.alerts.battery_critical.sound_repeats = clamp_u64(
+                value,
+                1,
+                max_repeats,
+                settings.alerts.battery_critical.sound_repeats,
+            );
+        }
     }
 
-    let reason = lock(&state.scheduled_shutdown_reason)
-        .clone()
-        .unwrap_or_else(|| "shutdown-scheduled".to_string());
-    let _ = cancel_scheduled_shutdown(state, app, false);
+    if let Some(custom_path) = config.custom_sounds_path {
+        settings.custom_sounds_paths = custom_path.into_iter().collect();
+    }
+    if let Some(custom_paths) = config.custom_sounds_paths {
+        settings.custom_sounds_paths = normalize_sound_paths(custom_paths);
+    }
 
-    let title = "Apagado de seguridad";
-    let message = format!("Ejecutando accion configurada ({})", reason);
-    let _ = notify_windows(app, title, &message);
-    if should_force_popup(app, state) {
-        force_windows_popup(title, &message, "critical");
+    if let Some(delay_value) = config.repeat_delay {
+        let _normalized_delay_ms = clamp_u64(delay_value, 250, 10_000, 2_500);
     }
-    emit_urgent_alert(app, title, &message, "critical");
-    state.log_event("Critical Event", "Shutdown execution", &reason);
 
-    if let Err(error) = execute_shutdown_command(settings) {
-        emit_error_once(app, state, error);
+    drop(settings);
+    state.save_settings();
+    true
+}
+
+#[tauri::command]
+fn set_custom_sounds_path(state: State<'_, SharedState>, sound_path: Option<String>) -> bool {
+    lock(&state.settings).custom_sounds_paths =
+        normalize_sound_paths(sound_path.into_iter().collect());
+    state.save_settings();
+    true
+}
+
+#[tauri::command]
+fn set_custom_sounds_paths(state: State<'_, SharedState>, sound_paths: Vec<String>) -> Vec<String> {
+    let normalized = normalize_sound_paths(sound_paths);
+    lock(&state.settings).custom_sounds_paths = normalized.clone();
+    state.save_settings();
+    normalized
+}
+
+#[cfg(target_os = "windows")]
+fn apply_rounded_corners(window: &tauri::WebviewWindow) {
+    use windows::Win32::Foundation::HWND;
+    // Tauri v2 exposes hwnd() directly on Windows
+    if let Ok(hwnd_raw) = window.hwnd() {
+        let hwnd = HWND(hwnd_raw.0 as *mut _);
+        let preference = DWM_WINDOW_CORNER_PREFERENCE(2); // DWMWCP_ROUND
+        unsafe {
+            let _ = DwmSetWindowAttribute(
+                hwnd,
+                DWMWA_WINDOW_CORNER_PREFERENCE,
+                &preference as *const _ as *const _,
+                std::mem::size_of::<DWM_WINDOW_CORNER_PREFERENCE>() as u32,
+            );
+        }
     }
 }
 
-fn handle_alert_transition(
-    app: &AppHandle,
-    state: &SharedState,
-    settings: &AppSettings,
-    kind: AlertKind,
-    status: &UpsData,
-) {
-    if settings.monitor_only_mode {
-        return;
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    tauri::Builder::default()
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
+        .setup(|app| {
+            let state = Arc::new(AppState::new(&app.handle().clone()));
+            let log_level =
+                parse_log_level(&lock(&state.settings).log_level).unwrap_or(log::LevelFilter::Info);
+            init_file_logging(&state.log_dir(), log_level);
+            log::info!(
+                "UPS Monitor {} started (session {})",
+                APP_VERSION,
+                state.session_id
+            );
+            let start_minimized = lock(&state.settings).start_minimized;
+            let always_on_top = lock(&state.settings).always_on_top;
+            state.record_app_started();
+            if let Some(marker) = state.previous_shutdown.as_ref() {
+                let message = format!(
+                    "El equipo se apago por un evento de energia ({}, {})",
+                    marker.reason, marker.attempted_at
+                );
+                let _ = notify_windows(app.handle(), "Apagado de seguridad", &message);
+            }
+            for notice in &state.settings_notices {
+                let _ = notify_windows(app.handle(), "Ajustes actualizados", notice);
+            }
+            start_ups_monitor(app.handle().clone(), state.clone());
+            if lock(&state.settings).remote_api.enabled {
+                start_remote_api_server(app.handle().clone(), state.clone());
+            }
+            if lock(&state.settings).nut_server.enabled {
+                start_nut_server_listener(app.handle().clone(), state.clone());
+            }
+            app.manage(state);
+
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.set_always_on_top(always_on_top);
+            }
+
+            #[cfg(target_os = "windows")]
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.set_decorations(false);
+                let _ = window.set_shadow(false);
+                apply_rounded_corners(&window);
+            }
+
+            if start_minimized {
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.set_skip_taskbar(true);
+                    let _ = window.hide();
+                }
+            } else {
+                // Window starts hidden (visible=false in config).
+                // Set flag so main_window_ready (called from JS) will show it
+                // once the frontend has fully rendered, avoiding white flash.
+                if let Some(st) = app.try_state::<SharedState>() {
+                    st.pending_show_main_window.store(true, Ordering::Relaxed);
+                }
+            }
+
+            let tray_show =
+                MenuItemBuilder::with_id("tray_show", "Mostrar UPS Monitor").build(app)?;
+            let tray_replay =
+                MenuItemBuilder::with_id("tray_replay", "Repetir ultima alerta").build(app)?;
+            let tray_quit = MenuItemBuilder::with_id("tray_quit", "Salir").build(app)?;
+            let tray_menu = MenuBuilder::new(app)
+                .items(&[&tray_show, &tray_replay, &tray_quit])
+                .build()?;
+
+            // macOS convention opens the menu on left click; Windows uses left
+            // click to restore the window. Linux (AppIndicator) only delivers
+            // menu events, so "Mostrar UPS Monitor" is the way back there.
+            let mut tray_builder = TrayIconBuilder::with_id("main-tray")
+                .menu(&tray_menu)
+                .tooltip("UPS Monitor")
+                .show_menu_on_left_click(cfg!(target_os = "macos"))
+                .on_menu_event(|app, event| match event.id().as_ref() {
+                    "tray_show" => show_main_window(app),
+                    "tray_replay" => {
+                        if let Some(state) = app.try_state::<SharedState>() {
+                            if let Err(error) = replay_last_alert_for(app, &state) {
+                                log::info!("alert replay skipped: {}", error);
+                            }
+                        }
+                    }
+                    "tray_quit" => request_app_exit(app),
+                    _ => {}
+                })
+                .on_tray_icon_event(|tray, event| {
+                    if cfg!(target_os = "macos") {
+                        return;
+                    }
+                    if let TrayIconEvent::Click {
+                        button: MouseButton::Left,
+                        button_state: MouseButtonState::Up,
+                        ..
+                    } = event
+                    {
+                        show_main_window(tray.app_handle());
+                    }
+                });
+
+            if let Some(default_icon) = app.default_window_icon() {
+                tray_builder = tray_builder.icon(default_icon.clone());
+            }
+
+            let _ = tray_builder.build(app)?;
+            if let Some(st) = app.try_state::<SharedState>() {
+                refresh_tray_tooltip(app.handle(), &st);
+            }
+
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Destroyed = event {
+                if let Some(st) = window.try_state::<SharedState>() {
+                    st.unsubscribe_ups_data(window.label());
+                }
+            }
+        })
+        .invoke_handler(tauri::generate_handler![
+            get_settings,
+            get_settings_diff,
+            save_settings,
+            get_ups_status,
+            get_connection_state,
+            get_setup_guidance,
+            check_conflicting_software,
+            subscribe_ups_data,
+            unsubscribe_ups_data,
+            set_monitor_only,
+            is_monitor_only,
+            suspend_protection,
+            resume_protection,
+            get_protection_state,
+            get_shutdown_phase,
+            inject_status,
+            get_ups_info,
+            get_last_raw_frame,
+            get_raw_packet_log,
+            snapshot_state,
+            get_resource_usage,
+            get_monitors,
+            is_audio_available,
+            set_device_name,
+            refresh_ups_info,
+            validate_shutdown_command,
+            dry_run_shutdown_command,
+            run_preflight_checks,
+            get_about,
+            open_data_folder,
+            test_notification,
+            minimize_main_window,
+            toggle_maximize_main_window,
+            set_always_on_top,
+            set_skip_taskbar,
+            close_main_window,
+            main_window_ready,
+            cancel_shutdown,
+            trigger_shutdown,
+            confirm_shutdown_now,
+            simulate_shutdown_flow,
+            simulate_outage,
+            cancel_simulation,
+            get_previous_shutdown,
+            get_battery_time,
+            get_events,
+            mark_events_resolved,
+            delete_events,
+            get_data_history,
+            delete_data_history,
+            list_discharge_sessions,
+            get_discharge_session,
+            update_history_interval,
+            play_sound,
+            stop_sound,
+            test_urgent_alert,
+            get_pending_alert,
+            acknowledge_alert,
+            replay_last_alert,
+            get_available_sounds,
+            get_sound_config,
+            set_sound_config,
+            set_custom_sounds_path,
+            set_custom_sounds_paths,
+            create_support_bundle,
+            generate_report,
+            get_lifetime_stats,
+            flush_storage,
+            reset_lifetime_stats,
+            get_hid_tuning,
+            set_hid_tuning,
+            get_voltage_histogram,
+            get_frequency_histogram,
+            reset_histograms,
+            test_routing,
+            test_alert,
+            get_event_statistics,
+            get_event_keys,
+            get_statistics,
+            get_log_tail,
+            set_log_level
+        ])
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| match event {
+            tauri::RunEvent::ExitRequested { api, .. } => {
+                if let Some(state) = app.try_state::<SharedState>() {
+                    if !state.allow_process_exit.load(Ordering::Relaxed) {
+                        api.prevent_exit();
+                    }
+                }
+            }
+            tauri::RunEvent::Exit => {
+                if let Some(state) = app.try_state::<SharedState>() {
+                    state.record_app_stopped();
+                }
+            }
+            _ => {}
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_status_frame() {
+        let decoded = parse_ups_string(
+            "(218.5 140.0 219.0 023 60.1 26.4 32.0 00001001",
+            &FrameFormat::default(),
+        );
+        let Some(DecodedPacket::Status(status)) = decoded else {
+            panic!("expected status frame");
+        };
+        assert_eq!(status.input_voltage, 218.5);
+        assert_eq!(status.load_percent, 23);
+        assert!(!status.status.utility_fail);
+        assert!(status.status.beeper_on);
     }
 
-    let config = alert_config_for_kind(settings, kind);
-    let title = kind.event_name();
-    let message = format!(
-        "Entrada {:.1}V · Bateria {}% · Carga {}%",
-        status.input_voltage, status.battery_percent, status.load_percent
-    );
+    #[test]
+    fn parses_firmware_frame() {
+        let decoded = parse_ups_string("#RICHCOMM        UPS USB   V2.1", &FrameFormat::default());
+        let Some(DecodedPacket::Version(firmware)) = decoded else {
+            panic!("expected version frame");
+        };
+        assert_eq!(firmware, "RICHCOMM UPS USB V2.1");
+    }
+
+    #[test]
+    fn rejects_rating_and_garbage_frames() {
+        assert!(parse_ups_string("#220.0 000 024.0 60.0", &FrameFormat::default()).is_none());
+        assert!(parse_ups_string("(218.5 140.0 219.0", &FrameFormat::default()).is_none());
+        assert!(parse_ups_string("V?@@ 12", &FrameFormat::default()).is_none());
+        assert!(parse_ups_string("", &FrameFormat::default()).is_none());
+    }
+
+    #[test]
+    fn redacts_credential_fields_recursively() {
+        let mut value = serde_json::json!({
+            "pollingInterval": 1000,
+            "remoteApi": { "bearerToken": "abc123", "enabled": true },
+            "mqtt": [{ "password": "hunter2", "host": "broker" }],
+            "webhookSecret": "",
+            "customSoundsPath": null
+        });
+        let mut redacted = Vec::new();
+        redact_secrets(&mut value, "", &mut redacted);
+
+        assert_eq!(value["remoteApi"]["bearerToken"], "[redacted]");
+        assert_eq!(value["mqtt"][0]["password"], "[redacted]");
+        assert_eq!(value["mqtt"][0]["host"], "broker");
+        assert_eq!(value["pollingInterval"], 1000);
+        assert_eq!(redacted, vec!["mqtt[0].password", "remoteApi.bearerToken"]);
+
+        let text = value.to_string();
+        assert!(!text.contains("abc123"));
+        assert!(!text.contains("hunter2"));
+    }
+
+    #[test]
+    fn support_bundle_redacts_shutdown_environment_values() {
+        let mut settings = AppSettings::default();
+        settings.shutdown_pc.environment = BTreeMap::from([
+            ("PGPASS".to_string(), "hunter2".to_string()),
+            ("BACKUP_TARGET".to_string(), "D:\\backup".to_string()),
+        ]);
+        let (value, redacted) = support_bundle_settings(&settings).unwrap();
+
+        let environment = &value["shutdownPC"]["environment"];
+        assert_eq!(environment["PGPASS"], "[redacted]");
+        assert_eq!(environment["BACKUP_TARGET"], "[redacted]");
+        assert!(redacted.contains(&"shutdownPC.environment.PGPASS".to_string()));
+        assert!(!value.to_string().contains("hunter2"));
+    }
+
+    #[test]
+    fn strips_report_id_only_when_prefixed() {
+        let prefixed = b"\x00(218.5 140.0 219.0 023 60.1 26.4 32.0 00001001\r";
+        let bare = b"(218.5 140.0 219.0 023 60.1 26.4 32.0 00001001\r";
+
+        for (raw, report_id_prefixed) in [(&prefixed[..], true), (&bare[..], false)] {
+            let mut pending = Vec::new();
+            let frames =
+                take_complete_frames(&mut pending, hid_report_payload(raw, report_id_prefixed));
+            assert_eq!(frames.len(), 1);
+            let Some(DecodedPacket::Status(status)) =
+                decode_packet(&frames[0], &FrameFormat::default())
+            else {
+                panic!("expected status frame");
+            };
+            assert_eq!(status.input_voltage, 218.5);
+        }
+
+        let mut pending = Vec::new();
+        let frames = take_complete_frames(&mut pending, hid_report_payload(bare, true));
+        assert!(decode_packet(&frames[0], &FrameFormat::default()).is_none());
+    }
+
+    #[test]
+    fn reassembles_frames_split_across_reports() {
+        let mut pending = Vec::new();
+        assert!(take_complete_frames(&mut pending, b"(218.5 140.0 219.0 023").is_empty());
+        let frames = take_complete_frames(&mut pending, b" 60.1 26.4 32.0 00001001\r\0\0(219.0");
+        assert_eq!(frames.len(), 1);
+        assert_eq!(pending, b"(219.0");
+        assert!(matches!(
+            decode_packet(&frames[0], &FrameFormat::default()),
+            Some(DecodedPacket::Status(_))
+        ));
+    }
+
+    #[test]
+    fn strict_restore_bands_follow_the_supply_unless_set() {
+        let mut strict = StrictRestoreSettings::default();
+        let ((min_v, max_v), (min_f, max_f)) = strict_restore_bands(&strict, (228.0, 50.1));
+        assert!(min_v < 200.0 && max_v > 260.0);
+        assert_eq!((min_f, max_f), (49.0, 51.0));
+        let ((min_v, max_v), (min_f, max_f)) = strict_restore_bands(&strict, (119.0, 59.9));
+        assert!(min_v > 100.0 && max_v < 140.0);
+        assert_eq!((min_f, max_f), (59.0, 61.0));
+
+        // A band the user picked stays, even one matching a grid's usual range.
+        strict.min_frequency = Some(59.0);
+        strict.max_frequency = Some(61.0);
+        strict.min_voltage = Some(200.0);
+        strict.max_voltage = Some(250.0);
+        strict.normalize();
+        assert_eq!(strict.min_frequency, Some(59.0));
+        assert_eq!(strict.max_frequency, Some(61.0));
+        assert_eq!(
+            strict_restore_bands(&strict, (230.0, 50.0)).0,
+            (200.0, 250.0)
+        );
+        strict.max_voltage = None;
+        strict.normalize();
+        assert_eq!(strict.min_voltage, None);
+    }
+
+    #[test]
+    fn remote_api_requires_matching_bearer_token() {
+        let mut api = RemoteApiSettings::default();
+        let headers = "Host: ups\r\nAuthorization: Bearer s3cret\r\n";
+        assert!(!remote_request_authorized(&api, headers));
+
+        api.token = Some("s3cret".to_string());
+        assert!(remote_request_authorized(&api, headers));
+        assert!(!remote_request_authorized(
+            &api,
+            "Authorization: Bearer wrong\r\n"
+        ));
+        assert!(!remote_request_authorized(&api, "Host: ups\r\n"));
+    }
+
+    #[test]
+    fn nut_client_is_dropped_on_an_over_long_line() {
+        let mut input = b"VER\n".to_vec();
+        input.extend_from_slice(&[b'A'; 4096]);
+        let mut reader = input.as_slice();
+        let mut line = String::new();
+        assert!(read_nut_line(&mut reader, &mut line));
+        assert_eq!(line, "VER\n");
+        assert!(!read_nut_line(&mut reader, &mut line));
+    }
+
+    #[test]
+    fn connection_slots_cap_open_connections() {
+        let open = Arc::new(AtomicU64::new(0));
+        let first = ConnectionSlot::acquire(&open, 2).unwrap();
+        let second = ConnectionSlot::acquire(&open, 2).unwrap();
+        assert!(ConnectionSlot::acquire(&open, 2).is_none());
+        drop(first);
+        assert!(ConnectionSlot::acquire(&open, 2).is_some());
+        drop(second);
+        assert_eq!(open.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn answers_nut_variable_queries() {
+        let Some(DecodedPacket::Status(status)) = parse_ups_string(
+            "(208.4 140.0 208.4 034 59.9 2.05 35.0 10000001",
+            &FrameFormat::default(),
+        ) else {
+            panic!("expected status frame");
+        };
+
+        let reply = nut_response("GET VAR ups ups.status", "ups", Some(&status), None);
+        assert_eq!(reply, "VAR ups ups.status \"OB\"\n");
+        let reply = nut_response("GET VAR ups ups.load", "ups", Some(&status), None);
+        assert_eq!(reply, "VAR ups ups.load \"34\"\n");
+
+        let listing = nut_response("LIST VAR ups", "ups", Some(&status), None);
+        assert!(listing.starts_with("BEGIN LIST VAR ups\n"));
+        assert!(listing.contains("VAR ups input.voltage \"208.4\"\n"));
+        assert!(listing.ends_with("END LIST VAR ups\n"));
+
+        assert_eq!(
+            nut_response("LIST VAR other", "ups", Some(&status), None),
+            "ERR UNKNOWN-UPS\n"
+        );
+        assert_eq!(
+            nut_response("LIST VAR ups", "ups", None, None),
+            "ERR DATA-STALE\n"
+        );
+        assert_eq!(
+            nut_response("INSTCMD ups beeper.off", "ups", Some(&status), None),
+            "ERR ACCESS-DENIED\n"
+        );
+    }
+
+    #[test]
+    fn translates_legacy_alert_booleans_into_channels() {
+        let legacy: AlertConfig =
+            serde_json::from_str(r#"{"playSound":true,"showPopup":false,"soundRepeats":4}"#)
+                .unwrap();
+        assert_eq!(legacy.channels, vec!["sound".to_string()]);
+        assert_eq!(legacy.sound_repeats, 4);
+
+        let legacy: AlertConfig =
+            serde_json::from_str(r#"{"playSound":false,"showPopup":true,"soundRepeats":2}"#)
+                .unwrap();
+        assert!(legacy.has_channel("notification") && legacy.has_channel("popup"));
+        assert!(!legacy.has_channel("sound"));
+
+        let mut routed: AlertConfig =
+            serde_json::from_str(r#"{"channels":["sound","bogus","popup"],"soundRepeats":1}"#)
+                .unwrap();
+        routed.normalize_channels();
+        assert_eq!(
+            routed.channels,
+            vec!["popup".to_string(), "sound".to_string()]
+        );
+    }
+
+    #[test]
+    fn maps_legacy_classification_to_severity() {
+        for (classification, name, severity) in [
+            ("Critical Event", "AC Fault", Severity::Warning),
+            ("Critical Event", "Battery Critical", Severity::Critical),
+            ("General Event", "Normal AC value", Severity::Info),
+        ] {
+            let mut legacy: HistoryEvent = serde_json::from_value(serde_json::json!({
+                "id": 1,
+                "time": "2026-01-01T00:00:00Z",
+                "classification": classification,
+                "name": name,
+                "remarks": "",
+            }))
+            .unwrap();
+            assert_eq!(legacy.severity, None);
+            legacy.key = event_key_for_name(&legacy.name);
+            assert_eq!(legacy.severity(), severity, "{}", name);
+        }
+        assert!(Severity::Critical > Severity::Warning && Severity::Warning > Severity::Info);
+        assert_eq!(Severity::Info.classification(), "General Event");
+    }
+
+    fn trace(points: impl Iterator<Item = (u64, f64)>) -> Vec<(u64, f64)> {
+        points
+            .map(|(seconds, percent)| (seconds * 1000, percent))
+            .collect()
+    }
+
+    #[test]
+    fn projects_time_to_empty_from_linear_discharge() {
+        // 1% per minute, sampled every 10 s for 3 minutes: 80% -> 77%.
+        let samples = trace((0..=18).map(|i| (i * 10, 80.0 - i as f64 / 6.0)));
+        assert_eq!(project_percent_minutes(&samples, 77.0, true), Some(77));
+        assert_eq!(project_percent_minutes(&samples, 77.0, false), None);
+    }
+
+    #[test]
+    fn projects_time_to_full_from_noisy_charge() {
+        // 0.5% per minute with +/-1% quantisation jitter over 4 minutes.
+        let jitter = [0.0, 1.0, -1.0, 0.5, -0.5];
+        let samples = trace((0..=24).map(|i| {
+            (
+                i * 10,
+                60.0 + i as f64 / 12.0 + jitter[i as usize % jitter.len()],
+            )
+        }));
+        let minutes = project_percent_minutes(&samples, 62.0, false).unwrap();
+        assert!((60..=95).contains(&minutes), "got {}", minutes);
+    }
+
+    #[test]
+    fn skips_projection_without_enough_slope_data() {
+        let flat = trace((0..=12).map(|i| (i * 10, 90.0)));
+        assert_eq!(project_percent_minutes(&flat, 90.0, true), None);
+        let short = trace((0..4).map(|i| (i * 10, 90.0 - i as f64)));
+        assert_eq!(project_percent_minutes(&short, 87.0, true), None);
+    }
+
+    #[test]
+    fn splits_shutdown_command_with_quoted_paths() {
+        assert_eq!(
+            split_command_line(r#""C:\Program Files\Tool\off.exe" /force  'two words'"#).unwrap(),
+            vec![r"C:\Program Files\Tool\off.exe", "/force", "two words"]
+        );
+        assert_eq!(
+            split_command_line("shutdown /s /t 0").unwrap(),
+            vec!["shutdown", "/s", "/t", "0"]
+        );
+        assert_eq!(split_command_line(r#"a "" b"#).unwrap(), vec!["a", "", "b"]);
+        assert!(split_command_line(r#"off.exe "unterminated"#).is_err());
+    }
+
+    #[test]
+    fn keeps_shell_mode_for_legacy_custom_commands() {
+        let shell = if cfg!(target_os = "windows") {
+            "cmd"
+        } else {
+            "sh"
+        };
+        for legacy in [
+            "backup.bat && shutdown /s /t 0",
+            "echo apagando > C:\\log.txt",
+            "del %TEMP%\\lock",
+        ] {
+            let argv = custom_command_argv(legacy).unwrap();
+            assert_eq!(argv.first().map(String::as_str), Some(shell));
+            assert_eq!(argv.last().map(String::as_str), Some(legacy));
+        }
+        assert_eq!(
+            custom_command_argv(r#""C:\Tools\off.exe" /force"#).unwrap(),
+            vec![r"C:\Tools\off.exe", "/force"]
+        );
+        assert!(custom_command_argv("   ").is_err());
+
+        // Validation looks past the shell at the program the command starts.
+        let typo = check_shutdown_argv(custom_command_argv(
+            "definitely-not-bakup.bat && shutdown /s /t 0",
+        ));
+        assert!(!typo.ok);
+        assert!(typo.error.unwrap().contains("definitely-not-bakup.bat"));
+        assert!(check_shutdown_argv(custom_command_argv("echo apagando > apagado.txt")).ok);
+    }
+
+    #[test]
+    fn queues_alerts_with_critical_first_and_collapses_duplicates() {
+        let alert = |key: &str, message: &str, alert_type: &str| QueuedAlert {
+            key: key.to_string(),
+            payload: urgent_alert_payload(key, message, alert_type),
+        };
+        let mut queue = AlertQueue::default();
+        queue.push(alert("ac", "primero", "warning"));
+        queue.push(alert("low", "bateria", "battery"));
+        queue.push(alert("ac", "segundo", "warning"));
+        assert!(!queue.push(alert("critical", "critica", "critical")));
+
+        let order: Vec<(&str, &str)> = queue
+            .items
+            .iter()
+            .map(|item| (item.key.as_str(), item.payload.message.as_str()))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                ("critical", "critica"),
+                ("ac", "segundo"),
+                ("low", "bateria")
+            ]
+        );
 
-    if settings.enable_notifications && config.show_popup {
-        let _ = notify_windows(app, title, &message);
+        // An alert that is not ready yet stays queued behind the ones that are.
+        let next = queue.pop_ready(|item| item.key != "critical");
+        assert_eq!(next.map(|item| item.key), Some("ac".to_string()));
+        assert_eq!(queue.items.len(), 2);
+
+        // A critical alert cuts short a lesser one on screen, never another critical.
+        queue.showing = Some("warning".to_string());
+        assert!(queue.push(alert("critical", "otra", "critical")));
+        assert!(!queue.push(alert("low", "bateria", "battery")));
+        queue.showing = Some("critical".to_string());
+        assert!(!queue.push(alert("critical", "otra", "critical")));
     }
 
-    if config.show_popup {
-        emit_urgent_alert(app, title, &message, kind.alert_type());
-        if should_force_popup(app, state) {
-            force_windows_popup(title, &message, kind.alert_type());
-        }
+    fn write_json_pretty<T: Serialize>(path: &Path, value: &T) {
+        fs::write(path, serde_json::to_vec_pretty(value).unwrap()).unwrap();
     }
 
-    if config.play_sound {
-        let sound_path = resolve_sound_path(state, settings, kind);
-        let _ = play_sound_with_generation(state.clone(), sound_path, config.sound_repeats);
+    struct ManualClock {
+        now_ms: AtomicU64,
     }
 
-    match kind {
-        AlertKind::AcFault if settings.shutdown_pc.on_ac_fault.enabled => {
-            let _ = schedule_shutdown_after_minutes(
-                state,
-                app,
-                settings.shutdown_pc.on_ac_fault.delay_minutes,
-                "ac-fault",
-            );
-        }
-        AlertKind::BatteryLow if settings.shutdown_pc.on_battery_low.enabled => {
-            let _ = schedule_shutdown_after_minutes(
-                state,
-                app,
-                BATTERY_LOW_SHUTDOWN_DELAY_MINUTES,
-                "battery-low",
-            );
-        }
-        AlertKind::BatteryCritical if settings.shutdown_pc.on_battery_critical.enabled => {
-            let _ = schedule_shutdown_after_minutes(
-                state,
-                app,
-                BATTERY_CRITICAL_SHUTDOWN_DELAY_MINUTES,
-                "battery-critical",
-            );
+    impl ManualClock {
+        fn new() -> Arc<Self> {
+            Arc::new(Self {
+                now_ms: AtomicU64::new(1_700_000_000_000),
+            })
         }
-        _ => {}
-    }
-}
 
-fn poll_ups(
-    app: &AppHandle,
-    state: &SharedState,
-    api: &HidApi,
-    connected_device: &mut Option<hidapi::HidDevice>,
-    read_timeout_ms: i32,
-) {
-    if let Some(device) = connected_device.as_ref() {
-        clear_last_error(state);
-        mark_connected(app, state);
-        if !read_one_packet(app, state, device, read_timeout_ms) {
-            *connected_device = None;
+        fn advance(&self, ms: u64) {
+            self.now_ms.fetch_add(ms, Ordering::Relaxed);
         }
-        return;
     }
 
-    let mut found = false;
+    impl Clock for ManualClock {
+        fn now_ms(&self) -> u64 {
+            self.now_ms.load(Ordering::Relaxed)
+        }
 
-    for device_info in api.device_list() {
-        if device_info.vendor_id() != UPS_VID || device_info.product_id() != UPS_PID {
-            continue;
+        fn sleep(&self, duration: Duration) {
+            self.advance(duration.as_millis() as u64);
         }
+    }
 
-        found = true;
+    // Pass a ManualClock to drive the state's timers from the test.
+    fn temp_state(
+        name: &str,
+        settings: &AppSettings,
+        clock: Option<Arc<ManualClock>>,
+    ) -> (PathBuf, AppState) {
+        let dir = std::env::temp_dir().join(format!(
+            "ups-monitor-test-{}-{}-{}",
+            name,
+            std::process::id(),
+            now_millis()
+        ));
+        let _ = fs::create_dir_all(&dir);
+        write_json_pretty(&dir.join("config.json"), settings);
+        let mut state = AppState::load(dir.clone());
+        if let Some(clock) = clock {
+            state.clock = clock;
+        }
+        (dir, state)
+    }
 
-        let info = UpsInfo {
-            manufacturer: device_info
-                .manufacturer_string()
-                .unwrap_or("RICHCOMM")
-                .to_string(),
-            product: device_info
-                .product_string()
-                .unwrap_or("UPS USB Mon")
-                .to_string(),
-            vendor_id: format!("{:04X}", UPS_VID),
-            product_id: format!("{:04X}", UPS_PID),
-            firmware: lock(&state.device_info)
-                .as_ref()
-                .and_then(|existing| existing.firmware.clone()),
+    #[test]
+    fn logs_events_in_monitor_only_mode_when_history_is_enabled() {
+        let settings = AppSettings {
+            monitor_only_mode: true,
+            ..AppSettings::default()
         };
-        *lock(&state.device_info) = Some(info);
+        let (dir, state) = temp_state("monitor-only", &settings, None);
+        let settings = state.effective_settings();
+        assert!(settings.monitor_only_mode);
+        assert!(settings.save_history);
+        assert!(!settings.shutdown_pc.on_battery_critical.enabled);
+
+        state.log_event("ac_fault", Severity::Warning, "corte");
+        assert_eq!(lock(&state.events).len(), 1);
+        let saved: Vec<HistoryEvent> = read_json_or_default(&state.events_path);
+        assert_eq!(saved.len(), 1);
+        let _ = fs::remove_dir_all(dir);
+    }
 
-        match api.open_path(device_info.path()) {
-            Ok(device) => {
-                clear_last_error(state);
-                mark_connected(app, state);
-                *connected_device = Some(device);
-                if let Some(active_device) = connected_device.as_ref() {
-                    let _ = read_one_packet(app, state, active_device, read_timeout_ms.min(150));
-                }
-                return;
-            }
-            Err(error) => {
-                emit_error_once(app, state, format!("Cannot open UPS HID device: {}", error));
-            }
+    #[test]
+    fn skips_events_when_history_is_disabled() {
+        for monitor_only_mode in [false, true] {
+            let settings = AppSettings {
+                monitor_only_mode,
+                save_history: false,
+                ..AppSettings::default()
+            };
+            let (dir, state) = temp_state("no-history", &settings, None);
+            state.log_event("ac_fault", Severity::Warning, "corte");
+            assert!(lock(&state.events).is_empty());
+            let _ = fs::remove_dir_all(dir);
         }
     }
 
-    if !found {
-        clear_last_error(state);
-        mark_disconnected(app, state);
+    #[test]
+    fn keeps_history_off_for_migrated_monitor_only_configs() {
+        let mut legacy = serde_json::to_value(AppSettings {
+            monitor_only_mode: true,
+            save_history: false,
+            ..AppSettings::default()
+        })
+        .unwrap();
+        legacy.as_object_mut().unwrap().remove("settingsRevision");
+        let mut settings: AppSettings = serde_json::from_value(legacy).unwrap();
+
+        assert_eq!(settings.migrate().len(), 1);
+        assert!(!settings.normalize().save_history);
+        let mut current = AppSettings::default();
+        assert!(current.migrate().is_empty());
     }
-}
 
-fn read_one_packet(
-    app: &AppHandle,
-    state: &SharedState,
-    device: &hidapi::HidDevice,
-    read_timeout_ms: i32,
-) -> bool {
-    let mut buffer = [0u8; 64];
-    match device.read_timeout(&mut buffer, read_timeout_ms.max(100)) {
-        Ok(size) if size > 0 => {
-            if let Some(decoded) = decode_packet(&buffer[..size]) {
-                match decoded {
-                    DecodedPacket::Version(firmware) => {
-                        if let Some(info) = lock(&state.device_info).as_mut() {
-                            info.firmware = Some(firmware);
-                        }
-                    }
-                    DecodedPacket::Status(status) => {
-                        handle_status_packet(app, state, status);
-                    }
-                }
-            }
-            true
-        }
-        Ok(_) => true,
-        Err(error) => {
-            emit_error_once(app, state, format!("HID read error: {}", error));
-            mark_disconnected(app, state);
-            false
+    #[test]
+    fn flush_storage_writes_every_store() {
+        let (dir, state) = temp_state("flush", &AppSettings::default(), None);
+        state.log_event("Test", Severity::Info, "flush");
+        let _ = fs::remove_file(&state.events_path);
+
+        state.flush_storage().unwrap();
+        for path in [
+            &state.config_path,
+            &state.events_path,
+            &state.data_path,
+            &state.lifetime_stats_path,
+            &state.runtime_state_path,
+        ] {
+            assert!(path.exists(), "{}", path.display());
         }
+        let saved: Vec<HistoryEvent> = read_json_or_default(&state.events_path);
+        assert_eq!(saved.len(), 1);
+        let _ = fs::remove_dir_all(dir);
     }
-}
 
-fn handle_status_packet(app: &AppHandle, state: &SharedState, status: UpsData) {
-    let settings = lock(&state.settings).clone();
+    #[test]
+    fn monitor_only_mode_keeps_stored_settings_intact() {
+        let mut configured = AppSettings::default();
+        configured.shutdown_pc.shutdown_command = "C:\\tools\\apagar.exe /now".to_string();
+        configured.alerts.ac_fault = AlertConfig::new(&["sound"], 7);
+        configured.display_off_on_ac_fault = true;
 
-    let was_on_battery = *lock(&state.is_on_battery);
-    let is_on_battery = status.status.utility_fail;
+        let mut stored = AppSettings {
+            monitor_only_mode: true,
+            ..configured.clone()
+        }
+        .normalize();
+        assert_eq!(
+            stored.shutdown_pc.shutdown_command,
+            configured.shutdown_pc.shutdown_command
+        );
 
-    let mut ac_fault_triggered = false;
-    if is_on_battery && !was_on_battery {
-        *lock(&state.battery_start_ms) = Some(now_millis());
-        state.log_event("Critical Event", "AC Fault", "AC Fault");
-        ac_fault_triggered = true;
+        let effective = stored.effective();
+        assert!(effective.shutdown_pc.shutdown_command.is_empty());
+        assert!(effective.alerts.ac_fault.channels.is_empty());
+        assert!(!effective.enable_notifications);
+        assert!(!effective.display_off_on_ac_fault);
+
+        stored.monitor_only_mode = false;
+        let restored = stored.normalize().effective();
+        assert_eq!(
+            restored.shutdown_pc.shutdown_command,
+            configured.shutdown_pc.shutdown_command
+        );
+        assert_eq!(restored.alerts.ac_fault.channels, vec!["sound".to_string()]);
+        assert_eq!(restored.alerts.ac_fault.sound_repeats, 7);
+        assert!(restored.display_off_on_ac_fault);
     }
 
-    if !is_on_battery && was_on_battery {
-        *lock(&state.battery_start_ms) = None;
-        *lock(&state.was_battery_low) = false;
-        *lock(&state.was_battery_critical) = false;
-        state.log_event("General Event", "Normal AC value", "Normal AC value");
-        let _ = cancel_scheduled_shutdown(state, app, true);
-        state.sound_generation.fetch_add(1, Ordering::Relaxed);
+    #[test]
+    fn restores_defaults_for_configs_flattened_by_old_monitor_only_mode() {
+        let mut flattened = AppSettings {
+            monitor_only_mode: true,
+            settings_revision: 1,
+            ..AppSettings::default()
+        };
+        flattened.apply_monitor_only_defaults();
+
+        let notices = flattened.migrate();
+        assert_eq!(notices.len(), 1);
+        assert!(flattened.enable_notifications);
+        assert!(flattened.alerts.battery_critical.has_channel("sound"));
+        assert!(flattened.shutdown_pc.on_battery_critical.enabled);
+        assert_eq!(flattened.settings_revision, SETTINGS_REVISION);
+        assert!(flattened.migrate().is_empty());
     }
 
-    let is_low_battery = is_on_battery
-        && (status.status.battery_low || status.battery_percent <= settings.low_battery_threshold);
-    let is_critical_battery =
-        is_on_battery && status.battery_percent <= settings.critical_battery_threshold;
+    #[test]
+    fn escalates_until_the_condition_clears_or_the_cap_expires() {
+        let mut settings = AppSettings::default();
+        settings.alerts.ac_fault.escalate = true;
+        settings.alerts.ac_fault.repeat_delay_seconds = 30;
+        let (dir, state) = temp_state("escalation", &settings, None);
+        let state = Arc::new(state);
+        *lock(&state.is_on_battery) = true;
+
+        start_escalation(&state, &settings, AlertKind::AcFault, "corte");
+        start_escalation(&state, &settings, AlertKind::BatteryLow, "baja");
+        let started = lock(&state.escalations)[0].started_ms;
+        assert_eq!(lock(&state.escalations).len(), 1);
+
+        assert!(due_escalations(&state, &settings, started + 10_000).is_empty());
+        let due = due_escalations(&state, &settings, started + 30_000);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].0, AlertKind::AcFault);
+        assert!(due_escalations(&state, &settings, started + 40_000).is_empty());
+
+        assert!(
+            due_escalations(&state, &settings, started + ESCALATION_MAX_DURATION_MS).is_empty()
+        );
+        assert!(lock(&state.escalations).is_empty());
 
-    let battery_low_triggered = {
-        let mut was_low = lock(&state.was_battery_low);
-        let mut triggered = false;
-        if is_low_battery && !is_critical_battery && !*was_low {
-            state.log_event("Critical Event", "Battery Low", "Battery Low");
-            *was_low = true;
-            triggered = true;
-        }
-        if !is_low_battery {
-            *was_low = false;
-        }
-        triggered
-    };
+        start_escalation(&state, &settings, AlertKind::AcFault, "corte");
+        *lock(&state.is_on_battery) = false;
+        assert!(due_escalations(&state, &settings, now_millis() + 60_000).is_empty());
+        assert!(lock(&state.escalations).is_empty());
+        let _ = fs::remove_dir_all(dir);
+    }
 
-    let battery_critical_triggered = {
-        let mut was_critical = lock(&state.was_battery_critical);
-        let mut triggered = false;
-        if is_critical_battery && !*was_critical {
-            state.log_event("Critical Event", "Battery Critical", "Battery Critical");
-            *was_critical = true;
-            triggered = true;
-        }
-        if !is_critical_battery {
-            *was_critical = false;
-        }
-        triggered
-    };
+    #[test]
+    fn merges_competing_shutdown_schedules_by_severity() {
+        let schedule = |reason: &str, at_ms: u64| ScheduledShutdown {
+            at_ms,
+            reason: reason.to_string(),
+            origin: EventOrigin::Real,
+        };
+        let merged = |current: ScheduledShutdown, candidate: ScheduledShutdown, forced: bool| {
+            merge_shutdown_schedule(Some(&current), candidate, forced)
+                .map(|item| (item.reason, item.at_ms))
+        };
 
-    if ac_fault_triggered {
-        handle_alert_transition(app, state, &settings, AlertKind::AcFault, &status);
-    }
-    if battery_low_triggered {
-        handle_alert_transition(app, state, &settings, AlertKind::BatteryLow, &status);
+        assert_eq!(
+            merge_shutdown_schedule(None, schedule("ac-fault", 900), false),
+            Some(schedule("ac-fault", 900))
+        );
+
+        // A rehearsal never moves a real schedule; a real one replaces it.
+        let rehearsal = ScheduledShutdown {
+            origin: EventOrigin::Simulation,
+            ..schedule("battery-critical", 60)
+        };
+        assert_eq!(
+            merge_shutdown_schedule(Some(&schedule("ac-fault", 900)), rehearsal.clone(), true),
+            None
+        );
+        assert_eq!(
+            merged(rehearsal, schedule("ac-fault", 900), false),
+            Some(("ac-fault".to_string(), 900))
+        );
+
+        // More severe and sooner: replaces both.
+        assert_eq!(
+            merged(
+                schedule("ac-fault", 900),
+                schedule("battery-low", 300),
+                false
+            ),
+            Some(("battery-low".to_string(), 300))
+        );
+        assert_eq!(
+            merged(
+                schedule("battery-low", 300),
+                schedule("battery-critical", 60),
+                false
+            ),
+            Some(("battery-critical".to_string(), 60))
+        );
+        // More severe but later: the reason escalates, the deadline stays.
+        assert_eq!(
+            merged(
+                schedule("ac-fault", 60),
+                schedule("battery-critical", 120),
+                false
+            ),
+            Some(("battery-critical".to_string(), 60))
+        );
+        // Less severe but sooner: the deadline moves, the reason is kept.
+        assert_eq!(
+            merged(
+                schedule("battery-critical", 120),
+                schedule("ac-fault", 60),
+                false
+            ),
+            Some(("battery-critical".to_string(), 60))
+        );
+        // Less severe and later: nothing changes.
+        assert_eq!(
+            merged(
+                schedule("battery-critical", 60),
+                schedule("battery-low", 300),
+                false
+            ),
+            None
+        );
+        assert_eq!(
+            merged(
+                schedule("battery-low", 300),
+                schedule("ac-fault", 900),
+                false
+            ),
+            None
+        );
+        // Same reason again, sooner: only the deadline moves.
+        assert_eq!(
+            merged(schedule("ac-fault", 900), schedule("ac-fault", 600), false),
+            Some(("ac-fault".to_string(), 600))
+        );
+        assert_eq!(
+            merged(schedule("ac-fault", 600), schedule("ac-fault", 900), false),
+            None
+        );
+
+        // Manual is the lowest automatic priority unless forced.
+        assert_eq!(
+            merged(
+                schedule("manual-trigger", 600),
+                schedule("ac-fault", 900),
+                false
+            ),
+            Some(("ac-fault".to_string(), 600))
+        );
+        assert_eq!(
+            merged(
+                schedule("battery-critical", 60),
+                schedule("manual-trigger", 30),
+                false
+            ),
+            Some(("battery-critical".to_string(), 30))
+        );
+        // A later forced trigger leaves the protective reason and deadline.
+        assert_eq!(
+            merged(
+                schedule("battery-critical", 60),
+                schedule("manual-trigger", 300),
+                true
+            ),
+            None
+        );
+        assert_eq!(
+            merged(
+                schedule("battery-critical", 60),
+                schedule("manual-trigger", 30),
+                true
+            ),
+            Some(("manual-trigger".to_string(), 30))
+        );
     }
-    if battery_critical_triggered {
-        handle_alert_transition(app, state, &settings, AlertKind::BatteryCritical, &status);
+
+    #[test]
+    fn classifies_outage_depth_from_input_voltage() {
+        assert_eq!(UtilityState::classify(false, 0.0), UtilityState::Normal);
+        assert_eq!(UtilityState::classify(true, 0.0), UtilityState::Lost);
+        assert_eq!(UtilityState::classify(true, 12.5), UtilityState::Lost);
+        assert_eq!(UtilityState::classify(true, 168.0), UtilityState::Degraded);
+        assert_eq!(
+            serde_json::to_value(UtilityState::Degraded).unwrap(),
+            "degraded"
+        );
     }
 
-    process_pending_shutdown(app, state, &settings);
+    #[test]
+    fn reconciles_outage_restored_from_disk() {
+        let on_battery = parse_ups_string(
+            "(000.0 140.0 219.0 023 60.1 24.4 32.0 10001001",
+            &FrameFormat::default(),
+        );
+        let on_ac = parse_ups_string(
+            "(218.5 140.0 219.0 023 60.1 26.4 32.0 00001001",
+            &FrameFormat::default(),
+        );
+        let (Some(DecodedPacket::Status(on_battery)), Some(DecodedPacket::Status(on_ac))) =
+            (on_battery, on_ac)
+        else {
+            panic!("expected status frames");
+        };
 
-    *lock(&state.is_on_battery) = is_on_battery;
-    *lock(&state.last_status) = Some(status.clone());
+        let cases = [
+            (&on_battery, 20 * 60 * 1000, true),
+            (&on_battery, RESTORED_OUTAGE_MAX_AGE_MS + 1, false),
+            (&on_ac, 20 * 60 * 1000, false),
+        ];
+        for (status, age_ms, kept) in cases {
+            let (dir, _) = temp_state("restored-outage", &AppSettings::default(), None);
+            let started = now_millis() - age_ms;
+            write_json_pretty(
+                &dir.join("runtime-state.json"),
+                &RuntimeSnapshot {
+                    is_on_battery: true,
+                    battery_start_ms: Some(started),
+                    ..RuntimeSnapshot::default()
+                },
+            );
+            let state = Arc::new(AppState::load(dir.clone()));
 
-    state.log_data_point_if_needed(&status);
-    emit_if_possible(app, "ups-data", status);
-}
+            reconcile_restored_outage(&state, status);
+            assert_eq!(*lock(&state.is_on_battery), kept);
+            assert_eq!(*lock(&state.battery_start_ms), kept.then_some(started));
+            let expected = if kept {
+                "Outage resumed"
+            } else {
+                "Normal AC value"
+            };
+            assert_eq!(lock(&state.events)[0].name, expected);
 
-fn decode_packet(raw_data: &[u8]) -> Option<DecodedPacket> {
-    if raw_data.is_empty() {
-        return None;
+            reconcile_restored_outage(&state, status);
+            assert_eq!(lock(&state.events).len(), 1);
+            let _ = fs::remove_dir_all(dir);
+        }
     }
 
-    let data_bytes = if raw_data.len() > 1 {
-        &raw_data[1..]
-    } else {
-        raw_data
-    };
-    let content_end = data_bytes
+    #[test]
+    fn lifetime_stats_resume_without_counting_downtime() {
+        let clock = ManualClock::new();
+        let (dir, _) = temp_state("lifetime-resume", &AppSettings::default(), None);
+        let started = clock.now_ms() - 3_600_000;
+        write_json_pretty(
+            &dir.join("history").join("lifetime.json"),
+            &LifetimeStats {
+                transfers_to_battery: 1,
+                on_battery_ms: 60_000,
+                on_battery_since_ms: Some(started),
+                last_accounted_ms: Some(started + 60_000),
+                ..LifetimeStats::fresh()
+            },
+        );
+        write_json_pretty(
+            &dir.join("runtime-state.json"),
+            &RuntimeSnapshot {
+                is_on_battery: true,
+                battery_start_ms: Some(started),
+                ..RuntimeSnapshot::default()
+            },
+        );
+        let mut state = AppState::load(dir.clone());
+        state.clock = clock.clone();
+
+        // The hour the app was down is not on-battery time.
+        state.update_lifetime_stats(true);
+        assert_eq!(lock(&state.lifetime_stats).on_battery_ms, 60_000);
+        clock.advance(10_000);
+        state.update_lifetime_stats(true);
+        assert_eq!(lock(&state.lifetime_stats).on_battery_ms, 70_000);
+        clock.advance(5_000);
+        state.update_lifetime_stats(false);
+        let stats = lock(&state.lifetime_stats).clone();
+        assert_eq!(stats.on_battery_ms, 75_000);
+        assert_eq!(stats.transfers_to_battery, 1);
+        assert_eq!(stats.on_battery_since_ms, None);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn backfills_event_keys_from_display_names() {
+        assert_eq!(event_key_for_name("AC Fault"), "ac_fault");
+        assert_eq!(event_key_for_name("Normal AC value"), "ac_restored");
+        assert_eq!(
+            event_key_for_name("Shutdown execution"),
+            "shutdown_executed"
+        );
+        assert_eq!(event_key_for_name("Something New"), "something_new");
+        assert_eq!(event_display_name("battery_critical"), "Battery Critical");
+
+        let legacy: HistoryEvent = serde_json::from_str(
+            r#"{"id":1,"time":"2026-01-01T00:00:00Z","classification":"Critical Event","name":"AC Fault","remarks":""}"#,
+        )
+        .unwrap();
+        assert!(legacy.key.is_empty());
+    }
+
+    fn timeline_event(at: DateTime<Utc>, key: &str) -> HistoryEvent {
+        HistoryEvent {
+            id: at.timestamp_millis() as u64,
+            seq: 0,
+            time: at.to_rfc3339(),
+            classification: "Information".to_string(),
+            severity: Some(Severity::Info),
+            key: key.to_string(),
+            name: event_display_name(key).to_string(),
+            remarks: String::new(),
+            session_id: None,
+            app_version: None,
+            resolved: false,
+            repeat_count: None,
+            last_seen: None,
+        }
+    }
+
+    #[test]
+    fn availability_excludes_unmonitored_part_of_outage_across_restart() {
+        let base = Utc::now() - ChronoDuration::days(1);
+        let at = |minutes: i64| base + ChronoDuration::minutes(minutes);
+        // Stored newest first, like the real history file.
+        let events: Vec<HistoryEvent> = [
+            (at(0), "app_started"),
+            (at(10), "ac_fault"),
+            (at(20), "app_terminated"),
+            (at(50), "app_started"),
+            (at(60), "ac_restored"),
+            (at(100), "app_stopped"),
+        ]
         .iter()
-        .position(|byte| *byte == 0x0D)
-        .unwrap_or(data_bytes.len());
+        .rev()
+        .map(|(when, key)| timeline_event(*when, key))
+        .collect();
+
+        let stats = compute_availability(&events, at(0), at(100));
+        assert_eq!(stats.monitored_seconds, 70 * 60);
+        // 10..20 before the crash and 50..60 after the restart; the gap is unmonitored.
+        assert_eq!(stats.on_battery_seconds, 20 * 60);
+        assert_eq!(stats.unmonitored_seconds, 30 * 60);
+        assert_eq!(stats.availability_percent, Some(71.43));
+    }
 
-    let ascii = data_bytes
+    #[test]
+    fn availability_clips_spans_to_period_boundaries() {
+        let base = Utc::now() - ChronoDuration::days(1);
+        let at = |minutes: i64| base + ChronoDuration::minutes(minutes);
+        let events: Vec<HistoryEvent> = [
+            (at(0), "app_started"),
+            (at(30), "ac_fault"),
+            (at(70), "ac_restored"),
+            (at(80), "ups_disconnected"),
+            (at(90), "ups_connected"),
+        ]
         .iter()
-        .take(content_end)
-        .filter(|byte| **byte >= 32 && **byte <= 126)
-        .map(|byte| *byte as char)
-        .collect::<String>();
+        .rev()
+        .map(|(when, key)| timeline_event(*when, key))
+        .collect();
+
+        // The outage started before the period; the session is still open at its end.
+        let stats = compute_availability(&events, at(60), at(120));
+        assert_eq!(stats.monitored_seconds, 50 * 60);
+        assert_eq!(stats.on_battery_seconds, 10 * 60);
+        assert_eq!(stats.unmonitored_seconds, 10 * 60);
+        assert_eq!(stats.availability_percent, Some(80.0));
+
+        // Nothing recorded before the first event means nothing was monitored.
+        let empty = compute_availability(&events, at(-120), at(-60));
+        assert_eq!(empty.monitored_seconds, 0);
+        assert_eq!(empty.unmonitored_seconds, 60 * 60);
+        assert_eq!(empty.availability_percent, None);
+    }
 
-    parse_ups_string(ascii.trim())
-}
+    #[test]
+    fn ups_shutdown_pulls_pending_schedule_to_now() {
+        let schedule = |reason: &str, at_ms: u64| ScheduledShutdown {
+            at_ms,
+            reason: reason.to_string(),
+            origin: EventOrigin::Real,
+        };
 
-fn parse_ups_string(input: &str) -> Option<DecodedPacket> {
-    if input.starts_with('(') {
-        let parts = input
-            .trim_start_matches('(')
-            .split_whitespace()
-            .collect::<Vec<_>>();
-        if parts.len() < 8 {
-            return None;
+        assert_eq!(ups_shutdown_escalation(None, 1_000), None);
+        assert_eq!(
+            ups_shutdown_escalation(Some(&schedule("battery-critical", 61_000)), 1_000),
+            Some(schedule("ups-shutdown", 1_000))
+        );
+        // Already due: the regular path executes it, nothing to escalate.
+        assert_eq!(
+            ups_shutdown_escalation(Some(&schedule("ac-fault", 1_000)), 1_000),
+            None
+        );
+        // A later, less severe trigger cannot take the reason back.
+        assert_eq!(
+            merge_shutdown_schedule(
+                Some(&schedule("ups-shutdown", 1_000)),
+                schedule("battery-critical", 900),
+                false
+            ),
+            Some(schedule("ups-shutdown", 900))
+        );
+    }
+
+    #[test]
+    fn log_writer_rotates_by_size_and_tail_spans_files() {
+        let dir = std::env::temp_dir().join(format!("ups-monitor-log-{}", now_millis()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(LOG_FILE_NAME);
+
+        let (sender, receiver) = mpsc::channel();
+        let writer_path = path.clone();
+        let writer = thread::spawn(move || run_log_writer(writer_path, receiver, 40, 3));
+        for index in 0..12 {
+            sender.send(format!("line {:02} padding", index)).unwrap();
         }
+        drop(sender);
+        writer.join().unwrap();
+
+        // 18 bytes per line, rotation after the 40 byte mark: three lines per file.
+        assert!(path.exists());
+        assert!(rotated_log_path(&path, 1).exists());
+        assert!(rotated_log_path(&path, 2).exists());
+        assert!(!rotated_log_path(&path, 3).exists());
+
+        let tail = read_log_tail(&dir, 100);
+        assert_eq!(tail.len(), 9);
+        assert_eq!(tail.first().map(String::as_str), Some("line 03 padding"));
+        assert_eq!(tail.last().map(String::as_str), Some("line 11 padding"));
+        assert_eq!(
+            read_log_tail(&dir, 2),
+            vec!["line 10 padding", "line 11 padding"]
+        );
+        let _ = fs::remove_dir_all(dir);
+    }
 
-        let status_bits = parts[7];
-        let battery_voltage = parse_f64(parts[5]);
-        let load_percent = parse_u64(parts[3]);
-        let battery_percent = calculate_battery_percent(battery_voltage);
+    #[test]
+    fn scales_battery_voltage_before_computing_percent() {
+        let frame = "(218.5 140.0 219.0 023 60.1 2.20 32.0 00001001";
+        let Some(DecodedPacket::Status(raw)) = parse_ups_string(frame, &FrameFormat::default())
+        else {
+            panic!("expected status frame");
+        };
+        assert_eq!(raw.battery_percent, 0);
 
-        let status = UpsData {
-            r#type: "STATUS".to_string(),
-            input_voltage: parse_f64(parts[0]),
-            fault_voltage: parse_f64(parts[1]),
-            output_voltage: parse_f64(parts[2]),
-            load_percent,
-            frequency: parse_f64(parts[4]),
-            battery_voltage,
-            temperature: parse_f64(parts[6]),
-            battery_percent,
-            estimated_runtime: estimate_runtime(battery_percent, load_percent),
-            timestamp: now_iso(),
-            status: UpsStatusFlags {
-                raw: status_bits.to_string(),
-                utility_fail: status_bit(status_bits, 0),
-                battery_low: status_bit(status_bits, 1),
-                bypass_active: status_bit(status_bits, 2),
-                ups_failed: status_bit(status_bits, 3),
-                ups_is_standby: status_bit(status_bits, 4),
-                test_in_progress: status_bit(status_bits, 5),
-                shutdown_active: status_bit(status_bits, 6),
-                beeper_on: status_bit(status_bits, 7),
+        let Some(DecodedPacket::Status(scaled)) = parse_ups_string(
+            frame,
+            &FrameFormat {
+                battery_voltage_scale: 12.0,
+                ..FrameFormat::default()
             },
+        ) else {
+            panic!("expected status frame");
+        };
+        assert!((scaled.battery_voltage - 26.4).abs() < 1e-9);
+        assert_eq!(scaled.battery_percent, calculate_battery_percent(26.4));
+
+        let mut settings = AppSettings {
+            battery_voltage_scale: -2.0,
+            ..AppSettings::default()
+        };
+        settings = settings.normalize();
+        assert_eq!(settings.battery_voltage_scale, 1.0);
+        settings.battery_voltage_scale = f64::NAN;
+        assert_eq!(settings.normalize().battery_voltage_scale, 1.0);
+    }
+
+    #[test]
+    fn builds_histograms_from_counters_and_data_history() {
+        let settings = AppSettings::default();
+        let clock = ManualClock::new();
+        let (dir, state) = temp_state("histogram", &settings, Some(clock.clone()));
+        for frame in [
+            "(218.5 140.0 219.0 023 60.1 26.4 32.0 00001001",
+            "(219.9 140.0 219.0 023 60.2 26.4 32.0 00001001",
+            "(224.0 140.0 219.0 023 59.7 26.4 32.0 00001001",
+        ] {
+            let Some(DecodedPacket::Status(status)) =
+                parse_ups_string(frame, &FrameFormat::default())
+            else {
+                panic!("expected status frame");
+            };
+            state.record_histogram_sample(&status);
+            // A second poll inside the same interval is not counted again.
+            state.record_histogram_sample(&status);
+            clock.advance(settings.history_interval * 1000);
+        }
+
+        let all = state
+            .histogram(HistogramMetric::InputVoltage, "all", Utc::now())
+            .unwrap();
+        // 218.5 and 219.9 share [218, 220); [220, 222) is empty.
+        assert_eq!(all.edges, vec![218.0, 220.0, 222.0, 224.0, 226.0]);
+        assert_eq!(all.counts, vec![2, 0, 0, 1]);
+        assert_eq!(all.samples, 3);
+        let frequency = state
+            .histogram(HistogramMetric::Frequency, "all", Utc::now())
+            .unwrap();
+        assert_eq!(frequency.edges, vec![59.5, 60.0, 60.5]);
+        assert_eq!(frequency.counts, vec![1, 2]);
+
+        let now = Utc::now();
+        let recent = now - ChronoDuration::hours(1);
+        let old = now - ChronoDuration::days(3);
+        let entry = |at: DateTime<Utc>, voltage: f64| DataHistoryEntry {
+            id: at.timestamp_millis() as u64,
+            time: at.to_rfc3339(),
+            input_voltage: voltage,
+            output_voltage: 220.0,
+            frequency: 60.0,
+            load_percent: 20,
+            battery_voltage: 26.4,
+            battery_percent: 100,
+            temperature: 30.0,
+        };
+        *lock(&state.data_history) = vec![entry(recent, 210.0), entry(old, 230.0)];
+        let day = state
+            .histogram(HistogramMetric::InputVoltage, "day", now)
+            .unwrap();
+        assert_eq!(day.counts, vec![1]);
+        assert_eq!(day.edges, vec![210.0, 212.0]);
+        assert!(state
+            .histogram(HistogramMetric::InputVoltage, "bogus", now)
+            .is_none());
+
+        // Limits bound the readings that reach the buckets, so they are capped.
+        let mut limits = ReadingLimits {
+            voltage_min: -1e12,
+            voltage_max: 1e12,
+            frequency_max: f64::MAX,
+            ..ReadingLimits::default()
         };
+        limits.normalize();
+        assert_eq!(limits.voltage_min, 0.0);
+        assert_eq!(limits.voltage_max, VOLTAGE_READING_CEIL);
+        assert_eq!(limits.frequency_max, FREQUENCY_READING_CEIL);
+        let _ = fs::remove_dir_all(dir);
+    }
 
-        return Some(DecodedPacket::Status(status));
+    #[test]
+    fn hid_tuning_defaults_keep_loop_timing_and_normalize_bounds() {
+        let tuning = HidTuning::default();
+        assert_eq!(tuning.read_timeout_ms(1000, true, true), 600);
+        assert_eq!(tuning.read_timeout_ms(500, true, true), 500);
+        assert_eq!(tuning.read_timeout_ms(1000, true, false), 120);
+        assert_eq!(tuning.read_timeout_ms(1000, false, false), 100);
+        assert_eq!(tuning.sleep_ms(1000, 600, true, true), 280);
+        assert_eq!(tuning.sleep_ms(500, 500, true, true), 30);
+        assert_eq!(tuning.sleep_ms(1000, 120, true, false), 30);
+        assert_eq!(tuning.sleep_ms(1000, 100, false, false), 150);
+
+        let mut tuned = HidTuning {
+            connect_read_timeout_ms: 5,
+            idle_read_timeout_ms: 400,
+            status_read_timeout_ms: 900,
+            sleep_floor_ms: 300,
+            sleep_ceil_ms: 100,
+            disconnected_sleep_ms: 0,
+        };
+        tuned.normalize();
+        assert_eq!(tuned.connect_read_timeout_ms, 20);
+        assert_eq!(tuned.idle_read_timeout_ms, 400);
+        assert_eq!(tuned.sleep_ceil_ms, 300);
+        assert_eq!(tuned.disconnected_sleep_ms, 150);
+        // Every bound normalize allows is the value the loop actually uses.
+        assert_eq!(tuned.read_timeout_ms(1000, true, true), 900);
+        assert_eq!(tuned.read_timeout_ms(1000, true, false), 20);
+        assert_eq!(tuned.read_timeout_ms(1000, false, false), 400);
     }
 
-    if input.contains('V') && (input.contains('#') || input.contains("V")) {
-        return Some(DecodedPacket::Version(
-            input.replace('#', "").trim().to_string(),
-        ));
+    #[test]
+    fn fabricated_alert_status_matches_kind_and_test_hold_yields_to_outage() {
+        let settings = AppSettings::default();
+        let ac = fabricated_status(AlertKind::AcFault, &settings);
+        assert!(ac.status.utility_fail);
+        assert_eq!(ac.utility_state, UtilityState::Lost);
+        let critical = fabricated_status(AlertKind::BatteryCritical, &settings);
+        assert_eq!(
+            critical.battery_percent,
+            settings.critical_battery_threshold
+        );
+        assert!(
+            !fabricated_status(AlertKind::ChargeFailure, &settings)
+                .status
+                .utility_fail
+        );
+
+        let (dir, state) = temp_state("alert-test-hold", &settings, None);
+        let state = Arc::new(state);
+        assert!(!alert_test_holds_shutdown(&state));
+        with_event_origin(EventOrigin::AlertTest, || {
+            state.log_event("ac_fault", Severity::Warning, "AC Fault");
+            state.schedule_shutdown(1, "ac-fault", false);
+        });
+        assert!(alert_test_holds_shutdown(&state));
+        assert_eq!(lock(&state.events)[0].key, "ac_fault_test");
+        // The real outage takes over the schedule, so its shutdown is not held.
+        state.schedule_shutdown(5, "ac-fault", false);
+        assert!(!alert_test_holds_shutdown(&state));
+        assert!(!state.clear_scheduled_shutdown(Some(EventOrigin::AlertTest)));
+        let _ = fs::remove_dir_all(dir);
     }
 
-    None
-}
+    #[test]
+    fn resolved_events_persist_and_filter() {
+        let (dir, state) = temp_state("resolved-events", &AppSettings::default(), None);
+        let base = Utc::now();
+        *lock(&state.events) = vec![
+            timeline_event(base, "ac_restored"),
+            timeline_event(base - ChronoDuration::minutes(5), "ac_fault"),
+        ];
+        let fault_id = lock(&state.events)[1].id;
+
+        assert_eq!(state.set_events_resolved(&[fault_id], true), 1);
+        assert_eq!(state.set_events_resolved(&[fault_id], true), 0);
+        let saved: Vec<HistoryEvent> = read_json_or_default(&state.events_path);
+        assert!(saved
+            .iter()
+            .any(|event| event.id == fault_id && event.resolved));
+
+        let only = |resolved: bool| HistoryFilter {
+            classification: None,
+            keys: None,
+            severity: None,
+            resolved: Some(resolved),
+            date_from: None,
+            date_to: None,
+        };
+        let events = lock(&state.events).clone();
+        let open = filter_events(events.clone(), Some(only(false)));
+        assert_eq!(
+            open.iter()
+                .map(|event| event.key.as_str())
+                .collect::<Vec<_>>(),
+            vec!["ac_restored"]
+        );
+        let resolved = filter_events(events.clone(), Some(only(true)));
+        assert_eq!(
+            resolved.iter().map(|event| event.id).collect::<Vec<_>>(),
+            vec![fault_id]
+        );
+        assert_eq!(filter_events(events, None).len(), 2);
+        let _ = fs::remove_dir_all(dir);
+    }
 
-fn parse_f64(value: &str) -> f64 {
-    value.parse::<f64>().unwrap_or(0.0)
-}
+    #[test]
+    fn folds_repeated_events_within_window() {
+        let (dir, state) = temp_state("duplicate-events", &AppSettings::default(), None);
+        let start = now_millis();
+        for offset in [0, 5_000, 10_000] {
+            state.log_event_at(
+                start + offset,
+                "usb_reenumerated",
+                Severity::Warning,
+                "USB re-enumeration",
+            );
+            state.log_event_at(
+                start + offset + 1_000,
+                "audio_restored",
+                Severity::Info,
+                "Audio output restored",
+            );
+        }
+        {
+            let events = lock(&state.events);
+            assert_eq!(events.len(), 2);
+            let reenumerated = events
+                .iter()
+                .find(|event| event.key == "usb_reenumerated")
+                .unwrap();
+            assert_eq!(reenumerated.repeat_count, Some(3));
+            assert_eq!(
+                reenumerated.last_seen.as_deref(),
+                Some(iso_from_millis(start + 10_000).as_str())
+            );
+        }
 
-fn parse_u64(value: &str) -> u64 {
-    value.parse::<u64>().unwrap_or(0)
-}
+        // Past the window, resolved, different remarks or a span boundary: a
+        // new row each time.
+        state.log_event_at(
+            start + 50_000,
+            "audio_restored",
+            Severity::Info,
+            "Audio output restored",
+        );
+        let newest_id = lock(&state.events)[0].id;
+        state.set_events_resolved(&[newest_id], true);
+        state.log_event_at(
+            start + 51_000,
+            "audio_restored",
+            Severity::Info,
+            "Audio output restored",
+        );
+        state.log_event_at(
+            start + 51_500,
+            "audio_restored",
+            Severity::Info,
+            "Default device",
+        );
+        state.log_event_at(start + 52_000, "ac_fault", Severity::Warning, "corte");
+        state.log_event_at(start + 53_000, "ac_fault", Severity::Warning, "corte");
+        state.log_event_at(start + 54_000, "ups_disconnected", Severity::Warning, "");
+        state.log_event_at(start + 55_000, "ups_disconnected", Severity::Warning, "");
+        assert_eq!(lock(&state.events).len(), 9);
+
+        let saved: Vec<HistoryEvent> = read_json_or_default(&state.events_path);
+        assert!(saved.iter().any(|event| event.repeat_count == Some(3)));
+        let _ = fs::remove_dir_all(dir);
+    }
 
-fn status_bit(bits: &str, index: usize) -> bool {
-    bits.chars().nth(index).map(|ch| ch == '1').unwrap_or(false)
-}
+    #[test]
+    fn searches_sound_paths_in_order_and_migrates_single_path() {
+        let mut raw = serde_json::to_value(AppSettings::default()).unwrap();
+        raw["customSoundsPath"] = serde_json::json!("/srv/sounds");
+        let legacy: AppSettings = serde_json::from_value(raw).unwrap();
+        let migrated = legacy.normalize();
+        assert_eq!(
+            migrated.custom_sounds_paths,
+            vec!["/srv/sounds".to_string()]
+        );
+        let saved = serde_json::to_value(&migrated).unwrap();
+        assert!(saved.get("customSoundsPath").is_none());
+
+        let (dir, state) = temp_state("sound-paths", &AppSettings::default(), None);
+        let state = Arc::new(state);
+        let shared = dir.join("shared");
+        let local = dir.join("local");
+        fs::create_dir_all(&shared).unwrap();
+        fs::create_dir_all(&local).unwrap();
+        let file_name = alert_sound_file_name(AlertKind::AcFault);
+        fs::write(local.join(file_name), b"local").unwrap();
+        fs::write(shared.join(file_name), b"shared").unwrap();
+
+        let mut settings = AppSettings {
+            custom_sounds_paths: vec![
+                format!(" {} ", local.display()),
+                shared.to_string_lossy().to_string(),
+                local.to_string_lossy().to_string(),
+                String::new(),
+            ],
+            ..AppSettings::default()
+        }
+        .normalize();
+        assert_eq!(settings.custom_sounds_paths.len(), 2);
+        assert_eq!(
+            resolve_sound_path(&state, &settings, AlertKind::AcFault),
+            Some(local.join(file_name))
+        );
 
-fn calculate_battery_percent(voltage: f64) -> u64 {
-    let min_voltage = 21.0;
-    let max_voltage = 26.8;
+        fs::remove_file(local.join(file_name)).unwrap();
+        assert_eq!(
+            resolve_sound_path(&state, &settings, AlertKind::AcFault),
+            Some(shared.join(file_name))
+        );
 
-    if voltage <= min_voltage {
-        return 0;
-    }
-    if voltage >= max_voltage {
-        return 100;
+        settings.custom_sounds_paths.clear();
+        let bundled = resolve_sound_path(&state, &settings, AlertKind::AcFault);
+        assert!(bundled
+            .map(|path| path.starts_with(&state.sounds_path))
+            .unwrap_or(true));
+        let _ = fs::remove_dir_all(dir);
     }
 
-    (((voltage - min_voltage) / (max_voltage - min_voltage)) * 100.0)
-        .round()
-        .clamp(0.0, 100.0) as u64
-}
-
-fn estimate_runtime(battery_percent: u64, load_percent: u64) -> u64 {
-    let base_runtime_minutes = 15.0;
-    let load_factor = (load_percent.max(10) as f64) / 100.0;
-    ((battery_percent as f64 / 100.0) * (base_runtime_minutes / load_factor)).round() as u64
-}
+    #[test]
+    fn preflight_reports_unresolvable_command_and_missing_sounds() {
+        let mut settings = AppSettings::default();
+        settings.shutdown_pc.shutdown_command =
+            "definitely-not-a-real-shutdown-tool --now".to_string();
+        for config in settings.alerts.configs_mut() {
+            config.channels.retain(|channel| channel != "sound");
+        }
+        settings.alerts.ac_fault.channels = vec!["sound".to_string()];
+        let (dir, state) = temp_state("preflight", &settings, None);
+        let state = Arc::new(state);
+        let sounds = dir.join("sounds-empty");
+        fs::create_dir_all(&sounds).unwrap();
+        settings.custom_sounds_paths = vec![sounds.to_string_lossy().to_string()];
+
+        let checks = preflight_checks(&state, &settings);
+        let find = |name: &str| checks.iter().find(|check| check.name == name).unwrap();
+        let custom = find("custom_command");
+        assert!(!custom.ok && !custom.skipped);
+        assert!(custom
+            .detail
+            .contains("definitely-not-a-real-shutdown-tool"));
+
+        let has_bundled = resolve_sound_path(&state, &settings, AlertKind::AcFault).is_some();
+        assert_eq!(find("sounds").ok, has_bundled);
+        fs::write(
+            sounds.join(alert_sound_file_name(AlertKind::AcFault)),
+            b"wav",
+        )
+        .unwrap();
+        assert!(preflight_checks(&state, &settings)
+            .iter()
+            .any(|check| check.name == "sounds" && check.ok && !check.skipped));
+
+        settings.shutdown_pc.shutdown_command.clear();
+        assert!(preflight_checks(&state, &settings)
+            .iter()
+            .any(|check| check.name == "custom_command" && check.skipped));
+        let _ = fs::remove_dir_all(dir);
+    }
 
-fn start_ups_monitor(app: AppHandle, state: SharedState) {
-    tauri::async_runtime::spawn_blocking(move || {
-        let mut api: Option<HidApi> = None;
-        let mut connected_device: Option<hidapi::HidDevice> = None;
-        let mut last_device_refresh_ms = 0_u64;
+    #[test]
+    fn monitor_only_logging_flags_gate_events_and_data_separately() {
+        let settings = AppSettings {
+            monitor_only_mode: true,
+            monitor_only_logging: MonitorOnlyLogging {
+                events: false,
+                data_points: true,
+            },
+            ..AppSettings::default()
+        };
+        let (dir, state) = temp_state("monitor-only-logging", &settings, None);
+        let status = fabricated_status(AlertKind::AcFault, &settings);
+        state.log_event("ac_fault", Severity::Warning, "corte");
+        assert!(lock(&state.events).is_empty());
+        state.log_data_point_if_needed(&status);
+        assert_eq!(lock(&state.data_history).len(), 1);
+        state.start_discharge_session(1);
+        state.log_discharge_point_if_needed(&status);
+        assert_eq!(lock(&state.discharge_sessions)[0].points.len(), 1);
 
-        while !state.stop_monitor.load(Ordering::Relaxed) {
-            let settings = lock(&state.settings).clone();
-            let polling_interval_ms = settings.polling_interval.max(500);
-            let has_recent_status = lock(&state.last_status).is_some();
-            let read_timeout_ms = if connected_device.is_some() {
-                if has_recent_status {
-                    polling_interval_ms.min(600) as i32
-                } else {
-                    120
-                }
-            } else {
-                90
+        {
+            let mut stored = lock(&state.settings);
+            stored.monitor_only_logging = MonitorOnlyLogging {
+                events: true,
+                data_points: false,
             };
+        }
+        *lock(&state.last_data_save_ms) = 0;
+        state.log_event("ac_fault", Severity::Warning, "corte");
+        assert_eq!(lock(&state.events).len(), 1);
+        state.log_data_point_if_needed(&status);
+        assert_eq!(lock(&state.data_history).len(), 1);
+        // Discharge curves are data points too.
+        *lock(&state.last_discharge_point_ms) = 0;
+        state.log_discharge_point_if_needed(&status);
+        state.start_discharge_session(2);
+        {
+            let sessions = lock(&state.discharge_sessions);
+            assert_eq!(sessions.len(), 1);
+            assert_eq!(sessions[0].points.len(), 1);
+        }
 
-            if api.is_none() {
-                match HidApi::new() {
-                    Ok(next_api) => api = Some(next_api),
-                    Err(error) => {
-                        mark_disconnected(&app, &state);
-                        emit_error_once(&app, &state, format!("HID init error: {}", error));
-                        thread::sleep(Duration::from_millis(1_500));
-                        continue;
-                    }
-                }
-            }
-
-            if let Some(api_ref) = api.as_mut() {
-                let now = now_millis();
-                let refresh_interval_ms = if connected_device.is_some() { 2_000 } else { 350 };
+        lock(&state.settings).monitor_only_mode = false;
+        *lock(&state.last_data_save_ms) = 0;
+        state.log_data_point_if_needed(&status);
+        assert_eq!(lock(&state.data_history).len(), 2);
+        state.start_discharge_session(3);
+        assert_eq!(lock(&state.discharge_sessions).len(), 2);
+        let _ = fs::remove_dir_all(dir);
+    }
 
-                if now.saturating_sub(last_device_refresh_ms) >= refresh_interval_ms {
-                    if let Err(error) = api_ref.refresh_devices() {
-                        mark_disconnected(&app, &state);
-                        emit_error_once(&app, &state, format!("HID refresh error: {}", error));
-                        api = None;
-                        connected_device = None;
-                        thread::sleep(Duration::from_millis(1_500));
-                        continue;
-                    }
-                    last_device_refresh_ms = now;
-                }
+    #[test]
+    fn scheduled_shutdown_becomes_due_exactly_at_deadline() {
+        let clock = ManualClock::new();
+        let (dir, state) = temp_state(
+            "clock-deadline",
+            &AppSettings::default(),
+            Some(clock.clone()),
+        );
+        let schedule = state.schedule_shutdown(2, "battery-low", false).unwrap();
+        assert_eq!(schedule.at_ms, clock.now_ms() + 120_000);
+
+        clock.sleep(Duration::from_millis(119_999));
+        assert!(!state.shutdown_due());
+        clock.advance(1);
+        assert!(state.shutdown_due());
+
+        // Only the first caller claims the schedule and runs it.
+        *lock(&state.manual_shutdown_action) = Some("hibernate".to_string());
+        let (claimed, action) = state.take_due_shutdown().unwrap();
+        assert_eq!(claimed, schedule);
+        assert_eq!(action.as_deref(), Some("hibernate"));
+        assert!(state.take_due_shutdown().is_none());
+        let _ = fs::remove_dir_all(dir);
+    }
 
-                poll_ups(
-                    &app,
-                    &state,
-                    api_ref,
-                    &mut connected_device,
-                    read_timeout_ms,
-                );
-            }
+    #[test]
+    fn cancelled_shutdown_never_becomes_due() {
+        let clock = ManualClock::new();
+        let (dir, state) = temp_state("clock-cancel", &AppSettings::default(), Some(clock.clone()));
+        state.schedule_shutdown(1, "ac-fault", false).unwrap();
+        clock.advance(59_000);
+        assert!(state.clear_scheduled_shutdown(None));
+        clock.advance(10 * 60_000);
+        assert!(!state.shutdown_due());
+        assert!(!state.clear_scheduled_shutdown(None));
+        let _ = fs::remove_dir_all(dir);
+    }
 
-            process_pending_shutdown(&app, &state, &settings);
+    #[test]
+    fn data_points_respect_history_interval() {
+        let settings = AppSettings {
+            history_interval: 60,
+            ..AppSettings::default()
+        };
+        let clock = ManualClock::new();
+        let (dir, state) = temp_state("clock-history", &settings, Some(clock.clone()));
+        let status = fabricated_status(AlertKind::AcFault, &settings);
+
+        state.log_data_point_if_needed(&status);
+        clock.advance(59_999);
+        state.log_data_point_if_needed(&status);
+        assert_eq!(lock(&state.data_history).len(), 1);
+        clock.advance(1);
+        state.log_data_point_if_needed(&status);
+        assert_eq!(lock(&state.data_history).len(), 2);
+        let _ = fs::remove_dir_all(dir);
+    }
 
-            let is_connected = *lock(&state.is_connected);
-            let sleep_ms = if is_connected {
-                if has_recent_status {
-                    polling_interval_ms
-                        .saturating_sub(read_timeout_ms.max(0) as u64)
-                        .clamp(30, 280)
-                } else {
-                    30
-                }
-            } else {
-                150
-            };
-            thread::sleep(Duration::from_millis(sleep_ms));
+    #[test]
+    fn forced_popups_are_rate_limited_per_key_without_dropping() {
+        let clock = ManualClock::new();
+        let (dir, state) = temp_state("clock-popup", &AppSettings::default(), Some(clock.clone()));
+        let interval_ms = AppSettings::default().popup_min_interval_seconds * 1000;
+        assert_eq!(state.popup_wait_ms("AC Fault", interval_ms), 0);
+        state.mark_popup_shown("AC Fault");
+        assert_eq!(state.popup_wait_ms("AC Fault", interval_ms), interval_ms);
+        assert_eq!(state.popup_wait_ms("Battery Low", interval_ms), 0);
+
+        // A popup inside the interval is held in the queue, not dropped.
+        let alert = |key: &str| QueuedAlert {
+            key: key.to_string(),
+            payload: urgent_alert_payload(key, "mensaje", "warning"),
+        };
+        let mut queue = AlertQueue::default();
+        queue.push(alert("AC Fault"));
+        queue.push(alert("Battery Low"));
+        let ready = |item: &QueuedAlert| state.popup_wait_ms(&item.key, interval_ms) == 0;
+        assert_eq!(queue.pop_ready(ready).unwrap().key, "Battery Low");
+        assert!(queue.pop_ready(ready).is_none());
+        assert_eq!(queue.items.len(), 1);
+
+        clock.advance(interval_ms - 1);
+        assert_eq!(state.popup_wait_ms("AC Fault", interval_ms), 1);
+        clock.advance(1);
+        assert_eq!(queue.pop_ready(ready).unwrap().key, "AC Fault");
+
+        let settings = AppSettings {
+            popup_min_interval_seconds: 0,
+            popup_timeout_seconds: 1000,
+            ..AppSettings::default()
         }
-    });
-}
+        .normalize();
+        assert_eq!(settings.popup_min_interval_seconds, 6);
+        assert_eq!(settings.popup_timeout_seconds, 120);
+        let _ = fs::remove_dir_all(dir);
+    }
 
-fn parse_date_bound(date_str: &str, end_of_day: bool) -> Option<DateTime<Utc>> {
-    let date = NaiveDate::parse_from_str(date_str, "%Y-%m-%d").ok()?;
-    let naive_dt = if end_of_day {
-        date.and_hms_opt(23, 59, 59)?
-    } else {
-        date.and_hms_opt(0, 0, 0)?
-    };
-    Some(DateTime::<Utc>::from_naive_utc_and_offset(naive_dt, Utc))
-}
+    #[test]
+    fn implausible_readings_fall_back_to_last_known_values() {
+        let limits = ReadingLimits::default();
+        let Some(DecodedPacket::Status(last)) = parse_ups_string(
+            "(221.0 221.0 220.0 030 50.0 26.0 31.0 00001000",
+            &FrameFormat::default(),
+        ) else {
+            panic!("expected status frame");
+        };
+        let Some(DecodedPacket::Status(mut garbage)) = parse_ups_string(
+            "(221.0 221.0 999.0 500 50.0 26.0 999.0 00001000",
+            &FrameFormat::default(),
+        ) else {
+            panic!("expected status frame");
+        };
 
-fn parse_rfc3339_utc(date_str: &str) -> Option<DateTime<Utc>> {
-    DateTime::parse_from_rfc3339(date_str)
-        .ok()
-        .map(|dt| dt.with_timezone(&Utc))
-}
+        let rejected = sanitize_reading(&mut garbage, Some(&last), &limits);
+        let fields: Vec<&str> = rejected
+            .iter()
+            .map(|reading| reading.field.as_str())
+            .collect();
+        assert_eq!(fields, vec!["outputVoltage", "loadPercent", "temperature"]);
+        assert_eq!(rejected[1].value, 500.0);
+        assert_eq!(garbage.output_voltage, 220.0);
+        assert_eq!(garbage.load_percent, 30);
+        assert_eq!(garbage.temperature, 31.0);
+        assert_eq!(
+            garbage.estimated_runtime,
+            Some(estimate_runtime(garbage.battery_percent, 30))
+        );
 
-#[tauri::command]
-fn get_settings(state: State<'_, SharedState>) -> AppSettings {
-    lock(&state.settings).clone()
-}
+        let Some(DecodedPacket::Status(mut first)) = parse_ups_string(
+            "(221.0 221.0 220.0 030 50.0 26.0 -45.0 00001000",
+            &FrameFormat::default(),
+        ) else {
+            panic!("expected status frame");
+        };
+        let rejected = sanitize_reading(&mut first, None, &limits);
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(first.temperature, limits.temperature_min);
 
-#[tauri::command]
-fn save_settings(
-    app: AppHandle,
-    state: State<'_, SharedState>,
-    new_settings: AppSettings,
-) -> Result<bool, String> {
-    let normalized = new_settings.normalize();
-    if normalized.monitor_only_mode {
-        state.sound_generation.fetch_add(1, Ordering::Relaxed);
-        let _ = cancel_scheduled_shutdown(&state, &app, true);
+        let mut clean = last.clone();
+        assert!(sanitize_reading(&mut clean, Some(&last), &limits).is_empty());
     }
-    *lock(&state.settings) = normalized.clone();
-    state.save_settings();
 
-    Ok(true)
-}
+    #[test]
+    fn temperature_trend_alerts_on_sustained_rise_and_clears_with_hysteresis() {
+        let settings = AppSettings::default();
+        let trend = &settings.temperature_trend;
+        let clock = ManualClock::new();
+        let (dir, state) = temp_state("temperature-trend", &settings, Some(clock.clone()));
+        let step = |temperature: f64| {
+            let result = state.update_temperature_trend(trend, temperature);
+            clock.advance(60_000);
+            result
+        };
 
-#[tauri::command]
-fn get_ups_status(state: State<'_, SharedState>) -> Option<UpsData> {
-    lock(&state.last_status).clone()
-}
+        // 40 C/h, but nothing is reported until 15 minutes are covered.
+        for minute in 0..15 {
+            assert_eq!(step(30.0 + minute as f64 * 2.0 / 3.0), None);
+        }
+        let Some(TemperatureTrend::Rising(rate)) = step(40.0) else {
+            panic!("expected rising trend");
+        };
+        assert!((rate - 40.0).abs() < 1e-9);
 
-#[tauri::command]
-fn get_ups_info(state: State<'_, SharedState>) -> Option<UpsInfo> {
-    lock(&state.device_info).clone()
-}
+        // Slowing to 20 C/h is under the rise threshold but above the clear one.
+        for minute in 1..=15 {
+            assert_eq!(step(40.0 + minute as f64 / 3.0), None);
+        }
+        assert!(*lock(&state.temperature_rising));
 
-#[tauri::command]
-fn test_notification(app: AppHandle, state: State<'_, SharedState>) -> bool {
-    let _ = notify_windows(
-        &app,
-        "UPS Monitor",
-        "Notificacion de prueba enviada correctamente",
-    );
-    if should_force_popup(&app, &state) {
-        force_windows_popup(
-            "UPS Monitor",
-            "Notificacion de prueba enviada correctamente",
-            "warning",
+        // Leveling off clears once the fitted rate drops under the clear threshold.
+        let Some(TemperatureTrend::Cleared(rate)) = (0..15).find_map(|_| step(45.0)) else {
+            panic!("expected cleared trend");
+        };
+        assert!(rate < trend.clear_per_hour);
+        assert!(!*lock(&state.temperature_rising));
+
+        // A single spike is outweighed by the rest of the window.
+        for _ in 0..15 {
+            assert_eq!(step(45.0), None);
+        }
+        assert_eq!(step(55.0), None);
+        assert!(!*lock(&state.temperature_rising));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn settings_diff_lists_only_changed_leaves() {
+        let defaults = AppSettings::default().normalize();
+        assert!(settings_diff(&defaults).is_empty());
+
+        let mut changed = defaults.clone();
+        changed.polling_interval = 2000;
+        changed.alerts.ac_fault.channels = vec!["sound".to_string()];
+        changed.shutdown_pc.on_ac_fault.delay_minutes = 5;
+        let diff = settings_diff(&changed.normalize());
+        assert_eq!(
+            diff.keys().map(String::as_str).collect::<Vec<_>>(),
+            vec![
+                "alerts.acFault.channels",
+                "pollingInterval",
+                "shutdownPC.onAcFault.delayMinutes"
+            ]
+        );
+        assert_eq!(diff["pollingInterval"], serde_json::json!(2000));
+        assert_eq!(
+            diff["alerts.acFault.channels"],
+            serde_json::json!(["sound"])
         );
     }
-    true
-}
 
-#[tauri::command]
-fn minimize_main_window(app: AppHandle) -> bool {
-    if let Some(window) = app.get_webview_window("main") {
-        let _ = window.set_skip_taskbar(true);
-        return window.close().is_ok();
+    #[test]
+    fn connection_state_reports_transition_and_packet_times() {
+        let clock = ManualClock::new();
+        let (dir, state) = temp_state(
+            "connection-state",
+            &AppSettings::default(),
+            Some(clock.clone()),
+        );
+        let initial = state.connection_state();
+        assert!(!initial.connected);
+        assert_eq!(initial.since, None);
+        assert_eq!(initial.last_packet_at, None);
+
+        let connected_at = clock.now_ms();
+        *lock(&state.is_connected) = true;
+        state.record_connection_change(connected_at, Some("/dev/hidraw3".to_string()));
+        clock.advance(1_500);
+        state.record_packet();
+        *lock(&state.last_error) = Some("HID read error: timeout".to_string());
+
+        let snapshot = state.connection_state();
+        assert!(snapshot.connected);
+        assert_eq!(snapshot.since, Some(iso_from_millis(connected_at)));
+        assert_eq!(
+            snapshot.last_packet_at,
+            Some(iso_from_millis(connected_at + 1_500))
+        );
+        assert_eq!(
+            snapshot.last_error.as_deref(),
+            Some("HID read error: timeout")
+        );
+        assert_eq!(snapshot.device_path.as_deref(), Some("/dev/hidraw3"));
+
+        *lock(&state.is_connected) = false;
+        state.record_connection_change(connected_at + 5_000, None);
+        let snapshot = state.connection_state();
+        assert_eq!(snapshot.since, Some(iso_from_millis(connected_at + 5_000)));
+        assert_eq!(snapshot.device_path, None);
+        let _ = fs::remove_dir_all(dir);
     }
-    false
-}
 
-#[tauri::command]
-fn toggle_maximize_main_window(app: AppHandle) -> bool {
-    if let Some(window) = app.get_webview_window("main") {
-        let is_maximized = window.is_maximized().unwrap_or(false);
-        if is_maximized {
-            return window.unmaximize().is_ok();
+    #[test]
+    fn keepalive_paces_writes_and_gives_up_after_repeated_failures() {
+        let settings = AppSettings {
+            keepalive: KeepaliveSettings {
+                enabled: true,
+                command: " q1 ".to_string(),
+                interval_seconds: 30,
+                max_failures: 3,
+            },
+            ..AppSettings::default()
         }
-        return window.maximize().is_ok();
+        .normalize();
+        let keepalive = &settings.keepalive;
+        assert_eq!(keepalive.frame(), b"q1\r".to_vec());
+
+        let clock = ManualClock::new();
+        let (dir, state) = temp_state("keepalive", &settings, Some(clock.clone()));
+        assert!(state.keepalive_due(keepalive));
+        assert!(!state.keepalive_due(keepalive));
+        clock.advance(29_999);
+        assert!(!state.keepalive_due(keepalive));
+        clock.advance(1);
+        assert!(state.keepalive_due(keepalive));
+
+        assert!(!state.record_keepalive_result(false, keepalive.max_failures));
+        assert!(!state.record_keepalive_result(false, keepalive.max_failures));
+        assert!(!state.record_keepalive_result(true, keepalive.max_failures));
+        assert!(!state.record_keepalive_result(false, keepalive.max_failures));
+        assert!(!state.record_keepalive_result(false, keepalive.max_failures));
+        assert!(state.record_keepalive_result(false, keepalive.max_failures));
+
+        let disabled = KeepaliveSettings::default();
+        assert!(!state.keepalive_due(&disabled));
+
+        let mut invalid = AppSettings::default();
+        invalid.keepalive.command = "TOO-LONG-CMD".to_string();
+        assert_eq!(invalid.normalize().keepalive.command, "Q1");
+        let _ = fs::remove_dir_all(dir);
     }
-    false
-}
 
-#[tauri::command]
-fn close_main_window(app: AppHandle) -> bool {
-    request_app_exit(&app);
-    true
-}
+    #[test]
+    fn restores_last_status_as_stale_until_a_live_packet() {
+        let settings = AppSettings::default();
+        let (dir, state) = temp_state("last-status", &settings, None);
+        let mut status = fabricated_status(AlertKind::AcFault, &settings);
+        *lock(&state.last_status) = Some(status.clone());
+        state.save_last_status();
+
+        let restored = AppState::load(dir.clone());
+        let snapshot = lock(&restored.last_status).clone().unwrap();
+        assert!(snapshot.stale);
+        assert!(snapshot.status.utility_fail);
+        assert!(!*lock(&restored.is_on_battery));
+        assert!(!restored.has_live_status());
+        assert!(restored.live_status().is_none());
+
+        // A stale snapshot is never written back as if it were fresh.
+        let _ = fs::remove_file(&restored.last_status_path);
+        restored.save_last_status();
+        assert!(!restored.last_status_path.exists());
+
+        status.stale = false;
+        *lock(&restored.last_status) = Some(status);
+        assert!(restored.has_live_status());
+        assert!(restored.live_status().is_some());
+        let _ = fs::remove_dir_all(dir);
+    }
 
-fn create_main_window(app: &AppHandle) -> bool {
-    let window_config = app
-        .config()
-        .app
-        .windows
-        .iter()
-        .find(|w| w.label == "main")
-        .or_else(|| app.config().app.windows.first());
+    #[test]
+    fn hid_retry_delay_backs_off_exponentially_up_to_the_cap() {
+        let delays: Vec<u64> = (1..=8).map(hid_retry_delay_ms).collect();
+        assert_eq!(
+            delays,
+            vec![1_500, 3_000, 6_000, 12_000, 24_000, 48_000, 60_000, 60_000]
+        );
+        assert_eq!(hid_retry_delay_ms(0), HID_RETRY_BASE_MS);
+        assert_eq!(hid_retry_delay_ms(u32::MAX), HID_RETRY_MAX_MS);
+    }
 
-    let Some(window_config) = window_config else {
-        return false;
-    };
+    #[test]
+    fn ups_data_targets_respect_window_intervals() {
+        let clock = ManualClock::new();
+        let (dir, state) = temp_state(
+            "ups-data-throttle",
+            &AppSettings::default(),
+            Some(clock.clone()),
+        );
+        let labels = vec![
+            "main".to_string(),
+            "widget".to_string(),
+            "other".to_string(),
+        ];
+        state.subscribe_ups_data("main", 0);
+        state.subscribe_ups_data("widget", 5_000);
 
-    if let Some(state) = app.try_state::<SharedState>() {
-        state.pending_show_main_window.store(true, Ordering::Relaxed);
+        assert_eq!(state.ups_data_targets(&labels), labels);
+
+        clock.advance(1_000);
+        assert_eq!(state.ups_data_targets(&labels), vec!["main".to_string()]);
+
+        clock.advance(4_000);
+        assert_eq!(state.ups_data_targets(&labels), labels);
+
+        assert!(state.unsubscribe_ups_data("widget"));
+        assert!(!state.unsubscribe_ups_data("widget"));
+        clock.advance(1_000);
+        assert_eq!(state.ups_data_targets(&labels), vec!["main".to_string()]);
+
+        let _ = fs::remove_dir_all(dir);
     }
 
-    let window = match tauri::WebviewWindowBuilder::from_config(app, window_config) {
-        Ok(builder) => match builder
-            .decorations(false)
-            .visible(false)
-            .build()
-        {
-            Ok(window) => window,
-            Err(_) => return false,
-        },
-        Err(_) => return false,
-    };
+    #[test]
+    fn charge_state_follows_post_outage_recovery_trace() {
+        let settings = AppSettings::default();
+        let clock = ManualClock::new();
+        let (dir, state) = temp_state("charge-state", &settings, Some(clock.clone()));
+        let state = Arc::new(state);
+        let feed = |percent: u64, on_battery: bool, was_on_battery: bool| {
+            let kind = if on_battery {
+                AlertKind::AcFault
+            } else {
+                AlertKind::TemperatureRising
+            };
+            let mut status = fabricated_status(kind, &settings);
+            status.battery_percent = percent;
+            let slope =
+                apply_percent_projections(&state, &mut status, on_battery, was_on_battery, None);
+            let completed = state.advance_charge_cycle(&status, on_battery, was_on_battery, slope);
+            clock.advance(30_000);
+            (
+                ChargeState::derive(status.status.utility_fail, percent, slope),
+                completed,
+            )
+        };
 
-    #[cfg(target_os = "windows")]
-    {
-        let _ = window.set_decorations(false);
-        let _ = window.set_shadow(false);
-        apply_rounded_corners(&window);
+        assert_eq!(feed(70, true, false), (ChargeState::Discharging, false));
+        assert_eq!(feed(60, true, true), (ChargeState::Discharging, false));
+
+        // AC returns and the battery climbs 2 % every 30 s up to 100 %.
+        assert_eq!(feed(60, false, true), (ChargeState::Charging, false));
+        for percent in (62..=100).step_by(2) {
+            assert_eq!(feed(percent, false, false), (ChargeState::Charging, false));
+        }
+
+        // Once the rising samples leave the window the trend flattens at the top.
+        let mut trace = Vec::new();
+        for _ in 0..12 {
+            trace.push(feed(100, false, false));
+        }
+        assert_eq!(trace.iter().filter(|(_, completed)| *completed).count(), 1);
+        assert_eq!(trace.last(), Some(&(ChargeState::Charged, false)));
+
+        let events = lock(&state.events);
+        let full = events
+            .iter()
+            .filter(|event| event.key == "battery_full")
+            .collect::<Vec<_>>();
+        assert_eq!(full.len(), 1);
+        assert_eq!(full[0].remarks, "Battery recharged after outage");
+        assert_eq!(full[0].classification, "General Event");
+        drop(events);
+
+        // Falling at the top is ambiguous; a single reading at 100 % counts as charged.
+        assert_eq!(
+            ChargeState::derive(false, 97, Some(-0.5)),
+            ChargeState::Unknown
+        );
+        assert_eq!(ChargeState::derive(false, 100, None), ChargeState::Charged);
+        assert_eq!(
+            ChargeState::derive(false, 90, Some(0.0)),
+            ChargeState::Charging
+        );
+
+        let _ = fs::remove_dir_all(dir);
     }
 
-    let _ = window.set_skip_taskbar(true);
-    true
-}
+    #[test]
+    fn state_snapshot_is_consistent_and_redacted() {
+        let settings = AppSettings {
+            remote_api: RemoteApiSettings {
+                token: Some("s3cr3t-token".to_string()),
+                ..RemoteApiSettings::default()
+            },
+            ..AppSettings::default()
+        };
+        let mut settings = settings;
+        settings.shutdown_pc.working_directory = Some("/home/ana/ups".to_string());
+        settings
+            .shutdown_pc
+            .environment
+            .insert("UPS_HOST".to_string(), "srv-ana-01".to_string());
+        let clock = ManualClock::new();
+        let (dir, state) = temp_state("state-snapshot", &settings, Some(clock.clone()));
+        let state = Arc::new(state);
+        *lock(&state.device_path) = Some("\\\\?\\hid#vid_0925&pid_1234#7&1a2b".to_string());
+        let started_at = clock.now_ms();
+        *lock(&state.is_on_battery) = true;
+        *lock(&state.battery_start_ms) = Some(started_at);
+        *lock(&state.last_status) = Some(fabricated_status(AlertKind::AcFault, &settings));
+        let schedule = state.schedule_shutdown(3, "ac-fault", false).unwrap();
+        clock.advance(42_000);
+
+        let snapshot = state_snapshot(&state);
+        assert!(snapshot.on_battery);
+        assert_eq!(snapshot.battery_time_seconds, Some(42));
+        assert_eq!(
+            snapshot.scheduled_shutdown_at,
+            Some(iso_from_millis(schedule.at_ms))
+        );
+        assert_eq!(
+            snapshot.scheduled_shutdown_reason.as_deref(),
+            Some("ac-fault")
+        );
+        assert!(snapshot
+            .last_status
+            .is_some_and(|status| status.status.utility_fail));
+        assert_eq!(
+            snapshot.redacted_fields,
+            vec!["remoteApi.token", "shutdownPC.environment.UPS_HOST"]
+        );
 
-fn show_main_window(app: &AppHandle) {
-    let app_handle = app.clone();
-    tauri::async_runtime::spawn(async move {
-        if let Some(window) = app_handle.get_webview_window("main") {
-            if let Some(state) = app_handle.try_state::<SharedState>() {
-                if state.pending_show_main_window.load(Ordering::Relaxed) {
-                    return;
-                }
-            }
-            let _ = window.set_skip_taskbar(false);
-            let _ = window.show();
-            let _ = window.unminimize();
-            let _ = window.set_focus();
-            return;
+        let text = serde_json::to_string(&state_snapshot(&state)).unwrap();
+        assert!(!text.contains("s3cr3t-token"));
+        assert!(text.contains("\"connection\""));
+
+        let redacted = state_snapshot_value(&state, true);
+        let text = redacted.to_string();
+        for identifier in ["1a2b", "/home/ana/ups", "srv-ana-01"] {
+            assert!(!text.contains(identifier), "{} leaked", identifier);
         }
+        assert_eq!(redacted["connection"]["devicePath"], "[device-path-1]");
+        assert_eq!(
+            redacted["settings"]["shutdownPC"]["environment"]["UPS_HOST"],
+            "[env-1]"
+        );
 
-        let _ = create_main_window(&app_handle);
-    });
-}
+        let _ = fs::remove_dir_all(dir);
+    }
 
-#[tauri::command]
-fn main_window_ready(app: AppHandle, state: State<'_, SharedState>) -> bool {
-    if !state.pending_show_main_window.swap(false, Ordering::Relaxed) {
-        return false;
+    #[test]
+    fn sound_repeats_follow_configurable_cap() {
+        let mut settings = AppSettings::default().normalize();
+        assert_eq!(settings.max_sound_repeats, 30);
+        assert_eq!(settings.alerts.battery_critical.sound_repeats, 10);
+
+        settings.alerts.battery_critical.sound_repeats = 200;
+        assert_eq!(
+            settings
+                .clone()
+                .normalize()
+                .alerts
+                .battery_critical
+                .sound_repeats,
+            30
+        );
+
+        settings.max_sound_repeats = 300;
+        assert_eq!(
+            settings
+                .clone()
+                .normalize()
+                .alerts
+                .battery_critical
+                .sound_repeats,
+            200
+        );
+
+        settings.max_sound_repeats = 10_000;
+        settings.alerts.battery_critical.sound_repeats = 10_000;
+        let normalized = settings.clone().normalize();
+        assert_eq!(normalized.max_sound_repeats, SOUND_REPEATS_LIMIT);
+        assert_eq!(
+            normalized.alerts.battery_critical.sound_repeats,
+            SOUND_REPEATS_LIMIT
+        );
+
+        settings.max_sound_repeats = 2;
+        settings.alerts.battery_critical.sound_repeats = 0;
+        assert_eq!(
+            settings.normalize().alerts.battery_critical.sound_repeats,
+            2
+        );
+
+        let legacy: AppSettings = {
+            let mut value = serde_json::to_value(AppSettings::default()).unwrap();
+            value.as_object_mut().unwrap().remove("maxSoundRepeats");
+            serde_json::from_value(value).unwrap()
+        };
+        assert_eq!(legacy.max_sound_repeats, 30);
     }
 
-    if let Some(window) = app.get_webview_window("main") {
-        let _ = window.set_skip_taskbar(false);
-        let _ = window.show();
-        let _ = window.unminimize();
-        let _ = window.set_focus();
-        return true;
+    #[test]
+    fn sound_sleep_stops_when_generation_changes() {
+        let (dir, state) = temp_state("sound-cancel", &AppSettings::default(), None);
+        let generation = state.sound_generation.load(Ordering::Relaxed);
+        assert!(sleep_unless_cancelled(&state, generation, 0));
+        state.sound_generation.fetch_add(1, Ordering::Relaxed);
+        let started = std::time::Instant::now();
+        assert!(!sleep_unless_cancelled(&state, generation, 60_000));
+        assert!(started.elapsed() < Duration::from_millis(SOUND_CANCEL_POLL_MS));
+        let _ = fs::remove_dir_all(dir);
     }
-    false
-}
 
-fn request_app_exit(app: &AppHandle) {
-    if let Some(state) = app.try_state::<SharedState>() {
-        state.allow_process_exit.store(true, Ordering::Relaxed);
+    #[test]
+    fn standby_charge_alert_needs_full_ac_window_and_is_weekly() {
+        let settings = AppSettings::default().normalize();
+        let standby = &settings.standby_charge;
+        let clock = ManualClock::new();
+        let (dir, state) = temp_state("standby-charge", &settings, Some(clock.clone()));
+        let hour = 3_600_000;
+
+        // Recharging after an outage: low for a while, recovers inside the window.
+        assert!(!state.update_standby_charge(standby, true, 40));
+        for percent in [45, 55, 65, 75] {
+            assert!(!state.update_standby_charge(standby, false, percent));
+            clock.advance(hour);
+        }
+        assert!(!state.update_standby_charge(standby, false, 85));
+
+        // An outage in the middle restarts the AC window.
+        assert!(!state.update_standby_charge(standby, false, 60));
+        clock.advance(5 * hour);
+        assert!(!state.update_standby_charge(standby, true, 58));
+        assert!(!state.update_standby_charge(standby, false, 58));
+        clock.advance(5 * hour);
+        assert!(!state.update_standby_charge(standby, false, 58));
+
+        clock.advance(hour);
+        assert!(state.update_standby_charge(standby, false, 58));
+        assert!(*lock(&state.battery_not_holding));
+        assert!(!state.update_standby_charge(standby, false, 58));
+
+        // Hovering just above the minimum does not clear it.
+        assert!(!state.update_standby_charge(standby, false, 82));
+        assert!(*lock(&state.battery_not_holding));
+        assert!(!state.update_standby_charge(standby, false, 90));
+        assert!(!*lock(&state.battery_not_holding));
+
+        // Raised again two days later, but the weekly cap holds the alert back.
+        assert!(!state.update_standby_charge(standby, false, 50));
+        clock.advance(48 * hour);
+        assert!(!state.update_standby_charge(standby, false, 50));
+        assert!(*lock(&state.battery_not_holding));
+
+        state.update_standby_charge(standby, false, 95);
+        clock.advance(6 * 24 * hour);
+        assert!(!state.update_standby_charge(standby, false, 50));
+        clock.advance(6 * hour);
+        assert!(state.update_standby_charge(standby, false, 50));
+
+        let _ = fs::remove_dir_all(dir);
     }
-    app.exit(0);
-}
 
-#[tauri::command]
-fn cancel_shutdown(app: AppHandle, state: State<'_, SharedState>) -> bool {
-    cancel_scheduled_shutdown(&state, &app, true)
-}
+    #[test]
+    fn runtime_source_prefers_device_value_when_requested() {
+        let Some(DecodedPacket::Status(plain)) = parse_ups_string(
+            "(218.5 140.0 219.0 023 60.1 26.4 32.0 00001001",
+            &FrameFormat::default(),
+        ) else {
+            panic!("expected status");
+        };
+        let Some(DecodedPacket::Status(extended)) = parse_ups_string(
+            "(218.5 140.0 219.0 023 60.1 26.4 32.0 00001001 047",
+            &FrameFormat::default(),
+        ) else {
+            panic!("expected status");
+        };
+        assert_eq!(plain.device_runtime, None);
+        assert_eq!(extended.device_runtime, Some(47));
+        let computed = estimate_runtime(extended.battery_percent, extended.load_percent);
+
+        let runtime_for = |status: &UpsData, source: RuntimeSource| {
+            let mut status = status.clone();
+            apply_runtime_source(&mut status, source);
+            status.estimated_runtime
+        };
+        assert_eq!(runtime_for(&extended, RuntimeSource::Auto), Some(47));
+        assert_eq!(runtime_for(&extended, RuntimeSource::Device), Some(47));
+        assert_eq!(
+            runtime_for(&extended, RuntimeSource::Computed),
+            Some(computed)
+        );
+        assert_eq!(runtime_for(&plain, RuntimeSource::Auto), Some(computed));
+        assert_eq!(runtime_for(&plain, RuntimeSource::Device), None);
 
-#[tauri::command]
-fn trigger_shutdown(app: AppHandle, state: State<'_, SharedState>, minutes: u64) -> bool {
-    if lock(&state.settings).monitor_only_mode {
-        return false;
+        let settings: AppSettings = serde_json::from_value({
+            let mut value = serde_json::to_value(AppSettings::default()).unwrap();
+            value["runtimeSource"] = serde_json::json!("device");
+            value
+        })
+        .unwrap();
+        assert_eq!(settings.runtime_source, RuntimeSource::Device);
     }
 
-    schedule_shutdown_after_minutes(&state, &app, minutes, "manual-trigger")
-}
+    #[test]
+    fn deletes_events_by_filter_or_ids() {
+        let (dir, state) = temp_state("delete-filter", &AppSettings::default(), None);
+        let now = Utc::now();
+        let mut old_info = timeline_event(now - chrono::Duration::days(120), "ac_restored");
+        old_info.classification = Severity::Info.classification().to_string();
+        let mut old_warning = timeline_event(now - chrono::Duration::days(100), "ac_fault");
+        old_warning.severity = Some(Severity::Warning);
+        old_warning.classification = Severity::Warning.classification().to_string();
+        let mut recent_info = timeline_event(now - chrono::Duration::days(5), "app_started");
+        recent_info.classification = Severity::Info.classification().to_string();
+        let ids = [old_info.id, old_warning.id, recent_info.id];
+        *lock(&state.events) = vec![recent_info, old_warning, old_info];
+
+        let cutoff = (now - chrono::Duration::days(90))
+            .format("%Y-%m-%d")
+            .to_string();
+        let filter = || HistoryFilter {
+            classification: Some("General Event".to_string()),
+            keys: None,
+            severity: None,
+            resolved: None,
+            date_from: None,
+            date_to: Some(cutoff.clone()),
+        };
+        assert!(state
+            .delete_events(Some(vec![ids[0]]), Some(filter()))
+            .is_err());
+
+        let summary = state.delete_events(None, Some(filter())).unwrap();
+        assert_eq!(
+            summary,
+            DeleteSummary {
+                deleted: 1,
+                remaining: 2
+            }
+        );
+        assert!(lock(&state.events).iter().all(|event| event.id != ids[0]));
+
+        // A filter that matches nothing must not fall through to "delete all".
+        let summary = state
+            .delete_events(Some(Vec::new()), Some(filter()))
+            .unwrap();
+        assert_eq!(
+            summary,
+            DeleteSummary {
+                deleted: 0,
+                remaining: 2
+            }
+        );
 
-#[tauri::command]
-fn simulate_shutdown_flow(
-    app: AppHandle,
-    minutes: Option<u64>,
-    auto_cancel_ms: Option<u64>,
-    state: State<'_, SharedState>,
-) -> Result<ShutdownSimulationResult, String> {
-    if lock(&state.settings).monitor_only_mode {
-        return Err("Modo solo monitor activo".to_string());
-    }
+        let summary = state.delete_events(Some(vec![ids[1]]), None).unwrap();
+        assert_eq!(
+            summary,
+            DeleteSummary {
+                deleted: 1,
+                remaining: 1
+            }
+        );
+        let summary = state.delete_events(Some(Vec::new()), None).unwrap();
+        assert_eq!(
+            summary,
+            DeleteSummary {
+                deleted: 1,
+                remaining: 0
+            }
+        );
 
-    let safe_minutes = minutes.unwrap_or(5).max(1);
-    let safe_cancel_ms = auto_cancel_ms.unwrap_or(1200).max(100);
-    let shutdown_time = (Utc::now() + ChronoDuration::minutes(safe_minutes as i64)).to_rfc3339();
+        let _ = fs::remove_dir_all(dir);
+    }
 
-    emit_if_possible(
-        &app,
-        "shutdown-scheduled",
-        ShutdownScheduledPayload {
-            minutes: safe_minutes,
-            shutdown_time: shutdown_time.clone(),
-        },
-    );
+    #[test]
+    fn toggling_monitor_only_keeps_configured_alerts_and_shutdown() {
+        let mut settings = AppSettings::default();
+        settings.alerts.ac_fault.channels = vec!["sound".to_string()];
+        settings.shutdown_pc.shutdown_command = "systemctl poweroff".to_string();
+        let (dir, state) = temp_state("monitor-only-toggle", &settings, None);
+
+        assert!(state.set_monitor_only(true));
+        assert!(!state.set_monitor_only(true));
+        let suppressed = state.effective_settings();
+        assert!(suppressed.alerts.ac_fault.channels.is_empty());
+        assert!(suppressed.shutdown_pc.shutdown_command.is_empty());
+
+        assert!(state.set_monitor_only(false));
+        let reloaded = AppState::load(dir.clone());
+        let restored = reloaded.effective_settings();
+        assert!(!restored.monitor_only_mode);
+        assert_eq!(restored.alerts.ac_fault.channels, vec!["sound".to_string()]);
+        assert_eq!(restored.shutdown_pc.shutdown_command, "systemctl poweroff");
+
+        let _ = fs::remove_dir_all(dir);
+    }
 
-    thread::sleep(Duration::from_millis(safe_cancel_ms));
-    emit_if_possible(&app, "shutdown-cancelled", ());
+    #[test]
+    fn events_keep_insertion_order_and_unique_ids_when_clock_jumps() {
+        let settings = AppSettings {
+            duplicate_event_window_seconds: 0,
+            ..AppSettings::default()
+        };
+        let (dir, state) = temp_state("event-seq", &settings, None);
+        lock(&state.events).clear();
+        let start = now_millis();
+        state.log_event_at(start, "ac_fault", Severity::Warning, "corte");
+        // Clock stepped back an hour, then two events in the same millisecond.
+        state.log_event_at(start - 3_600_000, "ac_restored", Severity::Info, "vuelta");
+        state.log_event_at(start - 3_600_000, "battery_full", Severity::Info, "llena");
+
+        let events = lock(&state.events).clone();
+        let keys = events
+            .iter()
+            .map(|event| event.key.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, vec!["battery_full", "ac_restored", "ac_fault"]);
+        assert!(events.windows(2).all(|pair| pair[0].seq > pair[1].seq));
+        let ids = events.iter().map(|event| event.id).collect::<HashSet<_>>();
+        assert_eq!(ids.len(), 3);
+
+        // Legacy rows carry duplicate millisecond ids and no sequence.
+        let mut legacy = vec![
+            timeline_event(Utc::now(), "ac_restored"),
+            timeline_event(Utc::now(), "ac_fault"),
+            timeline_event(Utc::now() - ChronoDuration::minutes(5), "app_started"),
+        ];
+        legacy[1].id = legacy[0].id;
+        assert_eq!(assign_event_sequences(&mut legacy), 4);
+        assert_eq!(
+            legacy.iter().map(|event| event.seq).collect::<Vec<_>>(),
+            vec![3, 2, 1]
+        );
+        assert_eq!(
+            legacy.iter().map(|event| event.id).collect::<Vec<_>>(),
+            vec![3, 2, 1]
+        );
 
-    Ok(ShutdownSimulationResult {
-        scheduled: true,
-        cancelled: true,
-        minutes: safe_minutes,
-        shutdown_time,
-    })
-}
+        write_json_pretty(&dir.join("history").join("events.json"), &legacy);
+        let reloaded = AppState::load(dir.clone());
+        assert_eq!(reloaded.next_event_seq.load(Ordering::Relaxed), 4);
 
-#[tauri::command]
-fn get_battery_time(state: State<'_, SharedState>) -> Option<u64> {
-    let start_ms = *lock(&state.battery_start_ms);
-    start_ms.map(|started_at| now_millis().saturating_sub(started_at) / 1000)
-}
+        let _ = fs::remove_dir_all(dir);
+    }
 
-#[tauri::command]
-fn get_events(state: State<'_, SharedState>, filter: Option<HistoryFilter>) -> Vec<HistoryEvent> {
-    let mut events = lock(&state.events).clone();
+    #[test]
+    fn restart_action_is_supported_for_manual_triggers_only() {
+        let restart = builtin_shutdown_argv("restart");
+        assert_ne!(restart, builtin_shutdown_argv("shutdown"));
+        if cfg!(target_os = "linux") {
+            assert_eq!(restart, vec!["systemctl", "reboot"]);
+        }
 
-    if let Some(filter) = filter {
-        if let Some(classification) = filter.classification {
-            if classification != "All Events" {
-                events = events
-                    .into_iter()
-                    .filter(|item| item.classification == classification)
-                    .collect();
-            }
+        let mut settings = AppSettings::default();
+        settings.shutdown_pc.action = "sleep".to_string();
+        assert_eq!(settings.clone().normalize().shutdown_pc.action, "sleep");
+        for rejected in ["restart", "hibernate"] {
+            settings.shutdown_pc.action = rejected.to_string();
+            assert_eq!(settings.clone().normalize().shutdown_pc.action, "shutdown");
         }
 
-        if let Some(date_from) = filter.date_from {
-            if let Some(from_dt) = parse_date_bound(&date_from, false) {
-                events = events
-                    .into_iter()
-                    .filter(|item| {
-                        parse_rfc3339_utc(&item.time)
-                            .map(|item_dt| item_dt >= from_dt)
-                            .unwrap_or(true)
-                    })
-                    .collect();
-            }
+        let settings = AppSettings::default();
+        let configured = shutdown_argv(&settings).unwrap();
+        assert_eq!(
+            argv_for_schedule(&settings, "manual-trigger", Some("restart")).unwrap(),
+            restart
+        );
+        assert_eq!(
+            argv_for_schedule(&settings, "battery-critical", Some("restart")).unwrap(),
+            configured
+        );
+        assert_eq!(
+            argv_for_schedule(&settings, "manual-trigger", None).unwrap(),
+            configured
+        );
+        assert_eq!(
+            action_for_schedule(&settings, "manual-trigger", Some("restart")),
+            "restart"
+        );
+        assert_eq!(
+            action_for_schedule(&settings, "battery-critical", Some("restart")),
+            "shutdown"
+        );
+
+        let (dir, state) = temp_state("manual-restart", &settings, None);
+        state.schedule_shutdown(1, "manual-trigger", true);
+        *lock(&state.manual_shutdown_action) = Some("restart".to_string());
+        assert!(state.clear_scheduled_shutdown(None));
+        assert_eq!(*lock(&state.manual_shutdown_action), None);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn ids_stay_unique_and_individually_deletable_in_tight_loops() {
+        let settings = AppSettings {
+            duplicate_event_window_seconds: 0,
+            ..AppSettings::default()
+        };
+        let (dir, state) = temp_state("unique-ids", &settings, None);
+        lock(&state.events).clear();
+        lock(&state.data_history).clear();
+        let at = now_millis();
+        let status = fabricated_status(AlertKind::AcFault, &settings);
+        for _ in 0..5 {
+            state.log_event_at(at, "ac_fault", Severity::Warning, "corte");
+            state.push_data_point(at, &status);
         }
 
-        if let Some(date_to) = filter.date_to {
-            if let Some(to_dt) = parse_date_bound(&date_to, true) {
-                events = events
-                    .into_iter()
-                    .filter(|item| {
-                        parse_rfc3339_utc(&item.time)
-                            .map(|item_dt| item_dt <= to_dt)
-                            .unwrap_or(true)
-                    })
-                    .collect();
+        let event_ids = lock(&state.events)
+            .iter()
+            .map(|event| event.id)
+            .collect::<Vec<_>>();
+        let data_ids = lock(&state.data_history)
+            .iter()
+            .map(|entry| entry.id)
+            .collect::<Vec<_>>();
+        assert_eq!(event_ids.iter().collect::<HashSet<_>>().len(), 5);
+        assert_eq!(data_ids.iter().collect::<HashSet<_>>().len(), 5);
+
+        let summary = state.delete_events(Some(vec![event_ids[2]]), None).unwrap();
+        assert_eq!(
+            summary,
+            DeleteSummary {
+                deleted: 1,
+                remaining: 4
             }
-        }
+        );
+        let summary = state
+            .delete_data_history(Some(vec![data_ids[2]]), None)
+            .unwrap();
+        assert_eq!(
+            summary,
+            DeleteSummary {
+                deleted: 1,
+                remaining: 4
+            }
+        );
+
+        // Legacy data points sharing a millisecond id get distinct ids on load, in order.
+        let legacy = vec![
+            DataHistoryEntry::from_status(at, at, &status),
+            DataHistoryEntry::from_status(at, at, &status),
+            DataHistoryEntry::from_status(at - 1, at - 1, &status),
+        ];
+        write_json_pretty(&state.data_path, &legacy);
+        let reloaded = AppState::load(dir.clone());
+        let ids = lock(&reloaded.data_history)
+            .iter()
+            .map(|entry| entry.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![3, 2, 1]);
+        reloaded.push_data_point(at, &status);
+        assert_eq!(lock(&reloaded.data_history)[0].id, 4);
+
+        // Ids survive later loads untouched, and a deleted id is not reused.
+        reloaded
+            .delete_data_history(Some(vec![4, 2]), None)
+            .unwrap();
+        reloaded.save_runtime_state();
+        let again = AppState::load(dir.clone());
+        let ids = lock(&again.data_history)
+            .iter()
+            .map(|entry| entry.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![3, 1]);
+        again.push_data_point(at, &status);
+        assert_eq!(lock(&again.data_history)[0].id, 5);
+
+        let _ = fs::remove_dir_all(dir);
     }
 
-    events
-}
+    #[test]
+    fn significant_changes_log_data_points_between_intervals() {
+        let mut settings = AppSettings {
+            history_interval: 300,
+            ..AppSettings::default()
+        };
+        settings.change_logging.enabled = true;
+        let clock = ManualClock::new();
+        let (dir, state) = temp_state("change-logging", &settings, Some(clock.clone()));
+        let steady = fabricated_status(AlertKind::AcFault, &settings);
+        let mut dip = steady.clone();
+        dip.input_voltage = steady.input_voltage + 25.0;
+
+        state.log_data_point_if_needed(&steady);
+        clock.advance(1_000);
+        state.log_data_point_if_needed(&dip);
+        assert_eq!(
+            lock(&state.data_history).len(),
+            1,
+            "inside the minimum floor"
+        );
+        clock.advance(4_000);
+        state.log_data_point_if_needed(&steady);
+        assert_eq!(
+            lock(&state.data_history).len(),
+            1,
+            "no change, interval not due"
+        );
+        state.log_data_point_if_needed(&dip);
+        assert_eq!(lock(&state.data_history).len(), 2);
+        assert_eq!(
+            lock(&state.data_history)[0].input_voltage,
+            dip.input_voltage
+        );
 
-#[tauri::command]
-fn delete_events(state: State<'_, SharedState>, ids: Vec<u64>) -> Vec<HistoryEvent> {
-    let mut events = lock(&state.events);
-    if ids.is_empty() {
-        events.clear();
-    } else {
-        events.retain(|item| !ids.contains(&item.id));
+        // Deltas are measured against the last logged point, not the previous sample.
+        clock.advance(5_000);
+        state.log_data_point_if_needed(&dip);
+        assert_eq!(lock(&state.data_history).len(), 2);
+        state.log_data_point_if_needed(&steady);
+        assert_eq!(lock(&state.data_history).len(), 3);
+
+        // A flapping reading stops adding points once the hourly budget is spent.
+        lock(&state.settings).change_logging.max_points_per_hour = 3;
+        clock.advance(5_000);
+        state.log_data_point_if_needed(&dip);
+        assert_eq!(lock(&state.data_history).len(), 4, "third change point");
+        clock.advance(5_000);
+        state.log_data_point_if_needed(&steady);
+        assert_eq!(lock(&state.data_history).len(), 4, "hourly budget spent");
+        clock.advance(300_000);
+        state.log_data_point_if_needed(&steady);
+        assert_eq!(
+            lock(&state.data_history).len(),
+            5,
+            "regular interval still due"
+        );
+
+        let mut disabled = lock(&state.settings).clone();
+        disabled.change_logging.enabled = false;
+        disabled.change_logging.max_points_per_hour = default_change_points_per_hour();
+        *lock(&state.settings) = disabled;
+        clock.advance(5_000);
+        state.log_data_point_if_needed(&dip);
+        assert_eq!(lock(&state.data_history).len(), 5);
+        let _ = fs::remove_dir_all(dir);
     }
 
-    let result = events.clone();
-    drop(events);
-    state.save_events();
-    result
-}
+    #[test]
+    fn data_history_reads_both_gzip_and_plain_json() {
+        assert!(!AppSettings::default().compress_data_history);
+        let settings = AppSettings {
+            compress_data_history: true,
+            ..AppSettings::default()
+        };
+        let (dir, state) = temp_state("gzip-data", &settings, None);
+        let status = fabricated_status(AlertKind::AcFault, &AppSettings::default());
+        for at in 0..50 {
+            state.push_data_point(at * 1000, &status);
+        }
 
-#[tauri::command]
-fn get_data_history(
-    state: State<'_, SharedState>,
-    filter: Option<HistoryFilter>,
-) -> Vec<DataHistoryEntry> {
-    let mut data = lock(&state.data_history).clone();
+        state.save_data_history();
+        let compressed = fs::read(&state.data_path).unwrap();
+        assert!(compressed.starts_with(&GZIP_MAGIC));
+        let saved: Vec<DataHistoryEntry> = read_json_or_default(&state.data_path);
+        assert_eq!(saved.len(), 50);
+
+        lock(&state.settings).compress_data_history = false;
+        state.save_data_history();
+        let plain = fs::read(&state.data_path).unwrap();
+        assert_eq!(plain.first(), Some(&b'['));
+        assert!(compressed.len() < plain.len());
+
+        lock(&state.settings).compress_data_history = true;
+        state.save_settings();
+        let reloaded = AppState::load(dir.clone());
+        assert_eq!(lock(&reloaded.data_history).len(), 50);
+        let config = fs::read_to_string(&state.config_path).unwrap();
+        assert!(config.contains("\n  \"compressDataHistory\": true"));
+        let _ = fs::remove_dir_all(dir);
+    }
 
-    if let Some(filter) = filter {
-        if let Some(date_from) = filter.date_from {
-            if let Some(from_dt) = parse_date_bound(&date_from, false) {
-                data = data
-                    .into_iter()
-                    .filter(|item| {
-                        parse_rfc3339_utc(&item.time)
-                            .map(|item_dt| item_dt >= from_dt)
-                            .unwrap_or(true)
-                    })
-                    .collect();
-            }
+    #[test]
+    fn resource_usage_reports_loop_rate_per_window() {
+        let clock = ManualClock::new();
+        let (dir, state) = temp_state("loop-stats", &AppSettings::default(), Some(clock.clone()));
+        let state = Arc::new(state);
+        state
+            .monitoring_started_ms
+            .store(clock.now_ms(), Ordering::Relaxed);
+        for _ in 0..10 {
+            state.record_loop_iteration(1000, 600);
+            state.sleep_monitor_loop(400);
+            clock.advance(600);
         }
+        let usage = resource_usage(&state);
+        assert_eq!(usage.monitor_loop.iterations, 10);
+        assert_eq!(
+            usage.monitor_loop.iterations_per_second, 0.0,
+            "window not complete yet"
+        );
+        assert_eq!(usage.uptime_seconds, 10);
+        assert_eq!(usage.average_cpu_percent, None, "no CPU baseline");
+
+        state.record_loop_iteration(1000, 600);
+        let stats = resource_usage(&state).monitor_loop;
+        assert_eq!(stats.iterations_per_second, 1.0);
+        assert_eq!(
+            (
+                stats.polling_interval_ms,
+                stats.read_timeout_ms,
+                stats.sleep_ms
+            ),
+            (1000, 600, 400)
+        );
 
-        if let Some(date_to) = filter.date_to {
-            if let Some(to_dt) = parse_date_bound(&date_to, true) {
-                data = data
-                    .into_iter()
-                    .filter(|item| {
-                        parse_rfc3339_utc(&item.time)
-                            .map(|item_dt| item_dt <= to_dt)
-                            .unwrap_or(true)
-                    })
-                    .collect();
-            }
+        #[cfg(target_os = "linux")]
+        {
+            assert!(usage.memory_bytes.is_some_and(|bytes| bytes > 0));
+            *lock(&state.monitoring_started_cpu_ms) = process_usage().0;
+            assert!(resource_usage(&state)
+                .average_cpu_percent
+                .is_some_and(|percent| percent >= 0.0));
         }
+        let _ = fs::remove_dir_all(dir);
     }
 
-    data
-}
+    #[test]
+    fn concurrent_atomic_writes_never_mix() {
+        let dir = std::env::temp_dir().join(format!("ups-monitor-atomic-{}", now_millis()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("data.json");
+        let writers: Vec<_> = (0..8u8)
+            .map(|index| {
+                let path = path.clone();
+                thread::spawn(move || {
+                    for _ in 0..20 {
+                        write_atomic(&path, &[index; 4096], true).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
 
-#[tauri::command]
-fn delete_data_history(state: State<'_, SharedState>, ids: Vec<u64>) -> Vec<DataHistoryEntry> {
-    let mut data = lock(&state.data_history);
-    if ids.is_empty() {
-        data.clear();
-    } else {
-        data.retain(|item| !ids.contains(&item.id));
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(bytes.len(), 4096);
+        assert!(bytes.iter().all(|byte| *byte == bytes[0]));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        let _ = fs::remove_dir_all(dir);
     }
 
-    let result = data.clone();
-    drop(data);
-    state.save_data_history();
-    result
-}
-
-#[tauri::command]
-fn update_history_interval(state: State<'_, SharedState>, seconds: u64) -> bool {
-    let mut settings = lock(&state.settings);
-    settings.history_interval = clamp_u64(seconds, 60, 3600, settings.history_interval);
-    drop(settings);
-    state.save_settings();
-    true
-}
+    #[test]
+    fn truncated_stores_are_recovered_from_backup() {
+        let settings = AppSettings {
+            custom_name: Some("Rack".to_string()),
+            ..AppSettings::default()
+        };
+        let (dir, state) = temp_state("backup-recovery", &settings, None);
+        state.log_event("ac_fault", Severity::Warning, "first");
+        state.log_event("ac_restored", Severity::Info, "second");
+        assert!(path_with_suffix(&state.config_path, ".bak").exists());
+        assert!(fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .all(|entry| !entry.file_name().to_string_lossy().ends_with(".tmp")));
+
+        // A power cut during a non-atomic save used to leave these behind.
+        fs::write(&state.config_path, b"").unwrap();
+        fs::write(&state.events_path, b"[{\"id\": 1, \"na").unwrap();
+        let reloaded = AppState::load(dir.clone());
+
+        assert_eq!(
+            lock(&reloaded.settings).custom_name.as_deref(),
+            Some("Rack")
+        );
+        let restored: AppSettings = read_json_or_default(&reloaded.config_path);
+        assert_eq!(restored.custom_name.as_deref(), Some("Rack"));
+        let events = lock(&reloaded.events);
+        let recoveries = events
+            .iter()
+            .filter(|event| event.key == "storage_recovered")
+            .map(|event| event.remarks.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            recoveries,
+            vec!["Restored from backup after a failed read: config.json, events.json".to_string()]
+        );
+        assert!(events.iter().any(|event| event.key == "ac_fault"));
+        drop(events);
+        let _ = fs::remove_dir_all(dir);
+    }
 
-#[tauri::command]
-fn play_sound(state: State<'_, SharedState>, sound_type: String, repeats: Option<u64>) -> bool {
-    let settings = lock(&state.settings).clone();
-    let kind = AlertKind::from_str(sound_type.as_str()).unwrap_or(AlertKind::BatteryCritical);
-    let sound_path = resolve_sound_path(&state, &settings, kind);
-    play_sound_with_generation(state.inner().clone(), sound_path, repeats.unwrap_or(1))
-}
+    #[test]
+    fn identifier_redaction_is_stable_and_keeps_readings() {
+        let settings = AppSettings {
+            custom_name: Some("Rack Oficina".to_string()),
+            ..AppSettings::default()
+        };
+        let status = fabricated_status(AlertKind::AcFault, &settings);
+        let mut settings_value = serde_json::to_value(&settings).unwrap();
+        settings_value["shutdownPC"]["shutdownCommand"] =
+            serde_json::json!("/opt/acme/halt --host srv-01");
+        let device = serde_json::json!({
+            "manufacturer": "ACME Power",
+            "product": "Backup Pro 2000",
+            "serialNumber": "SN-44718",
+            "vendorId": "0925",
+        });
+        let mut devices = serde_json::json!([device.clone(), device]);
+        let mut diagnostics = serde_json::json!({
+            "lastError": "HID open error on SN-44718",
+            "lastStatus": status,
+            "deviceInfo": { "product": "Backup Pro 2000", "displayName": "Rack Oficina" },
+        });
 
-#[tauri::command]
-fn stop_sound(state: State<'_, SharedState>) -> bool {
-    state.sound_generation.fetch_add(1, Ordering::Relaxed);
-    true
-}
+        let mut redactor = IdentifierRedactor::default();
+        redactor.redact(&mut [&mut settings_value, &mut devices, &mut diagnostics]);
+
+        assert_eq!(devices[0], devices[1]);
+        assert_eq!(devices[0]["serialNumber"], "[serial-1]");
+        assert_eq!(devices[0]["product"], "[product-1]");
+        assert_eq!(devices[0]["vendorId"], "0925");
+        assert_eq!(diagnostics["deviceInfo"]["product"], "[product-1]");
+        assert_eq!(
+            diagnostics["deviceInfo"]["displayName"],
+            settings_value["customName"]
+        );
+        assert_eq!(diagnostics["lastError"], "HID open error on [serial-1]");
+        assert_eq!(
+            settings_value["shutdownPC"]["shutdownCommand"],
+            "[command-1]"
+        );
+        assert_eq!(
+            diagnostics["lastStatus"],
+            serde_json::to_value(&status).unwrap()
+        );
+        assert_eq!(
+            redactor.scrub("Connected to Backup Pro 2000"),
+            "Connected to [product-1]"
+        );
+    }
 
-#[tauri::command]
-fn test_urgent_alert(
-    app: AppHandle,
-    state: State<'_, SharedState>,
-    title: String,
-    message: String,
-    alert_type: String,
-) -> bool {
-    let _ = notify_windows(&app, &title, &message);
-    if should_force_popup(&app, &state) {
-        force_windows_popup(&title, &message, &alert_type);
+    #[test]
+    fn protection_suspension_expires_on_its_own_and_survives_restart() {
+        let clock = ManualClock::new();
+        let (dir, state) = temp_state("protection", &AppSettings::default(), Some(clock.clone()));
+        let local_now = NaiveDate::from_ymd_opt(2026, 10, 14)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        assert!(state.effective_settings().shutdown_pc.on_ac_fault.enabled);
+
+        state.suspend_protection(30, "Generator test");
+        let suppressed = state.effective_settings();
+        assert!(!suppressed.shutdown_pc.on_ac_fault.enabled);
+        assert!(suppressed
+            .alerts
+            .configs()
+            .iter()
+            .all(|config| config.channels.is_empty()));
+        assert!(suppressed.logs_events() && suppressed.logs_data_points());
+        let protection = state.protection_state(local_now);
+        assert_eq!(protection.source.as_deref(), Some("manual"));
+        assert_eq!(protection.reason.as_deref(), Some("Generator test"));
+
+        let persisted: RuntimeSnapshot = read_json_or_default(&state.runtime_state_path);
+        assert!(persisted.protection_override.is_some());
+        let mut reloaded = AppState::load(dir.clone());
+        reloaded.clock = clock.clone();
+        assert!(reloaded.protection_suspended());
+
+        clock.advance(29 * 60_000);
+        assert_eq!(state.refresh_protection(local_now), None);
+        clock.advance(60_000);
+        let resumed = state.refresh_protection(local_now).unwrap();
+        assert!(!resumed.suspended);
+        assert!(state.effective_settings().shutdown_pc.on_ac_fault.enabled);
+        assert!(lock(&state.events)
+            .iter()
+            .any(|event| event.key == "protection_resumed" && event.remarks.contains("expired")));
+        let persisted: RuntimeSnapshot = read_json_or_default(&state.runtime_state_path);
+        assert!(persisted.protection_override.is_none());
+        let _ = fs::remove_dir_all(dir);
     }
-    emit_urgent_alert(&app, &title, &message, &alert_type);
-    emit_if_possible(&app, "show-status", ());
-    true
-}
 
-#[tauri::command]
-fn get_available_sounds(state: State<'_, SharedState>) -> Vec<SoundInfo> {
-    let settings = lock(&state.settings).clone();
-    let mut sounds = Vec::new();
+    #[test]
+    fn weekly_protection_window_follows_local_time_and_can_be_dismissed() {
+        let settings = AppSettings {
+            protection_window: ProtectionWindowSettings {
+                enabled: true,
+                weekday: 5,
+                start_hour: 23,
+                start_minute: 0,
+                duration_minutes: 120,
+            },
+            ..AppSettings::default()
+        };
+        let window = &settings.protection_window;
+        // 2026-10-17 is a Saturday; the window runs into Sunday.
+        let at = |day: u32, hour: u32, minute: u32| {
+            NaiveDate::from_ymd_opt(2026, 10, day)
+                .unwrap()
+                .and_hms_opt(hour, minute, 0)
+                .unwrap()
+        };
+        assert_eq!(window.active_until(at(17, 22, 59)), None);
+        assert_eq!(window.active_until(at(17, 23, 0)), Some(at(18, 1, 0)));
+        assert_eq!(window.active_until(at(18, 0, 30)), Some(at(18, 1, 0)));
+        assert_eq!(window.active_until(at(18, 1, 0)), None);
+        assert_eq!(window.active_until(at(24, 23, 30)), Some(at(25, 1, 0)));
+
+        let (dir, state) = temp_state("protection-window", &settings, None);
+        assert_eq!(state.refresh_protection(at(17, 12, 0)), None);
+        let started = state.refresh_protection(at(17, 23, 5)).unwrap();
+        assert_eq!(started.source.as_deref(), Some("schedule"));
+        assert!(!state.effective_settings().shutdown_pc.on_ac_fault.enabled);
+
+        assert!(state.resume_protection(at(17, 23, 10)));
+        assert_eq!(
+            state.refresh_protection(at(17, 23, 20)),
+            None,
+            "dismissed for this occurrence"
+        );
+        assert!(state.effective_settings().shutdown_pc.on_ac_fault.enabled);
+        assert_eq!(state.refresh_protection(at(18, 2, 0)), None);
+        assert!(state
+            .refresh_protection(at(24, 23, 0))
+            .is_some_and(|next| next.suspended));
+        let _ = fs::remove_dir_all(dir);
+    }
 
-    if let Ok(entries) = fs::read_dir(&state.sounds_path) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if !is_sound_file(&path) {
-                continue;
-            }
-            sounds.push(SoundInfo {
-                name: file_name_or_default(&path),
-                path: path.to_string_lossy().to_string(),
-                custom: false,
-                location: Some("AppData".to_string()),
-            });
-        }
+    #[test]
+    fn ac_fault_delay_is_capped_to_estimated_runtime() {
+        assert_eq!(runtime_capped_delay(18, Some(12), 3), Some(9));
+        assert_eq!(runtime_capped_delay(18, Some(25), 3), None);
+        assert_eq!(runtime_capped_delay(18, Some(21), 3), None);
+        assert_eq!(runtime_capped_delay(18, Some(2), 3), Some(1));
+        assert_eq!(runtime_capped_delay(18, None, 3), None);
+
+        let mut settings = AppSettings::default();
+        settings.shutdown_pc.on_ac_fault.cap_delay_to_runtime = true;
+        settings
+            .shutdown_pc
+            .on_ac_fault
+            .runtime_safety_margin_minutes = 0;
+        let normalized = settings.normalize();
+        assert_eq!(
+            normalized
+                .shutdown_pc
+                .on_ac_fault
+                .runtime_safety_margin_minutes,
+            3
+        );
     }
 
-    if let Some(custom_path) = settings.custom_sounds_path {
-        let custom_dir = PathBuf::from(custom_path);
-        if let Ok(entries) = fs::read_dir(custom_dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if !is_sound_file(&path) {
-                    continue;
-                }
-                sounds.push(SoundInfo {
-                    name: file_name_or_default(&path),
-                    path: path.to_string_lossy().to_string(),
-                    custom: true,
-                    location: Some("Custom".to_string()),
-                });
-            }
-        }
+    #[test]
+    fn config_watch_ignores_own_saves_and_picks_up_external_edits() {
+        let settings = AppSettings {
+            watch_config_file: true,
+            ..AppSettings::default()
+        };
+        let clock = ManualClock::new();
+        let (dir, state) = temp_state("config-watch", &settings, Some(clock.clone()));
+        assert!(state.poll_external_config().is_none());
+
+        clock.advance(CONFIG_WATCH_INTERVAL_MS);
+        lock(&state.settings).polling_interval = 2500;
+        state.save_settings();
+        assert!(state.poll_external_config().is_none());
+
+        let mut external: serde_json::Value =
+            serde_json::from_slice(&fs::read(&state.config_path).unwrap()).unwrap();
+        external["lowBatteryThreshold"] = serde_json::json!(45);
+        fs::write(
+            &state.config_path,
+            serde_json::to_vec_pretty(&external).unwrap(),
+        )
+        .unwrap();
+        assert!(
+            state.poll_external_config().is_none(),
+            "polls are throttled"
+        );
+
+        clock.advance(CONFIG_WATCH_INTERVAL_MS);
+        let reloaded = state.poll_external_config().unwrap().unwrap();
+        assert_eq!(reloaded.low_battery_threshold, 45);
+        assert_eq!(reloaded.polling_interval, 2500);
+
+        clock.advance(CONFIG_WATCH_INTERVAL_MS);
+        assert!(state.poll_external_config().is_none());
+
+        fs::write(&state.config_path, b"{ \"pollingInterval\": ").unwrap();
+        clock.advance(CONFIG_WATCH_INTERVAL_MS);
+        assert!(state.poll_external_config().unwrap().is_err());
+        clock.advance(CONFIG_WATCH_INTERVAL_MS);
+        assert!(state.poll_external_config().is_none());
+
+        let _ = fs::remove_dir_all(dir);
     }
 
-    sounds
-}
+    #[test]
+    fn outage_simulation_flags_events_and_clears_latched_state() {
+        let settings = AppSettings::default();
+        let (dir, state) = temp_state("simulation", &settings, None);
+        let real = fabricated_status(AlertKind::UpsConnected, &settings);
+        *lock(&state.last_status) = Some(real.clone());
+
+        assert!(state.begin_outage_simulation());
+        assert!(!state.begin_outage_simulation());
+        with_event_origin(EventOrigin::Simulation, || {
+            state.log_event("ac_fault", Severity::Warning, "On battery");
+            state.schedule_shutdown(1, "battery-critical", false);
+        });
+        // Other threads keep logging and scheduling for real meanwhile.
+        state.log_event("app_started", Severity::Info, "Real");
+        assert!(!state.clear_scheduled_shutdown(Some(EventOrigin::Real)));
+        assert!(state.clear_scheduled_shutdown(Some(EventOrigin::Simulation)));
+        state.schedule_shutdown(5, "manual-trigger", true);
+        assert!(!state.clear_scheduled_shutdown(Some(EventOrigin::Simulation)));
+        assert!(lock(&state.scheduled_shutdown).is_some());
+        let mut real_later = real.clone();
+        real_later.battery_percent = 97;
+        state.update_simulation_baseline(real_later);
+        *lock(&state.is_on_battery) = true;
+        *lock(&state.was_battery_low) = true;
+        *lock(&state.was_battery_critical) = true;
+        *lock(&state.battery_start_ms) = Some(1);
+        *lock(&state.last_status) = Some(simulated_status(&settings, 12, true));
+        assert!(state.cancel_outage_simulation());
+        assert!(state.simulation_cancelled());
+
+        let simulation = state.end_outage_simulation().unwrap();
+        assert_eq!(simulation.timeline.len(), 1);
+        assert_eq!(simulation.timeline[0].key, "ac_fault");
+        assert!(!state.simulation_active());
+        assert!(!*lock(&state.is_on_battery));
+        assert!(!*lock(&state.was_battery_low));
+        assert!(!*lock(&state.was_battery_critical));
+        assert!(lock(&state.battery_start_ms).is_none());
+        assert_eq!(
+            lock(&state.last_status).as_ref().unwrap().battery_percent,
+            97
+        );
 
-fn is_sound_file(path: &Path) -> bool {
-    path.extension()
-        .and_then(|ext| ext.to_str())
-        .map(|ext| {
-            let ext = ext.to_ascii_lowercase();
-            ext == "wav" || ext == "mp3"
-        })
-        .unwrap_or(false)
-}
+        state.log_event("ac_fault", Severity::Warning, "On battery");
+        let events = lock(&state.events).clone();
+        let keys: Vec<&str> = events.iter().map(|event| event.key.as_str()).collect();
+        assert_eq!(keys, vec!["ac_fault", "app_started", "ac_fault_simulated"]);
+        assert_eq!(events[2].name, "AC Fault (simulated)");
 
-fn file_name_or_default(path: &Path) -> String {
-    path.file_name()
-        .and_then(|value| value.to_str())
-        .map(|value| value.to_string())
-        .unwrap_or_else(|| "unknown.wav".to_string())
-}
+        assert_eq!(simulated_percent(80, 2.5, 10), 55);
+        assert_eq!(simulated_percent(10, 5.0, 10), 0);
+        let low = simulated_status(&settings, settings.low_battery_threshold, true);
+        assert!(low.status.utility_fail && low.status.battery_low);
+        assert!(!simulated_status(&settings, 50, false).status.utility_fail);
 
-#[tauri::command]
-fn get_sound_config(state: State<'_, SharedState>) -> SoundConfig {
-    let settings = lock(&state.settings).clone();
-    SoundConfig {
-        repeat_config: SoundRepeatConfig {
-            ac_fault: settings.alerts.ac_fault.sound_repeats,
-            battery_low: settings.alerts.battery_low.sound_repeats,
-            critical: settings.alerts.battery_critical.sound_repeats,
-            default: 3,
-        },
-        repeat_delay: 2500,
-        custom_sounds_path: settings.custom_sounds_path,
-        sounds: SoundFiles {
-            ac_fault: "alert-ac-fault.wav".to_string(),
-            battery_low: "alert-battery-low.wav".to_string(),
-            critical: "alert-critical.wav".to_string(),
-        },
+        let _ = fs::remove_dir_all(dir);
     }
-}
 
-#[tauri::command]
-fn set_sound_config(state: State<'_, SharedState>, config: SoundConfigPatch) -> bool {
-    let mut settings = lock(&state.settings);
+    #[test]
+    fn shutdown_phase_follows_schedule_confirmation_and_execution() {
+        let clock = ManualClock::new();
+        let (dir, state) = temp_state(
+            "shutdown-phase",
+            &AppSettings::default(),
+            Some(clock.clone()),
+        );
+        assert_eq!(state.shutdown_phase_state().phase, ShutdownPhase::Idle);
+        assert!(state.update_shutdown_phase().is_none());
+
+        lock(&state.deferred_shutdown_kinds).push(AlertKind::AcFault);
+        let grace = state.update_shutdown_phase().unwrap();
+        assert_eq!(grace.phase, ShutdownPhase::GracePeriod);
+        assert_eq!(grace.reason.as_deref(), Some("ac_fault"));
+        lock(&state.deferred_shutdown_kinds).clear();
+
+        let schedule = state.schedule_shutdown(2, "ac-fault", false).unwrap();
+        let scheduled = state.update_shutdown_phase().unwrap();
+        assert_eq!(scheduled.phase, ShutdownPhase::Scheduled);
+        assert_eq!(scheduled.deadline, Some(iso_from_millis(schedule.at_ms)));
+        assert!(state.update_shutdown_phase().is_none());
+
+        clock.advance(2 * 60_000);
+        *lock(&state.shutdown_confirmation_deadline_ms) = Some(state.now_ms() + 30_000);
+        assert_eq!(
+            state.shutdown_phase_state().phase,
+            ShutdownPhase::AwaitingConfirmation
+        );
+        *lock(&state.shutdown_confirmation_deadline_ms) = Some(0);
+        assert_eq!(state.shutdown_phase_state().phase, ShutdownPhase::Scheduled);
+
+        state.clear_scheduled_shutdown(None);
+        state
+            .shutdown_executing_ms
+            .store(state.now_ms(), Ordering::Relaxed);
+        assert_eq!(
+            state.update_shutdown_phase().unwrap().phase,
+            ShutdownPhase::Executing
+        );
+        clock.advance(SHUTDOWN_EXECUTING_HOLD_MS);
+        assert_eq!(
+            state.update_shutdown_phase().unwrap().phase,
+            ShutdownPhase::Idle
+        );
 
-    if let Some(repeat_config) = config.repeat_config {
-        if let Some(value) = repeat_config.default {
-            let default_value = clamp_u64(value, 1, 30, settings.alerts.ac_fault.sound_repeats);
-            settings.alerts.ac_fault.sound_repeats = default_value;
-            settings.alerts.battery_low.sound_repeats = default_value;
-            settings.alerts.battery_critical.sound_repeats = default_value;
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn status_bits_are_anchored_by_shape_not_position() {
+        let format = FrameFormat::default();
+        let parse = |frame: &str, format: &FrameFormat| match parse_ups_string(frame, format) {
+            Some(DecodedPacket::Status(status)) => status,
+            _ => panic!("expected status for {}", frame),
+        };
+
+        let extra = parse(
+            "(000.0 140.0 219.0 023 60.1 24.4 32.0 050 10001001 047",
+            &format,
+        );
+        assert!(extra.status.utility_fail);
+        assert_eq!(extra.input_voltage, 0.0);
+        assert_eq!(extra.temperature, 32.0);
+        assert_eq!(extra.device_runtime, Some(47));
+
+        let missing = parse("(000.0 140.0 219.0 023 60.1 24.4 10001001", &format);
+        assert!(missing.status.utility_fail);
+        assert_eq!(missing.battery_voltage, 24.4);
+        assert_eq!(missing.temperature, 0.0);
+
+        let short_bits = FrameFormat {
+            status_bits_count: 4,
+            ..FrameFormat::default()
+        };
+        let four = parse("(000.0 140.0 219.0 023 60.1 24.4 32.0 1100", &short_bits);
+        assert!(four.status.utility_fail && four.status.battery_low);
+        assert!(!four.status.beeper_on);
+
+        let pinned = FrameFormat {
+            status_bits_field: Some(7),
+            status_bits_count: 5,
+            ..FrameFormat::default()
+        };
+        let odd = parse(
+            "(000.0 140.0 219.0 023 60.1 24.4 32.0 10001 01000000",
+            &pinned,
+        );
+        assert_eq!(odd.status.raw, "10001");
+        assert!(odd.status.utility_fail);
+        // The pinned token must be all bits and exactly status_bits_count long.
+        for bad in ["1000", "100011", "10a01"] {
+            let frame = format!("(000.0 140.0 219.0 023 60.1 24.4 32.0 {} 01000000", bad);
+            assert!(parse_ups_string(&frame, &pinned).is_none(), "{}", bad);
         }
 
-        if let Some(value) = repeat_config.ac_fault {
-            settings.alerts.ac_fault.sound_repeats =
-                clamp_u64(value, 1, 30, settings.alerts.ac_fault.sound_repeats);
+        assert!(parse_ups_string("(000.0 140.0 219.0 023 60.1", &format).is_none());
+        assert!(parse_ups_string("(218.5 10001001", &format).is_none());
+
+        let settings = AppSettings {
+            status_bits_count: 0,
+            status_bits_field: Some(2),
+            ..AppSettings::default()
         }
-        if let Some(value) = repeat_config.battery_low {
-            settings.alerts.battery_low.sound_repeats =
-                clamp_u64(value, 1, 30, settings.alerts.battery_low.sound_repeats);
+        .normalize();
+        assert_eq!(settings.status_bits_count, 8);
+        assert_eq!(settings.status_bits_field, None);
+    }
+
+    #[test]
+    fn alert_display_defaults_to_primary() {
+        let blank = AppSettings {
+            alert_display: "  ".to_string(),
+            ..AppSettings::default()
         }
-        if let Some(value) = repeat_config.critical {
-            settings.alerts.battery_critical.sound_repeats =
-                clamp_u64(value, 1, 30, settings.alerts.battery_critical.sound_repeats);
+        .normalize();
+        assert_eq!(blank.alert_display, "primary");
+
+        let named = AppSettings {
+            alert_display: " \\\\.\\DISPLAY2 ".to_string(),
+            ..AppSettings::default()
         }
+        .normalize();
+        assert_eq!(named.alert_display, "\\\\.\\DISPLAY2");
     }
 
-    if let Some(custom_path) = config.custom_sounds_path {
-        settings.custom_sounds_path = custom_path;
+    #[test]
+    fn visual_alerts_default_off_and_clamp_flash_count() {
+        let mut raw = serde_json::to_value(AppSettings::default()).unwrap();
+        raw.as_object_mut().unwrap().remove("visualAlerts");
+        let legacy: AppSettings = serde_json::from_value(raw).unwrap();
+        assert!(!legacy.visual_alerts.enabled);
+        assert!(!legacy.visual_alerts.persist_until_ack);
+
+        let settings = AppSettings {
+            visual_alerts: VisualAlertSettings {
+                flash_count: 500,
+                ..VisualAlertSettings::default()
+            },
+            ..AppSettings::default()
+        }
+        .normalize();
+        assert_eq!(settings.visual_alerts.flash_count, 50);
+        let settings = AppSettings {
+            visual_alerts: VisualAlertSettings {
+                flash_count: 0,
+                ..VisualAlertSettings::default()
+            },
+            ..AppSettings::default()
+        }
+        .normalize();
+        assert_eq!(settings.visual_alerts.flash_count, 5);
     }
 
-    if let Some(delay_value) = config.repeat_delay {
-        let _normalized_delay_ms = clamp_u64(delay_value, 250, 10_000, 2_500);
-    }
+    #[test]
+    fn projects_empty_and_shutdown_times_from_observed_drain() {
+        let settings = AppSettings::default();
+        let clock = ManualClock::new();
+        let (dir, state) = temp_state("drain-projection", &settings, Some(clock.clone()));
+        let state = Arc::new(state);
+        let feed = |percent: u64, was_on_battery: bool| {
+            let mut status = fabricated_status(AlertKind::AcFault, &settings);
+            status.battery_percent = percent;
+            apply_percent_projections(&state, &mut status, true, was_on_battery, Some(20));
+            clock.advance(15_000);
+            status
+        };
 
-    drop(settings);
-    state.save_settings();
-    true
-}
+        let first = feed(80, false);
+        assert_eq!(first.projected_empty_at, None);
+        let mut last = first;
+        // One percent every 15 s, i.e. four percent per minute.
+        for step in 1..=5 {
+            last = feed(80 - step, true);
+        }
+        let now = state.now_ms() - 15_000;
+        let empty_at =
+            DateTime::parse_from_rfc3339(last.projected_empty_at.as_deref().unwrap()).unwrap();
+        let shutdown_at =
+            DateTime::parse_from_rfc3339(last.projected_shutdown_at.as_deref().unwrap()).unwrap();
+        let minutes_from_now =
+            |at: DateTime<chrono::FixedOffset>| (at.timestamp_millis() as u64 - now) / 60_000;
+        assert_eq!(minutes_from_now(empty_at), 18);
+        assert_eq!(minutes_from_now(shutdown_at), 13);
+        let _ = fs::remove_dir_all(dir);
+    }
 
-#[tauri::command]
-fn set_custom_sounds_path(state: State<'_, SharedState>, sound_path: Option<String>) -> bool {
-    lock(&state.settings).custom_sounds_path = sound_path;
-    state.save_settings();
-    true
-}
+    #[test]
+    fn reports_audio_loss_once_after_repeated_failures() {
+        let (dir, state) = temp_state("audio-output", &AppSettings::default(), None);
+        for _ in 1..AUDIO_UNAVAILABLE_AFTER_FAILURES {
+            assert_eq!(state.record_audio_output(false), None);
+        }
+        assert!(!state.audio_unavailable.load(Ordering::Relaxed));
+        assert_eq!(state.record_audio_output(false), Some(false));
+        assert_eq!(state.record_audio_output(false), None);
+        assert!(state.audio_unavailable.load(Ordering::Relaxed));
+
+        assert_eq!(state.record_audio_output(true), Some(true));
+        assert_eq!(state.record_audio_output(true), None);
+        // A single failure after recovery is not enough to warn again.
+        assert_eq!(state.record_audio_output(false), None);
+        let _ = fs::remove_dir_all(dir);
+    }
 
-#[cfg(target_os = "windows")]
-fn apply_rounded_corners(window: &tauri::WebviewWindow) {
-    use windows::Win32::Foundation::HWND;
-    // Tauri v2 exposes hwnd() directly on Windows
-    if let Ok(hwnd_raw) = window.hwnd() {
-        let hwnd = HWND(hwnd_raw.0 as *mut _);
-        let preference = DWM_WINDOW_CORNER_PREFERENCE(2); // DWMWCP_ROUND
-        unsafe {
-            let _ = DwmSetWindowAttribute(
-                hwnd,
-                DWMWA_WINDOW_CORNER_PREFERENCE,
-                &preference as *const _ as *const _,
-                std::mem::size_of::<DWM_WINDOW_CORNER_PREFERENCE>() as u32,
-            );
+    #[test]
+    fn raw_packet_log_keeps_the_latest_frames_while_capturing() {
+        let (dir, state) = temp_state("raw-packets", &AppSettings::default(), None);
+        let frame = |index: usize| RawFrameInfo {
+            ascii: format!("({:03}", index),
+            parsed: index % 2 == 0,
+            received_at: now_iso(),
+        };
+        for index in 0..RAW_PACKET_LOG_CAPACITY + 5 {
+            state.record_raw_frame(frame(index), true);
         }
+        let log = lock(&state.raw_packet_log);
+        assert_eq!(log.len(), RAW_PACKET_LOG_CAPACITY);
+        assert_eq!(log.front().unwrap().ascii, "(005");
+        assert_eq!(
+            log.back().unwrap().ascii,
+            format!("({:03}", RAW_PACKET_LOG_CAPACITY + 4)
+        );
+        drop(log);
+
+        state.record_raw_frame(frame(0), false);
+        assert!(lock(&state.raw_packet_log).is_empty());
+        assert_eq!(lock(&state.last_raw_frame).as_ref().unwrap().ascii, "(000");
+        let _ = fs::remove_dir_all(dir);
     }
-}
 
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    tauri::Builder::default()
-        .plugin(tauri_plugin_dialog::init())
-        .plugin(tauri_plugin_notification::init())
-        .setup(|app| {
-            let state = Arc::new(AppState::new(&app.handle().clone()));
-            let start_minimized = lock(&state.settings).start_minimized;
-            start_ups_monitor(app.handle().clone(), state.clone());
-            app.manage(state);
+    #[test]
+    fn classifies_permission_errors_and_builds_udev_rule() {
+        assert!(is_permission_error(
+            "Failed to open a device with path '/dev/hidraw3': Permission denied"
+        ));
+        assert!(is_permission_error(
+            "CreateFile: (0x00000005) Access is denied."
+        ));
+        assert!(is_permission_error(
+            "hidapi error: Operation not permitted (os error 1)"
+        ));
+        assert!(!is_permission_error(
+            "Failed to open a device with path '/dev/hidraw3': No such device"
+        ));
 
-            if cfg!(debug_assertions) {
-                app.handle().plugin(
-                    tauri_plugin_log::Builder::default()
-                        .level(log::LevelFilter::Info)
-                        .build(),
-                )?;
-            }
+        assert_eq!(
+            udev_rule(0x0925, 0x1234),
+            r#"SUBSYSTEM=="hidraw", ATTRS{idVendor}=="0925", ATTRS{idProduct}=="1234", MODE="0660", TAG+="uaccess""#
+        );
+        let guidance = setup_guidance(
+            Some("/dev/hidraw3".to_string()),
+            0x0925,
+            0x1234,
+            Some("denied".to_string()),
+        );
+        assert!(guidance.permission_denied);
+        assert_eq!(guidance.vendor_id, "0925");
+        assert!(!guidance.steps.is_empty());
+        assert_eq!(guidance.udev_rule.is_some(), cfg!(target_os = "linux"));
+    }
 
-            #[cfg(target_os = "windows")]
-            if let Some(window) = app.get_webview_window("main") {
-                let _ = window.set_decorations(false);
-                let _ = window.set_shadow(false);
-                apply_rounded_corners(&window);
-            }
+    #[test]
+    fn refused_devices_are_not_reopened_until_the_backoff_ends() {
+        let clock = ManualClock::new();
+        let (dir, state) = temp_state(
+            "permission-backoff",
+            &AppSettings::default(),
+            Some(clock.clone()),
+        );
+        assert!(!state.device_open_deferred("/dev/hidraw3"));
+        state.defer_device_open("/dev/hidraw3");
+        assert!(state.device_open_deferred("/dev/hidraw3"));
+        assert!(!state.device_open_deferred("/dev/hidraw4"));
+        clock.advance(HID_PERMISSION_RETRY_MS - 1);
+        assert!(state.device_open_deferred("/dev/hidraw3"));
+        clock.advance(1);
+        assert!(!state.device_open_deferred("/dev/hidraw3"));
+        let _ = fs::remove_dir_all(dir);
+    }
 
-            if start_minimized {
-                if let Some(window) = app.get_webview_window("main") {
-                    let _ = window.set_skip_taskbar(true);
-                    let _ = window.hide();
-                }
-            } else {
-                // Window starts hidden (visible=false in config).
-                // Set flag so main_window_ready (called from JS) will show it
-                // once the frontend has fully rendered, avoiding white flash.
-                if let Some(st) = app.try_state::<SharedState>() {
-                    st.pending_show_main_window.store(true, Ordering::Relaxed);
-                }
-            }
+    #[test]
+    fn normalizes_shutdown_working_directory_and_environment() {
+        let (dir, _state) = temp_state("shutdown-cwd", &AppSettings::default(), None);
+        let mut settings = AppSettings::default();
+        settings.shutdown_pc.working_directory = Some(format!("  {}  ", dir.display()));
+        settings.shutdown_pc.environment = BTreeMap::from([
+            (" BACKUP_TARGET ".to_string(), "D:\\backup".to_string()),
+            ("".to_string(), "ignored".to_string()),
+            ("A=B".to_string(), "ignored".to_string()),
+        ]);
+        let normalized = settings.clone().normalize();
+        assert_eq!(
+            normalized.shutdown_pc.working_directory,
+            Some(dir.display().to_string())
+        );
+        assert_eq!(
+            normalized.shutdown_pc.environment,
+            BTreeMap::from([("BACKUP_TARGET".to_string(), "D:\\backup".to_string())])
+        );
 
-            #[cfg(target_os = "windows")]
-            {
-                let tray_show = MenuItemBuilder::with_id("tray_show", "Mostrar UPS Monitor")
-                    .build(app)?;
-                let tray_quit = MenuItemBuilder::with_id("tray_quit", "Salir").build(app)?;
-                let tray_menu = MenuBuilder::new(app)
-                    .items(&[&tray_show, &tray_quit])
-                    .build()?;
-
-                let mut tray_builder = TrayIconBuilder::with_id("main-tray")
-                    .menu(&tray_menu)
-                    .tooltip("UPS Monitor")
-                    .show_menu_on_left_click(false)
-                    .on_menu_event(|app, event| match event.id().as_ref() {
-                        "tray_show" => show_main_window(app),
-                        "tray_quit" => request_app_exit(app),
-                        _ => {}
-                    })
-                    .on_tray_icon_event(|tray, event| {
-                        if let TrayIconEvent::Click {
-                            button: MouseButton::Left,
-                            button_state: MouseButtonState::Up,
-                            ..
-                        } = event
-                        {
-                            show_main_window(&tray.app_handle());
-                        }
-                    });
+        assert_eq!(
+            shutdown_working_directory(&normalized.shutdown_pc),
+            Some(dir.display().to_string().as_str())
+        );
 
-                if let Some(default_icon) = app.default_window_icon() {
-                    tray_builder = tray_builder.icon(default_icon.clone());
-                }
+        // A missing directory is kept, reported at startup and only skipped
+        // when the command runs.
+        let missing = dir.join("missing").display().to_string();
+        settings.shutdown_pc.working_directory = Some(missing.clone());
+        let normalized = settings.clone().normalize();
+        assert_eq!(
+            normalized.shutdown_pc.working_directory,
+            Some(missing.clone())
+        );
+        assert_eq!(shutdown_working_directory(&normalized.shutdown_pc), None);
+        let (missing_dir, state) = temp_state("shutdown-cwd-missing", &settings, None);
+        assert!(state
+            .settings_notices
+            .iter()
+            .any(|notice| notice.contains(&missing)));
+        assert!(lock(&state.settings)
+            .missing_shutdown_working_directory()
+            .is_some());
+        let _ = fs::remove_dir_all(missing_dir);
+        let _ = fs::remove_dir_all(dir);
+    }
 
-                let _ = tray_builder.build(app)?;
-            }
+    #[test]
+    fn finds_conflicting_ups_software_in_process_lists() {
+        let tasklist = parse_tasklist_csv(concat!(
+            "\"System Idle Process\",\"0\",\"N/A\"\r\n",
+            "\"UPSilon2000.exe\",\"4120\",\"UPSilonSvc\"\r\n",
+            "\"svchost.exe\",\"900\",\"Dnscache,LanmanWorkstation\"\r\n",
+            "\"UPS Monitor.exe\",\"5000\",\"N/A\"\r\n",
+        ));
+        assert_eq!(tasklist.len(), 4);
+        assert_eq!(tasklist[2].services, vec!["Dnscache", "LanmanWorkstation"]);
+        let conflicts = find_conflicting_software(&tasklist);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].product, "UPSilon 2000");
+        assert_eq!(conflicts[0].pid, Some(4120));
+        assert_eq!(conflicts[0].services, vec!["UPSilonSvc"]);
+
+        let ps = parse_ps_output(
+            "    1 systemd\n  812 /usr/lib/nut/usbhid-ups\n  830 upsd\n 2201 ups-monitor\n",
+        );
+        let conflicts = find_conflicting_software(&ps);
+        assert_eq!(
+            conflicts
+                .iter()
+                .map(|conflict| conflict.product.as_str())
+                .collect::<Vec<_>>(),
+            vec!["NUT usbhid-ups", "NUT upsd"]
+        );
+        assert_eq!(conflict_products(&conflicts), "NUT usbhid-ups, NUT upsd");
+    }
 
-            Ok(())
-        })
-        .invoke_handler(tauri::generate_handler![
-            get_settings,
-            save_settings,
-            get_ups_status,
-            get_ups_info,
-            test_notification,
-            minimize_main_window,
-            toggle_maximize_main_window,
-            close_main_window,
-            main_window_ready,
-            cancel_shutdown,
-            trigger_shutdown,
-            simulate_shutdown_flow,
-            get_battery_time,
-            get_events,
-            delete_events,
-            get_data_history,
-            delete_data_history,
-            update_history_interval,
-            play_sound,
-            stop_sound,
-            test_urgent_alert,
-            get_available_sounds,
-            get_sound_config,
-            set_sound_config,
-            set_custom_sounds_path
-        ])
-        .build(tauri::generate_context!())
-        .expect("error while building tauri application")
-        .run(|app, event| {
-            if let tauri::RunEvent::ExitRequested { api, .. } = event {
-                if let Some(state) = app.try_state::<SharedState>() {
-                    if !state.allow_process_exit.load(Ordering::Relaxed) {
-                        api.prevent_exit();
-                    }
-                }
-            }
-        });
+    #[test]
+    fn report_coverage_starts_at_the_oldest_entry_of_a_full_store() {
+        let from = parse_rfc3339_utc("2026-09-01T00:00:00Z").unwrap();
+        let to = parse_rfc3339_utc("2026-10-01T00:00:00Z").unwrap();
+        let oldest = from + ChronoDuration::days(10);
+        let entry = |at: DateTime<Utc>| DataHistoryEntry {
+            id: 0,
+            time: at.to_rfc3339(),
+            input_voltage: 220.0,
+            output_voltage: 220.0,
+            frequency: 60.0,
+            load_percent: 20,
+            battery_voltage: 26.4,
+            battery_percent: 100,
+            temperature: 30.0,
+        };
+        let mut data = vec![entry(oldest)];
+        assert_eq!(history_covered_from(&[], &data, from, to), from);
+
+        data.resize(MAX_DATA_POINTS, entry(oldest + ChronoDuration::days(1)));
+        assert_eq!(history_covered_from(&[], &data, from, to), oldest);
+        data[0] = entry(from - ChronoDuration::days(1));
+        assert_eq!(history_covered_from(&[], &data, from, to), from);
+    }
 }
@@ -3,7 +3,7 @@ import { UPSData, TabId, PowerMode } from './types/ups';
 import { Sidebar } from './components/Sidebar';
 import { AlertTriangle, Minus, X } from 'lucide-react';
 import { ViewErrorBoundary } from './components/ViewErrorBoundary';
-import type { DesktopAPI, UrgentAlertPayload } from './types/desktop';
+import type { DesktopAPI, ShutdownImminentPayload, UrgentAlertPayload } from './types/desktop';
 import './styles/global.css';
 
 const Dashboard = lazy(() => import('./components/Dashboard').then((m) => ({ default: m.Dashboard })));
@@ -68,6 +68,7 @@ const App: React.FC = () => {
   const [error, setError] = useState<string | null>(null);
   const [loading, setLoading] = useState(true);
   const [urgentAlerts, setUrgentAlerts] = useState<ActiveUrgentAlert[]>([]);
+  const [imminentShutdown, setImminentShutdown] = useState<ShutdownImminentPayload | null>(null);
 
   const [curveHistory, setCurveHistory] = useState<CurveDataPoint[]>([]);
   const [dashboardHistory, setDashboardHistory] = useState<DashboardVoltagePoint[]>([]);
@@ -75,6 +76,11 @@ const App: React.FC = () => {
   const lastCurveTimestamp = useRef<string>('');
   const lastDashboardTimestamp = useRef<string>('');
 
+  const acknowledgeUrgentAlert = useCallback((alertId: string) => {
+    setUrgentAlerts((prev) => prev.filter((item) => item.id !== alertId));
+    void window.desktopAPI?.acknowledgeAlert();
+  }, []);
+
   const getPowerMode = useCallback((): PowerMode => {
     if (!isConnected) return 'offline';
     if (upsData?.status?.utilityFail) return 'battery';
@@ -141,6 +147,8 @@ const App: React.FC = () => {
     }
 
     void desktopAPI.mainWindowReady?.();
+    // The dashboard wants every packet; other windows fall back to the throttled rate.
+    void desktopAPI.subscribeUPSData(0);
 
     const unsubscribers: Array<() => void> = [];
 
@@ -162,10 +170,10 @@ const App: React.FC = () => {
       setLoading(false);
     }));
 
-    unsubscribers.push(desktopAPI.onUPSDisconnected(() => {
+    unsubscribers.push(desktopAPI.onUPSDisconnected((connection) => {
       setIsConnected(false);
       setUpsData(null);
-      setError('UPS desconectado');
+      setError(connection?.lastError || 'UPS desconectado');
       setLoading(false);
     }));
 
@@ -173,6 +181,14 @@ const App: React.FC = () => {
       setCurrentTab('dashboard');
     }));
 
+    unsubscribers.push(desktopAPI.onShutdownImminent(setImminentShutdown));
+    unsubscribers.push(desktopAPI.onShutdownCancelled(() => setImminentShutdown(null)));
+    unsubscribers.push(desktopAPI.onShutdownPhaseChanged((phase) => {
+      if (phase.phase !== 'awaiting-confirmation') {
+        setImminentShutdown(null);
+      }
+    }));
+
     unsubscribers.push(
       desktopAPI.onUrgentAlert((payload) => {
         const alertId = `${Date.now()}-${Math.random().toString(36).slice(2, 8)}`;
@@ -197,15 +213,27 @@ const App: React.FC = () => {
 
     const fetchInitialData = async () => {
       try {
-        const status = await desktopAPI.getUPSStatus();
-        if (status) {
+        const [status, connection] = await Promise.all([
+          desktopAPI.getUPSStatus(),
+          desktopAPI.getConnectionState?.() ?? Promise.resolve(null),
+        ]);
+        const connected = connection ? connection.connected : Boolean(status && !status.stale);
+        if (status?.stale) {
+          // Se muestra atenuada hasta que llegue el primer paquete real.
           setUpsData(status);
+          setIsConnected(connected);
+          setError(null);
+        } else if (status && connected) {
+          setUpsData(status);
+          setIsConnected(true);
+          setError(null);
+        } else if (connected) {
           setIsConnected(true);
           setError(null);
         } else {
           setIsConnected(false);
           setUpsData(null);
-          setError('UPS desconectado');
+          setError(connection?.lastError || 'UPS desconectado');
         }
       } catch {
         setIsConnected(false);
@@ -336,6 +364,30 @@ const App: React.FC = () => {
           </div>
         )}
 
+        {imminentShutdown && (
+          <div className="flex items-center gap-3 px-4 py-2 text-xs bg-red-500/15 text-red-200 border-b border-red-500/25">
+            <AlertTriangle className="h-4 w-4 shrink-0" />
+            <span className="flex-1">
+              Apagado de seguridad ({imminentShutdown.reason}) a las{' '}
+              {new Date(imminentShutdown.deadline).toLocaleTimeString('es-ES')}
+              {imminentShutdown.estimatedRuntimeMinutes !== null &&
+                ` · autonomia ~${imminentShutdown.estimatedRuntimeMinutes} min`}
+            </span>
+            <button
+              onClick={() => void window.desktopAPI?.confirmShutdownNow()}
+              className="rounded px-2 py-0.5 bg-red-500/25 text-white hover:bg-red-500/40"
+            >
+              Apagar ahora
+            </button>
+            <button
+              onClick={() => void window.desktopAPI?.cancelShutdown()}
+              className="rounded px-2 py-0.5 text-white/80 hover:bg-white/10 hover:text-white"
+            >
+              Cancelar apagado
+            </button>
+          </div>
+        )}
+
         {urgentAlerts.length > 0 && (
           <div className="pointer-events-none absolute right-4 top-12 z-50 flex max-w-md flex-col gap-2">
             {urgentAlerts.map((alert) => {
@@ -349,7 +401,7 @@ const App: React.FC = () => {
               return (
                 <div
                   key={alert.id}
-                  className={`rounded-xl border px-4 py-3 shadow-xl backdrop-blur-sm ${borderClass}`}
+                  className={`pointer-events-auto rounded-xl border px-4 py-3 shadow-xl backdrop-blur-sm ${borderClass}`}
                 >
                   <div className="flex items-start gap-3">
                     <AlertTriangle className="mt-0.5 h-4 w-4 text-white/90" />
@@ -357,6 +409,12 @@ const App: React.FC = () => {
                       <p className="text-sm font-semibold text-white">{alert.title}</p>
                       <p className="mt-1 text-xs text-white/80">{alert.message}</p>
                     </div>
+                    <button
+                      onClick={() => acknowledgeUrgentAlert(alert.id)}
+                      className="rounded px-2 py-0.5 text-xs text-white/80 hover:bg-white/10 hover:text-white"
+                    >
+                      Entendido
+                    </button>
                   </div>
                 </div>
               );
@@ -0,0 +1,58 @@
+import React, { useEffect, useState } from 'react';
+import { AlertTriangle, X } from 'lucide-react';
+import type { UrgentAlertPayload } from '../types/desktop';
+import '../styles/global.css';
+
+export const AlertWindow: React.FC = () => {
+  const [alert, setAlert] = useState<UrgentAlertPayload | null>(null);
+
+  useEffect(() => {
+    const desktopAPI = window.desktopAPI;
+    if (!desktopAPI) return;
+
+    void desktopAPI.getPendingAlert().then((payload) => {
+      if (payload) setAlert(payload);
+    });
+
+    return desktopAPI.onAlertWindowUpdate((payload) => {
+      setAlert(payload);
+    });
+  }, []);
+
+  const acknowledge = () => {
+    void window.desktopAPI?.acknowledgeAlert();
+  };
+
+  const isCritical = alert?.alertType === 'critical';
+  const isBattery = alert?.alertType === 'battery';
+  const borderClass = isCritical
+    ? 'border-red-400/60 bg-red-500/15'
+    : isBattery
+      ? 'border-amber-400/60 bg-amber-500/15'
+      : 'border-cyan-400/60 bg-cyan-500/15';
+
+  return (
+    <div className="h-screen w-screen bg-dark-900 p-2 text-white">
+      <div className={`flex h-full flex-col rounded-xl border px-4 py-3 ${borderClass}`}>
+        <div className="flex items-start gap-3">
+          <AlertTriangle className="mt-0.5 h-5 w-5 shrink-0 text-white/90" />
+          <div className="min-w-0 flex-1">
+            <p className="text-sm font-semibold">{alert?.title || 'Alerta UPS'}</p>
+            <p className="mt-1 text-xs text-white/80">{alert?.message || 'Se detecto un evento del UPS.'}</p>
+          </div>
+          <button onClick={acknowledge} className="rounded p-1 text-white/60 hover:bg-white/10 hover:text-white">
+            <X className="h-4 w-4" />
+          </button>
+        </div>
+        <div className="mt-auto flex justify-end">
+          <button
+            onClick={acknowledge}
+            className="rounded-lg bg-white/10 px-3 py-1.5 text-xs font-medium hover:bg-white/20"
+          >
+            Entendido
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+};
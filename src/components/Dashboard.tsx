@@ -36,7 +36,8 @@ export const Dashboard: React.FC<DashboardProps> = ({
   const outputVoltage = data.outputVoltage || 0;
 
   // Clase para elementos deshabilitados
-  const disabledClass = isDisconnected ? 'opacity-40 pointer-events-none' : '';
+  const isStale = data.stale === true;
+  const disabledClass = isDisconnected || isStale ? 'opacity-40 pointer-events-none' : '';
   
   return (
     <div className={`flex-1 flex flex-col overflow-hidden ${disabledClass}`}>
@@ -45,7 +46,10 @@ export const Dashboard: React.FC<DashboardProps> = ({
         <div className="flex items-center justify-between">
           <div>
             <h1 className="text-2xl font-bold text-white">Dashboard</h1>
-            <p className="text-white/40 text-sm mt-1">UPS {UPS_VA}VA / {UPS_WATTS}W</p>
+            <p className="text-white/40 text-sm mt-1">
+              UPS {UPS_VA}VA / {UPS_WATTS}W
+              {isStale && ` · Ultima lectura guardada (${new Date(data.timestamp).toLocaleString()})`}
+            </p>
           </div>
           <div className={`
             px-4 py-2 rounded-full text-sm font-medium flex items-center gap-2
@@ -238,7 +242,19 @@ export const Dashboard: React.FC<DashboardProps> = ({
                 <div className="flex-1 space-y-3">
                   <div>
                     <p className="text-lg font-semibold text-white">Batería</p>
-                    <p className="text-xs text-white/40">{!isOnBattery ? 'Cargando' : 'Descargando'}</p>
+                    <p className="text-xs text-white/40">
+                      {isOnBattery
+                        ? data.utilityState === 'degraded'
+                          ? 'Descargando · Red degradada'
+                          : data.utilityState === 'lost'
+                            ? 'Descargando · Sin red'
+                            : 'Descargando'
+                        : data.charging || data.chargeState === 'charging'
+                          ? `Cargando${data.chargingProjectionMinutes !== undefined ? ` · ~${data.chargingProjectionMinutes} min` : ''}`
+                          : data.chargeState === 'charged' || (data.batteryPercent || 0) >= 99
+                            ? 'Completa'
+                            : 'En AC'}
+                    </p>
                   </div>
                   <div className="grid grid-cols-2 gap-3">
                     <div>
@@ -246,8 +262,17 @@ export const Dashboard: React.FC<DashboardProps> = ({
                       <p className="text-sm font-medium text-white">{(data.batteryVoltage || 0).toFixed(2)} V</p>
                     </div>
                     <div>
-                      <p className="text-xs text-white/30">Autonomía</p>
-                      <p className="text-sm font-medium text-emerald-400">{data.estimatedRuntime || 0} min</p>
+                      <p className="text-xs text-white/30">{data.runtimePredicted ? 'Autonomía prevista' : 'Autonomía'}</p>
+                      <p className="text-sm font-medium text-emerald-400">
+                        {data.estimatedRuntime !== undefined ? `${data.estimatedRuntime} min` : '—'}
+                      </p>
+                      {data.projectedEmptyAt && (
+                        <p className="text-[11px] text-white/40">
+                          Vacía ~{new Date(data.projectedEmptyAt).toLocaleTimeString('es-ES', { hour: '2-digit', minute: '2-digit' })}
+                          {data.projectedShutdownAt &&
+                            ` · apagado ~${new Date(data.projectedShutdownAt).toLocaleTimeString('es-ES', { hour: '2-digit', minute: '2-digit' })}`}
+                        </p>
+                      )}
                     </div>
                   </div>
                 </div>
@@ -73,7 +73,11 @@ export const DetailsView: React.FC<DetailsViewProps> = ({ data, isDisconnected =
               <DetailRow label="Capacidad" value={data.batteryPercent || 0} unit="%" highlight />
               <DetailRow label="Temperatura" value={(data.temperature || 0).toFixed(1)} unit="°C" />
               {data.estimatedRuntime !== undefined && (
-                <DetailRow label="Autonomía Est." value={data.estimatedRuntime} unit="min" />
+                <DetailRow
+                  label={data.runtimePredicted ? 'Autonomía Prevista' : 'Autonomía Est.'}
+                  value={data.estimatedRuntime}
+                  unit="min"
+                />
               )}
             </div>
           </div>
@@ -8,17 +8,25 @@ import {
   RefreshCw,
   Filter,
   Activity,
-  Clock
+  Clock,
+  CheckCircle
 } from 'lucide-react';
+import type { EventFilter, EventSeverity } from '../types/desktop';
 
 interface EventRecord {
   id: number;
   time: string;
   classification: string;
+  severity?: EventSeverity;
   name: string;
   remarks: string;
+  resolved?: boolean;
+  repeatCount?: number;
+  lastSeen?: string;
 }
 
+type ResolvedFilter = 'all' | 'open' | 'resolved';
+
 interface DataRecord {
   id: number;
   time: string;
@@ -43,6 +51,7 @@ export const HistoryView: React.FC = () => {
   
   // Filters
   const [eventFilter, setEventFilter] = useState('All Events');
+  const [resolvedFilter, setResolvedFilter] = useState<ResolvedFilter>('all');
   const [dateFrom, setDateFrom] = useState('');
   const [dateTo, setDateTo] = useState('');
   const normalizedDateFrom = dateFrom && dateTo && dateFrom > dateTo ? dateTo : dateFrom;
@@ -56,6 +65,7 @@ export const HistoryView: React.FC = () => {
       const parsed = JSON.parse(raw) as {
         activeTab?: TabType;
         eventFilter?: string;
+        resolvedFilter?: ResolvedFilter;
         dateFrom?: string;
         dateTo?: string;
       };
@@ -66,6 +76,9 @@ export const HistoryView: React.FC = () => {
       if (typeof parsed.eventFilter === 'string') {
         setEventFilter(parsed.eventFilter);
       }
+      if (parsed.resolvedFilter === 'open' || parsed.resolvedFilter === 'resolved') {
+        setResolvedFilter(parsed.resolvedFilter);
+      }
       if (typeof parsed.dateFrom === 'string') {
         setDateFrom(parsed.dateFrom);
       }
@@ -84,6 +97,7 @@ export const HistoryView: React.FC = () => {
         JSON.stringify({
           activeTab,
           eventFilter,
+          resolvedFilter,
           dateFrom,
           dateTo,
         }),
@@ -91,14 +105,15 @@ export const HistoryView: React.FC = () => {
     } catch (error) {
       console.error('Error saving history filters:', error);
     }
-  }, [activeTab, eventFilter, dateFrom, dateTo]);
+  }, [activeTab, eventFilter, resolvedFilter, dateFrom, dateTo]);
 
   const loadEvents = useCallback(async () => {
     if (!window.desktopAPI?.getEvents) return;
     setLoading(true);
     try {
-      const filter: Record<string, string> = {};
+      const filter: EventFilter = {};
       if (eventFilter !== 'All Events') filter.classification = eventFilter;
+      if (resolvedFilter !== 'all') filter.resolved = resolvedFilter === 'resolved';
       if (normalizedDateFrom) filter.dateFrom = normalizedDateFrom;
       if (normalizedDateTo) filter.dateTo = normalizedDateTo;
       
@@ -109,7 +124,7 @@ export const HistoryView: React.FC = () => {
     } finally {
       setLoading(false);
     }
-  }, [eventFilter, normalizedDateFrom, normalizedDateTo]);
+  }, [eventFilter, resolvedFilter, normalizedDateFrom, normalizedDateTo]);
 
   const loadDataHistory = useCallback(async () => {
     if (!window.desktopAPI?.getDataHistory) return;
@@ -174,6 +189,22 @@ export const HistoryView: React.FC = () => {
     }
   };
 
+  // Resolves the selection unless every selected event is already resolved.
+  const handleToggleResolved = async () => {
+    if (selectedIds.length === 0) return;
+    const resolved = !events
+      .filter((event) => selectedIds.includes(event.id))
+      .every((event) => event.resolved);
+
+    try {
+      await window.desktopAPI?.markEventsResolved(selectedIds, resolved);
+      loadEvents();
+      setSelectedIds([]);
+    } catch (error) {
+      console.error('Error updating resolved state:', error);
+    }
+  };
+
   const handleDeleteAll = async () => {
     try {
       if (activeTab === 'events') {
@@ -217,11 +248,13 @@ export const HistoryView: React.FC = () => {
     });
   };
 
-  const getClassificationStyle = (classification: string) => {
-    if (classification === 'Critical Event') {
-      return 'bg-red-500/20 text-red-400';
-    }
-    return 'bg-emerald-500/20 text-emerald-400';
+  const getSeverity = (event: EventRecord): EventSeverity =>
+    event.severity ?? (event.classification === 'Critical Event' ? 'warning' : 'info');
+
+  const severityStyles: Record<EventSeverity, { className: string; label: string }> = {
+    critical: { className: 'bg-red-500/20 text-red-400', label: 'Crítico' },
+    warning: { className: 'bg-amber-500/20 text-amber-400', label: 'Advertencia' },
+    info: { className: 'bg-emerald-500/20 text-emerald-400', label: 'Info' },
   };
 
   return (
@@ -300,6 +333,16 @@ export const HistoryView: React.FC = () => {
               <option value="General Event">Eventos generales</option>
               <option value="Critical Event">Eventos críticos</option>
             </select>
+            <select
+              value={resolvedFilter}
+              onChange={(e) => setResolvedFilter(e.target.value as ResolvedFilter)}
+              className="bg-dark-700 border border-white/10 rounded-lg px-3 py-2 text-sm text-white
+                         focus:outline-none focus:border-cyan-500/50"
+            >
+              <option value="all">Todos</option>
+              <option value="open">Abiertos</option>
+              <option value="resolved">Resueltos</option>
+            </select>
           </div>
         )}
         
@@ -331,6 +374,17 @@ export const HistoryView: React.FC = () => {
           </span>
         )}
 
+        {activeTab === 'events' && selectedIds.length > 0 && (
+          <button
+            onClick={handleToggleResolved}
+            className="flex items-center gap-2 px-3 py-2 rounded-lg bg-emerald-500/20 text-emerald-400
+                       hover:bg-emerald-500/30 transition-colors text-sm"
+          >
+            <CheckCircle className="w-4 h-4" />
+            Resolver ({selectedIds.length})
+          </button>
+        )}
+
         {selectedIds.length > 0 && (
           <button
             onClick={handleDelete}
@@ -381,7 +435,8 @@ export const HistoryView: React.FC = () => {
                       key={event.id}
                       className={`grid grid-cols-[40px_180px_140px_1fr_1fr] gap-4 px-4 py-3 border-b border-white/5 
                                  hover:bg-white/5 transition-colors cursor-pointer
-                                 ${selectedIds.includes(event.id) ? 'bg-cyan-500/10' : ''}`}
+                                 ${selectedIds.includes(event.id) ? 'bg-cyan-500/10' : ''}
+                                 ${event.resolved ? 'opacity-50' : ''}`}
                       onClick={() => toggleSelect(event.id)}
                     >
                       <div className="flex items-center">
@@ -395,11 +450,16 @@ export const HistoryView: React.FC = () => {
                       </div>
                       <div className="text-sm text-white/70">{formatDateTime(event.time)}</div>
                       <div>
-                        <span className={`px-2 py-1 rounded-md text-xs font-medium ${getClassificationStyle(event.classification)}`}>
-                          {event.classification === 'Critical Event' ? 'Crítico' : 'General'}
+                        <span className={`px-2 py-1 rounded-md text-xs font-medium ${severityStyles[getSeverity(event)].className}`}>
+                          {severityStyles[getSeverity(event)].label}
                         </span>
                       </div>
-                      <div className="text-sm text-white">{event.name}</div>
+                      <div className="text-sm text-white" title={event.lastSeen ? `Ultima vez: ${formatDateTime(event.lastSeen)}` : undefined}>
+                        {event.name}
+                        {event.repeatCount && event.repeatCount > 1 && (
+                          <span className="ml-2 text-xs text-white/50">(×{event.repeatCount})</span>
+                        )}
+                      </div>
                       <div className="text-sm text-white/50">{event.remarks}</div>
                     </div>
                   ))
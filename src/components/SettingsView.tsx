@@ -21,10 +21,11 @@ import {
   FolderOpen,
   FolderX,
   Music,
-  Square
+  Square,
+  FileText
 } from 'lucide-react';
-import { AppSettings, AlertConfig, defaultAppSettings } from '../types/settings';
-import type { SoundInfo } from '../types/desktop';
+import { AppSettings, AlertChannel, AlertConfig, LogLevel, defaultAppSettings } from '../types/settings';
+import type { ConflictReport, MonitorInfo, ProtectionState, RawFrameInfo, ResourceUsage, SetupGuidance, SoundInfo } from '../types/desktop';
 
 type TabType = 'general' | 'alerts' | 'sounds' | 'shutdown' | 'history' | 'about';
 
@@ -90,6 +91,16 @@ export const SettingsView: React.FC = () => {
   const [availableSounds, setAvailableSounds] = useState<SoundInfo[]>([]);
   const [loadingSounds, setLoadingSounds] = useState(false);
   const [playingSoundType, setPlayingSoundType] = useState<string | null>(null);
+  const [logLines, setLogLines] = useState<string[] | null>(null);
+  const [rawPackets, setRawPackets] = useState<RawFrameInfo[] | null>(null);
+  const [resourceUsage, setResourceUsage] = useState<ResourceUsage | null>(null);
+  const [protection, setProtection] = useState<ProtectionState | null>(null);
+  const [monitors, setMonitors] = useState<MonitorInfo[]>([]);
+  const [audioAvailable, setAudioAvailable] = useState(true);
+  const [setupGuidance, setSetupGuidance] = useState<SetupGuidance | null>(null);
+  const [conflictReport, setConflictReport] = useState<ConflictReport | null>(null);
+  const [checkingConflicts, setCheckingConflicts] = useState(false);
+  const [suspendMinutes, setSuspendMinutes] = useState(60);
 
   const refreshAvailableSounds = useCallback(async () => {
     if (!window.desktopAPI?.getAvailableSounds) return;
@@ -125,45 +136,73 @@ export const SettingsView: React.FC = () => {
     refreshAvailableSounds();
   }, [refreshAvailableSounds]);
 
+  // config.json was changed by another program; its values replace any
+  // unsaved edits.
+  useEffect(() => {
+    if (!window.desktopAPI?.onSettingsReloaded) return;
+    return window.desktopAPI.onSettingsReloaded((reloaded) => {
+      setSettings({ ...defaultAppSettings, ...reloaded } as AppSettings);
+      setSaved(false);
+    });
+  }, []);
+
   const updateSetting = useCallback(<K extends keyof AppSettings>(key: K, value: AppSettings[K]) => {
     setSettings(prev => ({ ...prev, [key]: value }));
     setSaved(false);
   }, []);
 
+  // The backend applies monitor-only suppression at evaluation time, so the
+  // configured alerts and shutdown options are kept as they are. The mode is
+  // applied right away without saving the rest of the pending edits.
   const toggleMonitorOnlyMode = useCallback((enabled: boolean) => {
-    setSettings((prev) => {
-      if (!enabled) {
-        return {
-          ...prev,
-          monitorOnlyMode: false,
-        };
-      }
+    setSettings((prev) => ({
+      ...prev,
+      monitorOnlyMode: enabled,
+    }));
+    void window.desktopAPI?.setMonitorOnly(enabled);
+  }, []);
 
-      return {
-        ...prev,
-        monitorOnlyMode: true,
-        enableNotifications: false,
-        alerts: {
-          acFault: { ...prev.alerts.acFault, playSound: false, showPopup: false },
-          batteryLow: { ...prev.alerts.batteryLow, playSound: false, showPopup: false },
-          batteryCritical: { ...prev.alerts.batteryCritical, playSound: false, showPopup: false },
-        },
-        shutdownPC: {
-          ...prev.shutdownPC,
-          onAcFault: { ...prev.shutdownPC.onAcFault, enabled: false },
-          onBatteryLow: { ...prev.shutdownPC.onBatteryLow, enabled: false },
-          onBatteryCritical: { ...prev.shutdownPC.onBatteryCritical, enabled: false },
-          autoSaveFiles: false,
-          shutdownCommand: '',
-        },
-        upsControl: {
-          ...prev.upsControl,
-          shutdownUpsAfterPC: false,
-        },
-        saveHistory: false,
-      };
-    });
-    setSaved(false);
+  useEffect(() => {
+    void window.desktopAPI?.getMonitors?.().then(setMonitors);
+  }, []);
+
+  useEffect(() => {
+    const api = window.desktopAPI;
+    if (!api?.getSetupGuidance) return;
+    void api.getSetupGuidance().then(setSetupGuidance);
+    return api.onUPSPermissionError(setSetupGuidance);
+  }, []);
+
+  useEffect(() => {
+    const api = window.desktopAPI;
+    if (!api?.isAudioAvailable) return;
+    void api.isAudioAvailable().then(setAudioAvailable);
+    const offUnavailable = api.onAudioUnavailable(() => setAudioAvailable(false));
+    const offRestored = api.onAudioRestored(() => setAudioAvailable(true));
+    return () => {
+      offUnavailable();
+      offRestored();
+    };
+  }, []);
+
+  useEffect(() => {
+    if (!window.desktopAPI?.getProtectionState) return;
+    void window.desktopAPI.getProtectionState().then(setProtection);
+    return window.desktopAPI.onProtectionStateChanged(setProtection);
+  }, []);
+
+  const suspendProtection = useCallback(async () => {
+    if (!window.desktopAPI?.suspendProtection) return;
+    try {
+      setProtection(await window.desktopAPI.suspendProtection(suspendMinutes));
+    } catch (error) {
+      console.error('Error suspending protection:', error);
+    }
+  }, [suspendMinutes]);
+
+  const resumeProtection = useCallback(async () => {
+    const next = await window.desktopAPI?.resumeProtection();
+    if (next) setProtection(next);
   }, []);
 
   const saveSettings = useCallback(async () => {
@@ -197,7 +236,7 @@ export const SettingsView: React.FC = () => {
   ];
 
   // Helper para actualizar alertas anidadas
-  const updateAlert = (alertType: keyof typeof settings.alerts, field: keyof AlertConfig, value: boolean | number) => {
+  const updateAlert = (alertType: keyof typeof settings.alerts, field: keyof AlertConfig, value: AlertConfig[keyof AlertConfig]) => {
     setSettings(prev => ({
       ...prev,
       alerts: {
@@ -211,32 +250,50 @@ export const SettingsView: React.FC = () => {
     setSaved(false);
   };
 
+  const hasChannel = (alertType: keyof typeof settings.alerts, channel: AlertChannel) =>
+    settings.alerts[alertType].channels.includes(channel);
+
+  // The popup toggle keeps routing the native notification alongside it.
+  const toggleChannels = (alertType: keyof typeof settings.alerts, channels: AlertChannel[], enabled: boolean) => {
+    const current = settings.alerts[alertType].channels.filter((item) => !channels.includes(item));
+    updateAlert(alertType, 'channels', enabled ? [...current, ...channels] : current);
+  };
+
+  const saveSoundsFolders = useCallback(async (paths: string[]) => {
+    updateSetting('customSoundsPaths', paths);
+    if (window.desktopAPI?.setCustomSoundsPaths) {
+      const saved = await window.desktopAPI.setCustomSoundsPaths(paths);
+      updateSetting('customSoundsPaths', saved);
+    }
+    await refreshAvailableSounds();
+  }, [refreshAvailableSounds, updateSetting]);
+
   const selectSoundsFolder = useCallback(async () => {
     if (!window.desktopAPI?.selectFile) return;
     try {
       const selectedPath = await window.desktopAPI.selectFile();
       if (!selectedPath) return;
-      updateSetting('customSoundsPath', selectedPath);
-      if (window.desktopAPI?.setCustomSoundsPath) {
-        await window.desktopAPI.setCustomSoundsPath(selectedPath);
-      }
-      await refreshAvailableSounds();
+      await saveSoundsFolders([...settings.customSoundsPaths, selectedPath]);
     } catch (error) {
       console.error('Error selecting sounds folder:', error);
     }
-  }, [refreshAvailableSounds, updateSetting]);
+  }, [saveSoundsFolders, settings.customSoundsPaths]);
+
+  const removeSoundsFolder = useCallback(async (path: string) => {
+    try {
+      await saveSoundsFolders(settings.customSoundsPaths.filter((item) => item !== path));
+    } catch (error) {
+      console.error('Error removing sounds folder:', error);
+    }
+  }, [saveSoundsFolders, settings.customSoundsPaths]);
 
   const clearSoundsFolder = useCallback(async () => {
-    updateSetting('customSoundsPath', null);
     try {
-      if (window.desktopAPI?.setCustomSoundsPath) {
-        await window.desktopAPI.setCustomSoundsPath(null);
-      }
-      await refreshAvailableSounds();
+      await saveSoundsFolders([]);
     } catch (error) {
       console.error('Error clearing sounds folder:', error);
     }
-  }, [refreshAvailableSounds, updateSetting]);
+  }, [saveSoundsFolders]);
 
   const previewSound = useCallback(async (type: 'acFault' | 'batteryLow' | 'critical') => {
     if (!window.desktopAPI?.playSound) return;
@@ -261,6 +318,63 @@ export const SettingsView: React.FC = () => {
     }
   }, []);
 
+  const flushStorage = useCallback(async () => {
+    if (!window.desktopAPI?.flushStorage) return;
+    try {
+      await window.desktopAPI.flushStorage();
+    } catch (error) {
+      console.error('Error flushing storage:', error);
+    }
+  }, []);
+
+  const loadLogTail = useCallback(async () => {
+    if (!window.desktopAPI?.getLogTail) return;
+    try {
+      setLogLines(await window.desktopAPI.getLogTail(200));
+    } catch (error) {
+      console.error('Error reading log:', error);
+    }
+  }, []);
+
+  const checkConflicts = useCallback(async () => {
+    if (!window.desktopAPI?.checkConflictingSoftware) return;
+    setCheckingConflicts(true);
+    try {
+      setConflictReport(await window.desktopAPI.checkConflictingSoftware());
+    } catch (error) {
+      console.error('Error checking conflicting software:', error);
+    } finally {
+      setCheckingConflicts(false);
+    }
+  }, []);
+
+  const loadRawPackets = useCallback(async () => {
+    if (!window.desktopAPI?.getRawPacketLog) return;
+    try {
+      setRawPackets(await window.desktopAPI.getRawPacketLog());
+    } catch (error) {
+      console.error('Error reading raw packets:', error);
+    }
+  }, []);
+
+  const loadResourceUsage = useCallback(async () => {
+    if (!window.desktopAPI?.getResourceUsage) return;
+    try {
+      setResourceUsage(await window.desktopAPI.getResourceUsage());
+    } catch (error) {
+      console.error('Error reading resource usage:', error);
+    }
+  }, []);
+
+  const changeLogLevel = useCallback(async (level: LogLevel) => {
+    updateSetting('logLevel', level);
+    try {
+      await window.desktopAPI?.setLogLevel(level);
+    } catch (error) {
+      console.error('Error setting log level:', error);
+    }
+  }, [updateSetting]);
+
   const renderTabContent = () => {
     const monitorOnlyClass = settings.monitorOnlyMode ? 'opacity-55 pointer-events-none select-none' : '';
 
@@ -277,7 +391,7 @@ export const SettingsView: React.FC = () => {
                 <div>
                   <p className="text-sm text-white">Activar modo solo monitor</p>
                   <p className="text-xs text-white/40">
-                    Desactiva automaticamente alertas, apagado y guardado de historial.
+                    Desactiva automaticamente alertas, sonidos y apagado.
                   </p>
                 </div>
                 <Toggle
@@ -285,6 +399,124 @@ export const SettingsView: React.FC = () => {
                   onChange={toggleMonitorOnlyMode}
                 />
               </div>
+              <div className="flex items-center justify-between py-2">
+                <div>
+                  <p className="text-sm text-white">Registrar eventos en modo solo monitor</p>
+                  <p className="text-xs text-white/40">Requiere guardar historial activado</p>
+                </div>
+                <Toggle
+                  checked={settings.monitorOnlyLogging.events}
+                  onChange={(v) => updateSetting('monitorOnlyLogging', { ...settings.monitorOnlyLogging, events: v })}
+                  disabled={!settings.saveHistory}
+                />
+              </div>
+              <div className="flex items-center justify-between py-2">
+                <div>
+                  <p className="text-sm text-white">Registrar datos en modo solo monitor</p>
+                  <p className="text-xs text-white/40">Muestras de voltaje, carga y bateria</p>
+                </div>
+                <Toggle
+                  checked={settings.monitorOnlyLogging.dataPoints}
+                  onChange={(v) => updateSetting('monitorOnlyLogging', { ...settings.monitorOnlyLogging, dataPoints: v })}
+                  disabled={!settings.saveHistory}
+                />
+              </div>
+            </div>
+
+            <div className="glass-card p-5">
+              <h3 className="text-sm font-semibold text-white mb-4 flex items-center gap-2">
+                <Clock className="w-4 h-4 text-amber-400" />
+                Suspender Protección
+              </h3>
+              <div className="flex items-center justify-between py-2">
+                <div>
+                  <p className="text-sm text-white">
+                    {protection?.suspended ? 'Protección suspendida' : 'Protección activa'}
+                  </p>
+                  <p className="text-xs text-white/40">
+                    {protection?.suspended && protection.until
+                      ? `${protection.reason ?? ''} · hasta ${new Date(protection.until).toLocaleString()}`
+                      : 'Sin alertas ni apagado durante el tiempo indicado; el historial continúa'}
+                  </p>
+                </div>
+                {protection?.suspended ? (
+                  <button
+                    onClick={resumeProtection}
+                    className="px-3 py-2 rounded-lg bg-dark-600 hover:bg-dark-500 text-white text-sm transition-colors"
+                  >
+                    Reanudar
+                  </button>
+                ) : (
+                  <div className="flex items-center gap-2">
+                    <NumberInput value={suspendMinutes} onChange={setSuspendMinutes} min={1} max={10080} suffix="min" />
+                    <button
+                      onClick={suspendProtection}
+                      className="px-3 py-2 rounded-lg bg-dark-600 hover:bg-dark-500 text-white text-sm transition-colors"
+                    >
+                      Suspender
+                    </button>
+                  </div>
+                )}
+              </div>
+              <div className="flex items-center justify-between py-2">
+                <div>
+                  <p className="text-sm text-white">Ventana semanal</p>
+                  <p className="text-xs text-white/40">Por ejemplo, la prueba del generador cada sábado</p>
+                </div>
+                <Toggle
+                  checked={settings.protectionWindow.enabled}
+                  onChange={(v) => updateSetting('protectionWindow', { ...settings.protectionWindow, enabled: v })}
+                />
+              </div>
+              <div className="flex items-center justify-between py-2">
+                <div>
+                  <p className="text-sm text-white">Inicio</p>
+                  <p className="text-xs text-white/40">Día y hora local</p>
+                </div>
+                <div className="flex items-center gap-2">
+                  <select
+                    value={settings.protectionWindow.weekday}
+                    onChange={(e) => updateSetting('protectionWindow', { ...settings.protectionWindow, weekday: Number(e.target.value) })}
+                    disabled={!settings.protectionWindow.enabled}
+                    className="px-3 py-2 bg-dark-700 border border-white/10 rounded-lg text-white text-sm
+                               focus:outline-none focus:border-cyan-500/50"
+                  >
+                    {['Lunes', 'Martes', 'Miércoles', 'Jueves', 'Viernes', 'Sábado', 'Domingo'].map((day, index) => (
+                      <option key={day} value={index}>{day}</option>
+                    ))}
+                  </select>
+                  <NumberInput
+                    value={settings.protectionWindow.startHour}
+                    onChange={(v) => updateSetting('protectionWindow', { ...settings.protectionWindow, startHour: v })}
+                    min={0}
+                    max={23}
+                    suffix="h"
+                    disabled={!settings.protectionWindow.enabled}
+                  />
+                  <NumberInput
+                    value={settings.protectionWindow.startMinute}
+                    onChange={(v) => updateSetting('protectionWindow', { ...settings.protectionWindow, startMinute: v })}
+                    min={0}
+                    max={59}
+                    suffix="min"
+                    disabled={!settings.protectionWindow.enabled}
+                  />
+                </div>
+              </div>
+              <div className="flex items-center justify-between py-2">
+                <div>
+                  <p className="text-sm text-white">Duración</p>
+                  <p className="text-xs text-white/40">Al terminar se restablece la protección</p>
+                </div>
+                <NumberInput
+                  value={settings.protectionWindow.durationMinutes}
+                  onChange={(v) => updateSetting('protectionWindow', { ...settings.protectionWindow, durationMinutes: v })}
+                  min={5}
+                  max={1440}
+                  suffix="min"
+                  disabled={!settings.protectionWindow.enabled}
+                />
+              </div>
             </div>
 
             {/* Startup Section */}
@@ -317,6 +549,31 @@ export const SettingsView: React.FC = () => {
                     onChange={(v) => updateSetting('startMinimized', v)} 
                   />
                 </div>
+
+                <div className="flex items-center justify-between py-2">
+                  <div>
+                    <p className="text-sm text-white">Siempre visible</p>
+                    <p className="text-xs text-white/40">Mantener la ventana sobre las demas</p>
+                  </div>
+                  <Toggle 
+                    checked={settings.alwaysOnTop} 
+                    onChange={(v) => {
+                      updateSetting('alwaysOnTop', v);
+                      void window.desktopAPI?.setAlwaysOnTop(v);
+                    }} 
+                  />
+                </div>
+
+                <div className="flex items-center justify-between py-2">
+                  <div>
+                    <p className="text-sm text-white">Vigilar config.json</p>
+                    <p className="text-xs text-white/40">Aplicar cambios hechos por otros programas sin reiniciar</p>
+                  </div>
+                  <Toggle
+                    checked={settings.watchConfigFile}
+                    onChange={(v) => updateSetting('watchConfigFile', v)}
+                  />
+                </div>
               </div>
             </div>
 
@@ -339,6 +596,98 @@ export const SettingsView: React.FC = () => {
                     disabled={settings.monitorOnlyMode}
                   />
                 </div>
+                <div className="flex items-center justify-between py-2">
+                  <div>
+                    <p className="text-sm text-white">Ventana emergente del sistema</p>
+                    <p className="text-xs text-white/40">Mostrar la alerta encima de otras aplicaciones; la alerta dentro de la app se mantiene</p>
+                  </div>
+                  <Toggle
+                    checked={settings.forceSystemPopup}
+                    onChange={(v) => updateSetting('forceSystemPopup', v)}
+                  />
+                </div>
+                {settings.forceSystemPopup && (
+                  <>
+                    <div className="flex items-center justify-between py-2 pl-4">
+                      <span className="text-xs text-white/50">Intervalo mínimo entre ventanas iguales</span>
+                      <NumberInput
+                        value={settings.popupMinIntervalSeconds}
+                        onChange={(v) => updateSetting('popupMinIntervalSeconds', v)}
+                        min={1}
+                        max={300}
+                        suffix="s"
+                      />
+                    </div>
+                    <div className="flex items-center justify-between py-2 pl-4">
+                      <span className="text-xs text-white/50">Monitor de las alertas</span>
+                      <select
+                        value={settings.alertDisplay}
+                        onChange={(e) => updateSetting('alertDisplay', e.target.value)}
+                        className="px-3 py-2 bg-dark-700 border border-white/10 rounded-lg text-white text-sm"
+                      >
+                        <option value="primary">Principal</option>
+                        <option value="active">El de la ventana activa</option>
+                        {monitors.map((monitor) => (
+                          <option key={monitor.index} value={monitor.name ?? String(monitor.index)}>
+                            {monitor.name ?? `Monitor ${monitor.index + 1}`} ({monitor.width}×{monitor.height})
+                            {monitor.primary ? ' · principal' : ''}
+                          </option>
+                        ))}
+                        {!['primary', 'active'].includes(settings.alertDisplay) &&
+                          !monitors.some((monitor) => (monitor.name ?? String(monitor.index)) === settings.alertDisplay) && (
+                            <option value={settings.alertDisplay}>{settings.alertDisplay} (desconectado)</option>
+                          )}
+                      </select>
+                    </div>
+                    <div className="flex items-center justify-between py-2 pl-4">
+                      <span className="text-xs text-white/50">Tiempo visible</span>
+                      <NumberInput
+                        value={settings.popupTimeoutSeconds}
+                        onChange={(v) => updateSetting('popupTimeoutSeconds', v)}
+                        min={3}
+                        max={120}
+                        suffix="s"
+                      />
+                    </div>
+                  </>
+                )}
+                <div className="flex items-center justify-between py-2">
+                  <div>
+                    <p className="text-sm text-white">Alertas visuales</p>
+                    <p className="text-xs text-white/40">Hacer parpadear la barra de tareas o el icono de bandeja al sonar una alerta</p>
+                  </div>
+                  <Toggle
+                    checked={settings.visualAlerts.enabled}
+                    onChange={(v) => updateSetting('visualAlerts', { ...settings.visualAlerts, enabled: v })}
+                  />
+                </div>
+                {settings.visualAlerts.enabled && (
+                  <>
+                    <div className="flex items-center justify-between py-2 pl-4">
+                      <span className="text-xs text-white/50">Parpadeos</span>
+                      <NumberInput
+                        value={settings.visualAlerts.flashCount}
+                        onChange={(v) => updateSetting('visualAlerts', { ...settings.visualAlerts, flashCount: v })}
+                        min={1}
+                        max={50}
+                      />
+                    </div>
+                    <div className="flex items-center justify-between py-2 pl-4">
+                      <span className="text-xs text-white/50">Destello de pantalla completa (un pulso por segundo)</span>
+                      <Toggle
+                        checked={settings.visualAlerts.flashScreen}
+                        onChange={(v) => updateSetting('visualAlerts', { ...settings.visualAlerts, flashScreen: v })}
+                      />
+                    </div>
+                    <div className="flex items-center justify-between py-2 pl-4">
+                      <span className="text-xs text-white/50">Mantener la ventana de alerta hasta confirmarla</span>
+                      <Toggle
+                        checked={settings.visualAlerts.persistUntilAck}
+                        onChange={(v) => updateSetting('visualAlerts', { ...settings.visualAlerts, persistUntilAck: v })}
+                      />
+                    </div>
+                  </>
+                )}
                 <div className="flex items-center justify-between py-2">
                   <div>
                     <p className="text-sm text-white">Intervalo de consulta</p>
@@ -352,6 +701,32 @@ export const SettingsView: React.FC = () => {
                     suffix="ms"
                   />
                 </div>
+                <div className="flex items-center justify-between py-2">
+                  <div>
+                    <p className="text-sm text-white">Autonomía prevista en AC</p>
+                    <p className="text-xs text-white/40">Mostrar cuánto duraría la batería si fallara la energía ahora</p>
+                  </div>
+                  <Toggle
+                    checked={settings.showPredictedRuntime}
+                    onChange={(v) => updateSetting('showPredictedRuntime', v)}
+                  />
+                </div>
+                <div className="flex items-center justify-between py-2">
+                  <div>
+                    <p className="text-sm text-white">Origen de la autonomía</p>
+                    <p className="text-xs text-white/40">Algunos UPS informan su propia estimación</p>
+                  </div>
+                  <select
+                    value={settings.runtimeSource}
+                    onChange={(e) => updateSetting('runtimeSource', e.target.value as AppSettings['runtimeSource'])}
+                    className="px-3 py-2 bg-dark-700 border border-white/10 rounded-lg text-white text-sm
+                               focus:outline-none focus:border-cyan-500/50"
+                  >
+                    <option value="auto">Automático</option>
+                    <option value="device">Solo el UPS</option>
+                    <option value="computed">Calculada</option>
+                  </select>
+                </div>
               </div>
             </div>
 
@@ -390,6 +765,88 @@ export const SettingsView: React.FC = () => {
                     suffix="%"
                   />
                 </div>
+
+                <div className="flex items-center justify-between py-2">
+                  <div>
+                    <p className="text-sm text-white">Alerta por temperatura en aumento</p>
+                    <p className="text-xs text-white/40">Avisa si la temperatura sube rapido aunque no sea alta</p>
+                  </div>
+                  <Toggle
+                    checked={settings.temperatureTrend.enabled}
+                    onChange={(v) => updateSetting('temperatureTrend', { ...settings.temperatureTrend, enabled: v })}
+                  />
+                </div>
+
+                <div className="flex items-center justify-between py-2">
+                  <div>
+                    <p className="text-sm text-white">Subida maxima</p>
+                    <p className="text-xs text-white/40">Grados por hora sostenidos durante la ventana</p>
+                  </div>
+                  <NumberInput
+                    value={settings.temperatureTrend.risePerHour}
+                    onChange={(v) => updateSetting('temperatureTrend', { ...settings.temperatureTrend, risePerHour: v })}
+                    min={1}
+                    max={200}
+                    suffix="°C/h"
+                    disabled={!settings.temperatureTrend.enabled}
+                  />
+                </div>
+
+                <div className="flex items-center justify-between py-2">
+                  <div>
+                    <p className="text-sm text-white">Ventana de tendencia</p>
+                    <p className="text-xs text-white/40">Tiempo sobre el que se mide la subida</p>
+                  </div>
+                  <NumberInput
+                    value={settings.temperatureTrend.windowMinutes}
+                    onChange={(v) => updateSetting('temperatureTrend', { ...settings.temperatureTrend, windowMinutes: v })}
+                    min={5}
+                    max={120}
+                    suffix="min"
+                    disabled={!settings.temperatureTrend.enabled}
+                  />
+                </div>
+
+                <div className="flex items-center justify-between py-2">
+                  <div>
+                    <p className="text-sm text-white">Alerta si la batería no retiene carga</p>
+                    <p className="text-xs text-white/40">Avisa si sigue baja con energía durante horas</p>
+                  </div>
+                  <Toggle
+                    checked={settings.standbyCharge.enabled}
+                    onChange={(v) => updateSetting('standbyCharge', { ...settings.standbyCharge, enabled: v })}
+                  />
+                </div>
+
+                <div className="flex items-center justify-between py-2">
+                  <div>
+                    <p className="text-sm text-white">Mínimo en AC</p>
+                    <p className="text-xs text-white/40">Carga esperada con la energía presente</p>
+                  </div>
+                  <NumberInput
+                    value={settings.standbyCharge.minPercent}
+                    onChange={(v) => updateSetting('standbyCharge', { ...settings.standbyCharge, minPercent: v })}
+                    min={10}
+                    max={95}
+                    suffix="%"
+                    disabled={!settings.standbyCharge.enabled}
+                  />
+                </div>
+
+                <div className="flex items-center justify-between py-2">
+                  <div>
+                    <p className="text-sm text-white">Tiempo por debajo del mínimo</p>
+                    <p className="text-xs text-white/40">Horas seguidas con energía antes de avisar</p>
+                  </div>
+                  <NumberInput
+                    value={settings.standbyCharge.durationHours}
+                    onChange={(v) => updateSetting('standbyCharge', { ...settings.standbyCharge, durationHours: v })}
+                    min={1}
+                    max={72}
+                    suffix="h"
+                    disabled={!settings.standbyCharge.enabled}
+                  />
+                </div>
               </div>
             </div>
           </div>
@@ -412,6 +869,42 @@ export const SettingsView: React.FC = () => {
               </div>
             </div>
 
+            {/* Límite de repeticiones */}
+            <div className="glass-card p-5">
+              <div className="flex items-center justify-between">
+                <div>
+                  <h3 className="text-sm font-semibold text-white">Máximo de repeticiones de sonido</h3>
+                  <p className="text-xs text-white/40">Tope para todas las alertas; súbelo para una alarma persistente</p>
+                </div>
+                <NumberInput
+                  value={settings.maxSoundRepeats}
+                  onChange={(v) => updateSetting('maxSoundRepeats', v)}
+                  min={1}
+                  max={500}
+                  suffix="veces"
+                />
+              </div>
+              {!audioAvailable && (
+                <div className="mt-4 flex gap-3 rounded-lg border border-amber-500/30 bg-amber-500/10 p-3">
+                  <AlertTriangle className="w-4 h-4 text-amber-400 flex-shrink-0 mt-0.5" />
+                  <p className="text-xs text-white/70">
+                    No hay dispositivo de audio: los sonidos de alerta no se oirán. Usa notificaciones,
+                    la ventana emergente o webhooks.
+                  </p>
+                </div>
+              )}
+              <div className="flex items-center justify-between pt-4">
+                <div>
+                  <p className="text-sm text-white">Ventana emergente sin audio</p>
+                  <p className="text-xs text-white/40">Si no hay dispositivo de audio, el canal de sonido abre la ventana de alerta</p>
+                </div>
+                <Toggle
+                  checked={settings.popupWhenAudioUnavailable}
+                  onChange={(v) => updateSetting('popupWhenAudioUnavailable', v)}
+                />
+              </div>
+            </div>
+
             {/* Fallo de Energía (AC Fault) */}
             <div className="glass-card p-5">
               <div className="flex items-center gap-3 mb-4">
@@ -429,15 +922,15 @@ export const SettingsView: React.FC = () => {
                   <span className="text-sm text-white/70">Reproducir sonido</span>
                   <div className="flex items-center gap-3">
                     <Toggle 
-                      checked={settings.alerts.acFault.playSound}
-                      onChange={(v) => updateAlert('acFault', 'playSound', v)}
+                      checked={hasChannel('acFault', 'sound')}
+                      onChange={(v) => toggleChannels('acFault', ['sound'], v)}
                     />
-                    {settings.alerts.acFault.playSound && (
+                    {hasChannel('acFault', 'sound') && (
                       <NumberInput
                         value={settings.alerts.acFault.soundRepeats}
                         onChange={(v) => updateAlert('acFault', 'soundRepeats', v)}
                         min={1}
-                        max={20}
+                        max={settings.maxSoundRepeats}
                         suffix="veces"
                       />
                     )}
@@ -446,10 +939,28 @@ export const SettingsView: React.FC = () => {
                 <div className="flex items-center justify-between py-2">
                   <span className="text-sm text-white/70">Mostrar popup urgente</span>
                   <Toggle 
-                    checked={settings.alerts.acFault.showPopup}
-                    onChange={(v) => updateAlert('acFault', 'showPopup', v)}
+                    checked={hasChannel('acFault', 'popup')}
+                    onChange={(v) => toggleChannels('acFault', ['notification', 'popup'], v)}
                   />
                 </div>
+                <div className="flex items-center justify-between py-2">
+                  <span className="text-sm text-white/70">Repetir hasta confirmar</span>
+                  <div className="flex items-center gap-3">
+                    <Toggle
+                      checked={settings.alerts.acFault.escalate}
+                      onChange={(v) => updateAlert('acFault', 'escalate', v)}
+                    />
+                    {settings.alerts.acFault.escalate && (
+                      <NumberInput
+                        value={settings.alerts.acFault.repeatDelaySeconds}
+                        onChange={(v) => updateAlert('acFault', 'repeatDelaySeconds', v)}
+                        min={10}
+                        max={600}
+                        suffix="seg"
+                      />
+                    )}
+                  </div>
+                </div>
               </div>
             </div>
 
@@ -470,15 +981,15 @@ export const SettingsView: React.FC = () => {
                   <span className="text-sm text-white/70">Reproducir sonido</span>
                   <div className="flex items-center gap-3">
                     <Toggle 
-                      checked={settings.alerts.batteryLow.playSound}
-                      onChange={(v) => updateAlert('batteryLow', 'playSound', v)}
+                      checked={hasChannel('batteryLow', 'sound')}
+                      onChange={(v) => toggleChannels('batteryLow', ['sound'], v)}
                     />
-                    {settings.alerts.batteryLow.playSound && (
+                    {hasChannel('batteryLow', 'sound') && (
                       <NumberInput
                         value={settings.alerts.batteryLow.soundRepeats}
                         onChange={(v) => updateAlert('batteryLow', 'soundRepeats', v)}
                         min={1}
-                        max={20}
+                        max={settings.maxSoundRepeats}
                         suffix="veces"
                       />
                     )}
@@ -487,10 +998,28 @@ export const SettingsView: React.FC = () => {
                 <div className="flex items-center justify-between py-2">
                   <span className="text-sm text-white/70">Mostrar popup urgente</span>
                   <Toggle 
-                    checked={settings.alerts.batteryLow.showPopup}
-                    onChange={(v) => updateAlert('batteryLow', 'showPopup', v)}
+                    checked={hasChannel('batteryLow', 'popup')}
+                    onChange={(v) => toggleChannels('batteryLow', ['notification', 'popup'], v)}
                   />
                 </div>
+                <div className="flex items-center justify-between py-2">
+                  <span className="text-sm text-white/70">Repetir hasta confirmar</span>
+                  <div className="flex items-center gap-3">
+                    <Toggle
+                      checked={settings.alerts.batteryLow.escalate}
+                      onChange={(v) => updateAlert('batteryLow', 'escalate', v)}
+                    />
+                    {settings.alerts.batteryLow.escalate && (
+                      <NumberInput
+                        value={settings.alerts.batteryLow.repeatDelaySeconds}
+                        onChange={(v) => updateAlert('batteryLow', 'repeatDelaySeconds', v)}
+                        min={10}
+                        max={600}
+                        suffix="seg"
+                      />
+                    )}
+                  </div>
+                </div>
               </div>
             </div>
 
@@ -511,15 +1040,15 @@ export const SettingsView: React.FC = () => {
                   <span className="text-sm text-white/70">Reproducir sonido</span>
                   <div className="flex items-center gap-3">
                     <Toggle 
-                      checked={settings.alerts.batteryCritical.playSound}
-                      onChange={(v) => updateAlert('batteryCritical', 'playSound', v)}
+                      checked={hasChannel('batteryCritical', 'sound')}
+                      onChange={(v) => toggleChannels('batteryCritical', ['sound'], v)}
                     />
-                    {settings.alerts.batteryCritical.playSound && (
+                    {hasChannel('batteryCritical', 'sound') && (
                       <NumberInput
                         value={settings.alerts.batteryCritical.soundRepeats}
                         onChange={(v) => updateAlert('batteryCritical', 'soundRepeats', v)}
                         min={1}
-                        max={30}
+                        max={settings.maxSoundRepeats}
                         suffix="veces"
                       />
                     )}
@@ -528,10 +1057,65 @@ export const SettingsView: React.FC = () => {
                 <div className="flex items-center justify-between py-2">
                   <span className="text-sm text-white/70">Mostrar popup urgente</span>
                   <Toggle 
-                    checked={settings.alerts.batteryCritical.showPopup}
-                    onChange={(v) => updateAlert('batteryCritical', 'showPopup', v)}
+                    checked={hasChannel('batteryCritical', 'popup')}
+                    onChange={(v) => toggleChannels('batteryCritical', ['notification', 'popup'], v)}
                   />
                 </div>
+                <div className="flex items-center justify-between py-2">
+                  <span className="text-sm text-white/70">Repetir hasta confirmar</span>
+                  <div className="flex items-center gap-3">
+                    <Toggle
+                      checked={settings.alerts.batteryCritical.escalate}
+                      onChange={(v) => updateAlert('batteryCritical', 'escalate', v)}
+                    />
+                    {settings.alerts.batteryCritical.escalate && (
+                      <NumberInput
+                        value={settings.alerts.batteryCritical.repeatDelaySeconds}
+                        onChange={(v) => updateAlert('batteryCritical', 'repeatDelaySeconds', v)}
+                        min={10}
+                        max={600}
+                        suffix="seg"
+                      />
+                    )}
+                  </div>
+                </div>
+              </div>
+            </div>
+
+            {/* Conexión del UPS */}
+            <div className="glass-card p-5">
+              <div className="flex items-center gap-3 mb-4">
+                <div className="w-10 h-10 rounded-lg bg-cyan-500/20 flex items-center justify-center">
+                  <Plug className="w-5 h-5 text-cyan-400" />
+                </div>
+                <div>
+                  <h3 className="text-sm font-semibold text-white">Conexión del UPS</h3>
+                  <p className="text-xs text-white/40">Se desconectó o volvió a conectarse el cable USB</p>
+                </div>
+              </div>
+
+              <div className="space-y-4 ml-13">
+                {([
+                  ['upsDisconnected', 'UPS desconectado'],
+                  ['upsConnected', 'UPS reconectado'],
+                ] as const).map(([alertType, label]) => (
+                  <div key={alertType} className="space-y-1">
+                    <span className="text-xs text-white/50">{label}</span>
+                    {([
+                      ['notification', 'Notificación'],
+                      ['popup', 'Mostrar popup urgente'],
+                      ['sound', 'Reproducir sonido'],
+                    ] as const).map(([channel, channelLabel]) => (
+                      <div key={channel} className="flex items-center justify-between py-2 pl-4">
+                        <span className="text-sm text-white/70">{channelLabel}</span>
+                        <Toggle
+                          checked={hasChannel(alertType, channel)}
+                          onChange={(v) => toggleChannels(alertType, [channel], v)}
+                        />
+                      </div>
+                    ))}
+                  </div>
+                ))}
               </div>
             </div>
           </div>
@@ -562,8 +1146,22 @@ export const SettingsView: React.FC = () => {
               </h3>
 
               <div className="rounded-lg bg-dark-700/50 border border-white/10 p-3">
-                <p className="text-xs text-white/40 mb-1">Ruta actual</p>
-                <p className="text-sm text-white break-all">{settings.customSoundsPath ?? 'Predeterminada (assets/sounds)'}</p>
+                <p className="text-xs text-white/40 mb-1">Rutas de busqueda (en orden)</p>
+                {settings.customSoundsPaths.map((path, index) => (
+                  <div key={path} className="flex items-center justify-between gap-2 py-1">
+                    <p className="text-sm text-white break-all">{index + 1}. {path}</p>
+                    <button
+                      onClick={() => removeSoundsFolder(path)}
+                      className="p-1 rounded bg-white/10 hover:bg-white/20 text-white/70"
+                      title="Quitar carpeta"
+                    >
+                      <FolderX className="w-3.5 h-3.5" />
+                    </button>
+                  </div>
+                ))}
+                <p className="text-sm text-white/60 break-all">
+                  {settings.customSoundsPaths.length + 1}. Predeterminada (assets/sounds)
+                </p>
               </div>
 
               <div className="flex items-center gap-2">
@@ -572,15 +1170,15 @@ export const SettingsView: React.FC = () => {
                   className="flex items-center gap-2 px-3 py-2 rounded-lg bg-cyan-500 hover:bg-cyan-400 text-white text-sm transition-colors"
                 >
                   <FolderOpen className="w-4 h-4" />
-                  Seleccionar carpeta
+                  Agregar carpeta
                 </button>
                 <button
                   onClick={clearSoundsFolder}
-                  disabled={!settings.customSoundsPath}
+                  disabled={settings.customSoundsPaths.length === 0}
                   className="flex items-center gap-2 px-3 py-2 rounded-lg bg-white/10 hover:bg-white/20 disabled:opacity-50 text-white text-sm transition-colors"
                 >
                   <FolderX className="w-4 h-4" />
-                  Limpiar ruta
+                  Limpiar rutas
                 </button>
                 <button
                   onClick={refreshAvailableSounds}
@@ -678,6 +1276,31 @@ export const SettingsView: React.FC = () => {
               </h3>
               
               <div className="space-y-4">
+                <div className="flex items-center justify-between py-2">
+                  <div>
+                    <p className="text-sm text-white">Espera al iniciar</p>
+                    <p className="text-xs text-white/40">No programar apagados durante los primeros segundos de monitoreo</p>
+                  </div>
+                  <NumberInput
+                    value={settings.startupGraceSeconds}
+                    onChange={(v) => updateSetting('startupGraceSeconds', v)}
+                    min={0}
+                    max={600}
+                    suffix="seg"
+                  />
+                </div>
+
+                <div className="flex items-center justify-between py-2">
+                  <div>
+                    <p className="text-sm text-white">Apagar la pantalla al perder AC</p>
+                    <p className="text-xs text-white/40">Ahorra batería; el equipo sigue encendido hasta el apagado programado</p>
+                  </div>
+                  <Toggle
+                    checked={settings.displayOffOnAcFault}
+                    onChange={(v) => updateSetting('displayOffOnAcFault', v)}
+                  />
+                </div>
+
                 {/* Por fallo de AC */}
                 <div className="p-4 rounded-lg bg-dark-700/50 space-y-3">
                   <div className="flex items-center justify-between">
@@ -718,6 +1341,42 @@ export const SettingsView: React.FC = () => {
                       />
                     </div>
                   )}
+                  {settings.shutdownPC.onAcFault.enabled && (
+                    <div className="flex items-center justify-between pl-7">
+                      <div>
+                        <p className="text-xs text-white/50">Limitar a la autonomía estimada</p>
+                        <p className="text-xs text-white/30">Apaga antes si la batería no alcanza la espera</p>
+                      </div>
+                      <Toggle
+                        checked={settings.shutdownPC.onAcFault.capDelayToRuntime}
+                        onChange={(v) => setSettings(prev => ({
+                          ...prev,
+                          shutdownPC: {
+                            ...prev.shutdownPC,
+                            onAcFault: { ...prev.shutdownPC.onAcFault, capDelayToRuntime: v }
+                          }
+                        }))}
+                      />
+                    </div>
+                  )}
+                  {settings.shutdownPC.onAcFault.enabled && settings.shutdownPC.onAcFault.capDelayToRuntime && (
+                    <div className="flex items-center justify-between pl-7">
+                      <span className="text-xs text-white/50">Margen de seguridad:</span>
+                      <NumberInput
+                        value={settings.shutdownPC.onAcFault.runtimeSafetyMarginMinutes}
+                        onChange={(v) => setSettings(prev => ({
+                          ...prev,
+                          shutdownPC: {
+                            ...prev.shutdownPC,
+                            onAcFault: { ...prev.shutdownPC.onAcFault, runtimeSafetyMarginMinutes: v }
+                          }
+                        }))}
+                        min={1}
+                        max={30}
+                        suffix="min"
+                      />
+                    </div>
+                  )}
                 </div>
 
                 {/* Por batería baja */}
@@ -823,6 +1482,50 @@ export const SettingsView: React.FC = () => {
                                placeholder:text-white/30 focus:outline-none focus:border-cyan-500/50"
                   />
                 </div>
+
+                <div>
+                  <p className="text-sm text-white mb-2">Directorio de trabajo (opcional)</p>
+                  <p className="text-xs text-white/40 mb-3">Carpeta desde la que se ejecuta el comando; si no existe se usa la de la aplicación</p>
+                  <input
+                    type="text"
+                    value={settings.shutdownPC.workingDirectory ?? ''}
+                    onChange={(e) => setSettings(prev => ({
+                      ...prev,
+                      shutdownPC: { ...prev.shutdownPC, workingDirectory: e.target.value || null }
+                    }))}
+                    placeholder="C:\\proyectos\\servidor"
+                    className="w-full px-3 py-2 bg-dark-700 border border-white/10 rounded-lg text-white text-sm
+                               placeholder:text-white/30 focus:outline-none focus:border-cyan-500/50"
+                  />
+                </div>
+
+                <div>
+                  <p className="text-sm text-white mb-2">Variables de entorno (opcional)</p>
+                  <p className="text-xs text-white/40 mb-3">Una por línea, en formato NOMBRE=valor</p>
+                  <textarea
+                    key={JSON.stringify(settings.shutdownPC.environment)}
+                    defaultValue={Object.entries(settings.shutdownPC.environment)
+                      .map(([key, value]) => `${key}=${value}`)
+                      .join('\n')}
+                    onBlur={(e) => {
+                      const environment: Record<string, string> = {};
+                      for (const line of e.target.value.split('\n')) {
+                        const separator = line.indexOf('=');
+                        if (separator > 0) {
+                          environment[line.slice(0, separator).trim()] = line.slice(separator + 1);
+                        }
+                      }
+                      setSettings(prev => ({
+                        ...prev,
+                        shutdownPC: { ...prev.shutdownPC, environment }
+                      }));
+                    }}
+                    rows={3}
+                    placeholder="BACKUP_TARGET=D:\\backup"
+                    className="w-full px-3 py-2 bg-dark-700 border border-white/10 rounded-lg text-white text-sm font-mono
+                               placeholder:text-white/30 focus:outline-none focus:border-cyan-500/50"
+                  />
+                </div>
               </div>
             </div>
 
@@ -878,7 +1581,7 @@ export const SettingsView: React.FC = () => {
 
       case 'history':
         return (
-          <div className={`space-y-6 ${monitorOnlyClass}`}>
+          <div className="space-y-6">
             <div className="glass-card p-5">
               <h3 className="text-sm font-semibold text-white mb-4 flex items-center gap-2">
                 <Database className="w-4 h-4 text-purple-400" />
@@ -897,6 +1600,18 @@ export const SettingsView: React.FC = () => {
                   />
                 </div>
                 
+                <div className="flex items-center justify-between py-2">
+                  <div>
+                    <p className="text-sm text-white">Comprimir historial</p>
+                    <p className="text-xs text-white/40">Guardar data.json con gzip para reducir escrituras en disco; versiones anteriores de la app no podran leerlo</p>
+                  </div>
+                  <Toggle
+                    checked={settings.compressDataHistory}
+                    onChange={(v) => updateSetting('compressDataHistory', v)}
+                    disabled={!settings.saveHistory}
+                  />
+                </div>
+
                 <div className="flex items-center justify-between py-2">
                   <div>
                     <p className="text-sm text-white">Intervalo de guardado</p>
@@ -911,6 +1626,105 @@ export const SettingsView: React.FC = () => {
                     disabled={!settings.saveHistory}
                   />
                 </div>
+
+                <div className="flex items-center justify-between py-2">
+                  <div>
+                    <p className="text-sm text-white">Registrar cambios bruscos</p>
+                    <p className="text-xs text-white/40">Guardar un punto extra cuando una lectura cambia de golpe</p>
+                  </div>
+                  <Toggle
+                    checked={settings.changeLogging.enabled}
+                    onChange={(v) => updateSetting('changeLogging', { ...settings.changeLogging, enabled: v })}
+                  />
+                </div>
+
+                <div className="flex items-center justify-between py-2">
+                  <div>
+                    <p className="text-sm text-white">Cambio de voltaje</p>
+                    <p className="text-xs text-white/40">Diferencia en entrada o salida desde el último punto</p>
+                  </div>
+                  <NumberInput
+                    value={settings.changeLogging.voltageDelta}
+                    onChange={(v) => updateSetting('changeLogging', { ...settings.changeLogging, voltageDelta: v })}
+                    min={1}
+                    max={100}
+                    suffix="V"
+                    disabled={!settings.saveHistory || !settings.changeLogging.enabled}
+                  />
+                </div>
+
+                <div className="flex items-center justify-between py-2">
+                  <div>
+                    <p className="text-sm text-white">Cambio de carga</p>
+                    <p className="text-xs text-white/40">Diferencia de carga desde el último punto</p>
+                  </div>
+                  <NumberInput
+                    value={settings.changeLogging.loadDelta}
+                    onChange={(v) => updateSetting('changeLogging', { ...settings.changeLogging, loadDelta: v })}
+                    min={1}
+                    max={100}
+                    suffix="%"
+                    disabled={!settings.saveHistory || !settings.changeLogging.enabled}
+                  />
+                </div>
+
+                <div className="flex items-center justify-between py-2">
+                  <div>
+                    <p className="text-sm text-white">Separación mínima</p>
+                    <p className="text-xs text-white/40">Tiempo mínimo entre puntos por cambio</p>
+                  </div>
+                  <NumberInput
+                    value={settings.changeLogging.minIntervalSeconds}
+                    onChange={(v) => updateSetting('changeLogging', { ...settings.changeLogging, minIntervalSeconds: v })}
+                    min={1}
+                    max={300}
+                    suffix="seg"
+                    disabled={!settings.saveHistory || !settings.changeLogging.enabled}
+                  />
+                </div>
+
+                <div className="flex items-center justify-between py-2">
+                  <div>
+                    <p className="text-sm text-white">Máximo por hora</p>
+                    <p className="text-xs text-white/40">Puntos por cambio permitidos en una hora</p>
+                  </div>
+                  <NumberInput
+                    value={settings.changeLogging.maxPointsPerHour}
+                    onChange={(v) => updateSetting('changeLogging', { ...settings.changeLogging, maxPointsPerHour: v })}
+                    min={1}
+                    max={360}
+                    suffix="puntos"
+                    disabled={!settings.saveHistory || !settings.changeLogging.enabled}
+                  />
+                </div>
+
+                <div className="flex items-center justify-between py-2">
+                  <div>
+                    <p className="text-sm text-white">Agrupar eventos repetidos</p>
+                    <p className="text-xs text-white/40">Ventana para sumar repeticiones en una fila (0 = desactivado)</p>
+                  </div>
+                  <NumberInput
+                    value={settings.duplicateEventWindowSeconds}
+                    onChange={(v) => updateSetting('duplicateEventWindowSeconds', v)}
+                    min={0}
+                    max={3600}
+                    suffix="seg"
+                    disabled={!settings.saveHistory}
+                  />
+                </div>
+
+                <div className="flex items-center justify-between py-2">
+                  <div>
+                    <p className="text-sm text-white">Guardar ahora</p>
+                    <p className="text-xs text-white/40">Escribir todos los datos pendientes en disco</p>
+                  </div>
+                  <button
+                    onClick={flushStorage}
+                    className="px-3 py-2 rounded-lg bg-dark-600 hover:bg-dark-500 text-white text-sm transition-colors"
+                  >
+                    Guardar
+                  </button>
+                </div>
               </div>
             </div>
           </div>
@@ -952,6 +1766,67 @@ export const SettingsView: React.FC = () => {
                   <span className="text-sm text-purple-400 font-medium">Megatec Q1</span>
                 </div>
               </div>
+
+              {setupGuidance && (
+                <div
+                  className={`mt-4 rounded-lg border p-3 space-y-2 ${
+                    setupGuidance.permissionDenied
+                      ? 'border-amber-500/30 bg-amber-500/10'
+                      : 'border-white/10 bg-dark-700/50'
+                  }`}
+                >
+                  <p className="text-sm text-white flex items-center gap-2">
+                    {setupGuidance.permissionDenied && <AlertTriangle className="w-4 h-4 text-amber-400" />}
+                    {setupGuidance.permissionDenied ? 'Sin permiso para abrir el UPS' : 'Configuración del acceso al UPS'}
+                  </p>
+                  {setupGuidance.error && (
+                    <p className="text-xs text-white/50 break-all">
+                      {setupGuidance.devicePath} · {setupGuidance.error}
+                    </p>
+                  )}
+                  {setupGuidance.udevRule && (
+                    <pre className="rounded bg-dark-800/70 p-2 text-xs font-mono text-cyan-300 whitespace-pre-wrap break-all select-all">
+                      {setupGuidance.udevRule}
+                    </pre>
+                  )}
+                  <ol className="list-decimal pl-5 text-xs text-white/60 space-y-1">
+                    {setupGuidance.steps.map((step) => (
+                      <li key={step}>{step}</li>
+                    ))}
+                  </ol>
+                </div>
+              )}
+
+              <div className="mt-4 flex items-center justify-between py-2">
+                <div>
+                  <p className="text-sm text-white">Software en conflicto</p>
+                  <p className="text-xs text-white/40">Busca programas del fabricante o NUT que puedan tener el UPS abierto</p>
+                </div>
+                <button
+                  onClick={checkConflicts}
+                  disabled={checkingConflicts}
+                  className="px-3 py-2 rounded-lg bg-dark-600 hover:bg-dark-500 disabled:opacity-50 text-white text-sm transition-colors"
+                >
+                  {checkingConflicts ? 'Buscando...' : 'Buscar'}
+                </button>
+              </div>
+              {conflictReport && (
+                <div className="rounded-lg bg-dark-800/50 p-3 text-xs text-white/70 space-y-1">
+                  {conflictReport.error ? (
+                    <p className="text-red-300">{conflictReport.error}</p>
+                  ) : conflictReport.conflicts.length === 0 ? (
+                    <p>No se encontró software en conflicto.</p>
+                  ) : (
+                    conflictReport.conflicts.map((conflict) => (
+                      <p key={`${conflict.process}-${conflict.pid ?? ''}`}>
+                        <span className="text-amber-300">{conflict.product}</span> · {conflict.process}
+                        {conflict.pid !== null && ` (PID ${conflict.pid})`}
+                        {conflict.services.length > 0 && ` · servicio ${conflict.services.join(', ')}`}
+                      </p>
+                    ))
+                  )}
+                </div>
+              )}
             </div>
 
             {/* App Info */}
@@ -976,10 +1851,118 @@ export const SettingsView: React.FC = () => {
                   <span className="text-sm text-white/50">React</span>
                   <span className="text-sm text-white">19.0.0</span>
                 </div>
-                <div className="flex items-center justify-between py-2">
+                <div className="flex items-center justify-between py-2 border-b border-white/5">
                   <span className="text-sm text-white/50">TypeScript</span>
                   <span className="text-sm text-white">5.x</span>
                 </div>
+                <div className="flex items-center justify-between py-2">
+                  <div>
+                    <p className="text-sm text-white">Uso de recursos</p>
+                    <p className="text-xs text-white/40">CPU y memoria de la aplicación y ritmo del monitor</p>
+                  </div>
+                  <button
+                    onClick={loadResourceUsage}
+                    className="px-3 py-2 rounded-lg bg-dark-600 hover:bg-dark-500 text-white text-sm transition-colors"
+                  >
+                    {resourceUsage ? 'Actualizar' : 'Medir'}
+                  </button>
+                </div>
+                {resourceUsage && (
+                  <div className="rounded-lg bg-dark-800/50 p-3 text-xs font-mono text-white/70 space-y-1">
+                    <p>PID {resourceUsage.pid} · activo {Math.floor(resourceUsage.uptimeSeconds / 60)} min</p>
+                    <p>
+                      CPU {resourceUsage.cpuTimeMs !== null ? `${(resourceUsage.cpuTimeMs / 1000).toFixed(1)} s` : '—'}
+                      {resourceUsage.averageCpuPercent !== null && ` (${resourceUsage.averageCpuPercent.toFixed(2)} % medio)`}
+                    </p>
+                    <p>
+                      Memoria {resourceUsage.memoryBytes !== null ? `${(resourceUsage.memoryBytes / 1048576).toFixed(1)} MB` : '—'}
+                    </p>
+                    <p>
+                      Monitor {resourceUsage.monitorLoop.iterationsPerSecond.toFixed(2)} ciclos/s · lectura {resourceUsage.monitorLoop.readTimeoutMs} ms · espera {resourceUsage.monitorLoop.sleepMs} ms
+                    </p>
+                  </div>
+                )}
+              </div>
+            </div>
+
+            {/* Log */}
+            <div className="glass-card p-5">
+              <h3 className="text-sm font-semibold text-white mb-4 flex items-center gap-2">
+                <FileText className="w-4 h-4 text-cyan-400" />
+                Registro
+              </h3>
+
+              <div className="space-y-4">
+                <div className="flex items-center justify-between py-2">
+                  <div>
+                    <p className="text-sm text-white">Nivel de detalle</p>
+                    <p className="text-xs text-white/40">Guardado en logs/ups-monitor.log</p>
+                  </div>
+                  <select
+                    value={settings.logLevel}
+                    onChange={(e) => changeLogLevel(e.target.value as LogLevel)}
+                    className="px-3 py-2 bg-dark-700 border border-white/10 rounded-lg text-white text-sm
+                               focus:outline-none focus:border-cyan-500/50"
+                  >
+                    <option value="error">Errores</option>
+                    <option value="warn">Avisos</option>
+                    <option value="info">Informacion</option>
+                    <option value="debug">Depuracion</option>
+                    <option value="trace">Todo</option>
+                    <option value="off">Desactivado</option>
+                  </select>
+                </div>
+
+                <div className="flex items-center justify-between py-2">
+                  <div>
+                    <p className="text-sm text-white">Ultimas lineas</p>
+                    <p className="text-xs text-white/40">Actividad reciente del monitor</p>
+                  </div>
+                  <button
+                    onClick={loadLogTail}
+                    className="px-3 py-2 rounded-lg bg-dark-600 hover:bg-dark-500 text-white text-sm transition-colors"
+                  >
+                    Ver registro
+                  </button>
+                </div>
+
+                {logLines && (
+                  <pre className="max-h-64 overflow-auto rounded-lg bg-dark-800/50 p-3 text-xs font-mono text-white/70 whitespace-pre-wrap">
+                    {logLines.length > 0 ? logLines.join('\n') : 'Sin entradas'}
+                  </pre>
+                )}
+
+                <div className="flex items-center justify-between py-2">
+                  <div>
+                    <p className="text-sm text-white">Capturar tramas crudas</p>
+                    <p className="text-xs text-white/40">Últimas 50 tramas del UPS; se incluyen en el paquete de soporte</p>
+                  </div>
+                  <div className="flex items-center gap-3">
+                    <button
+                      onClick={loadRawPackets}
+                      disabled={!settings.debugCapture}
+                      className="px-3 py-2 rounded-lg bg-dark-600 hover:bg-dark-500 disabled:opacity-50 text-white text-sm transition-colors"
+                    >
+                      Ver tramas
+                    </button>
+                    <Toggle
+                      checked={settings.debugCapture}
+                      onChange={(v) => updateSetting('debugCapture', v)}
+                    />
+                  </div>
+                </div>
+
+                {rawPackets && (
+                  <pre className="max-h-64 overflow-auto rounded-lg bg-dark-800/50 p-3 text-xs font-mono text-white/70 whitespace-pre-wrap">
+                    {rawPackets.length > 0
+                      ? rawPackets
+                          .map((packet) =>
+                            `${new Date(packet.receivedAt).toLocaleTimeString('es-ES')}  ${packet.parsed ? 'ok ' : 'ERR'}  ${packet.ascii}`,
+                          )
+                          .join('\n')
+                      : 'Sin tramas'}
+                  </pre>
+                )}
               </div>
             </div>
           </div>
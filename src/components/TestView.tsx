@@ -17,9 +17,11 @@ import {
   Square,
   Settings2,
   FolderOpen,
+  RotateCcw,
 } from 'lucide-react';
 import { UPSData } from '../types/ups';
 import type { AppSettings } from '../types/settings';
+import type { OutageSimulationReport, ShutdownPhase, ShutdownPhaseState } from '../types/desktop';
 
 interface TestViewProps {
   data: UPSData;
@@ -32,10 +34,20 @@ interface TestResult {
   message?: string;
 }
 
+const shutdownPhaseLabels: Record<ShutdownPhase, string> = {
+  idle: 'Sin apagado pendiente',
+  'grace-period': 'Retenido por el periodo de gracia',
+  scheduled: 'Programado',
+  'awaiting-confirmation': 'Esperando confirmación',
+  executing: 'Apagando',
+};
+
 interface ShutdownEventState {
   scheduledCount: number;
   cancelledCount: number;
   lastScheduledAt: string | null;
+  lastMinutes: number | null;
+  lastRuntimeMinutes: number | null;
 }
 
 export const TestView: React.FC<TestViewProps> = ({ data, isDisconnected = false }) => {
@@ -46,8 +58,17 @@ export const TestView: React.FC<TestViewProps> = ({ data, isDisconnected = false
     scheduledCount: 0,
     cancelledCount: 0,
     lastScheduledAt: null,
+    lastMinutes: null,
+    lastRuntimeMinutes: null,
   });
 
+  const [shutdownPhase, setShutdownPhase] = useState<ShutdownPhaseState | null>(null);
+  const [outageSeconds, setOutageSeconds] = useState(60);
+  const [drainRate, setDrainRate] = useState(1.5);
+  const [outageRunning, setOutageRunning] = useState(false);
+  const [outageReport, setOutageReport] = useState<OutageSimulationReport | null>(null);
+  const [outageError, setOutageError] = useState<string | null>(null);
+
   const disabledClass = isDisconnected ? 'opacity-70' : '';
 
   const updateTest = (name: string, status: TestResult['status'], message?: string) => {
@@ -81,6 +102,10 @@ export const TestView: React.FC<TestViewProps> = ({ data, isDisconnected = false
     loadSettingsSummary();
 
     const unsubscribers: Array<() => void> = [];
+    if (window.desktopAPI?.getShutdownPhase) {
+      void window.desktopAPI.getShutdownPhase().then(setShutdownPhase);
+      unsubscribers.push(window.desktopAPI.onShutdownPhaseChanged(setShutdownPhase));
+    }
     if (window.desktopAPI?.onShutdownScheduled) {
       unsubscribers.push(
         window.desktopAPI.onShutdownScheduled((payload) => {
@@ -88,6 +113,8 @@ export const TestView: React.FC<TestViewProps> = ({ data, isDisconnected = false
             scheduledCount: prev.scheduledCount + 1,
             cancelledCount: prev.cancelledCount,
             lastScheduledAt: payload?.shutdownTime ?? new Date().toISOString(),
+            lastMinutes: payload?.minutes ?? null,
+            lastRuntimeMinutes: payload?.estimatedRuntimeMinutes ?? null,
           }));
         }),
       );
@@ -146,6 +173,22 @@ export const TestView: React.FC<TestViewProps> = ({ data, isDisconnected = false
     });
   };
 
+  // Corre el flujo real de alertas y apagado con paquetes simulados; el
+  // comando de apagado solo se registra.
+  const runOutageSimulation = async () => {
+    if (!window.desktopAPI?.simulateOutage) return;
+    setOutageRunning(true);
+    setOutageError(null);
+    setOutageReport(null);
+    try {
+      setOutageReport(await window.desktopAPI.simulateOutage(outageSeconds, drainRate));
+    } catch (error) {
+      setOutageError(String(error));
+    } finally {
+      setOutageRunning(false);
+    }
+  };
+
   const testSoundInventory = async () => {
     await runTest('sound-inventory', async () => {
       if (!window.desktopAPI?.getAvailableSounds) {
@@ -222,6 +265,36 @@ export const TestView: React.FC<TestViewProps> = ({ data, isDisconnected = false
     });
   };
 
+  const testAlertPipeline = (kind: string, scheduleShutdown = false) => async () => {
+    await runTest(`pipeline-${kind}${scheduleShutdown ? '-shutdown' : ''}`, async () => {
+      if (!window.desktopAPI?.testAlert) {
+        throw new Error('testAlert no disponible');
+      }
+      const result = await window.desktopAPI.testAlert(kind, scheduleShutdown);
+      const channels = result.channels
+        .map((channel) => `${channel.channel}: ${channel.ok ? 'ok' : channel.error ?? 'error'}`)
+        .join(' · ');
+      const shutdown = result.shutdownScheduled
+        ? ` · apagado programado (se cancela en ${Math.round((result.cancelAfterMs ?? 0) / 1000)} s)`
+        : '';
+      return `${channels || 'Sin canales'}${shutdown}`;
+    });
+  };
+
+  const replayLastAlert = async () => {
+    await runTest('replay-last-alert', async () => {
+      if (!window.desktopAPI?.replayLastAlert) {
+        throw new Error('replayLastAlert no disponible');
+      }
+      const channels = await window.desktopAPI.replayLastAlert();
+      return (
+        channels
+          .map((channel) => `${channel.channel}: ${channel.ok ? 'ok' : channel.error ?? 'error'}`)
+          .join(' · ') || 'Sin canales'
+      );
+    });
+  };
+
   const getStatusIcon = (status?: TestResult['status']) => {
     switch (status) {
       case 'running':
@@ -324,6 +397,49 @@ export const TestView: React.FC<TestViewProps> = ({ data, isDisconnected = false
     },
   ];
 
+  const pipelineTests = [
+    {
+      id: 'pipeline-acFault',
+      name: 'Pipeline: fallo de energia',
+      description: 'Canales, sonido y escalado configurados',
+      icon: ZapOff,
+      action: testAlertPipeline('acFault'),
+      color: 'amber',
+    },
+    {
+      id: 'pipeline-batteryLow',
+      name: 'Pipeline: bateria baja',
+      description: 'Canales, sonido y escalado configurados',
+      icon: BatteryWarning,
+      action: testAlertPipeline('batteryLow'),
+      color: 'orange',
+    },
+    {
+      id: 'pipeline-batteryCritical',
+      name: 'Pipeline: bateria critica',
+      description: 'Canales, sonido y escalado configurados',
+      icon: AlertOctagon,
+      action: testAlertPipeline('batteryCritical'),
+      color: 'red',
+    },
+    {
+      id: 'pipeline-acFault-shutdown',
+      name: 'Pipeline: fallo con apagado',
+      description: 'Programa el apagado configurado y lo cancela en 1 minuto',
+      icon: Power,
+      action: testAlertPipeline('acFault', true),
+      color: 'rose',
+    },
+    {
+      id: 'replay-last-alert',
+      name: 'Repetir ultima alerta',
+      description: 'Vuelve a enviar la ultima alerta por sus canales',
+      icon: RotateCcw,
+      action: replayLastAlert,
+      color: 'slate',
+    },
+  ];
+
   const colorVariants: Record<string, string> = {
     cyan: 'bg-cyan-500/20 text-cyan-400',
     slate: 'bg-slate-500/20 text-slate-400',
@@ -526,12 +642,111 @@ export const TestView: React.FC<TestViewProps> = ({ data, isDisconnected = false
           </div>
         </div>
 
+        {renderTestSection('Pipeline de alertas', pipelineTests, <Megaphone className="w-4 h-4 text-amber-400" />)}
+
+        <div className="glass-card p-5">
+          <h2 className="text-sm font-semibold text-white/70 mb-4 flex items-center gap-2">
+            <ZapOff className="w-4 h-4 text-orange-400" />
+            Simulacro de corte completo
+          </h2>
+          <div className="bg-dark-800/50 rounded-lg p-4 space-y-3 text-sm">
+            <p className="text-white/40 text-xs">
+              Inyecta lecturas en batería, dispara las alertas y el apagado programado reales y restaura la energía al
+              final. El comando de apagado no se ejecuta y los eventos quedan marcados como "(simulated)".
+            </p>
+            <div className="flex flex-wrap items-center gap-4">
+              <label className="flex items-center gap-2 text-white/60">
+                Duración
+                <input
+                  type="number"
+                  min={1}
+                  max={600}
+                  value={outageSeconds}
+                  onChange={(e) => setOutageSeconds(Math.max(1, Math.min(600, Number(e.target.value) || 1)))}
+                  disabled={outageRunning}
+                  className="w-20 px-2 py-1 rounded bg-dark-700 border border-white/10 text-white"
+                />
+                s
+              </label>
+              <label className="flex items-center gap-2 text-white/60">
+                Descarga
+                <input
+                  type="number"
+                  min={0.1}
+                  max={20}
+                  step={0.1}
+                  value={drainRate}
+                  onChange={(e) => setDrainRate(Math.max(0.1, Math.min(20, Number(e.target.value) || 0.1)))}
+                  disabled={outageRunning}
+                  className="w-20 px-2 py-1 rounded bg-dark-700 border border-white/10 text-white"
+                />
+                %/s
+              </label>
+              <div className="flex gap-2 ml-auto">
+                <button
+                  onClick={runOutageSimulation}
+                  disabled={outageRunning}
+                  className="px-4 py-2 bg-white/10 hover:bg-white/20 disabled:opacity-50 rounded-lg text-sm text-white transition-colors"
+                >
+                  {outageRunning ? 'En curso...' : 'Iniciar'}
+                </button>
+                <button
+                  onClick={() => void window.desktopAPI?.cancelSimulation()}
+                  disabled={!outageRunning}
+                  className="px-4 py-2 bg-white/10 hover:bg-white/20 disabled:opacity-50 rounded-lg text-sm text-white transition-colors"
+                >
+                  Cancelar
+                </button>
+              </div>
+            </div>
+            {outageError && <p className="text-xs text-red-400">{outageError}</p>}
+            {outageReport && (
+              <div className="pt-2 border-t border-white/10 space-y-1">
+                <p className="text-white/50 text-xs">
+                  {outageReport.cancelled ? 'Cancelado' : 'Completado'} · batería {outageReport.startPercent}% →{' '}
+                  {outageReport.endPercent}%
+                </p>
+                {outageReport.timeline.map((step, index) => (
+                  <div key={index} className="flex gap-3 text-xs">
+                    <span className="text-white/40 font-mono w-14 text-right">{(step.offsetMs / 1000).toFixed(1)} s</span>
+                    <span className="text-white/80 font-mono">{step.key}</span>
+                    <span className="text-white/50 truncate">{step.detail}</span>
+                  </div>
+                ))}
+              </div>
+            )}
+          </div>
+        </div>
+
         <div className="glass-card p-5">
           <h2 className="text-sm font-semibold text-white/70 mb-4 flex items-center gap-2">
             <Clock className="w-4 h-4 text-blue-400" />
             Estado de flujo de apagado y configuracion
           </h2>
           <div className="bg-dark-800/50 rounded-lg p-4 space-y-3 text-sm">
+            <div className="flex justify-between items-center">
+              <span className="text-white/50">Fase actual:</span>
+              <span className="text-white text-xs">
+                {shutdownPhase ? shutdownPhaseLabels[shutdownPhase.phase] : 'N/A'}
+                {shutdownPhase?.deadline ? ` · ${new Date(shutdownPhase.deadline).toLocaleTimeString('es-ES')}` : ''}
+              </span>
+            </div>
+            {shutdownPhase?.phase === 'awaiting-confirmation' && (
+              <div className="flex justify-end gap-2">
+                <button
+                  onClick={() => void window.desktopAPI?.confirmShutdownNow()}
+                  className="px-3 py-1.5 bg-red-500/20 hover:bg-red-500/30 rounded-lg text-xs text-red-300 transition-colors"
+                >
+                  Apagar ahora
+                </button>
+                <button
+                  onClick={() => void window.desktopAPI?.cancelShutdown()}
+                  className="px-3 py-1.5 bg-white/10 hover:bg-white/20 rounded-lg text-xs text-white transition-colors"
+                >
+                  Abortar
+                </button>
+              </div>
+            )}
             <div className="flex justify-between">
               <span className="text-white/50">Eventos shutdown-scheduled:</span>
               <span className="text-white font-mono">{shutdownEvents.scheduledCount}</span>
@@ -548,18 +763,32 @@ export const TestView: React.FC<TestViewProps> = ({ data, isDisconnected = false
                   : 'N/A'}
               </span>
             </div>
+            <div className="flex justify-between">
+              <span className="text-white/50">Autonomia al programar:</span>
+              <span
+                className={
+                  shutdownEvents.lastRuntimeMinutes !== null &&
+                  shutdownEvents.lastMinutes !== null &&
+                  shutdownEvents.lastMinutes > shutdownEvents.lastRuntimeMinutes
+                    ? 'text-red-400 font-mono'
+                    : 'text-white font-mono'
+                }
+              >
+                {shutdownEvents.lastRuntimeMinutes !== null ? `${shutdownEvents.lastRuntimeMinutes} min` : 'N/A'}
+              </span>
+            </div>
             <div className="pt-2 border-t border-white/10 space-y-1">
               <p className="text-white/50">Resumen de ajustes:</p>
               <p className="text-white/80 text-xs">
-                AC fault: {settings?.alerts?.acFault?.playSound ? 'sonido ON' : 'sonido OFF'} | popup{' '}
-                {settings?.alerts?.acFault?.showPopup ? 'ON' : 'OFF'}
+                AC fault: {settings?.alerts?.acFault?.channels?.includes('sound') ? 'sonido ON' : 'sonido OFF'} | popup{' '}
+                {settings?.alerts?.acFault?.channels?.includes('popup') ? 'ON' : 'OFF'}
               </p>
               <p className="text-white/80 text-xs">
                 Bateria critica: umbral {settings?.criticalBatteryThreshold ?? 'N/A'}% | accion{' '}
                 {settings?.shutdownPC?.action ?? 'N/A'}
               </p>
               <p className="text-white/80 text-xs">
-                Ruta sonidos custom: {settings?.customSoundsPath ?? 'predeterminada'}
+                Ruta sonidos custom: {settings?.customSoundsPaths?.length ? settings.customSoundsPaths.join(' > ') : 'predeterminada'}
               </p>
             </div>
           </div>
@@ -1,8 +1,9 @@
 import { invoke } from '@tauri-apps/api/core';
 import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import { getCurrentWindow } from '@tauri-apps/api/window';
+import { getCurrentWebviewWindow } from '@tauri-apps/api/webviewWindow';
 import { open } from '@tauri-apps/plugin-dialog';
-import type { DesktopAPI, EventFilter, SoundConfig, UrgentAlertPayload } from '../types/desktop';
+import type { AlertTestResult, ChannelResult, ConflictReport, ConnectionState, DeleteSummary, DesktopAPI, EventFilter, MonitorInfo, OutageSimulationReport, ProtectionState, RawFrameInfo, RejectedReading, ResourceUsage, SetupGuidance, ShutdownImminentPayload, ShutdownPhaseState, SoundConfig, UrgentAlertPayload } from '../types/desktop';
 import type { AppSettings } from '../types/settings';
 
 type UnlistenEntry = {
@@ -52,13 +53,20 @@ async function invokeSafe<T>(command: string, args?: Record<string, unknown>, fa
   }
 }
 
-function onEvent<T = void>(channel: string, callback: (payload: T) => void): () => void {
+// windowScoped solo recibe los eventos enviados a esta ventana con emit_to;
+// un listen global los recibe todos, los de cualquier ventana.
+function onEvent<T = void>(channel: string, callback: (payload: T) => void, windowScoped = false): () => void {
   let entry: UnlistenEntry | null = null;
   let disposed = false;
 
-  void listen<T>(channel, (event) => {
+  const handler = (event: { payload: T }) => {
     callback(event.payload);
-  }).then((unlisten) => {
+  };
+  const subscription = windowScoped
+    ? getCurrentWebviewWindow().listen<T>(channel, handler)
+    : listen<T>(channel, handler);
+
+  void subscription.then((unlisten) => {
     if (disposed) {
       unlisten();
       return;
@@ -89,15 +97,33 @@ async function withCurrentWindow(action: (windowRef: ReturnType<typeof getCurren
 
 const tauriDesktopBridge: DesktopAPI = {
   getSettings: () => invokeSafe<AppSettings>('get_settings'),
+  getSettingsDiff: () => invokeSafe<Record<string, unknown>>('get_settings_diff', undefined, {}),
   saveSettings: (settings) => invokeSafe<boolean>('save_settings', { newSettings: settings }, false),
+  setMonitorOnly: (enabled) => invokeSafe<boolean>('set_monitor_only', { enabled }, false),
+  isMonitorOnly: () => invokeSafe<boolean>('is_monitor_only', undefined, false),
+  suspendProtection: (minutes, reason) => invoke<ProtectionState>('suspend_protection', { minutes, reason }),
+  resumeProtection: () => invokeSafe<ProtectionState | null>('resume_protection', undefined, null),
+  getProtectionState: () => invokeSafe<ProtectionState | null>('get_protection_state', undefined, null),
 
   getUPSStatus: () => invokeSafe('get_ups_status', undefined, null),
+  getConnectionState: () => invokeSafe<ConnectionState | null>('get_connection_state', undefined, null),
+  subscribeUPSData: (minIntervalMs) =>
+    invokeSafe<boolean>('subscribe_ups_data', { windowLabel: getCurrentWindow().label, minIntervalMs }, false),
+  unsubscribeUPSData: () =>
+    invokeSafe<boolean>('unsubscribe_ups_data', { windowLabel: getCurrentWindow().label }, false),
+  injectStatus: (status) => invokeSafe<boolean>('inject_status', { status }),
   getUPSInfo: () => invokeSafe('get_ups_info', undefined, null),
 
   cancelShutdown: () => invokeSafe<boolean>('cancel_shutdown', undefined, false),
-  triggerShutdown: (minutes) => invokeSafe<boolean>('trigger_shutdown', { minutes }, false),
+  confirmShutdownNow: () => invokeSafe<boolean>('confirm_shutdown_now', undefined, false),
+  getShutdownPhase: () =>
+    invokeSafe<ShutdownPhaseState>('get_shutdown_phase', undefined, { phase: 'idle', reason: null, deadline: null }),
+  triggerShutdown: (minutes, action) => invoke<void>('trigger_shutdown', { minutes, action }),
   simulateShutdownFlow: (minutes = 5, autoCancelMs = 1200) =>
     invokeSafe('simulate_shutdown_flow', { minutes, autoCancelMs }),
+  simulateOutage: (durationSeconds, drainRate) =>
+    invoke<OutageSimulationReport>('simulate_outage', { durationSeconds, drainRate }),
+  cancelSimulation: () => invokeSafe<boolean>('cancel_simulation', undefined, false),
   getBatteryTime: () => invokeSafe<number | null>('get_battery_time', undefined, null),
 
   testNotification: () => invokeSafe<boolean>('test_notification', undefined, true),
@@ -124,6 +150,8 @@ const tauriDesktopBridge: DesktopAPI = {
       await withCurrentWindow((windowRef) => windowRef.close());
     }
   },
+  setAlwaysOnTop: (enabled) => invokeSafe<boolean>('set_always_on_top', { enabled }, false),
+  setSkipTaskbar: (enabled) => invokeSafe<boolean>('set_skip_taskbar', { enabled }, false),
 
   selectFile: async () => {
     const result = await open({
@@ -140,28 +168,54 @@ const tauriDesktopBridge: DesktopAPI = {
   },
 
   getEvents: (filter?: EventFilter) => invokeSafe('get_events', { filter }, []),
-  deleteEvents: (ids) => invokeSafe('delete_events', { ids }, []),
+  deleteEvents: (ids, filter) => invokeSafe<DeleteSummary | null>('delete_events', { ids, filter }, null),
+  markEventsResolved: (ids, resolved) => invokeSafe('mark_events_resolved', { ids, resolved }, []),
   getDataHistory: (filter?: EventFilter) => invokeSafe('get_data_history', { filter }, []),
-  deleteDataHistory: (ids) => invokeSafe('delete_data_history', { ids }, []),
+  deleteDataHistory: (ids, filter) =>
+    invokeSafe<DeleteSummary | null>('delete_data_history', { ids, filter }, null),
+  flushStorage: () => invokeSafe<boolean>('flush_storage', undefined, false),
+  getLogTail: (lines) => invokeSafe<string[]>('get_log_tail', { lines }, []),
+  getRawPacketLog: () => invokeSafe<RawFrameInfo[]>('get_raw_packet_log', undefined, []),
+  setLogLevel: (level) => invoke<void>('set_log_level', { level }),
+  getResourceUsage: () => invokeSafe<ResourceUsage | null>('get_resource_usage', undefined, null),
+  getMonitors: () => invokeSafe<MonitorInfo[]>('get_monitors', undefined, []),
+  getSetupGuidance: () => invokeSafe<SetupGuidance | null>('get_setup_guidance', undefined, null),
+  checkConflictingSoftware: () => invoke<ConflictReport>('check_conflicting_software'),
   updateHistoryInterval: (seconds) => invokeSafe<boolean>('update_history_interval', { seconds }, true),
 
   playSound: (type, repeats) => invokeSafe<boolean>('play_sound', { soundType: type, repeats }, true),
   stopSound: () => invokeSafe<boolean>('stop_sound', undefined, true),
+  isAudioAvailable: () => invokeSafe<boolean>('is_audio_available', undefined, true),
   testUrgentAlert: (title, message, type) =>
     invokeSafe<boolean>('test_urgent_alert', { title, message, alertType: type }, true),
+  testAlert: (kind, scheduleShutdown) =>
+    invoke<AlertTestResult>('test_alert', { kind, scheduleShutdown }),
+  getPendingAlert: () => invokeSafe<UrgentAlertPayload | null>('get_pending_alert', undefined, null),
+  acknowledgeAlert: () => invokeSafe<boolean>('acknowledge_alert', undefined, true),
+  replayLastAlert: () => invoke<ChannelResult[]>('replay_last_alert'),
   getAvailableSounds: () => invokeSafe('get_available_sounds', undefined, []),
   getSoundConfig: () => invokeSafe<SoundConfig | null>('get_sound_config', undefined, null),
   setSoundConfig: (config) => invokeSafe<boolean>('set_sound_config', { config }, true),
   setCustomSoundsPath: (path) => invokeSafe<boolean>('set_custom_sounds_path', { soundPath: path }, true),
+  setCustomSoundsPaths: (paths) => invokeSafe<string[]>('set_custom_sounds_paths', { soundPaths: paths }, paths),
 
-  onUPSData: (callback) => onEvent('ups-data', callback),
-  onUPSConnected: (callback) => onEvent('ups-connected', callback),
-  onUPSDisconnected: (callback) => onEvent('ups-disconnected', callback),
+  onUPSData: (callback) => onEvent('ups-data', callback, true),
+  onUPSConnected: (callback) => onEvent<ConnectionState>('ups-connected', callback),
+  onUPSDisconnected: (callback) => onEvent<ConnectionState>('ups-disconnected', callback),
   onUPSError: (callback) => onEvent<string>('ups-error', callback),
+  onUPSPermissionError: (callback) => onEvent<SetupGuidance>('ups-permission-error', callback),
   onShutdownScheduled: (callback) => onEvent('shutdown-scheduled', callback),
   onShutdownCancelled: (callback) => onEvent('shutdown-cancelled', callback),
+  onShutdownImminent: (callback) => onEvent<ShutdownImminentPayload>('shutdown-imminent', callback),
   onShowStatus: (callback) => onEvent('show-status', callback),
   onUrgentAlert: (callback) => onEvent<UrgentAlertPayload>('urgent-alert', callback),
+  onAlertWindowUpdate: (callback) => onEvent<UrgentAlertPayload>('alert-window-update', callback),
+  onReadingRejected: (callback) => onEvent<RejectedReading>('reading-rejected', callback),
+  onProtectionStateChanged: (callback) => onEvent<ProtectionState>('protection-state-changed', callback),
+  onSettingsReloaded: (callback) => onEvent<AppSettings>('settings-reloaded', callback),
+  onShutdownPhaseChanged: (callback) => onEvent<ShutdownPhaseState>('shutdown-phase-changed', callback),
+  onAudioUnavailable: (callback) => onEvent('audio-unavailable', callback),
+  onAudioRestored: (callback) => onEvent('audio-restored', callback),
 
   removeAllListeners,
 };
@@ -1,13 +1,23 @@
 import React from 'react'
 import ReactDOM from 'react-dom/client'
+import { getCurrentWindow } from '@tauri-apps/api/window'
 import App from './App'
 import { AppErrorBoundary } from './components/AppErrorBoundary'
+import { AlertWindow } from './components/AlertWindow'
 import './desktop/tauriDesktopBridge'
 
-ReactDOM.createRoot(document.getElementById('root')!).render(
-  <AppErrorBoundary>
-    <App />
-  </AppErrorBoundary>,
-)
+const windowLabel = getCurrentWindow().label
 
+// The flash window is a plain white surface shown and hidden by the backend.
+const root =
+  windowLabel === 'flash' ? (
+    <div style={{ position: 'fixed', inset: 0, background: '#fff' }} />
+  ) : windowLabel === 'alert' ? (
+    <AlertWindow />
+  ) : (
+    <App />
+  )
 
+ReactDOM.createRoot(document.getElementById('root')!).render(
+  <AppErrorBoundary>{root}</AppErrorBoundary>,
+)
@@ -1,5 +1,5 @@
 import type { UPSData } from './ups';
-import type { AppSettings } from './settings';
+import type { AppSettings, LogLevel } from './settings';
 
 export interface SoundConfig {
   repeatConfig: {
@@ -10,6 +10,7 @@ export interface SoundConfig {
   };
   repeatDelay: number;
   customSoundsPath: string | null;
+  customSoundsPaths: string[];
   sounds: {
     acFault: string;
     batteryLow: string;
@@ -24,18 +25,108 @@ export interface SoundInfo {
   location?: string;
 }
 
+export type EventSeverity = 'info' | 'warning' | 'critical';
+
 export interface EventFilter {
   classification?: string;
+  severity?: EventSeverity;
+  keys?: string[];
+  resolved?: boolean;
   dateFrom?: string;
   dateTo?: string;
 }
 
+// ids y filtro son excluyentes; sin ninguno se borra todo
+export interface DeleteSummary {
+  deleted: number;
+  remaining: number;
+}
+
+export interface ProtectionState {
+  suspended: boolean;
+  source: 'manual' | 'schedule' | null;
+  reason: string | null;
+  until: string | null;
+}
+
+export interface MonitorLoopStats {
+  pollingIntervalMs: number;
+  readTimeoutMs: number;
+  sleepMs: number;
+  iterations: number;
+  iterationsPerSecond: number;
+}
+
+// Trama ASCII tal como llego del UPS, antes de interpretarla
+export interface RawFrameInfo {
+  ascii: string;
+  parsed: boolean;
+  receivedAt: string;
+}
+
+// Como dar acceso al UPS en esta plataforma; udevRule solo en Linux
+export interface SetupGuidance {
+  platform: string;
+  permissionDenied: boolean;
+  devicePath: string | null;
+  vendorId: string;
+  productId: string;
+  udevRule: string | null;
+  steps: string[];
+  error: string | null;
+  // Resultado de la ultima busqueda de software en conflicto
+  conflictingSoftware: ConflictingProcess[];
+}
+
+// Otro programa (software del fabricante, NUT...) que puede tener el UPS abierto
+export interface ConflictingProcess {
+  product: string;
+  process: string;
+  pid: number | null;
+  services: string[];
+}
+
+export interface ConflictReport {
+  checkedAt: string;
+  conflicts: ConflictingProcess[];
+  error: string | null;
+}
+
+export interface MonitorInfo {
+  index: number;
+  name: string | null;
+  width: number;
+  height: number;
+  primary: boolean;
+}
+
+// cpuTimeMs/memoryBytes son null si el sistema no los expone
+export interface ResourceUsage {
+  pid: number;
+  cpuTimeMs: number | null;
+  memoryBytes: number | null;
+  uptimeSeconds: number;
+  averageCpuPercent: number | null;
+  monitorLoop: MonitorLoopStats;
+}
+
 export interface HistoryEvent {
   id: number;
+  // Orden de insercion; la hora puede retroceder por cambios de reloj
+  seq?: number;
   time: string;
   classification: string;
+  severity?: EventSeverity;
+  // Identificador estable (ac_fault, ups_connected...); name es texto visible
+  key: string;
   name: string;
   remarks: string;
+  sessionId?: number;
+  appVersion?: string;
+  resolved: boolean;
+  // Repeticiones agrupadas por la supresion de duplicados
+  repeatCount?: number;
+  lastSeen?: string;
 }
 
 export interface DataHistoryEntry {
@@ -55,6 +146,52 @@ export interface ShutdownSimulationResult {
   cancelled: boolean;
   minutes: number;
   shutdownTime: string;
+  cancelAfterMs: number;
+}
+
+export type ShutdownPhase = 'idle' | 'grace-period' | 'scheduled' | 'awaiting-confirmation' | 'executing';
+
+export interface ShutdownPhaseState {
+  phase: ShutdownPhase;
+  reason: string | null;
+  // Hora del apagado si esta programado; fin de la ventana si espera confirmacion
+  deadline: string | null;
+}
+
+// Apagado retenido porque hay alguien usando el equipo
+export interface ShutdownImminentPayload {
+  seconds: number;
+  reason: string;
+  deadline: string;
+  estimatedRuntimeMinutes: number | null;
+}
+
+export interface SimulationStep {
+  offsetMs: number;
+  key: string;
+  detail: string;
+}
+
+export interface OutageSimulationReport {
+  durationSeconds: number;
+  drainRate: number;
+  startPercent: number;
+  endPercent: number;
+  cancelled: boolean;
+  timeline: SimulationStep[];
+}
+
+export interface ChannelResult {
+  channel: string;
+  ok: boolean;
+  error: string | null;
+}
+
+export interface AlertTestResult {
+  kind: string;
+  channels: ChannelResult[];
+  shutdownScheduled: string | null;
+  cancelAfterMs: number | null;
 }
 
 export interface UrgentAlertPayload {
@@ -64,16 +201,48 @@ export interface UrgentAlertPayload {
   createdAt: string;
 }
 
+export interface RejectedReading {
+  field: string;
+  value: number;
+  min: number;
+  max: number;
+  substituted: number;
+}
+
+export interface ConnectionState {
+  connected: boolean;
+  since: string | null;
+  lastPacketAt: string | null;
+  lastError: string | null;
+  devicePath: string | null;
+}
+
 export interface DesktopAPI {
   getSettings: () => Promise<AppSettings>;
+  getSettingsDiff: () => Promise<Record<string, unknown>>;
   saveSettings: (settings: AppSettings) => Promise<boolean>;
+  setMonitorOnly: (enabled: boolean) => Promise<boolean>;
+  isMonitorOnly: () => Promise<boolean>;
+  suspendProtection: (minutes: number, reason?: string) => Promise<ProtectionState>;
+  resumeProtection: () => Promise<ProtectionState | null>;
+  getProtectionState: () => Promise<ProtectionState | null>;
 
   getUPSStatus: () => Promise<UPSData | null>;
+  getConnectionState: () => Promise<ConnectionState | null>;
+  subscribeUPSData: (minIntervalMs: number) => Promise<boolean>;
+  unsubscribeUPSData: () => Promise<boolean>;
+  injectStatus: (status: UPSData) => Promise<boolean>;
   getUPSInfo: () => Promise<Record<string, unknown> | null>;
 
   cancelShutdown: () => Promise<boolean>;
-  triggerShutdown: (minutes: number) => Promise<boolean>;
+  confirmShutdownNow: () => Promise<boolean>;
+  getShutdownPhase: () => Promise<ShutdownPhaseState>;
+  // 'restart' solo se admite aqui: prueba el flujo completo con un resultado reversible
+  triggerShutdown: (minutes: number, action?: AppSettings['shutdownPC']['action'] | 'restart') => Promise<void>;
   simulateShutdownFlow: (minutes?: number, autoCancelMs?: number) => Promise<ShutdownSimulationResult>;
+  // Porcentaje de bateria perdido por segundo (0-20)
+  simulateOutage: (durationSeconds: number, drainRate: number) => Promise<OutageSimulationReport>;
+  cancelSimulation: () => Promise<boolean>;
   getBatteryTime: () => Promise<number | null>;
 
   testNotification: () => Promise<boolean>;
@@ -82,31 +251,57 @@ export interface DesktopAPI {
   minimizeWindow: () => Promise<void>;
   maximizeWindow: () => Promise<void>;
   closeWindow: () => Promise<void>;
+  setAlwaysOnTop: (enabled: boolean) => Promise<boolean>;
+  setSkipTaskbar: (enabled: boolean) => Promise<boolean>;
 
   selectFile: () => Promise<string | null>;
 
   getEvents: (filter?: EventFilter) => Promise<HistoryEvent[]>;
-  deleteEvents: (ids: number[]) => Promise<HistoryEvent[]>;
+  deleteEvents: (ids: number[], filter?: EventFilter) => Promise<DeleteSummary | null>;
+  markEventsResolved: (ids: number[], resolved: boolean) => Promise<HistoryEvent[]>;
   getDataHistory: (filter?: EventFilter) => Promise<DataHistoryEntry[]>;
-  deleteDataHistory: (ids: number[]) => Promise<DataHistoryEntry[]>;
+  deleteDataHistory: (ids: number[], filter?: EventFilter) => Promise<DeleteSummary | null>;
   updateHistoryInterval: (seconds: number) => Promise<boolean>;
+  flushStorage: () => Promise<boolean>;
+  getLogTail: (lines: number) => Promise<string[]>;
+  getRawPacketLog: () => Promise<RawFrameInfo[]>;
+  setLogLevel: (level: LogLevel) => Promise<void>;
+  getResourceUsage: () => Promise<ResourceUsage | null>;
+  getMonitors: () => Promise<MonitorInfo[]>;
+  getSetupGuidance: () => Promise<SetupGuidance | null>;
+  checkConflictingSoftware: () => Promise<ConflictReport>;
 
   playSound: (type: string, repeats?: number) => Promise<boolean>;
   stopSound: () => Promise<boolean>;
+  isAudioAvailable: () => Promise<boolean>;
   testUrgentAlert: (title: string, message: string, type: string) => Promise<boolean>;
+  testAlert: (kind: string, scheduleShutdown: boolean) => Promise<AlertTestResult>;
+  getPendingAlert: () => Promise<UrgentAlertPayload | null>;
+  acknowledgeAlert: () => Promise<boolean>;
+  replayLastAlert: () => Promise<ChannelResult[]>;
   getAvailableSounds: () => Promise<SoundInfo[]>;
   getSoundConfig: () => Promise<SoundConfig | null>;
   setSoundConfig: (config: Partial<SoundConfig>) => Promise<boolean>;
   setCustomSoundsPath: (path: string | null) => Promise<boolean>;
+  setCustomSoundsPaths: (paths: string[]) => Promise<string[]>;
 
   onUPSData: (callback: (data: UPSData) => void) => () => void;
-  onUPSConnected: (callback: () => void) => () => void;
-  onUPSDisconnected: (callback: () => void) => () => void;
+  onUPSConnected: (callback: (state: ConnectionState) => void) => () => void;
+  onUPSDisconnected: (callback: (state: ConnectionState) => void) => () => void;
   onUPSError: (callback: (error: string) => void) => () => void;
-  onShutdownScheduled: (callback: (data: { minutes: number; shutdownTime: string }) => void) => () => void;
+  onUPSPermissionError: (callback: (guidance: SetupGuidance) => void) => () => void;
+  onShutdownScheduled: (callback: (data: { minutes: number; shutdownTime: string; reason: string; estimatedRuntimeMinutes: number | null }) => void) => () => void;
   onShutdownCancelled: (callback: () => void) => () => void;
+  onShutdownImminent: (callback: (payload: ShutdownImminentPayload) => void) => () => void;
   onShowStatus: (callback: () => void) => () => void;
   onUrgentAlert: (callback: (payload: UrgentAlertPayload) => void) => () => void;
+  onAlertWindowUpdate: (callback: (payload: UrgentAlertPayload) => void) => () => void;
+  onReadingRejected: (callback: (reading: RejectedReading) => void) => () => void;
+  onProtectionStateChanged: (callback: (state: ProtectionState) => void) => () => void;
+  onSettingsReloaded: (callback: (settings: AppSettings) => void) => () => void;
+  onShutdownPhaseChanged: (callback: (state: ShutdownPhaseState) => void) => () => void;
+  onAudioUnavailable: (callback: () => void) => () => void;
+  onAudioRestored: (callback: () => void) => () => void;
 
   removeAllListeners: (channel: string) => void;
 }
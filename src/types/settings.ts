@@ -1,65 +1,362 @@
+export type AlertChannel = 'notification' | 'popup' | 'sound';
+
 export interface AlertConfig {
-  playSound: boolean;
-  showPopup: boolean;
+  channels: AlertChannel[];
   soundRepeats: number;
+  escalate: boolean;
+  repeatDelaySeconds: number;
 }
 
+export type LogLevel = 'off' | 'error' | 'warn' | 'info' | 'debug' | 'trace';
+
 export interface AppSettings {
   startWithWindows: boolean;
   startMinimized: boolean;
+  alwaysOnTop: boolean;
   monitorOnlyMode: boolean;
+  monitorOnlyLogging: {
+    events: boolean;
+    dataPoints: boolean;
+  };
   pollingInterval: number;
   enableNotifications: boolean;
   alerts: {
     acFault: AlertConfig;
     batteryLow: AlertConfig;
     batteryCritical: AlertConfig;
+    chargeFailure: AlertConfig;
+    upsDisconnected: AlertConfig;
+    upsConnected: AlertConfig;
+    temperatureRising: AlertConfig;
+    batteryNotHolding: AlertConfig;
   };
+  // Tope de soundRepeats para todas las alertas (1-500)
+  maxSoundRepeats: number;
+  // Sin dispositivo de audio, el canal de sonido abre la ventana de alerta
+  popupWhenAudioUnavailable: boolean;
   shutdownPC: {
-    onAcFault: { enabled: boolean; delayMinutes: number };
+    onAcFault: {
+      enabled: boolean;
+      delayMinutes: number;
+      sticky: boolean;
+      stickyRecoverySeconds: number;
+      // Acorta la espera a la autonomía estimada menos el margen (1-30 min)
+      capDelayToRuntime: boolean;
+      runtimeSafetyMarginMinutes: number;
+    };
     onBatteryLow: { enabled: boolean };
     onBatteryCritical: { enabled: boolean };
     autoSaveFiles: boolean;
     shutdownCommand: string;
+    // Directorio y variables de entorno del comando de apagado; si el directorio no existe al ejecutar, se ignora
+    workingDirectory: string | null;
+    environment: Record<string, string>;
     action: 'shutdown' | 'sleep';
+    confirmIfUserActive: boolean;
+    userActiveWithinSeconds: number;
+    confirmationWindowSeconds: number;
+    manualShutdownMinBatteryPercent: number;
   };
   upsControl: {
     shutdownUpsAfterPC: boolean;
     upsShutdownDelay: number;
   };
   saveHistory: boolean;
+  // data.json se guarda con gzip; versiones anteriores no pueden leerlo
+  compressDataHistory: boolean;
+  // Recarga config.json si otro programa lo modifica mientras la app corre
+  watchConfigFile: boolean;
   historyInterval: number;
+  onBatteryHistoryInterval: number;
   lowBatteryThreshold: number;
   criticalBatteryThreshold: number;
-  customSoundsPath: string | null;
+  rechargeCheck: {
+    enabled: boolean;
+    minRisePercent: number;
+    windowMinutes: number;
+  };
+  temperatureTrend: {
+    enabled: boolean;
+    risePerHour: number;
+    clearPerHour: number;
+    windowMinutes: number;
+  };
+  // En AC: avisa si la bateria sigue bajo minPercent durante durationHours
+  standbyCharge: {
+    enabled: boolean;
+    minPercent: number;
+    durationHours: number;
+  };
+  // Registra un punto extra si alguna metrica cambia mas que su delta
+  changeLogging: {
+    enabled: boolean;
+    voltageDelta: number;
+    frequencyDelta: number;
+    batteryVoltageDelta: number;
+    temperatureDelta: number;
+    loadDelta: number;
+    batteryPercentDelta: number;
+    minIntervalSeconds: number;
+    // Tope de puntos por cambio en una hora; los puntos periodicos no cuentan
+    maxPointsPerHour: number;
+  };
+  // Ventana semanal sin alertas ni apagado (hora local, weekday 0 = lunes)
+  protectionWindow: {
+    enabled: boolean;
+    weekday: number;
+    startHour: number;
+    startMinute: number;
+    durationMinutes: number;
+  };
+  // Bandas sin definir (null) se centran en la tension y frecuencia nominales de la red
+  strictRestore: {
+    enabled: boolean;
+    minFrequency: number | null;
+    maxFrequency: number | null;
+    minVoltage: number | null;
+    maxVoltage: number | null;
+    requiredSamples: number;
+  };
+  // Apaga la pantalla al perder AC; no afecta al apagado del equipo
+  displayOffOnAcFault: boolean;
+  alertInFocusMode: boolean;
+  // Ventana de alerta siempre visible (o popup de PowerShell); la alerta dentro de la app se mantiene
+  forceSystemPopup: boolean;
+  // Segundos minimos entre popups de la misma alerta (1-300)
+  popupMinIntervalSeconds: number;
+  // Segundos que el popup queda visible (3-120)
+  popupTimeoutSeconds: number;
+  // 'primary', 'active' (monitor con la ventana en primer plano) o nombre/indice del monitor
+  alertDisplay: string;
+  // Alertas visuales para quien no puede oir la alarma
+  visualAlerts: {
+    enabled: boolean;
+    // Parpadeos de la barra de tareas / icono de bandeja (1-50)
+    flashCount: number;
+    flashScreen: boolean;
+    // La ventana de alerta queda visible hasta confirmarla
+    persistUntilAck: boolean;
+  };
+  notifyOnShutdownCancel: boolean;
+  customName: string | null;
+  minimumNotificationSeverity: 'info' | 'warning' | 'critical';
+  showPredictedRuntime: boolean;
+  // Origen de la autonomia: formula propia, valor del UPS o el del UPS si lo envia
+  runtimeSource: 'computed' | 'device' | 'auto';
+  autoReport: {
+    schedule: 'off' | 'monthly';
+    destination: string | null;
+  };
+  remoteApi: {
+    enabled: boolean;
+    bindAddress: string;
+    port: number;
+    token: string | null;
+  };
+  nutServer: {
+    enabled: boolean;
+    bindAddress: string;
+    port: number;
+    upsName: string;
+  };
+  customSoundsPaths: string[];
+  reconnectGraceSeconds: number;
+  startupGraceSeconds: number;
+  reportIdPrefixed: boolean;
+  hidReadBufferSize: number;
+  hidTuning: {
+    connectReadTimeoutMs: number;
+    idleReadTimeoutMs: number;
+    statusReadTimeoutMs: number;
+    sleepFloorMs: number;
+    sleepCeilMs: number;
+    disconnectedSleepMs: number;
+  };
+  keepalive: {
+    enabled: boolean;
+    command: string;
+    intervalSeconds: number;
+    maxFailures: number;
+  };
+  readingLimits: {
+    voltageMin: number;
+    voltageMax: number;
+    loadMax: number;
+    frequencyMin: number;
+    frequencyMax: number;
+    temperatureMin: number;
+    temperatureMax: number;
+    batteryVoltageMax: number;
+  };
+  batteryVoltageScale: number;
+  // Cantidad de bits de estado; se localizan como el ultimo campo con esa forma
+  statusBitsCount: number;
+  // Fija la posicion del campo de estado (6-31) para equipos no estandar
+  statusBitsField: number | null;
+  logLevel: LogLevel;
+  // Guarda las ultimas tramas crudas para diagnostico y el paquete de soporte
+  debugCapture: boolean;
+  duplicateEventWindowSeconds: number;
+  settingsRevision: number;
 }
 
 export const defaultAppSettings: AppSettings = {
   startWithWindows: false,
   startMinimized: false,
+  alwaysOnTop: false,
   monitorOnlyMode: false,
+  monitorOnlyLogging: {
+    events: true,
+    dataPoints: true,
+  },
   pollingInterval: 1000,
   enableNotifications: true,
   alerts: {
-    acFault: { playSound: true, showPopup: true, soundRepeats: 3 },
-    batteryLow: { playSound: true, showPopup: true, soundRepeats: 5 },
-    batteryCritical: { playSound: true, showPopup: true, soundRepeats: 10 },
+    acFault: { channels: ['notification', 'popup', 'sound'], soundRepeats: 3, escalate: false, repeatDelaySeconds: 60 },
+    batteryLow: { channels: ['notification', 'popup', 'sound'], soundRepeats: 5, escalate: false, repeatDelaySeconds: 60 },
+    batteryCritical: { channels: ['notification', 'popup', 'sound'], soundRepeats: 10, escalate: false, repeatDelaySeconds: 60 },
+    chargeFailure: { channels: ['notification', 'popup', 'sound'], soundRepeats: 3, escalate: false, repeatDelaySeconds: 60 },
+    upsDisconnected: { channels: [], soundRepeats: 1, escalate: false, repeatDelaySeconds: 60 },
+    upsConnected: { channels: [], soundRepeats: 1, escalate: false, repeatDelaySeconds: 60 },
+    temperatureRising: { channels: ['notification', 'popup'], soundRepeats: 1, escalate: false, repeatDelaySeconds: 60 },
+    batteryNotHolding: { channels: ['notification', 'popup'], soundRepeats: 1, escalate: false, repeatDelaySeconds: 60 },
   },
+  maxSoundRepeats: 30,
+  popupWhenAudioUnavailable: true,
   shutdownPC: {
-    onAcFault: { enabled: true, delayMinutes: 18 },
+    onAcFault: { enabled: true, delayMinutes: 18, sticky: false, stickyRecoverySeconds: 120, capDelayToRuntime: false, runtimeSafetyMarginMinutes: 3 },
     onBatteryLow: { enabled: false },
     onBatteryCritical: { enabled: true },
     autoSaveFiles: true,
     shutdownCommand: '',
+    workingDirectory: null,
+    environment: {},
     action: 'shutdown',
+    confirmIfUserActive: false,
+    userActiveWithinSeconds: 120,
+    confirmationWindowSeconds: 60,
+    manualShutdownMinBatteryPercent: 0,
   },
   upsControl: {
     shutdownUpsAfterPC: true,
     upsShutdownDelay: 2,
   },
   saveHistory: true,
+  compressDataHistory: false,
+  watchConfigFile: false,
   historyInterval: 300,
+  onBatteryHistoryInterval: 15,
   lowBatteryThreshold: 20,
   criticalBatteryThreshold: 10,
-  customSoundsPath: null,
+  rechargeCheck: {
+    enabled: true,
+    minRisePercent: 10,
+    windowMinutes: 240,
+  },
+  temperatureTrend: {
+    enabled: true,
+    risePerHour: 30,
+    clearPerHour: 15,
+    windowMinutes: 15,
+  },
+  standbyCharge: {
+    enabled: true,
+    minPercent: 80,
+    durationHours: 6,
+  },
+  changeLogging: {
+    enabled: false,
+    voltageDelta: 10,
+    frequencyDelta: 1,
+    batteryVoltageDelta: 1,
+    temperatureDelta: 3,
+    loadDelta: 10,
+    batteryPercentDelta: 10,
+    minIntervalSeconds: 5,
+    maxPointsPerHour: 30,
+  },
+  protectionWindow: {
+    enabled: false,
+    weekday: 5,
+    startHour: 9,
+    startMinute: 0,
+    durationMinutes: 120,
+  },
+  strictRestore: {
+    enabled: false,
+    minFrequency: null,
+    maxFrequency: null,
+    minVoltage: null,
+    maxVoltage: null,
+    requiredSamples: 10,
+  },
+  displayOffOnAcFault: false,
+  alertInFocusMode: false,
+  forceSystemPopup: true,
+  popupMinIntervalSeconds: 6,
+  popupTimeoutSeconds: 12,
+  alertDisplay: 'primary',
+  visualAlerts: {
+    enabled: false,
+    flashCount: 5,
+    flashScreen: false,
+    persistUntilAck: false,
+  },
+  notifyOnShutdownCancel: true,
+  customName: null,
+  minimumNotificationSeverity: 'info',
+  showPredictedRuntime: true,
+  runtimeSource: 'auto',
+  autoReport: {
+    schedule: 'off',
+    destination: null,
+  },
+  remoteApi: {
+    enabled: false,
+    bindAddress: '127.0.0.1',
+    port: 8765,
+    token: null,
+  },
+  nutServer: {
+    enabled: false,
+    bindAddress: '127.0.0.1',
+    port: 3493,
+    upsName: 'ups',
+  },
+  customSoundsPaths: [],
+  reconnectGraceSeconds: 10,
+  startupGraceSeconds: 0,
+  reportIdPrefixed: true,
+  hidReadBufferSize: 64,
+  hidTuning: {
+    connectReadTimeoutMs: 120,
+    idleReadTimeoutMs: 100,
+    statusReadTimeoutMs: 600,
+    sleepFloorMs: 30,
+    sleepCeilMs: 280,
+    disconnectedSleepMs: 150,
+  },
+  keepalive: {
+    enabled: false,
+    command: 'Q1',
+    intervalSeconds: 30,
+    maxFailures: 3,
+  },
+  readingLimits: {
+    voltageMin: 0,
+    voltageMax: 300,
+    loadMax: 150,
+    frequencyMin: 0,
+    frequencyMax: 70,
+    temperatureMin: -20,
+    temperatureMax: 80,
+    batteryVoltageMax: 150,
+  },
+  batteryVoltageScale: 1,
+  statusBitsCount: 8,
+  statusBitsField: null,
+  logLevel: 'info',
+  debugCapture: true,
+  duplicateEventWindowSeconds: 30,
+  settingsRevision: 2,
 };
@@ -9,7 +9,24 @@ export interface UPSData {
   batteryVoltage: number;
   temperature: number;
   batteryPercent: number;
-  estimatedRuntime: number;
+  // Ausente si la prediccion en AC esta desactivada
+  estimatedRuntime?: number;
+  // Minutos informados por el propio UPS en tramas extendidas
+  deviceRuntime?: number;
+  // true en AC: autonomia prevista si fallara la energia ahora
+  runtimePredicted?: boolean;
+  // Proyecciones segun la tendencia reciente del porcentaje de bateria
+  projectedMinutesRemaining?: number;
+  chargingProjectionMinutes?: number;
+  // En bateria: hora ISO en que la descarga observada llega al 0% / al umbral de apagado
+  projectedEmptyAt?: string;
+  projectedShutdownAt?: string;
+  // Inferido: en AC, por debajo del 100% y con tendencia ascendente
+  charging?: boolean;
+  // En bateria: 'lost' sin tension de entrada, 'degraded' si la red sigue presente pero baja
+  utilityState?: 'normal' | 'degraded' | 'lost';
+  // Derivado de la tendencia en AC; 'discharging' cuando falla la red
+  chargeState?: 'charging' | 'charged' | 'discharging' | 'unknown';
   timestamp: string;
   status: {
     raw: string;
@@ -22,6 +39,8 @@ export interface UPSData {
     shutdownActive: boolean;
     beeperOn: boolean;
   };
+  // Ultima lectura de la sesion anterior, mostrada hasta recibir datos reales
+  stale?: boolean;
 }
 
 export interface UPSState {
@@ -80,14 +80,16 @@ describe('SettingsView', () => {
     expect(getAvailableSounds).toHaveBeenCalled();
   });
 
-  it('enables monitor-only mode and applies automatic safe preset', async () => {
+  it('enables monitor-only mode without rewriting the configured alerts', async () => {
     const saveSettings = vi.fn().mockResolvedValue(true);
     const updateHistoryInterval = vi.fn().mockResolvedValue(true);
+    const setMonitorOnly = vi.fn().mockResolvedValue(true);
 
     (window as any).desktopAPI = {
       getSettings: vi.fn().mockResolvedValue(defaultAppSettings),
       saveSettings,
       updateHistoryInterval,
+      setMonitorOnly,
     };
 
     render(<SettingsView />);
@@ -100,22 +102,21 @@ describe('SettingsView', () => {
     const monitorToggle = within(monitorCard).getByRole('button');
     await userEvent.click(monitorToggle);
 
+    expect(setMonitorOnly).toHaveBeenCalledWith(true);
+
     await userEvent.click(screen.getByRole('button', { name: /guardar/i }));
 
     await waitFor(() => {
       expect(saveSettings).toHaveBeenCalledTimes(1);
     });
 
+    // Suppression happens in the backend; only the mode itself flips here.
     const savedPayload = saveSettings.mock.calls[0][0];
     expect(savedPayload.monitorOnlyMode).toBe(true);
-    expect(savedPayload.enableNotifications).toBe(false);
-    expect(savedPayload.saveHistory).toBe(false);
-    expect(savedPayload.shutdownPC.onAcFault.enabled).toBe(false);
-    expect(savedPayload.shutdownPC.onBatteryLow.enabled).toBe(false);
-    expect(savedPayload.shutdownPC.onBatteryCritical.enabled).toBe(false);
-    expect(savedPayload.alerts.acFault.playSound).toBe(false);
-    expect(savedPayload.alerts.batteryLow.showPopup).toBe(false);
-    expect(savedPayload.alerts.batteryCritical.playSound).toBe(false);
+    expect(savedPayload.enableNotifications).toBe(defaultAppSettings.enableNotifications);
+    expect(savedPayload.saveHistory).toBe(defaultAppSettings.saveHistory);
+    expect(savedPayload.shutdownPC).toEqual(defaultAppSettings.shutdownPC);
+    expect(savedPayload.alerts).toEqual(defaultAppSettings.alerts);
   });
 });
 
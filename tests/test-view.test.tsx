@@ -41,7 +41,7 @@ describe('TestView', () => {
     (window as any).desktopAPI = {
       getSettings: vi.fn().mockResolvedValue({
         alerts: {
-          acFault: { playSound: true, showPopup: true, soundRepeats: 3 },
+          acFault: { channels: ['notification', 'popup', 'sound'], soundRepeats: 3, escalate: false, repeatDelaySeconds: 60 },
         },
         shutdownPC: { action: 'shutdown' },
         criticalBatteryThreshold: 10,